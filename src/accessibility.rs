@@ -0,0 +1,226 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use crate::{Venus, decode::in_background};
+
+// A check of the platform's preferences running on a worker thread
+pub(crate) type AccessibilityQuery = Pin<Box<dyn Future<Output = Option<Accessibility>>>>;
+
+/// The player's accessibility preferences from their operating system or browser
+///
+/// Where a platform doesn't report a preference, its default is used: motion isn't reduced and
+/// text isn't scaled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Accessibility {
+    /// Whether the player asked for less motion, so screenshake, parallax, and flashing
+    /// transitions should be toned down or turned off
+    pub reduce_motion: bool,
+    /// How much larger than normal text should be, where 1.0 is normal size
+    pub text_scale: f32,
+}
+
+impl Default for Accessibility {
+    fn default() -> Accessibility {
+        Accessibility {
+            reduce_motion: false,
+            text_scale: 1.0,
+        }
+    }
+}
+
+impl Accessibility {
+    /// Ask the platform for its current preferences
+    ///
+    /// This can block for a while, since some platforms are asked by running a program. It reads
+    /// GNOME's settings on Linux, the accessibility display settings on macOS, and the
+    /// `prefers-reduced-motion` media query and root font size on the web. Other platforms always
+    /// get the defaults.
+    pub fn query() -> Accessibility {
+        let defaults = Accessibility::default();
+        Accessibility {
+            reduce_motion: platform::reduce_motion().unwrap_or(defaults.reduce_motion),
+            text_scale: platform::text_scale()
+                .filter(|scale| *scale > 0.0)
+                .unwrap_or(defaults.text_scale),
+        }
+    }
+}
+
+impl Venus {
+    /// The player's accessibility preferences, see `Accessibility`
+    pub fn accessibility(&self) -> Accessibility {
+        self.accessibility
+    }
+
+    /// Whether the accessibility preferences changed since the input cache was last cleared
+    ///
+    /// Preferences are checked again whenever the window regains focus, since they're changed
+    /// in another app.
+    pub fn accessibility_changed(&self) -> bool {
+        self.accessibility_changed
+    }
+
+    /// Replace the platform's preferences with the game's own, e.g. from its options menu
+    ///
+    /// Once set, the platform isn't asked again.
+    pub fn set_accessibility(&mut self, accessibility: Accessibility) {
+        self.accessibility_overridden = true;
+        if accessibility != self.accessibility {
+            self.accessibility = accessibility;
+            self.accessibility_changed = true;
+        }
+    }
+
+    /// Ask the platform again on a worker thread, picking the answer up on a later frame with
+    /// `poll_accessibility`
+    pub(crate) fn refresh_accessibility(&mut self) {
+        if self.accessibility_overridden || self.accessibility_query.is_some() {
+            return;
+        }
+        self.accessibility_query = Some(Box::pin(in_background(Accessibility::query)));
+    }
+
+    pub(crate) fn poll_accessibility(&mut self) {
+        let Some(query) = &mut self.accessibility_query else {
+            return;
+        };
+        // The query runs on its own, so there's nothing to wake; checking once a frame is enough
+        let mut context = Context::from_waker(Waker::noop());
+        let Poll::Ready(result) = query.as_mut().poll(&mut context) else {
+            return;
+        };
+        self.accessibility_query = None;
+        match result {
+            Some(accessibility) => self.update_accessibility(accessibility),
+            None => log::error!("Checking the accessibility preferences panicked"),
+        }
+    }
+
+    pub(crate) fn update_accessibility(&mut self, accessibility: Accessibility) {
+        if self.accessibility_overridden {
+            return;
+        }
+        if accessibility != self.accessibility {
+            log::debug!("Accessibility preferences changed to {accessibility:?}");
+            self.accessibility = accessibility;
+            self.accessibility_changed = true;
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::process::Command;
+
+    fn gsettings(key: &str) -> Option<String> {
+        let output = Command::new("gsettings")
+            .args(["get", "org.gnome.desktop.interface", key])
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    pub fn reduce_motion() -> Option<bool> {
+        Some(gsettings("enable-animations")? == "false")
+    }
+
+    pub fn text_scale() -> Option<f32> {
+        gsettings("text-scaling-factor")?.parse().ok()
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::process::Command;
+
+    pub fn reduce_motion() -> Option<bool> {
+        let output = Command::new("defaults")
+            .args(["read", "com.apple.universalaccess", "reduceMotion"])
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim() == "1")
+    }
+
+    // macOS has no system-wide text size that apps are expected to follow
+    pub fn text_scale() -> Option<f32> {
+        None
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod platform {
+    // Browsers default to 16 pixel text, which players can change in their settings
+    const DEFAULT_FONT_SIZE: f32 = 16.0;
+
+    pub fn reduce_motion() -> Option<bool> {
+        let query = web_sys::window()?
+            .match_media("(prefers-reduced-motion: reduce)")
+            .ok()??;
+        Some(query.matches())
+    }
+
+    pub fn text_scale() -> Option<f32> {
+        let window = web_sys::window()?;
+        let root = window.document()?.document_element()?;
+        let size = window
+            .get_computed_style(&root)
+            .ok()??
+            .get_property_value("font-size")
+            .ok()?;
+        let pixels: f32 = size.strip_suffix("px")?.parse().ok()?;
+        Some(pixels / DEFAULT_FONT_SIZE)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_arch = "wasm32")))]
+mod platform {
+    pub fn reduce_motion() -> Option<bool> {
+        None
+    }
+
+    pub fn text_scale() -> Option<f32> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Accessibility;
+    use crate::{Settings, Venus};
+
+    #[test]
+    fn overriding() {
+        let mut venus = Venus::headless(Settings::default());
+        assert_eq!(venus.accessibility(), Accessibility::default());
+        let preferences = Accessibility {
+            reduce_motion: true,
+            text_scale: 1.5,
+        };
+        venus.set_accessibility(preferences);
+        assert!(venus.accessibility_changed());
+        venus.clear_input_cache();
+        assert!(!venus.accessibility_changed());
+        venus.refresh_accessibility();
+        assert_eq!(venus.accessibility(), preferences);
+    }
+
+    #[test]
+    fn refreshing() {
+        let mut venus = Venus::headless(Settings::default());
+        venus.refresh_accessibility();
+        // The platform is asked in the background, and the answer arrives on a later frame
+        while venus.accessibility_query.is_some() {
+            venus.poll_accessibility();
+            std::thread::yield_now();
+        }
+        assert_eq!(venus.accessibility(), Accessibility::query());
+    }
+}
@@ -0,0 +1,380 @@
+use crate::Texture;
+
+/// A sequence of textures shown one after another
+pub struct Animation {
+    frames: Vec<Texture>,
+    durations: Vec<u32>,
+    length: u32,
+    mode: PlayMode,
+    events: Vec<Vec<String>>,
+}
+
+/// What an animation does after its last frame
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PlayMode {
+    /// Start again from the first frame
+    #[default]
+    Loop,
+    /// Stay on the last frame
+    Once,
+    /// Play backwards to the first frame, then forwards again, without repeating the ends
+    PingPong,
+}
+
+impl Animation {
+    /// An animation which shows every frame for the same number of ticks
+    pub fn new(frames: Vec<Texture>, ticks_per_frame: u32) -> Animation {
+        let durations = vec![ticks_per_frame; frames.len()];
+        Animation::with_durations(frames, durations)
+    }
+
+    /// An animation which shows each frame for its own number of ticks
+    ///
+    /// Holding a frame longer this way is cheaper than repeating it.
+    pub fn with_durations(frames: Vec<Texture>, durations: Vec<u32>) -> Animation {
+        assert_eq!(
+            frames.len(),
+            durations.len(),
+            "every frame of an animation needs a duration"
+        );
+        Animation {
+            length: durations.iter().sum(),
+            durations,
+            mode: PlayMode::Loop,
+            events: vec![Vec::new(); frames.len()],
+            frames,
+        }
+    }
+
+    pub fn with_mode(self, mode: PlayMode) -> Animation {
+        Animation { mode, ..self }
+    }
+
+    pub fn mode(&self) -> PlayMode {
+        self.mode
+    }
+
+    /// Tag a frame with a named event, which fires whenever playback reaches that frame
+    ///
+    /// Frames with no duration are never shown, so their events never fire.
+    pub fn with_event(mut self, frame: usize, name: &str) -> Animation {
+        self.events[frame].push(name.to_string());
+        self
+    }
+
+    pub fn frame_events(&self, frame: usize) -> &[String] {
+        &self.events[frame]
+    }
+
+    /// The events of every frame that starts from the start tick up to and including the end tick
+    ///
+    /// If the range covers more than one loop, each frame's events are only included once.
+    pub fn events_between(&self, start: u32, end: u32) -> Vec<&str> {
+        if self.length == 0 {
+            return Vec::new();
+        }
+        // A frame that plays once can't start on or after the last tick
+        let end = match self.mode {
+            PlayMode::Once => end.min(self.length - 1),
+            _ => end,
+        };
+        let start = start.max(end.saturating_sub(self.period() - 1));
+        (start..=end)
+            .filter_map(|tick| match self.position(tick) {
+                (index, 0) => Some(&self.events[index]),
+                _ => None,
+            })
+            .flatten()
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Whether an animation that plays once has reached its end by the given tick
+    ///
+    /// Looping and ping-pong animations never finish.
+    pub fn is_finished(&self, tick: u32) -> bool {
+        self.mode == PlayMode::Once && tick >= self.length
+    }
+
+    pub fn frame(&self, tick: u32) -> &Texture {
+        &self.frames[self.frame_index(tick)]
+    }
+
+    /// Which frame is showing at the given tick
+    pub fn frame_index(&self, tick: u32) -> usize {
+        if self.length == 0 {
+            return 0;
+        }
+        self.position(tick).0
+    }
+
+    // The frame showing at a tick, and how many ticks it has been showing for
+    fn position(&self, tick: u32) -> (usize, u32) {
+        match self.mode {
+            PlayMode::Loop => self.forward_position(tick % self.length),
+            PlayMode::Once if tick >= self.length => {
+                let last = self.frames.len() - 1;
+                (last, tick - (self.length - self.durations[last]))
+            }
+            PlayMode::Once => self.forward_position(tick),
+            PlayMode::PingPong => {
+                let tick = tick % self.period();
+                if tick < self.length {
+                    return self.forward_position(tick);
+                }
+                // On the way back, the inner frames play in reverse
+                let mut remaining = tick - self.length;
+                for (index, duration) in self.inner_durations().iter().enumerate().rev() {
+                    if remaining < *duration {
+                        return (index + 1, remaining);
+                    }
+                    remaining -= duration;
+                }
+                unreachable!("the tick is within the ping-pong period")
+            }
+        }
+    }
+
+    fn forward_position(&self, tick: u32) -> (usize, u32) {
+        let mut remaining = tick;
+        for (index, duration) in self.durations.iter().enumerate() {
+            if remaining < *duration {
+                return (index, remaining);
+            }
+            remaining -= duration;
+        }
+        unreachable!("the tick is within the length of the animation")
+    }
+
+    // The frames played on the way back in a ping-pong, which skips the first and last
+    fn inner_durations(&self) -> &[u32] {
+        let last = self.durations.len() - 1;
+        &self.durations[1.min(last)..last]
+    }
+
+    // The number of ticks before the animation repeats
+    fn period(&self) -> u32 {
+        match self.mode {
+            PlayMode::PingPong => self.length + self.inner_durations().iter().sum::<u32>(),
+            _ => self.length,
+        }
+    }
+
+    pub fn frames(&self) -> &[Texture] {
+        &self.frames
+    }
+
+    /// How many ticks each frame is shown for
+    pub fn durations(&self) -> &[u32] {
+        &self.durations
+    }
+
+    /// The number of ticks in one loop of the animation
+    pub fn length(&self) -> u32 {
+        self.length
+    }
+}
+
+/// Playback state for an animation, advanced by the time between frames
+///
+/// The player doesn't own an animation, so many entities can share one while each keeps its own
+/// place in it. Ticks are converted to time with the player's ticks per second.
+#[derive(Clone, Debug)]
+pub struct AnimationPlayer {
+    ticks_per_second: f32,
+    ticks: f64,
+    speed: f32,
+    playing: bool,
+    // The tick before the last update, or None if it started from the beginning
+    previous: Option<u32>,
+    restarted: bool,
+}
+
+impl AnimationPlayer {
+    pub fn new(ticks_per_second: f32) -> AnimationPlayer {
+        AnimationPlayer {
+            ticks_per_second,
+            ticks: 0.0,
+            speed: 1.0,
+            playing: true,
+            previous: Some(0),
+            restarted: true,
+        }
+    }
+
+    /// Move the animation forward by a number of seconds, unless it's paused
+    pub fn update(&mut self, delta: f32) {
+        self.previous = if self.restarted {
+            None
+        } else {
+            Some(self.tick())
+        };
+        self.restarted = false;
+        if self.playing {
+            self.ticks += f64::from(delta * self.speed * self.ticks_per_second);
+        }
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Start from the first frame again, without changing whether it's paused
+    pub fn restart(&mut self) {
+        self.ticks = 0.0;
+        self.previous = Some(0);
+        self.restarted = true;
+    }
+
+    /// Set how fast time passes for the animation, where 1.0 is normal speed
+    ///
+    /// Animations can't play backwards, so negative speeds are treated as 0.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.0);
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// The number of whole ticks played since the start
+    pub fn tick(&self) -> u32 {
+        self.ticks as u32
+    }
+
+    pub fn frame<'a>(&self, animation: &'a Animation) -> &'a Texture {
+        animation.frame(self.tick())
+    }
+
+    pub fn frame_index(&self, animation: &Animation) -> usize {
+        animation.frame_index(self.tick())
+    }
+
+    pub fn is_finished(&self, animation: &Animation) -> bool {
+        animation.is_finished(self.tick())
+    }
+
+    /// The events of every frame reached during the last update
+    ///
+    /// The first update after starting or restarting includes the events of the first frame.
+    pub fn events<'a>(&self, animation: &'a Animation) -> Vec<&'a str> {
+        let tick = self.tick();
+        match self.previous {
+            None => animation.events_between(0, tick),
+            Some(previous) if previous < tick => animation.events_between(previous + 1, tick),
+            Some(_) => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Animation, AnimationPlayer, PlayMode};
+    use crate::{Rect, Texture, texture_atlas::TextureHandle};
+
+    fn frames(count: usize) -> Vec<Texture> {
+        let texture = Texture {
+            handle: TextureHandle::mock(),
+            uv: Rect::new(0.0, 0.0, 1.0, 1.0),
+            width: 16,
+            height: 16,
+        };
+        vec![texture; count]
+    }
+
+    #[test]
+    fn frame_durations() {
+        let uniform = Animation::new(frames(3), 4);
+        assert_eq!(uniform.frame_index(3), 0);
+        assert_eq!(uniform.frame_index(4), 1);
+        assert_eq!(uniform.frame_index(13), 0);
+
+        let held = Animation::with_durations(frames(3), vec![2, 10, 0]);
+        assert_eq!(held.length(), 12);
+        assert_eq!(held.frame_index(1), 0);
+        assert_eq!(held.frame_index(11), 1);
+        assert_eq!(held.frame_index(12), 0);
+    }
+
+    #[test]
+    fn play_modes() {
+        let once = Animation::new(frames(3), 2).with_mode(PlayMode::Once);
+        assert_eq!(once.frame_index(5), 2);
+        assert_eq!(once.frame_index(100), 2);
+        assert!(!once.is_finished(5));
+        assert!(once.is_finished(6));
+
+        let ping_pong =
+            Animation::with_durations(frames(4), vec![1, 2, 3, 4]).with_mode(PlayMode::PingPong);
+        let sequence: Vec<_> = (0..17).map(|tick| ping_pong.frame_index(tick)).collect();
+        assert_eq!(
+            sequence,
+            [0, 1, 1, 2, 2, 2, 3, 3, 3, 3, 2, 2, 2, 1, 1, 0, 1]
+        );
+        assert!(!ping_pong.is_finished(1000));
+    }
+
+    #[test]
+    fn player() {
+        let animation = Animation::new(frames(3), 2).with_mode(PlayMode::Once);
+        let mut player = AnimationPlayer::new(10.0);
+        player.update(0.25);
+        assert_eq!(player.tick(), 2);
+        assert_eq!(player.frame_index(&animation), 1);
+
+        player.pause();
+        player.update(1.0);
+        assert_eq!(player.tick(), 2);
+
+        player.play();
+        player.set_speed(2.0);
+        player.update(0.25);
+        assert_eq!(player.tick(), 7);
+        assert!(player.is_finished(&animation));
+
+        player.restart();
+        assert_eq!(player.frame_index(&animation), 0);
+        assert!(!player.is_finished(&animation));
+    }
+
+    #[test]
+    fn events() {
+        let animation = Animation::new(frames(3), 2)
+            .with_event(0, "start")
+            .with_event(1, "footstep")
+            .with_event(1, "dust");
+        assert_eq!(animation.events_between(1, 3), ["footstep", "dust"]);
+        assert_eq!(animation.events_between(3, 6), ["start"]);
+        assert!(animation.events_between(3, 3).is_empty());
+        // A long jump only includes each frame once
+        assert_eq!(animation.events_between(1, 100).len(), 3);
+
+        let once = Animation::new(frames(3), 2)
+            .with_mode(PlayMode::Once)
+            .with_event(0, "start")
+            .with_event(2, "end");
+        assert_eq!(once.events_between(0, once.length()), ["start", "end"]);
+        assert_eq!(once.events_between(0, 100), ["start", "end"]);
+        assert!(once.events_between(5, 100).is_empty());
+
+        let mut player = AnimationPlayer::new(1.0);
+        assert!(player.events(&animation).is_empty());
+        player.update(1.0);
+        assert_eq!(player.events(&animation), ["start"]);
+        player.update(1.0);
+        assert_eq!(player.events(&animation), ["footstep", "dust"]);
+        player.update(1.0);
+        assert!(player.events(&animation).is_empty());
+        player.pause();
+        player.update(1.0);
+        assert!(player.events(&animation).is_empty());
+    }
+}
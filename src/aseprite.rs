@@ -0,0 +1,217 @@
+use std::time::Duration;
+
+use glam::Vec2;
+use serde::Deserialize;
+
+use crate::{
+    Animation, Error, Texture, Venus,
+    sprite_sheet::{FramesJson, sheet_error},
+    tilemap::resolve_path,
+};
+
+/// Frames and tags exported from Aseprite as a sprite sheet and JSON data file
+///
+/// Export with "File > Export Sprite Sheet", with JSON data in either the hash or array layout
+/// and frame tags enabled. `.aseprite` files themselves can't be loaded directly.
+pub struct AsepriteSheet {
+    /// Every frame of the sprite, in timeline order
+    pub frames: Vec<AsepriteFrame>,
+    pub tags: Vec<AsepriteTag>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AsepriteFrame {
+    pub texture: Texture,
+    pub duration: Duration,
+    /// Where the texture sits within the full frame, if the export trimmed empty space
+    pub offset: Vec2,
+    /// The size of the untrimmed frame
+    pub source_size: Vec2,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsepriteTag {
+    pub name: String,
+    /// The first and last frame of the tag, inclusive
+    pub from: usize,
+    pub to: usize,
+    pub direction: AsepriteDirection,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AsepriteDirection {
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+}
+
+impl AsepriteSheet {
+    pub fn tag(&self, name: &str) -> Option<&AsepriteTag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+
+    /// The frames of a tag, in the order they play through one cycle
+    ///
+    /// Ping-pong tags play forwards then backwards, without repeating the frames at either end.
+    pub fn tag_frames(&self, name: &str) -> Option<Vec<&AsepriteFrame>> {
+        let tag = self.tag(name)?;
+        let frames = self.frames.get(tag.from..=tag.to)?;
+        let forward = frames.iter();
+        let backward = frames.iter().rev();
+        let inner = frames.len().saturating_sub(2);
+        let order = match tag.direction {
+            AsepriteDirection::Forward => forward.collect(),
+            AsepriteDirection::Reverse => backward.collect(),
+            AsepriteDirection::PingPong => forward.chain(backward.skip(1).take(inner)).collect(),
+            AsepriteDirection::PingPongReverse => {
+                backward.chain(forward.skip(1).take(inner)).collect()
+            }
+        };
+        Some(order)
+    }
+
+    /// An animation playing a tag, with frame durations converted to ticks
+    ///
+    /// Frames always last at least one tick, so short frames are held longer at low tick rates.
+    pub fn animation(&self, tag: &str, ticks_per_second: f32) -> Option<Animation> {
+        let (frames, durations) = self
+            .tag_frames(tag)?
+            .into_iter()
+            .map(|frame| {
+                let ticks = (frame.duration.as_secs_f32() * ticks_per_second).round() as u32;
+                (frame.texture.clone(), ticks.max(1))
+            })
+            .unzip();
+        Some(Animation::with_durations(frames, durations))
+    }
+}
+
+impl Venus {
+    /// Load a sprite sheet exported by Aseprite, from the path of its JSON data file
+    ///
+    /// The sheet image is loaded relative to the data file.
+    pub async fn load_aseprite_sheet(&mut self, path: &str) -> Result<AsepriteSheet, Error> {
+        let bytes = self.load_file(path).await?;
+        let data: SheetJson =
+            serde_json::from_slice(&bytes).map_err(|error| sheet_error(path, error))?;
+        let texture = self
+            .load_texture(&resolve_path(path, &data.meta.image))
+            .await?;
+        build_sheet(data, &texture).map_err(|error| sheet_error(path, error))
+    }
+}
+
+#[derive(Deserialize)]
+struct SheetJson {
+    frames: FramesJson,
+    meta: MetaJson,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetaJson {
+    image: String,
+    #[serde(default)]
+    frame_tags: Vec<TagJson>,
+}
+
+#[derive(Deserialize)]
+struct TagJson {
+    name: String,
+    from: usize,
+    to: usize,
+    direction: String,
+}
+
+fn build_sheet(data: SheetJson, texture: &Texture) -> Result<AsepriteSheet, String> {
+    let frames = data
+        .frames
+        .into_frames()?
+        .into_iter()
+        .map(|(_, frame)| {
+            Ok(AsepriteFrame {
+                texture: frame.texture(texture)?,
+                duration: Duration::from_millis(frame.duration),
+                offset: frame.offset(),
+                source_size: frame.source_size(),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    let tags = data
+        .meta
+        .frame_tags
+        .into_iter()
+        .map(|tag| AsepriteTag {
+            name: tag.name,
+            from: tag.from,
+            to: tag.to,
+            direction: match tag.direction.as_str() {
+                "reverse" => AsepriteDirection::Reverse,
+                "pingpong" => AsepriteDirection::PingPong,
+                "pingpong_reverse" => AsepriteDirection::PingPongReverse,
+                _ => AsepriteDirection::Forward,
+            },
+        })
+        .collect();
+
+    Ok(AsepriteSheet { frames, tags })
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{SheetJson, build_sheet};
+    use crate::{Rect, Texture, texture_atlas::TextureHandle};
+
+    const SHEET: &str = r##"{
+        "frames": {
+            "hero 0.aseprite": {
+                "frame": { "x": 0, "y": 0, "w": 16, "h": 16 }, "rotated": false, "trimmed": false,
+                "spriteSourceSize": { "x": 0, "y": 0, "w": 16, "h": 16 },
+                "sourceSize": { "w": 16, "h": 16 }, "duration": 100
+            },
+            "hero 1.aseprite": {
+                "frame": { "x": 16, "y": 0, "w": 16, "h": 16 }, "rotated": false, "trimmed": false,
+                "spriteSourceSize": { "x": 0, "y": 0, "w": 16, "h": 16 },
+                "sourceSize": { "w": 16, "h": 16 }, "duration": 250
+            },
+            "hero 2.aseprite": {
+                "frame": { "x": 32, "y": 0, "w": 16, "h": 16 }, "rotated": false, "trimmed": false,
+                "spriteSourceSize": { "x": 0, "y": 0, "w": 16, "h": 16 },
+                "sourceSize": { "w": 16, "h": 16 }, "duration": 100
+            }
+        },
+        "meta": {
+            "app": "https://www.aseprite.org/", "image": "hero.png",
+            "size": { "w": 48, "h": 16 },
+            "frameTags": [
+                { "name": "walk", "from": 0, "to": 2, "direction": "pingpong", "color": "#000000ff" }
+            ]
+        }
+    }"##;
+
+    #[test]
+    fn parse() {
+        let texture = Texture {
+            handle: TextureHandle::mock(),
+            uv: Rect::new(0.0, 0.0, 1.0, 1.0),
+            width: 48,
+            height: 16,
+        };
+        let data: SheetJson = serde_json::from_str(SHEET).unwrap();
+        let sheet = build_sheet(data, &texture).unwrap();
+        assert_eq!(sheet.frames.len(), 3);
+        assert_eq!(sheet.frames[1].duration, Duration::from_millis(250));
+        assert_eq!(sheet.frames[2].texture.uv.x, 32.0 / 48.0);
+
+        let walk: Vec<_> = sheet
+            .tag_frames("walk")
+            .unwrap()
+            .into_iter()
+            .map(|frame| frame.duration.as_millis())
+            .collect();
+        assert_eq!(walk, [100, 250, 100, 250]);
+    }
+}
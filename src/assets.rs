@@ -0,0 +1,197 @@
+use rustc_hash::FxHashMap as HashMap;
+use slotmap::{Key, SlotMap};
+
+#[cfg(feature = "audio")]
+use crate::Audio;
+#[cfg(feature = "text")]
+use crate::FontHandle;
+use crate::Texture;
+
+slotmap::new_key_type! {
+    pub struct TextureAsset;
+    #[cfg(feature = "audio")]
+    pub struct AudioAsset;
+    #[cfg(feature = "text")]
+    pub struct FontAsset;
+}
+
+struct Entry<T> {
+    value: T,
+    path: String,
+    references: u32,
+}
+
+// Assets of one type, deduplicated by path and reference counted
+pub(crate) struct AssetStore<K: Key, T> {
+    entries: SlotMap<K, Entry<T>>,
+    by_path: HashMap<String, K>,
+}
+
+impl<K: Key, T> AssetStore<K, T> {
+    fn new() -> AssetStore<K, T> {
+        AssetStore {
+            entries: SlotMap::with_key(),
+            by_path: HashMap::default(),
+        }
+    }
+
+    pub(crate) fn get(&self, key: K) -> Option<&T> {
+        self.entries.get(key).map(|entry| &entry.value)
+    }
+
+    pub(crate) fn path(&self, key: K) -> Option<&str> {
+        self.entries.get(key).map(|entry| entry.path.as_str())
+    }
+
+    pub(crate) fn find(&self, path: &str) -> Option<K> {
+        self.by_path.get(path).copied()
+    }
+
+    pub(crate) fn replace(&mut self, key: K, value: T) {
+        if let Some(entry) = self.entries.get_mut(key) {
+            entry.value = value;
+        }
+    }
+
+    /// If the path is already loaded, take another reference to it
+    pub(crate) fn retain_path(&mut self, path: &str) -> Option<K> {
+        let key = *self.by_path.get(path)?;
+        self.entries[key].references += 1;
+        Some(key)
+    }
+
+    pub(crate) fn insert(&mut self, path: &str, value: T) -> K {
+        let key = self.entries.insert(Entry {
+            value,
+            path: path.to_string(),
+            references: 1,
+        });
+        self.by_path.insert(path.to_string(), key);
+        key
+    }
+
+    /// Drop a reference, returning the asset if that was the last one
+    pub(crate) fn release(&mut self, key: K) -> Option<T> {
+        let entry = self.entries.get_mut(key)?;
+        entry.references -= 1;
+        if entry.references > 0 {
+            return None;
+        }
+        let entry = self.entries.remove(key)?;
+        self.by_path.remove(&entry.path);
+        Some(entry.value)
+    }
+}
+
+/// Assets loaded by path through `Venus`, shared between everything that loads the same path
+///
+/// Each `load_*_asset` call takes a reference to the asset, and each `unload_*` call gives one up.
+/// Once every reference is gone the asset is freed, and loading the path again reads it from
+/// scratch. The atlas space of a freed texture is reused by textures loaded later.
+pub struct Assets {
+    pub(crate) textures: AssetStore<TextureAsset, Texture>,
+    #[cfg(feature = "audio")]
+    pub(crate) audio: AssetStore<AudioAsset, Audio>,
+    #[cfg(feature = "text")]
+    pub(crate) fonts: AssetStore<FontAsset, FontHandle>,
+    names: HashMap<String, Named>,
+}
+
+// What a name refers to, see `Venus::load_manifest`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Named {
+    Texture(TextureAsset),
+    #[cfg(feature = "audio")]
+    Audio(AudioAsset),
+    #[cfg(feature = "text")]
+    Font(FontAsset),
+}
+
+impl Assets {
+    pub(crate) fn new() -> Assets {
+        Assets {
+            textures: AssetStore::new(),
+            #[cfg(feature = "audio")]
+            audio: AssetStore::new(),
+            #[cfg(feature = "text")]
+            fonts: AssetStore::new(),
+            names: HashMap::default(),
+        }
+    }
+
+    pub(crate) fn set_name(&mut self, name: &str, asset: Named) {
+        self.names.insert(name.to_string(), asset);
+    }
+
+    /// The texture given a name, e.g. in a manifest, if it's still loaded
+    pub fn named_texture(&self, name: &str) -> Option<&Texture> {
+        match self.names.get(name)? {
+            Named::Texture(handle) => self.texture(*handle),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn named_audio(&self, name: &str) -> Option<&Audio> {
+        match self.names.get(name)? {
+            Named::Audio(handle) => self.audio(*handle),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "text")]
+    pub fn named_font(&self, name: &str) -> Option<FontHandle> {
+        match self.names.get(name)? {
+            Named::Font(handle) => self.font(*handle),
+            _ => None,
+        }
+    }
+
+    pub fn texture(&self, handle: TextureAsset) -> Option<&Texture> {
+        self.textures.get(handle)
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn audio(&self, handle: AudioAsset) -> Option<&Audio> {
+        self.audio.get(handle)
+    }
+
+    #[cfg(feature = "text")]
+    pub fn font(&self, handle: FontAsset) -> Option<FontHandle> {
+        self.fonts.get(handle).copied()
+    }
+
+    pub fn texture_path(&self, handle: TextureAsset) -> Option<&str> {
+        self.textures.path(handle)
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn audio_path(&self, handle: AudioAsset) -> Option<&str> {
+        self.audio.path(handle)
+    }
+
+    #[cfg(feature = "text")]
+    pub fn font_path(&self, handle: FontAsset) -> Option<&str> {
+        self.fonts.path(handle)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AssetStore, TextureAsset};
+
+    #[test]
+    fn reference_counting() {
+        let mut store: AssetStore<TextureAsset, u32> = AssetStore::new();
+        assert!(store.retain_path("a.ogg").is_none());
+        let key = store.insert("a.ogg", 5);
+        assert_eq!(store.retain_path("a.ogg"), Some(key));
+        assert_eq!(store.release(key), None);
+        assert_eq!(store.get(key), Some(&5));
+        assert_eq!(store.release(key), Some(5));
+        assert_eq!(store.get(key), None);
+        assert!(store.retain_path("a.ogg").is_none());
+        assert_eq!(store.release(key), None);
+    }
+}
@@ -1,29 +1,44 @@
-use std::{io::Cursor, sync::Arc};
+use std::{io::Cursor, sync::Arc, time::Duration};
 
 use rodio::{Decoder, OutputStream, OutputStreamBuilder, Sink, Source};
 use slotmap::SlotMap;
 
+use crate::{Error, profiling::profile_scope};
+
 slotmap::new_key_type! {
     pub struct PlayingAudio;
 }
 
 pub struct AudioPlayer {
-    output: OutputStream,
+    // None for a player that discards everything, when running headless
+    output: Option<OutputStream>,
     slotmap: SlotMap<PlayingAudio, Sink>,
 }
 
 impl AudioPlayer {
-    pub fn new() -> AudioPlayer {
-        let output = OutputStreamBuilder::open_default_stream().expect("open default audio stream");
+    pub fn new() -> Result<AudioPlayer, Error> {
+        let output = OutputStreamBuilder::open_default_stream()
+            .map_err(|error| Error::AudioDeviceError(Box::new(error)))?;
 
+        Ok(AudioPlayer {
+            output: Some(output),
+            slotmap: SlotMap::with_key(),
+        })
+    }
+
+    pub fn null() -> AudioPlayer {
         AudioPlayer {
-            output,
+            output: None,
             slotmap: SlotMap::with_key(),
         }
     }
 
     pub fn start(&mut self, source: impl Source + Send + 'static) -> PlayingAudio {
-        let sink = Sink::connect_new(self.output.mixer());
+        profile_scope!("audio start");
+        let Some(output) = &self.output else {
+            return PlayingAudio::default();
+        };
+        let sink = Sink::connect_new(output.mixer());
         sink.append(source);
         self.slotmap.insert(sink)
     }
@@ -49,26 +64,245 @@ impl AudioPlayer {
         sink.stop();
     }
 
+    /// Queue a source to play after everything else on a sink, without a gap
+    pub fn append(&self, audio: PlayingAudio, source: impl Source + Send + 'static) {
+        if let Some(sink) = self.slotmap.get(audio) {
+            sink.append(source);
+        }
+    }
+
+    pub fn set_volume(&self, audio: PlayingAudio, volume: f32) {
+        if let Some(sink) = self.slotmap.get(audio) {
+            sink.set_volume(volume);
+        }
+    }
+
+    /// How far through its current source a sink is
+    pub fn position(&self, audio: PlayingAudio) -> Duration {
+        self.slotmap
+            .get(audio)
+            .map_or(Duration::ZERO, |sink| sink.get_pos())
+    }
+
+    /// Jump to a point in the source a sink is playing, if its format allows seeking
+    pub fn seek(&self, audio: PlayingAudio, position: Duration) {
+        if let Some(sink) = self.slotmap.get(audio)
+            && let Err(error) = sink.try_seek(position)
+        {
+            log::warn!("Couldn't seek audio: {error}");
+        }
+    }
+
+    /// How many sources a sink has left, including the one playing
+    pub fn queued(&self, audio: PlayingAudio) -> usize {
+        self.slotmap.get(audio).map_or(0, |sink| sink.len())
+    }
+
+    pub fn is_finished(&self, audio: PlayingAudio) -> bool {
+        self.slotmap.get(audio).is_none_or(|sink| sink.empty())
+    }
+
+    /// How many sounds are playing or paused
+    pub fn playing(&self) -> usize {
+        self.slotmap.len()
+    }
+
     pub fn gc(&mut self) {
+        profile_scope!("audio cleanup");
         self.slotmap.retain(|_, sink| !sink.empty());
     }
 }
 
 #[derive(Clone)]
 pub struct Audio {
-    contents: Arc<[u8]>,
+    contents: Contents,
+}
+
+#[derive(Clone)]
+enum Contents {
+    Encoded(Arc<[u8]>),
+    Samples {
+        samples: Arc<[f32]>,
+        sample_rate: u32,
+        channels: u16,
+    },
 }
 
 impl Audio {
     pub fn new(contents: Arc<[u8]>) -> Result<Audio, rodio::decoder::DecoderError> {
-        let audio = Audio { contents };
+        let audio = Audio {
+            contents: Contents::Encoded(contents),
+        };
         audio.source()?;
         Ok(audio)
     }
 
-    pub(crate) fn source(
-        &self,
-    ) -> Result<Decoder<Cursor<Arc<[u8]>>>, rodio::decoder::DecoderError> {
-        Decoder::new(Cursor::new(self.contents.clone()))
+    /// Audio from samples between -1 and 1, with the channels of each frame interleaved
+    ///
+    /// The sample rate and channel count are raised to at least 1.
+    pub fn from_samples(samples: Arc<[f32]>, sample_rate: u32, channels: u16) -> Audio {
+        Audio {
+            contents: Contents::Samples {
+                samples,
+                sample_rate: sample_rate.max(1),
+                channels: channels.max(1),
+            },
+        }
+    }
+
+    pub(crate) fn source(&self) -> Result<AudioSource, rodio::decoder::DecoderError> {
+        Ok(match &self.contents {
+            Contents::Encoded(contents) => {
+                AudioSource::Decoded(Decoder::new(Cursor::new(contents.clone()))?)
+            }
+            Contents::Samples {
+                samples,
+                sample_rate,
+                channels,
+            } => AudioSource::Samples {
+                samples: samples.clone(),
+                position: 0,
+                sample_rate: *sample_rate,
+                channels: *channels,
+            },
+        })
+    }
+}
+
+/// One play through of an `Audio`
+pub(crate) enum AudioSource {
+    Decoded(Decoder<Cursor<Arc<[u8]>>>),
+    Samples {
+        samples: Arc<[f32]>,
+        position: usize,
+        sample_rate: u32,
+        channels: u16,
+    },
+}
+
+impl Iterator for AudioSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        match self {
+            AudioSource::Decoded(decoder) => decoder.next(),
+            AudioSource::Samples {
+                samples, position, ..
+            } => {
+                let sample = samples.get(*position).copied();
+                *position += 1;
+                sample
+            }
+        }
+    }
+}
+
+impl Source for AudioSource {
+    fn current_span_len(&self) -> Option<usize> {
+        match self {
+            AudioSource::Decoded(decoder) => decoder.current_span_len(),
+            AudioSource::Samples {
+                samples, position, ..
+            } => Some(samples.len().saturating_sub(*position)),
+        }
+    }
+
+    fn channels(&self) -> rodio::ChannelCount {
+        match self {
+            AudioSource::Decoded(decoder) => decoder.channels(),
+            AudioSource::Samples { channels, .. } => *channels,
+        }
+    }
+
+    fn sample_rate(&self) -> rodio::SampleRate {
+        match self {
+            AudioSource::Decoded(decoder) => decoder.sample_rate(),
+            AudioSource::Samples { sample_rate, .. } => *sample_rate,
+        }
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        match self {
+            AudioSource::Decoded(decoder) => decoder.total_duration(),
+            AudioSource::Samples {
+                samples,
+                sample_rate,
+                channels,
+                ..
+            } => {
+                let frames = samples.len() / *channels as usize;
+                Some(Duration::from_secs_f64(frames as f64 / *sample_rate as f64))
+            }
+        }
+    }
+}
+
+/// Samples made as they're played, see `Venus::play_generated`
+pub(crate) struct Generated<F> {
+    generate: F,
+    sample_rate: u32,
+    channels: u16,
+}
+
+impl<F: FnMut() -> Option<f32>> Generated<F> {
+    pub fn new(sample_rate: u32, channels: u16, generate: F) -> Generated<F> {
+        Generated {
+            generate,
+            sample_rate: sample_rate.max(1),
+            channels: channels.max(1),
+        }
+    }
+}
+
+impl<F: FnMut() -> Option<f32>> Iterator for Generated<F> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        (self.generate)()
+    }
+}
+
+impl<F: FnMut() -> Option<f32>> Source for Generated<F> {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> rodio::ChannelCount {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> rodio::SampleRate {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rodio::Source;
+
+    use super::{Audio, Generated};
+
+    #[test]
+    fn samples() {
+        let samples: Vec<f32> = (0..8).map(|index| index as f32 / 8.0).collect();
+        let audio = Audio::from_samples(samples.clone().into(), 4, 2);
+        let source = audio.source().unwrap();
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.sample_rate(), 4);
+        assert_eq!(source.total_duration().unwrap().as_secs_f32(), 1.0);
+        assert_eq!(source.collect::<Vec<_>>(), samples);
+        // Each play starts from the beginning
+        assert_eq!(audio.source().unwrap().count(), 8);
+
+        let mut left = 3;
+        let generated = Generated::new(44100, 1, move || {
+            left -= 1;
+            (left >= 0).then_some(0.5)
+        });
+        assert_eq!(generated.collect::<Vec<_>>(), [0.5; 3]);
     }
 }
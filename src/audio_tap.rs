@@ -0,0 +1,224 @@
+use std::{
+    collections::VecDeque,
+    f32::consts::TAU,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rodio::Source;
+
+// How many samples the waveform and spectrum cover, a power of two for the FFT
+const WINDOW: usize = 1024;
+// Samples are handed to the tap in batches, so the audio thread isn't locking for each one
+const BATCH: usize = 128;
+
+struct Shared {
+    samples: VecDeque<f32>,
+    sample_rate: u32,
+}
+
+/// The most recent samples of a playing sound, for visualizers and music-reactive effects
+///
+/// Create one with `AudioTap::wrap` or `Venus::play_audio_tapped`. It can be read from any
+/// time, usually once a frame, and always reflects what was most recently played. Channels are
+/// mixed down to mono.
+#[derive(Clone)]
+pub struct AudioTap {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl AudioTap {
+    /// Wrap a source so its samples show up in the returned tap as it plays
+    pub fn wrap<S: Source>(source: S) -> (Tapped<S>, AudioTap) {
+        let shared = Arc::new(Mutex::new(Shared {
+            samples: VecDeque::from(vec![0.0; WINDOW]),
+            sample_rate: u32::from(source.sample_rate()),
+        }));
+        let tapped = Tapped {
+            channels: u16::from(source.channels()).max(1),
+            inner: source,
+            shared: shared.clone(),
+            channel: 0,
+            mix: 0.0,
+            pending: Vec::with_capacity(BATCH),
+        };
+        (tapped, AudioTap { shared })
+    }
+
+    /// The last 1024 samples, oldest first, with silence before the sound started
+    pub fn waveform(&self) -> Vec<f32> {
+        self.shared
+            .lock()
+            .unwrap()
+            .samples
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// How loud each frequency is in the waveform, from the lowest up to half the sample rate
+    ///
+    /// There are 512 bins, see `bin_frequency` for which frequency each one is.
+    pub fn spectrum(&self) -> Vec<f32> {
+        spectrum(&self.waveform())
+    }
+
+    /// The frequency in the middle of a spectrum bin, in hertz
+    pub fn bin_frequency(&self, bin: usize) -> f32 {
+        bin as f32 * self.shared.lock().unwrap().sample_rate as f32 / WINDOW as f32
+    }
+
+    /// The root mean square of the waveform, a rough measure of how loud it is right now
+    pub fn level(&self) -> f32 {
+        let shared = self.shared.lock().unwrap();
+        let sum: f32 = shared.samples.iter().map(|sample| sample * sample).sum();
+        (sum / WINDOW as f32).sqrt()
+    }
+}
+
+/// A source which passes its samples along to an `AudioTap`, see `AudioTap::wrap`
+pub struct Tapped<S> {
+    inner: S,
+    shared: Arc<Mutex<Shared>>,
+    channels: u16,
+    // The channel of the next sample and the sum of this frame's samples so far
+    channel: u16,
+    mix: f32,
+    pending: Vec<f32>,
+}
+
+impl<S> Tapped<S> {
+    fn flush(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        for sample in self.pending.drain(..) {
+            shared.samples.pop_front();
+            shared.samples.push_back(sample);
+        }
+    }
+}
+
+impl<S: Source> Iterator for Tapped<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let Some(sample) = self.inner.next() else {
+            self.flush();
+            return None;
+        };
+        self.mix += sample;
+        self.channel += 1;
+        if self.channel == self.channels {
+            self.pending.push(self.mix / self.channels as f32);
+            self.channel = 0;
+            self.mix = 0.0;
+            if self.pending.len() == BATCH {
+                self.flush();
+            }
+        }
+        Some(sample)
+    }
+}
+
+impl<S: Source> Source for Tapped<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.inner.current_span_len()
+    }
+
+    fn channels(&self) -> rodio::ChannelCount {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> rodio::SampleRate {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+// Magnitudes of the first half of a Hann-windowed FFT, scaled so a full-scale sine peaks near 1
+fn spectrum(samples: &[f32]) -> Vec<f32> {
+    let len = samples.len();
+    let mut real: Vec<f32> = samples
+        .iter()
+        .enumerate()
+        .map(|(index, sample)| sample * (0.5 - 0.5 * (TAU * index as f32 / len as f32).cos()))
+        .collect();
+    let mut imaginary = vec![0.0; len];
+    fft(&mut real, &mut imaginary);
+    // The window halves the average amplitude
+    let scale = 4.0 / len as f32;
+    (0..len / 2)
+        .map(|bin| (real[bin] * real[bin] + imaginary[bin] * imaginary[bin]).sqrt() * scale)
+        .collect()
+}
+
+// An in-place radix-2 FFT, for lengths that are powers of two
+fn fft(real: &mut [f32], imaginary: &mut [f32]) {
+    let len = real.len();
+    debug_assert!(len.is_power_of_two());
+    let bits = len.trailing_zeros();
+    for index in 0..len {
+        let reversed = index.reverse_bits() >> (usize::BITS - bits);
+        if index < reversed {
+            real.swap(index, reversed);
+            imaginary.swap(index, reversed);
+        }
+    }
+    let mut size = 2;
+    while size <= len {
+        let angle = -TAU / size as f32;
+        for start in (0..len).step_by(size) {
+            for offset in 0..size / 2 {
+                let (sin, cos) = (angle * offset as f32).sin_cos();
+                let (even, odd) = (start + offset, start + offset + size / 2);
+                let odd_real = real[odd] * cos - imaginary[odd] * sin;
+                let odd_imaginary = real[odd] * sin + imaginary[odd] * cos;
+                real[odd] = real[even] - odd_real;
+                imaginary[odd] = imaginary[even] - odd_imaginary;
+                real[even] += odd_real;
+                imaginary[even] += odd_imaginary;
+            }
+        }
+        size *= 2;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::TAU;
+
+    use rodio::buffer::SamplesBuffer;
+
+    use super::{AudioTap, WINDOW};
+
+    #[test]
+    fn spectrum_of_a_sine() {
+        // Exactly 64 cycles across the window, in both channels of a stereo source
+        let sine: Vec<f32> = (0..WINDOW * 2)
+            .flat_map(|index| {
+                let sample = (TAU * 64.0 * index as f32 / WINDOW as f32).sin();
+                [sample, sample]
+            })
+            .collect();
+        let (tapped, tap) = AudioTap::wrap(SamplesBuffer::new(2, 44100, sine.clone()));
+        assert_eq!(tap.level(), 0.0);
+        // The sound plays unchanged
+        assert_eq!(tapped.collect::<Vec<_>>(), sine);
+
+        let waveform = tap.waveform();
+        assert_eq!(waveform.len(), WINDOW);
+        assert_eq!(waveform[WINDOW - 1], sine[sine.len() - 1]);
+        let spectrum = tap.spectrum();
+        assert_eq!(spectrum.len(), WINDOW / 2);
+        let peak = (0..spectrum.len())
+            .max_by(|a, b| spectrum[*a].total_cmp(&spectrum[*b]))
+            .unwrap();
+        assert_eq!(peak, 64);
+        assert!((spectrum[peak] - 1.0).abs() < 0.01);
+        assert!(spectrum[10] < 0.01);
+        assert_eq!(tap.bin_frequency(64), 64.0 * 44100.0 / WINDOW as f32);
+        assert!((tap.level() - 0.5f32.sqrt()).abs() < 0.01);
+    }
+}
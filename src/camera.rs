@@ -0,0 +1,330 @@
+use glam::{Mat3, Vec2};
+
+use crate::{
+    noise::perlin_1d,
+    shape::{Rect, orthographic_projection},
+    transform::Transform,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Camera {
+    /// The point in the world that appears in the middle of the screen
+    pub center: Vec2,
+    /// How much of the world is visible at a zoom of 1
+    pub size: Vec2,
+    /// Scale factor for the view: 2.0 shows half as much of the world, twice as large
+    pub zoom: f32,
+    /// Clockwise rotation of the view, in radians
+    pub rotation: f32,
+    /// If set, the view is kept inside this region of the world
+    ///
+    /// When the region is smaller than the view along an axis, the view is centered on it instead
+    pub bounds: Option<Rect>,
+    shake: Option<Shake>,
+    follow: Option<Follow>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Follow {
+    target: Vec2,
+    smoothing: f32,
+    deadzone: Rect,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Shake {
+    amplitude: f32,
+    frequency: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+impl Camera {
+    pub fn new(center: impl Into<Vec2>, size: impl Into<Vec2>) -> Camera {
+        Camera {
+            center: center.into(),
+            size: size.into(),
+            zoom: 1.0,
+            rotation: 0.0,
+            bounds: None,
+            shake: None,
+            follow: None,
+        }
+    }
+
+    /// A camera which shows exactly the given region of the world
+    pub fn from_rect(rect: Rect) -> Camera {
+        Camera::new(rect.position() + rect.size() / 2.0, rect.size())
+    }
+
+    /// The size of the region of the world currently shown, taking zoom into account
+    pub fn visible_size(&self) -> Vec2 {
+        self.size / self.zoom
+    }
+
+    /// The smallest rectangle containing all of the world that's currently visible
+    pub fn visible_bounds(&self) -> Rect {
+        let extent = self.visible_extent();
+        let top_left = self.view_center() - extent;
+        Rect::new(top_left.x, top_left.y, extent.x * 2.0, extent.y * 2.0)
+    }
+
+    // Half the size of the axis-aligned box around the (possibly rotated) view
+    fn visible_extent(&self) -> Vec2 {
+        let half_size = self.visible_size() / 2.0;
+        let rotation = Vec2::from_angle(self.rotation);
+        let corner_a = rotation.rotate(half_size).abs();
+        let corner_b = rotation.rotate(Vec2::new(half_size.x, -half_size.y)).abs();
+        corner_a.max(corner_b)
+    }
+
+    // Where the view is actually centered, after effects and bounds
+    fn view_center(&self) -> Vec2 {
+        self.clamp_to_bounds(self.center + self.shake_offset())
+    }
+
+    fn clamp_to_bounds(&self, center: Vec2) -> Vec2 {
+        let Some(bounds) = &self.bounds else {
+            return center;
+        };
+        let extent = self.visible_extent();
+        let min = bounds.position() + extent;
+        let max = bounds.position() + bounds.size() - extent;
+        let bounds_center = bounds.position() + bounds.size() / 2.0;
+        let clamp_axis = |value: f32, min: f32, max: f32, middle: f32| {
+            if min > max {
+                middle
+            } else {
+                value.clamp(min, max)
+            }
+        };
+        Vec2::new(
+            clamp_axis(center.x, min.x, max.x, bounds_center.x),
+            clamp_axis(center.y, min.y, max.y, bounds_center.y),
+        )
+    }
+
+    /// A copy of this camera for a layer that scrolls at a different speed than the world
+    ///
+    /// A `scroll_factor` of 1.0 moves along with the world, 0.0 stays fixed on the screen, and
+    /// values in between suit distant backgrounds
+    pub fn parallax(&self, scroll_factor: Vec2) -> Camera {
+        Camera {
+            center: self.view_center() * scroll_factor,
+            bounds: None,
+            shake: None,
+            ..self.clone()
+        }
+    }
+
+    /// Start shaking the camera, replacing any shake already in progress
+    ///
+    /// The view is pushed up to `amplitude` world units away from the center, wandering
+    /// `frequency` times per second, and the effect fades out over `duration` seconds. Call
+    /// `update` every frame to advance it.
+    pub fn shake(&mut self, amplitude: f32, frequency: f32, duration: f32) {
+        self.shake = Some(Shake {
+            amplitude,
+            frequency,
+            duration,
+            elapsed: 0.0,
+        });
+    }
+
+    pub fn is_shaking(&self) -> bool {
+        self.shake.is_some()
+    }
+
+    /// Ease the camera towards `target` whenever it leaves the deadzone
+    ///
+    /// The `deadzone` is relative to the center of the camera: a target anywhere inside it doesn't
+    /// move the view. `smoothing` is the fraction of the remaining distance still left to travel
+    /// after one second, so 0.0 snaps straight to the target and values closer to 1.0 lag further
+    /// behind. Call this whenever the target moves, and `update` every frame. A deadzone with a
+    /// negative width or height covers the same area as its positive counterpart.
+    pub fn follow(&mut self, target: impl Into<Vec2>, smoothing: f32, deadzone: Rect) {
+        let corner = deadzone.position() + deadzone.size();
+        let min = deadzone.position().min(corner);
+        let size = (corner - deadzone.position()).abs();
+        let deadzone = Rect::new(min.x, min.y, size.x, size.y);
+        self.follow = Some(Follow {
+            target: target.into(),
+            smoothing,
+            deadzone,
+        });
+    }
+
+    pub fn stop_following(&mut self) {
+        self.follow = None;
+    }
+
+    /// Advance time-based effects on the camera
+    pub fn update(&mut self, delta_seconds: f32) {
+        if let Some(follow) = &self.follow {
+            let min = follow.deadzone.position();
+            let max = min + follow.deadzone.size();
+            let offset = follow.target - self.center;
+            let correction = offset - offset.clamp(min, max);
+            let t = 1.0 - follow.smoothing.powf(delta_seconds);
+            // Clamp the followed position so the camera doesn't lag behind a target which
+            // wandered past the edge of the bounds and then came back
+            self.center = self.clamp_to_bounds(self.center + correction * t);
+        }
+        if let Some(shake) = &mut self.shake {
+            shake.elapsed += delta_seconds;
+            if shake.elapsed >= shake.duration {
+                self.shake = None;
+            }
+        }
+    }
+
+    /// How far the view is currently displaced from `center` by shaking
+    pub fn shake_offset(&self) -> Vec2 {
+        let Some(shake) = self.shake.as_ref().filter(|shake| shake.duration > 0.0) else {
+            return Vec2::ZERO;
+        };
+        let remaining = 1.0 - shake.elapsed / shake.duration;
+        // Squaring the falloff makes the end of the shake settle instead of stopping abruptly
+        let strength = shake.amplitude * remaining * remaining;
+        let t = shake.elapsed * shake.frequency;
+        Vec2::new(perlin_1d(t, 0), perlin_1d(t, 1)) * strength
+    }
+
+    /// The transform from world coordinates into the unrotated view, centered where the camera is
+    pub fn view_transform(&self) -> Transform {
+        Transform::rotate_around(self.view_center(), -self.rotation)
+    }
+
+    /// The matrix that maps world coordinates to normalized device coordinates
+    pub fn projection(&self) -> Mat3 {
+        let center = self.view_center();
+        let size = self.visible_size();
+        let top_left = center - size / 2.0;
+        orthographic_projection(top_left.x, top_left.y, size.x, size.y)
+            * self.view_transform().to_matrix()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+    use glam::Vec2;
+
+    use super::Camera;
+    use crate::shape::{Rect, orthographic_projection};
+
+    #[test]
+    fn matches_orthographic() {
+        let camera = Camera::from_rect(Rect::new(20.0, 20.0, 100.0, 50.0));
+        let projection = orthographic_projection(20.0, 20.0, 100.0, 50.0);
+        for point in [
+            Vec2::new(20.0, 20.0),
+            Vec2::new(70.0, 45.0),
+            Vec2::new(120.0, 70.0),
+        ] {
+            let expected = projection.transform_point2(point);
+            let actual = camera.projection().transform_point2(point);
+            assert_abs_diff_eq!(expected.x, actual.x, epsilon = 1e-5);
+            assert_abs_diff_eq!(expected.y, actual.y, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn zoom_and_rotation() {
+        let mut camera = Camera::new(Vec2::ZERO, Vec2::new(100.0, 100.0));
+        camera.zoom = 2.0;
+        let edge = camera.projection().transform_point2(Vec2::new(25.0, 0.0));
+        assert_abs_diff_eq!(edge.x, 1.0, epsilon = 1e-5);
+
+        camera.zoom = 1.0;
+        camera.rotation = std::f32::consts::FRAC_PI_2;
+        // Turning the view clockwise moves things to the right of the center up the screen
+        let rotated = camera.projection().transform_point2(Vec2::new(50.0, 0.0));
+        assert_abs_diff_eq!(rotated.x, 0.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(rotated.y, 1.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn shake_fades_out() {
+        let mut camera = Camera::new(Vec2::ZERO, Vec2::new(100.0, 100.0));
+        camera.shake(10.0, 15.0, 0.5);
+        let mut largest = 0.0f32;
+        for _ in 0..29 {
+            camera.update(1.0 / 60.0);
+            let offset = camera.shake_offset();
+            assert!(offset.x.abs() <= 10.0 && offset.y.abs() <= 10.0);
+            largest = largest.max(offset.length());
+        }
+        assert!(largest > 0.0);
+        camera.update(0.1);
+        assert!(!camera.is_shaking());
+        assert_eq!(camera.shake_offset(), Vec2::ZERO);
+
+        camera.shake(10.0, 15.0, 0.0);
+        assert_eq!(camera.shake_offset(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn follow_deadzone() {
+        let mut camera = Camera::new(Vec2::ZERO, Vec2::new(100.0, 100.0));
+        let deadzone = Rect::new(-10.0, -10.0, 20.0, 20.0);
+        camera.follow(Vec2::new(5.0, -5.0), 0.0, deadzone);
+        camera.update(1.0 / 60.0);
+        assert_eq!(camera.center, Vec2::ZERO);
+
+        camera.follow(Vec2::new(30.0, 0.0), 0.0, deadzone);
+        camera.update(1.0 / 60.0);
+        assert_abs_diff_eq!(camera.center.x, 20.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(camera.center.y, 0.0, epsilon = 1e-5);
+
+        camera.follow(Vec2::new(20.0, 50.0), 0.5, deadzone);
+        camera.update(1.0);
+        assert_abs_diff_eq!(camera.center.y, 20.0, epsilon = 1e-5);
+
+        let flipped = Rect::new(10.0, 10.0, -20.0, -20.0);
+        camera.follow(Vec2::new(20.0, 25.0), 0.0, flipped);
+        camera.update(1.0 / 60.0);
+        assert_abs_diff_eq!(camera.center.y, 20.0, epsilon = 1e-5);
+        camera.follow(Vec2::new(20.0, 40.0), 0.0, flipped);
+        camera.update(1.0 / 60.0);
+        assert_abs_diff_eq!(camera.center.y, 30.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn visible_bounds() {
+        let mut camera = Camera::new(Vec2::new(50.0, 50.0), Vec2::new(100.0, 50.0));
+        let bounds = camera.visible_bounds();
+        assert_abs_diff_eq!(bounds.x, 0.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(bounds.y, 25.0, epsilon = 1e-4);
+        camera.rotation = std::f32::consts::FRAC_PI_2;
+        let bounds = camera.visible_bounds();
+        assert_abs_diff_eq!(bounds.width, 50.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(bounds.height, 100.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn parallax() {
+        let camera = Camera::new(Vec2::new(100.0, 40.0), Vec2::new(100.0, 50.0));
+        assert_eq!(
+            camera.parallax(Vec2::new(0.5, 1.0)).center,
+            Vec2::new(50.0, 40.0)
+        );
+        assert_eq!(camera.parallax(Vec2::ZERO).center, Vec2::ZERO);
+    }
+
+    #[test]
+    fn bounds() {
+        let mut camera = Camera::new(Vec2::new(10.0, 10.0), Vec2::new(100.0, 50.0));
+        camera.bounds = Some(Rect::new(0.0, 0.0, 400.0, 40.0));
+        let visible = camera.visible_bounds();
+        // Pushed in from the left edge, and centered vertically because the level is too short
+        assert_abs_diff_eq!(visible.x, 0.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(visible.y, -5.0, epsilon = 1e-4);
+
+        camera.zoom = 2.0;
+        camera.center = Vec2::new(1000.0, 0.0);
+        let visible = camera.visible_bounds();
+        assert_abs_diff_eq!(visible.x + visible.width, 400.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(visible.y, 0.0, epsilon = 1e-4);
+    }
+}
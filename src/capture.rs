@@ -0,0 +1,75 @@
+use std::fmt::{self, Display};
+
+use glam::{Mat3, Vec2};
+
+use crate::{DrawCommand, Rect, Transform, Venus};
+
+/// One step of a captured frame, see `Venus::capture_frame`
+#[derive(Clone, Debug, PartialEq)]
+pub enum CapturedCommand {
+    /// Something drawn, in world coordinates after any pushed transforms
+    Draw(DrawCommand),
+    /// A run of text, drawn as the quads that follow it
+    Text {
+        text: String,
+        position: Vec2,
+        size: u32,
+    },
+    SetTransform(Transform),
+    SetProjection(Mat3),
+    /// Drawing was restricted to a region of the window, in physical pixels
+    SetViewport(Rect),
+    /// An atlas page the shader can't always sample was bound, which ends the batch before it
+    BindPage(u32),
+    /// Everything since the last flush was sent to the GPU as one batch
+    Flush {
+        vertices: u32,
+        indices: usize,
+    },
+}
+
+/// Everything submitted for drawing during a frame, in order
+///
+/// Printing it lists each command along with the batch it was part of.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameCapture {
+    pub commands: Vec<CapturedCommand>,
+}
+
+impl FrameCapture {
+    /// How many batches were sent to the GPU, which is always 0 when running headless
+    pub fn batches(&self) -> usize {
+        self.commands
+            .iter()
+            .filter(|command| matches!(command, CapturedCommand::Flush { .. }))
+            .count()
+    }
+}
+
+impl Display for FrameCapture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut batch = 0;
+        for command in &self.commands {
+            writeln!(f, "batch {batch:<4} {command:?}")?;
+            if let CapturedCommand::Flush { .. } = command {
+                batch += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Venus {
+    /// Record everything drawn from now until the end of the frame, for finding out why
+    /// something doesn't show up
+    ///
+    /// Once the frame ends, the capture is available from `take_frame_capture`.
+    pub fn capture_frame(&mut self) {
+        self.gfx.start_capture();
+    }
+
+    /// The most recently finished capture, see `capture_frame`
+    pub fn take_frame_capture(&mut self) -> Option<FrameCapture> {
+        self.gfx.take_capture()
+    }
+}
@@ -0,0 +1,179 @@
+use glam::Vec2;
+
+use crate::shape::Rect;
+
+/// An obstacle hit during `move_and_collide`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collision {
+    /// The index of the obstacle in the slice passed to `move_and_collide`
+    pub index: usize,
+    /// The direction the obstacle pushed back in, e.g. `(0, -1)` when landing on a floor
+    pub normal: Vec2,
+    /// How far through the movement the hit happened, from 0 to 1
+    pub time: f32,
+}
+
+// Enough to slide into a corner and stop, without looping forever on degenerate input
+const MAX_ITERATIONS: usize = 4;
+
+/// Move `rect` by `velocity`, stopping at and sliding along any of `obstacles` in the way
+///
+/// The whole path is checked rather than just the destination, so fast-moving objects can't
+/// tunnel through thin walls. Obstacles the rect already overlaps at the start are ignored so it
+/// can escape them. Returns the new position of the rect and every obstacle hit on the way.
+pub fn move_and_collide(rect: Rect, velocity: Vec2, obstacles: &[Rect]) -> (Vec2, Vec<Collision>) {
+    let mut position = rect.position();
+    let mut remaining = velocity;
+    let mut elapsed = 0.0;
+    let mut collisions = Vec::new();
+    for _ in 0..MAX_ITERATIONS {
+        if remaining == Vec2::ZERO {
+            break;
+        }
+        let moving = Rect {
+            x: position.x,
+            y: position.y,
+            ..rect
+        };
+        let earliest = obstacles
+            .iter()
+            .enumerate()
+            .filter_map(|(index, obstacle)| {
+                sweep(&moving, remaining, obstacle).map(|hit| (index, hit))
+            })
+            // Prefer flat hits over corner hits at the same time, so a rect sliding along a row
+            // of tiles doesn't catch on the corners where they meet
+            .min_by(|(_, a), (_, b)| a.time.total_cmp(&b.time).then(a.corner.cmp(&b.corner)));
+        let Some((index, Hit { time, normal, .. })) = earliest else {
+            position += remaining;
+            break;
+        };
+        position += remaining * time;
+        elapsed += (1.0 - elapsed) * time;
+        collisions.push(Collision {
+            index,
+            normal,
+            time: elapsed,
+        });
+        // Slide along the obstacle with whatever movement is left
+        remaining *= 1.0 - time;
+        remaining -= normal * remaining.dot(normal);
+    }
+
+    (position, collisions)
+}
+
+struct Hit {
+    time: f32,
+    normal: Vec2,
+    corner: bool,
+}
+
+// When `moving` travelling along `velocity` would first touch `target`, as a fraction of the
+// velocity, along with the normal of the side it hits
+fn sweep(moving: &Rect, velocity: Vec2, target: &Rect) -> Option<Hit> {
+    let (entry_x, exit_x) = sweep_axis(moving.x, moving.width, target.x, target.width, velocity.x)?;
+    let (entry_y, exit_y) =
+        sweep_axis(moving.y, moving.height, target.y, target.height, velocity.y)?;
+    let entry = entry_x.max(entry_y);
+    let exit = exit_x.min(exit_y);
+    if entry > exit || entry >= 1.0 || exit <= 0.0 || entry < 0.0 {
+        return None;
+    }
+    // Hitting a corner exactly is ambiguous, so resolve along the main direction of travel.
+    // That way falling onto the corner of a ledge lands on it instead of sliding off the side.
+    let corner = entry_x == entry_y;
+    let hits_x = if corner {
+        velocity.x.abs() > velocity.y.abs()
+    } else {
+        entry_x > entry_y
+    };
+    let normal = if hits_x {
+        Vec2::new(-velocity.x.signum(), 0.0)
+    } else {
+        Vec2::new(0.0, -velocity.y.signum())
+    };
+    Some(Hit {
+        time: entry,
+        normal,
+        corner,
+    })
+}
+
+// The times along one axis that the moving span starts and stops overlapping the target span
+fn sweep_axis(
+    position: f32,
+    size: f32,
+    target_position: f32,
+    target_size: f32,
+    velocity: f32,
+) -> Option<(f32, f32)> {
+    if velocity == 0.0 {
+        let overlapping =
+            position < target_position + target_size && position + size > target_position;
+        return overlapping.then_some((f32::NEG_INFINITY, f32::INFINITY));
+    }
+    let (entry, exit) = if velocity > 0.0 {
+        (
+            target_position - (position + size),
+            target_position + target_size - position,
+        )
+    } else {
+        (
+            target_position + target_size - position,
+            target_position - (position + size),
+        )
+    };
+    Some((entry / velocity, exit / velocity))
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+    use glam::Vec2;
+
+    use super::move_and_collide;
+    use crate::shape::Rect;
+
+    #[test]
+    fn no_tunneling() {
+        let player = Rect::new(0.0, 0.0, 10.0, 10.0);
+        let wall = Rect::new(50.0, -100.0, 1.0, 200.0);
+        let (position, collisions) = move_and_collide(player, Vec2::new(500.0, 0.0), &[wall]);
+        assert_abs_diff_eq!(position.x, 40.0, epsilon = 1e-4);
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].normal, Vec2::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn slide_along_floor() {
+        let player = Rect::new(0.0, 0.0, 10.0, 10.0);
+        // A floor made of separate tiles shouldn't snag on the seams
+        let floor: Vec<Rect> = (0..10)
+            .rev()
+            .map(|i| Rect::new(i as f32 * 10.0, 10.0, 10.0, 10.0))
+            .collect();
+        let (position, collisions) = move_and_collide(player, Vec2::new(30.0, 5.0), &floor);
+        assert_abs_diff_eq!(position.x, 30.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(position.y, 0.0, epsilon = 1e-4);
+        assert!(
+            collisions
+                .iter()
+                .all(|collision| collision.normal == Vec2::new(0.0, -1.0))
+        );
+    }
+
+    #[test]
+    fn corners() {
+        let player = Rect::new(5.0, 0.0, 10.0, 10.0);
+        let ledge = Rect::new(20.0, 20.0, 10.0, 10.0);
+        // Heading exactly at the corner, mostly downwards: land on top
+        let (position, collisions) = move_and_collide(player, Vec2::new(10.0, 20.0), &[ledge]);
+        assert_eq!(collisions[0].normal, Vec2::new(0.0, -1.0));
+        assert_abs_diff_eq!(position.y, 10.0, epsilon = 1e-4);
+        // Already touching and moving away is not a collision
+        let standing = Rect::new(20.0, 10.0, 10.0, 10.0);
+        let (_, collisions) = move_and_collide(standing, Vec2::new(0.0, -5.0), &[ledge]);
+        assert!(collisions.is_empty());
+    }
+}
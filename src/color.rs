@@ -1,4 +1,9 @@
+use std::ops::{Add, Mul};
+
+use crate::Error;
+
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -32,6 +37,42 @@ impl Color {
         }
     }
 
+    /// Blend towards another color, where a `t` of 0 is this color and 1 is the other
+    ///
+    /// Every channel is blended directly, including alpha. See `lerp_gamma` for fades that keep
+    /// their brightness.
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let mix = |from: f32, to: f32| from + (to - from) * t;
+        Color {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: mix(self.a, other.a),
+        }
+    }
+
+    /// Blend towards another color in linear light, which keeps the midpoint from looking dim
+    ///
+    /// Colors are stored in sRGB, so blending red into green directly passes through a muddy
+    /// dark yellow. This converts to linear light first, like the physical mix of the two.
+    pub fn lerp_gamma(self, other: Color, t: f32) -> Color {
+        let mix = |from: f32, to: f32| {
+            let from = srgb_to_linear(from);
+            linear_to_srgb(from + (srgb_to_linear(to) - from) * t)
+        };
+        Color {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: self.a + (other.a - self.a) * t,
+        }
+    }
+
+    /// The channels in the order shaders expect them: red, green, blue, alpha
+    pub fn to_array(self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
     pub fn from_rgba(red: u8, green: u8, blue: u8, a: f32) -> Color {
         Color {
             r: red as f32 / 255.0,
@@ -41,27 +82,184 @@ impl Color {
         }
     }
 
+    /// Parse a hex color like `#f80`, `#ff8800`, or with alpha, `#ff880080`
+    ///
+    /// Panics if the string is malformed, see `try_from_hex` to handle that instead.
     pub fn from_hex(hex: &str) -> Color {
-        let trimmed_hex = hex.trim_start_matches('#');
-        match trimmed_hex.len() {
-            3 => {
-                let longer_hex: Vec<String> = trimmed_hex
-                    .chars()
-                    .map(|single_char| single_char.to_string().repeat(2))
-                    .collect();
-                Color::from_hex(&longer_hex.concat())
-            }
-            6 => {
-                let red = u8::from_str_radix(&trimmed_hex[0..=1], 16).unwrap();
-                let green = u8::from_str_radix(&trimmed_hex[2..=3], 16).unwrap();
-                let blue = u8::from_str_radix(&trimmed_hex[4..=5], 16).unwrap();
-                Color::from_rgba(red, green, blue, 1.0)
-            }
-            _ => panic!("Malformed hex string"),
+        match Color::try_from_hex(hex) {
+            Ok(color) => color,
+            Err(error) => panic!("{error}"),
+        }
+    }
+
+    /// Parse a hex color with 3, 4, 6, or 8 digits, where the last digits are alpha if present
+    ///
+    /// The leading `#` is optional.
+    pub fn try_from_hex(hex: &str) -> Result<Color, Error> {
+        let digits = hex.trim_start_matches('#');
+        let malformed = || Error::HexColorError(hex.to_string());
+        if !digits.chars().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(malformed());
+        }
+        let width = match digits.len() {
+            3 | 4 => 1,
+            6 | 8 => 2,
+            _ => return Err(malformed()),
+        };
+        let channels: Vec<u8> = (0..digits.len() / width)
+            .map(|index| {
+                let value = u8::from_str_radix(&digits[index * width..][..width], 16).unwrap();
+                // A single digit stands for the digit repeated, so f is ff
+                if width == 1 { value * 17 } else { value }
+            })
+            .collect();
+        let alpha = channels.get(3).map_or(1.0, |alpha| *alpha as f32 / 255.0);
+        Ok(Color::from_rgba(
+            channels[0],
+            channels[1],
+            channels[2],
+            alpha,
+        ))
+    }
+
+    /// Create an opaque color from a hue in degrees, and saturation and value from 0 to 1
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Color {
+        let chroma = value * saturation;
+        from_hue(hue, chroma, value - chroma)
+    }
+
+    /// The hue in degrees from 0 to 360, and the saturation and value from 0 to 1
+    ///
+    /// Grays have no hue, so they report a hue of 0.
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let (hue, max, chroma) = self.hue();
+        let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+        (hue, saturation, max)
+    }
+
+    /// Create an opaque color from a hue in degrees, and saturation and lightness from 0 to 1
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Color {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        from_hue(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// The hue in degrees from 0 to 360, and the saturation and lightness from 0 to 1
+    ///
+    /// Grays have no hue, so they report a hue of 0.
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let (hue, max, chroma) = self.hue();
+        let lightness = max - chroma / 2.0;
+        let saturation = if lightness <= 0.0 || lightness >= 1.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (hue, saturation, lightness)
+    }
+
+    // The hue in degrees, the largest channel, and the chroma
+    fn hue(self) -> (f32, f32, f32) {
+        let Color { r, g, b, .. } = self;
+        let max = r.max(g).max(b);
+        let chroma = max - r.min(g).min(b);
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == r {
+            (g - b) / chroma
+        } else if max == g {
+            (b - r) / chroma + 2.0
+        } else {
+            (r - g) / chroma + 4.0
+        };
+        ((hue * 60.0).rem_euclid(360.0), max, chroma)
+    }
+}
+
+/// Add each channel, including alpha
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, other: Color) -> Color {
+        Color {
+            r: self.r + other.r,
+            g: self.g + other.g,
+            b: self.b + other.b,
+            a: self.a + other.a,
         }
     }
 }
 
+/// Scale each channel, including alpha
+impl Mul<f32> for Color {
+    type Output = Color;
+
+    fn mul(self, scale: f32) -> Color {
+        Color {
+            r: self.r * scale,
+            g: self.g * scale,
+            b: self.b * scale,
+            a: self.a * scale,
+        }
+    }
+}
+
+/// Multiply each channel by the other color's, the same as `Color::multiply`
+impl Mul for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
+        self.multiply(other)
+    }
+}
+
+impl From<[f32; 4]> for Color {
+    fn from([r, g, b, a]: [f32; 4]) -> Color {
+        Color { r, g, b, a }
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    fn from(color: Color) -> [f32; 4] {
+        color.to_array()
+    }
+}
+
+fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(channel: f32) -> f32 {
+    if channel <= 0.0031308 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Build a color from its hue and chroma, plus an amount added to every channel
+fn from_hue(hue: f32, chroma: f32, offset: f32) -> Color {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let middle = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, middle, 0.0),
+        1 => (middle, chroma, 0.0),
+        2 => (0.0, chroma, middle),
+        3 => (0.0, middle, chroma),
+        4 => (middle, 0.0, chroma),
+        _ => (chroma, 0.0, middle),
+    };
+    Color {
+        r: r + offset,
+        g: g + offset,
+        b: b + offset,
+        a: 1.0,
+    }
+}
+
 impl Color {
     pub const WHITE: Color = Color {
         r: 1.0,
@@ -130,3 +328,84 @@ impl Color {
         a: 1.0,
     };
 }
+
+#[cfg(test)]
+mod test {
+    use super::Color;
+
+    fn assert_close(actual: Color, expected: Color) {
+        for (actual_channel, expected_channel) in actual.to_array().iter().zip(expected.to_array())
+        {
+            assert!(
+                (actual_channel - expected_channel).abs() < 0.001,
+                "{actual:?} != {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn hsv_and_hsl() {
+        assert_close(Color::from_hsv(0.0, 1.0, 1.0), Color::RED);
+        assert_close(Color::from_hsv(180.0, 1.0, 1.0), Color::CYAN);
+        assert_close(Color::from_hsv(-60.0, 1.0, 1.0), Color::PURPLE);
+        assert_close(Color::from_hsl(120.0, 1.0, 0.5), Color::GREEN);
+        assert_close(Color::from_hsl(0.0, 0.0, 1.0), Color::WHITE);
+
+        let orange = Color::from_rgba(255, 128, 0, 1.0);
+        let (hue, saturation, value) = orange.to_hsv();
+        assert_close(Color::from_hsv(hue, saturation, value), orange);
+        let (hue, saturation, lightness) = orange.to_hsl();
+        assert!((hue - 30.1).abs() < 0.1);
+        assert_close(Color::from_hsl(hue, saturation, lightness), orange);
+
+        assert_eq!(Color::BLACK.to_hsv(), (0.0, 0.0, 0.0));
+        assert_eq!(Color::WHITE.to_hsl(), (0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(Color::from_hex("#ff0000"), Color::RED);
+        assert_eq!(Color::from_hex("0f0"), Color::GREEN);
+        assert_eq!(
+            Color::try_from_hex("#00f8").unwrap(),
+            Color::BLUE.with_alpha(136.0 / 255.0)
+        );
+        assert_eq!(
+            Color::try_from_hex("#FFFFFF00").unwrap(),
+            Color::WHITE.with_alpha(0.0)
+        );
+        for malformed in ["", "#12", "#12345", "#ggg", "#ffé"] {
+            assert!(Color::try_from_hex(malformed).is_err(), "{malformed}");
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        let gray = Color::from([0.5, 0.5, 0.5, 1.0]);
+        assert_eq!(gray + gray, Color::from([1.0, 1.0, 1.0, 2.0]));
+        assert_eq!(Color::WHITE * 0.25, Color::from([0.25; 4]));
+        assert_eq!(Color::YELLOW * Color::CYAN, Color::GREEN);
+        assert_eq!(<[f32; 4]>::from(Color::RED), [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn interpolation() {
+        let half = Color::BLACK.lerp(Color::WHITE.with_alpha(0.0), 0.5);
+        assert_close(
+            half,
+            Color {
+                r: 0.5,
+                g: 0.5,
+                b: 0.5,
+                a: 0.5,
+            },
+        );
+        assert_close(Color::RED.lerp(Color::BLUE, 1.0), Color::BLUE);
+
+        // Half of white's light is brighter than half of its sRGB value
+        let half = Color::BLACK.lerp_gamma(Color::WHITE, 0.5);
+        assert!((half.r - 0.735).abs() < 0.001);
+        assert_close(Color::RED.lerp_gamma(Color::GREEN, 0.0), Color::RED);
+        assert_close(Color::RED.lerp_gamma(Color::GREEN, 1.0), Color::GREEN);
+    }
+}
@@ -0,0 +1,100 @@
+use glam::{Mat3, Vec3};
+
+/// A kind of color vision deficiency, each missing one type of cone
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColorBlindness {
+    /// No red cones
+    Protanopia,
+    /// No green cones, the most common kind
+    Deuteranopia,
+    /// No blue cones
+    Tritanopia,
+}
+
+/// A full-screen filter applied to everything drawn, see `Venus::set_color_filter`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorFilter {
+    #[default]
+    None,
+    /// Show the game the way it looks to players with a color vision deficiency, for checking
+    /// that nothing important relies on colors they can't tell apart
+    Simulate(ColorBlindness),
+    /// Shift the colors a player can't tell apart into ones they can (daltonization)
+    Correct(ColorBlindness),
+    /// Multiply every pixel's red, green, and blue by a matrix
+    Matrix(Mat3),
+}
+
+impl ColorFilter {
+    // The matrix to apply, or None when the filter doesn't change anything
+    pub(crate) fn matrix(self) -> Option<Mat3> {
+        match self {
+            ColorFilter::None => None,
+            ColorFilter::Simulate(kind) => Some(simulation(kind)),
+            ColorFilter::Correct(kind) => {
+                // Spread what the player can't see into the channels they can
+                let shift = rows([[0.0, 0.0, 0.0], [0.7, 1.0, 0.0], [0.7, 0.0, 1.0]]);
+                let lost = Mat3::IDENTITY - simulation(kind);
+                Some(Mat3::IDENTITY + shift * lost)
+            }
+            ColorFilter::Matrix(matrix) => Some(matrix),
+        }
+    }
+}
+
+// From Machado, Oliveira, and Fernandes (2009), at full severity
+fn simulation(kind: ColorBlindness) -> Mat3 {
+    match kind {
+        ColorBlindness::Protanopia => rows([
+            [0.152286, 1.052583, -0.204868],
+            [0.114503, 0.786281, 0.099216],
+            [-0.003882, -0.048116, 1.051998],
+        ]),
+        ColorBlindness::Deuteranopia => rows([
+            [0.367322, 0.860646, -0.227968],
+            [0.280085, 0.672501, 0.047413],
+            [-0.011820, 0.042940, 0.968881],
+        ]),
+        ColorBlindness::Tritanopia => rows([
+            [1.255528, -0.076749, -0.178779],
+            [-0.078411, 0.930809, 0.147602],
+            [0.004733, 0.691367, 0.303900],
+        ]),
+    }
+}
+
+fn rows([x, y, z]: [[f32; 3]; 3]) -> Mat3 {
+    Mat3::from_cols(Vec3::from(x), Vec3::from(y), Vec3::from(z)).transpose()
+}
+
+#[cfg(test)]
+mod test {
+    use glam::Vec3;
+
+    use super::{ColorBlindness, ColorFilter};
+
+    #[test]
+    fn filters() {
+        assert_eq!(ColorFilter::None.matrix(), None);
+        let kinds = [
+            ColorBlindness::Protanopia,
+            ColorBlindness::Deuteranopia,
+            ColorBlindness::Tritanopia,
+        ];
+        for kind in kinds {
+            for filter in [ColorFilter::Simulate(kind), ColorFilter::Correct(kind)] {
+                // Grays look the same to everyone
+                let gray = filter.matrix().unwrap() * Vec3::splat(0.5);
+                assert!(gray.abs_diff_eq(Vec3::splat(0.5), 0.001), "{filter:?}");
+            }
+        }
+        // Red and green look alike without green cones
+        let simulate = ColorFilter::Simulate(ColorBlindness::Deuteranopia)
+            .matrix()
+            .unwrap();
+        let red = simulate * Vec3::X;
+        let green = simulate * Vec3::Y;
+        assert!(red.z.abs() < 0.02 && green.z.abs() < 0.05);
+        assert!((red.x / red.y - green.x / green.y).abs() < 0.1);
+    }
+}
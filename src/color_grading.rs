@@ -0,0 +1,168 @@
+use crate::{Color, Error, Venus, decode::decode_image};
+
+/// A color grading lookup table, which maps every color drawn to another color
+///
+/// Tables are made in an image editor by grading a screenshot with a neutral table pasted in,
+/// then cropping the table back out. They're stored as a horizontal strip of square slices,
+/// e.g. 256x16 for 16 levels of each channel: red increases to the right within each slice,
+/// green increases down, and blue increases from one slice to the next.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorLut {
+    size: u32,
+    // RGBA, row by row from the top left of the strip
+    pixels: Vec<u8>,
+}
+
+impl ColorLut {
+    /// A table from the RGBA pixels of a strip, which has to be `size * size` pixels wide and
+    /// `size` pixels tall
+    pub fn from_strip(pixels: &[u8], width: u32, height: u32) -> Result<ColorLut, String> {
+        if height < 2 || width != height * height {
+            return Err(format!(
+                "a {width}x{height} image isn't a color lookup table strip, which should be as \
+                 wide as its height squared"
+            ));
+        }
+        if pixels.len() != (width * height * 4) as usize {
+            return Err(format!("expected {width}x{height} RGBA pixels"));
+        }
+        Ok(ColorLut {
+            size: height,
+            pixels: pixels.to_vec(),
+        })
+    }
+
+    /// The table that leaves every color as it is, the starting point for grading
+    pub fn neutral(size: u32) -> ColorLut {
+        let size = size.max(2);
+        let level = |value: u32| (value * 255 / (size - 1)) as u8;
+        let width = size * size;
+        let pixels = (0..size)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| [level(x % size), level(y), level(x / size), 255])
+            .collect();
+        ColorLut { size, pixels }
+    }
+
+    /// How many levels of each channel the table has
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// The pixels of the strip, four bytes per pixel in RGBA order
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Grade a color on the CPU, the same way the screen is graded, e.g. to match UI colors
+    pub fn apply(&self, color: Color) -> Color {
+        let last = (self.size - 1) as f32;
+        let channel = |value: f32| value.clamp(0.0, 1.0) * last;
+        let (red, green, blue) = (channel(color.r), channel(color.g), channel(color.b));
+        // Blend between the neighbouring levels of each channel
+        let mut graded = [0.0; 3];
+        for (corner, weight) in corners(red, green, blue) {
+            let [x, y, z] = corner;
+            let start = ((y * self.size * self.size + z * self.size + x) * 4) as usize;
+            for (total, value) in graded.iter_mut().zip(&self.pixels[start..start + 3]) {
+                *total += *value as f32 / 255.0 * weight;
+            }
+        }
+        let [r, g, b] = graded;
+        Color {
+            r,
+            g,
+            b,
+            a: color.a,
+        }
+    }
+}
+
+// The eight levels around a point in the table, with how much each contributes
+fn corners(red: f32, green: f32, blue: f32) -> impl Iterator<Item = ([u32; 3], f32)> {
+    let split = |value: f32| {
+        let low = value.floor();
+        ([low as u32, value.ceil() as u32], value - low)
+    };
+    let ([r0, r1], rt) = split(red);
+    let ([g0, g1], gt) = split(green);
+    let ([b0, b1], bt) = split(blue);
+    (0..8).map(move |corner| {
+        let pick = |bit: u32, [low, high]: [u32; 2], t: f32| {
+            if corner & bit == 0 {
+                (low, 1.0 - t)
+            } else {
+                (high, t)
+            }
+        };
+        let (r, rw) = pick(1, [r0, r1], rt);
+        let (g, gw) = pick(2, [g0, g1], gt);
+        let (b, bw) = pick(4, [b0, b1], bt);
+        ([r, g, b], rw * gw * bw)
+    })
+}
+
+impl Venus {
+    /// Load a color lookup table from a strip image, see `ColorLut`
+    pub async fn load_color_lut(&mut self, path: &str) -> Result<ColorLut, Error> {
+        let bytes = self.load_file(path).await?;
+        let image = decode_image(path.to_string(), bytes).await?;
+        ColorLut::from_strip(&image.pixels, image.width, image.height).map_err(|error| {
+            Error::ParseError {
+                path: path.to_string(),
+                error,
+            }
+        })
+    }
+
+    /// Grade the colors of everything drawn through a lookup table, starting with the next
+    /// frame, or stop with None
+    ///
+    /// Like `set_color_filter`, this draws each frame off screen first, and is ignored when
+    /// running headless. The table is applied after the color filter.
+    pub fn set_color_grading(&mut self, lut: Option<&ColorLut>) {
+        self.gfx.set_color_lut(lut);
+    }
+
+    /// How much of the color grading to apply, from 0 for none to 1 for all of it
+    ///
+    /// Fade this to blend between the normal look and a graded one, e.g. for a flashback.
+    pub fn set_color_grading_strength(&mut self, strength: f32) {
+        self.gfx.set_color_lut_strength(strength.clamp(0.0, 1.0));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+
+    use super::ColorLut;
+    use crate::Color;
+
+    #[test]
+    fn grading() {
+        let neutral = ColorLut::neutral(16);
+        assert_eq!(neutral.pixels().len(), 256 * 16 * 4);
+        let color = Color {
+            r: 0.3,
+            g: 0.6,
+            b: 0.9,
+            a: 0.5,
+        };
+        let graded = neutral.apply(color);
+        assert_abs_diff_eq!(graded.r, 0.3, epsilon = 0.01);
+        assert_abs_diff_eq!(graded.g, 0.6, epsilon = 0.01);
+        assert_abs_diff_eq!(graded.b, 0.9, epsilon = 0.01);
+        assert_eq!(graded.a, 0.5);
+
+        // Swap red and blue
+        let mut pixels = neutral.pixels().to_vec();
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        let swapped = ColorLut::from_strip(&pixels, 256, 16).unwrap();
+        assert_abs_diff_eq!(swapped.apply(color).r, 0.9, epsilon = 0.01);
+
+        assert!(ColorLut::from_strip(&pixels, 128, 32).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use std::borrow::Cow;
+
+use serde::Deserialize;
+
+use crate::{Error, ScaleMode, Settings};
+
+// Every field is optional, so a config file only needs the settings it changes
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SettingsToml {
+    width: Option<f32>,
+    height: Option<f32>,
+    fullscreen: Option<bool>,
+    title: Option<String>,
+    app_id: Option<String>,
+    resizable: Option<bool>,
+    scale_mode: Option<String>,
+    hot_reload: Option<bool>,
+    asset_root: Option<String>,
+}
+
+impl Settings {
+    /// Settings from the contents of a TOML file, with the defaults for anything it leaves out
+    ///
+    /// The keys match the fields of `Settings`, and `scale_mode` is one of `"stretch"`,
+    /// `"letterbox"`, or `"integer"`. Override anything that shouldn't be configurable with
+    /// struct update syntax:
+    ///
+    /// ```ignore
+    /// let settings = Settings {
+    ///     title: "My Game".into(),
+    ///     ..Settings::from_toml(include_str!("../settings.toml"))?
+    /// };
+    /// ```
+    pub fn from_toml(text: &str) -> Result<Settings, Error> {
+        parse_toml("settings", text)
+    }
+
+    /// Read settings from a TOML file next to the game, see `from_toml`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_toml(path: &str) -> Result<Settings, Error> {
+        let text = std::fs::read_to_string(path).map_err(|error| Error::FileLoadError {
+            path: path.to_string(),
+            error,
+        })?;
+        parse_toml(path, &text)
+    }
+}
+
+fn parse_toml(path: &str, text: &str) -> Result<Settings, Error> {
+    let parse_error = |error: String| Error::ParseError {
+        path: path.to_string(),
+        error,
+    };
+    let config: SettingsToml =
+        toml::from_str(text).map_err(|error| parse_error(error.to_string()))?;
+    let defaults = Settings::default();
+    let scale_mode = match config.scale_mode.as_deref() {
+        None => defaults.scale_mode,
+        Some("stretch") => ScaleMode::Stretch,
+        Some("letterbox") => ScaleMode::Letterbox,
+        Some("integer") => ScaleMode::Integer,
+        Some(other) => return Err(parse_error(format!("unknown scale mode {other:?}"))),
+    };
+
+    Ok(Settings {
+        width: config.width.unwrap_or(defaults.width),
+        height: config.height.unwrap_or(defaults.height),
+        fullscreen: config.fullscreen.unwrap_or(defaults.fullscreen),
+        title: config.title.map_or(defaults.title, Cow::Owned),
+        app_id: config.app_id.map(Cow::Owned).or(defaults.app_id),
+        resizable: config.resizable.unwrap_or(defaults.resizable),
+        scale_mode,
+        hot_reload: config.hot_reload.unwrap_or(defaults.hot_reload),
+        asset_root: config.asset_root.or(defaults.asset_root),
+        ..defaults
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ScaleMode, Settings};
+
+    #[test]
+    fn from_toml() {
+        let settings = Settings::from_toml(
+            r#"
+            width = 640
+            title = "Test"
+            app_id = "test"
+            scale_mode = "integer"
+            asset_root = "data"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(settings.width, 640.0);
+        assert_eq!(settings.height, Settings::default().height);
+        assert_eq!(settings.title, "Test");
+        assert_eq!(settings.app_id.as_deref(), Some("test"));
+        assert_eq!(settings.scale_mode, ScaleMode::Integer);
+        assert_eq!(settings.asset_root.as_deref(), Some("data"));
+
+        assert!(Settings::from_toml("widht = 640").is_err());
+        assert!(Settings::from_toml("scale_mode = \"zoom\"").is_err());
+    }
+}
@@ -0,0 +1,51 @@
+use blinds::CursorIcon;
+use glam::Vec2;
+
+use crate::{Texture, Venus};
+
+// An image drawn over each frame in place of the system cursor
+pub(crate) struct CursorImage {
+    texture: Texture,
+    hotspot: Vec2,
+}
+
+impl Venus {
+    /// Replace the mouse cursor with an image, where the hotspot is the pixel that points
+    ///
+    /// The window can only show the system's own cursors, so the image is drawn on top of each
+    /// frame at the mouse position, in the same units as the settings' width and height, with
+    /// the system cursor hidden. This means it lags a frame behind the mouse.
+    pub fn set_cursor_image(&mut self, texture: &Texture, hotspot: impl Into<Vec2>) {
+        self.cursor_image = Some(CursorImage {
+            texture: texture.clone(),
+            hotspot: hotspot.into(),
+        });
+        if let Some(window) = &self.window {
+            window.set_cursor_icon(None);
+        }
+    }
+
+    /// Use one of the system's cursors, or hide the cursor entirely with None
+    ///
+    /// This replaces any image set with `set_cursor_image`.
+    pub fn set_cursor_icon(&mut self, icon: Option<CursorIcon>) {
+        self.cursor_image = None;
+        if let Some(window) = &self.window {
+            window.set_cursor_icon(icon);
+        }
+    }
+
+    pub(crate) fn draw_cursor_image(&mut self) {
+        let Some(cursor) = &self.cursor_image else {
+            return;
+        };
+        if !self.focused {
+            return;
+        }
+        let texture = cursor.texture.clone();
+        let position = self.logical_mouse_position() - cursor.hotspot;
+        let previous_camera = self.begin_screen_space();
+        self.draw_image(&texture, position);
+        self.end_screen_space(&previous_camera);
+    }
+}
@@ -0,0 +1,397 @@
+use std::{collections::VecDeque, fmt::Display};
+
+use glam::Vec2;
+
+use crate::{Camera, Color, Rect, Transform, Venus};
+
+// How many frames the frame time graph covers
+const HISTORY: usize = 120;
+// The size of one pixel of the built-in font, in logical units
+const PIXEL: f32 = 2.0;
+pub(crate) const LINE_HEIGHT: f32 = 7.0 * PIXEL;
+pub(crate) const PADDING: f32 = 4.0;
+const GRAPH_HEIGHT: f32 = 40.0;
+// Frame times at the top of the graph, and the line marking 60 frames per second
+const GRAPH_MAX: f32 = 1.0 / 30.0;
+const TARGET: f32 = 1.0 / 60.0;
+
+pub(crate) struct DebugOverlay {
+    pub visible: bool,
+    pub toggle_key: Option<blinds::Key>,
+    pub freeze_key: Option<blinds::Key>,
+    pub step_key: Option<blinds::Key>,
+    frozen: bool,
+    // Whether a step was asked for during this frame, to run during the next one
+    step_requested: bool,
+    stepping: bool,
+    frame_times: VecDeque<f32>,
+    flushes: u32,
+    stats: Vec<(String, String)>,
+}
+
+impl DebugOverlay {
+    pub fn new(
+        toggle_key: Option<blinds::Key>,
+        freeze_key: Option<blinds::Key>,
+        step_key: Option<blinds::Key>,
+    ) -> DebugOverlay {
+        DebugOverlay {
+            visible: false,
+            toggle_key,
+            freeze_key,
+            step_key,
+            frozen: false,
+            step_requested: false,
+            stepping: false,
+            frame_times: VecDeque::with_capacity(HISTORY),
+            flushes: 0,
+            stats: Vec::new(),
+        }
+    }
+
+    pub fn record_frame(&mut self, frame_time: f32, flushes: u32) {
+        if self.frame_times.len() == HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+        self.flushes = flushes;
+    }
+
+    // Called as each frame starts, so a step asked for during a frame runs the next one
+    pub fn start_frame_step(&mut self) {
+        self.stepping = std::mem::take(&mut self.step_requested);
+    }
+
+    fn average_frame_time(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+        self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+    }
+}
+
+impl Venus {
+    /// Show or hide the built-in debug overlay
+    ///
+    /// The overlay is drawn over everything else at the end of each frame, and shows the frame
+    /// rate, a graph of recent frame times, how many batches were drawn, how full the texture
+    /// atlas is, how many sounds are playing, and anything passed to `debug_stat`. It can also be
+    /// toggled with `Settings::debug_overlay_key`.
+    pub fn set_debug_overlay(&mut self, visible: bool) {
+        self.debug_overlay.visible = visible;
+    }
+
+    pub fn is_debug_overlay_visible(&self) -> bool {
+        self.debug_overlay.visible
+    }
+
+    /// Freeze the game to look at it frame by frame, or unfreeze it
+    ///
+    /// While frozen, `delta` is 0 so everything driven by it stops, but drawing, input, and the
+    /// debug overlay carry on as normal. Game logic that doesn't use `delta` can check
+    /// `is_simulating` instead. It can also be toggled with `Settings::freeze_key`.
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.debug_overlay.frozen = frozen;
+        self.debug_overlay.step_requested = false;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.debug_overlay.frozen
+    }
+
+    /// Run the next frame of a frozen game, then freeze again, see `Settings::step_key`
+    pub fn step_frame(&mut self) {
+        if self.debug_overlay.frozen {
+            self.debug_overlay.step_requested = true;
+        }
+    }
+
+    /// Whether game logic should run this frame: always unless frozen, and on stepped frames
+    pub fn is_simulating(&self) -> bool {
+        !self.debug_overlay.frozen || self.debug_overlay.stepping
+    }
+
+    // Apply the overlay, freeze, and step keys from `Settings`
+    pub(crate) fn debug_key_pressed(&mut self, key: blinds::Key) {
+        if self.debug_overlay.toggle_key == Some(key) {
+            self.debug_overlay.visible = !self.debug_overlay.visible;
+        }
+        if self.debug_overlay.freeze_key == Some(key) {
+            self.set_frozen(!self.is_frozen());
+        }
+        if self.debug_overlay.step_key == Some(key) {
+            self.step_frame();
+        }
+    }
+
+    /// Show a value on the debug overlay for this frame, such as the number of entities
+    pub fn debug_stat(&mut self, label: &str, value: impl Display) {
+        if self.debug_overlay.visible {
+            self.debug_overlay
+                .stats
+                .push((label.to_string(), value.to_string()));
+        }
+    }
+
+    pub(crate) fn draw_debug_overlay(&mut self) {
+        let stats = std::mem::take(&mut self.debug_overlay.stats);
+        if !self.debug_overlay.visible {
+            return;
+        }
+        let overlay = &self.debug_overlay;
+        let frame_time = overlay.average_frame_time();
+        let fps = if frame_time > 0.0 {
+            1.0 / frame_time
+        } else {
+            0.0
+        };
+        let mut lines = vec![
+            format!("FPS {fps:.0} ({:.1} MS)", frame_time * 1000.0),
+            format!("FLUSHES {}", overlay.flushes),
+        ];
+        let pages = self.gfx.atlas_usage();
+        let usage: Vec<_> = pages
+            .iter()
+            .map(|used| format!("{:.0}%", used * 100.0))
+            .collect();
+        lines.push(format!("ATLAS {}", usage.join(" ")));
+        if overlay.frozen {
+            lines.push("FROZEN".to_string());
+        }
+        #[cfg(feature = "audio")]
+        lines.push(format!("SOUNDS {}", self.audio.playing()));
+        lines.extend(
+            stats
+                .iter()
+                .map(|(label, value)| format!("{label} {value}")),
+        );
+        for shader in self.shaders.values() {
+            if let Some(error) = &shader.error {
+                lines.push(format!("SHADER {error}"));
+            }
+        }
+
+        let previous_camera = self.begin_screen_space();
+
+        let width = lines
+            .iter()
+            .map(|line| text_width(line))
+            .fold(HISTORY as f32, f32::max);
+        let height = lines.len() as f32 * LINE_HEIGHT + GRAPH_HEIGHT;
+        self.gfx.push_rect(
+            Rect::new(0.0, 0.0, width + PADDING * 2.0, height + PADDING * 3.0),
+            Color::BLACK.with_alpha(0.7),
+            None,
+        );
+        let mut position = Vec2::splat(PADDING);
+        for line in &lines {
+            self.draw_debug_text(position, line, Color::WHITE);
+            position.y += LINE_HEIGHT;
+        }
+        position.y += PADDING;
+        let frame_times: Vec<_> = self.debug_overlay.frame_times.iter().copied().collect();
+        for (index, frame_time) in frame_times.into_iter().enumerate() {
+            let bar = (frame_time / GRAPH_MAX).min(1.0) * GRAPH_HEIGHT;
+            let color = if frame_time > TARGET * 1.05 {
+                Color::RED
+            } else {
+                Color::GREEN
+            };
+            self.gfx.push_rect(
+                Rect::new(
+                    position.x + index as f32,
+                    position.y + GRAPH_HEIGHT - bar,
+                    1.0,
+                    bar,
+                ),
+                color,
+                None,
+            );
+        }
+        let target = position.y + GRAPH_HEIGHT * (1.0 - TARGET / GRAPH_MAX);
+        self.gfx.push_rect(
+            Rect::new(position.x, target, HISTORY as f32, 1.0),
+            Color::WHITE.with_alpha(0.5),
+            None,
+        );
+
+        self.end_screen_space(&previous_camera);
+    }
+
+    /// Draw in the units of `Settings::width` and `Settings::height` over the whole screen,
+    /// ignoring the camera and transforms, until `end_screen_space`
+    pub(crate) fn begin_screen_space(&mut self) -> Camera {
+        let previous_camera = self.camera.clone();
+        self.use_camera(&Camera::from_rect(Rect::new(
+            0.0,
+            0.0,
+            self.logical_size.x,
+            self.logical_size.y,
+        )));
+        self.gfx.set_transform(Transform::IDENTITY);
+        previous_camera
+    }
+
+    pub(crate) fn end_screen_space(&mut self, previous_camera: &Camera) {
+        self.gfx.set_transform(self.transform);
+        self.use_camera(previous_camera);
+    }
+
+    /// Draw text in the built-in pixel font, with its top left at the position
+    pub(crate) fn draw_debug_text(&mut self, position: Vec2, text: &str, color: Color) {
+        for (index, ch) in text.chars().enumerate() {
+            let origin = position + Vec2::new(index as f32 * 4.0 * PIXEL, 0.0);
+            for (row, bits) in glyph(ch).into_iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) != 0 {
+                        self.gfx.push_rect(
+                            Rect::new(
+                                origin.x + column as f32 * PIXEL,
+                                origin.y + row as f32 * PIXEL,
+                                PIXEL,
+                                PIXEL,
+                            ),
+                            color,
+                            None,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn text_width(text: &str) -> f32 {
+    text.chars().count() as f32 * 4.0 * PIXEL
+}
+
+// A 3x5 pixel font, one row per byte with the leftmost pixel in the highest bit
+fn glyph(ch: char) -> [u8; 5] {
+    match ch.to_ascii_uppercase() {
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '[' => [0b011, 0b010, 0b010, 0b010, 0b011],
+        ']' => [0b110, 0b010, 0b010, 0b010, 0b110],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        _ => [0b111, 0b001, 0b011, 0b000, 0b010],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DebugOverlay, HISTORY, glyph};
+    use crate::{Key, Settings, Venus};
+
+    #[test]
+    fn frame_history() {
+        let mut overlay = DebugOverlay::new(None, None, None);
+        for _ in 0..HISTORY + 10 {
+            overlay.record_frame(0.02, 3);
+        }
+        overlay.record_frame(0.04, 5);
+        assert_eq!(overlay.frame_times.len(), HISTORY);
+        assert_eq!(overlay.flushes, 5);
+        let average = 0.02 + 0.02 / HISTORY as f32;
+        assert!((overlay.average_frame_time() - average).abs() < 1e-6);
+    }
+
+    #[test]
+    fn glyphs() {
+        assert_eq!(glyph('a'), glyph('A'));
+        // Every glyph fits in 3 pixels across
+        for ch in (' '..='~').chain(['é']) {
+            assert!(glyph(ch).iter().all(|row| *row < 0b1000));
+        }
+    }
+
+    #[test]
+    fn keys() {
+        let mut venus = Venus::headless(Settings {
+            debug_overlay_key: Some(Key::F1),
+            freeze_key: Some(Key::F2),
+            step_key: Some(Key::F3),
+            ..Settings::default()
+        });
+        venus.debug_key_pressed(Key::F3);
+        venus.debug_overlay.start_frame_step();
+        assert!(venus.is_simulating());
+
+        venus.debug_key_pressed(Key::F2);
+        assert!(venus.is_frozen());
+        venus.debug_key_pressed(Key::F3);
+        venus.debug_overlay.start_frame_step();
+        assert!(venus.is_simulating());
+        venus.debug_overlay.start_frame_step();
+        assert!(!venus.is_simulating());
+
+        venus.debug_key_pressed(Key::F1);
+        assert!(venus.is_debug_overlay_visible());
+        assert!(venus.is_frozen());
+        venus.debug_key_pressed(Key::F2);
+        assert!(!venus.is_frozen());
+    }
+
+    #[test]
+    fn draws_when_visible() {
+        let mut venus = Venus::headless(Settings::default());
+        venus.debug_stat("entities", 3);
+        venus.draw_debug_overlay();
+        assert!(venus.take_draw_commands().is_empty());
+
+        venus.set_debug_overlay(true);
+        venus.debug_stat("entities", 3);
+        assert_eq!(venus.debug_overlay.stats.len(), 1);
+        venus.draw_debug_overlay();
+        assert!(!venus.take_draw_commands().is_empty());
+        assert!(venus.debug_overlay.stats.is_empty());
+    }
+}
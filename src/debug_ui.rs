@@ -0,0 +1,245 @@
+use std::{fmt::Display, ops::RangeInclusive};
+
+use blinds::MouseButton;
+use glam::Vec2;
+
+use crate::{
+    Camera, Color, Rect, Venus,
+    debug_overlay::{LINE_HEIGHT, PADDING},
+};
+
+const PANEL_WIDTH: f32 = 240.0;
+const ROW_HEIGHT: f32 = LINE_HEIGHT + PADDING;
+const ROW_GAP: f32 = 2.0;
+const MARGIN: f32 = 8.0;
+// The built-in font is 5 pixels of 2 units tall
+const TEXT_HEIGHT: f32 = 10.0;
+
+const PANEL: Color = Color {
+    r: 0.1,
+    g: 0.1,
+    b: 0.1,
+    a: 0.8,
+};
+const BUTTON: Color = Color {
+    r: 0.25,
+    g: 0.25,
+    b: 0.3,
+    a: 0.9,
+};
+const HOVERED: Color = Color {
+    r: 0.35,
+    g: 0.35,
+    b: 0.45,
+    a: 0.9,
+};
+const PRESSED: Color = Color {
+    r: 0.2,
+    g: 0.4,
+    b: 0.7,
+    a: 0.9,
+};
+
+// What the debug UI remembers from one frame to the next
+#[derive(Default)]
+pub(crate) struct DebugUiState {
+    // The label of the slider being dragged
+    dragging: Option<String>,
+    mouse_was_down: bool,
+    next_row: f32,
+}
+
+impl DebugUiState {
+    pub fn end_frame(&mut self, mouse_down: bool) {
+        self.mouse_was_down = mouse_down;
+        self.next_row = 0.0;
+        if !mouse_down {
+            self.dragging = None;
+        }
+    }
+}
+
+/// Immediate-mode widgets for tweaking values while the game runs
+///
+/// Widgets are stacked in a panel in the top right corner, drawn over the scene as soon as
+/// they're added, and respond to the left mouse button. Labels identify widgets between frames,
+/// so give each one a different label. Get one each frame with `Venus::debug_ui`:
+///
+/// ```ignore
+/// let mut ui = venus.debug_ui();
+/// ui.slider("gravity", &mut gravity, 0.0..=2000.0);
+/// ui.checkbox("show hitboxes", &mut show_hitboxes);
+/// if ui.button("spawn") {
+///     enemies.push(Enemy::new());
+/// }
+/// ui.value("enemies", enemies.len());
+/// ```
+pub struct DebugUi<'a> {
+    venus: &'a mut Venus,
+    previous_camera: Camera,
+    mouse: Vec2,
+    mouse_down: bool,
+    clicked: bool,
+}
+
+impl Venus {
+    pub fn debug_ui(&mut self) -> DebugUi<'_> {
+        let mouse_down = self.is_left_mouse_down();
+        let clicked = mouse_down && !self.debug_ui_state.mouse_was_down;
+        let mouse = self.logical_mouse_position();
+        let previous_camera = self.begin_screen_space();
+        DebugUi {
+            venus: self,
+            previous_camera,
+            mouse,
+            mouse_down,
+            clicked,
+        }
+    }
+
+    pub(crate) fn is_left_mouse_down(&self) -> bool {
+        self.event_stream
+            .as_ref()
+            .is_some_and(|events| events.cache().mouse(MouseButton::Left))
+    }
+}
+
+impl DebugUi<'_> {
+    /// Show a value, such as the number of entities or the player's position
+    pub fn value(&mut self, label: &str, value: impl Display) {
+        let row = self.row();
+        self.venus.gfx.push_rect(row, PANEL, None);
+        self.text(row, 0.0, &format!("{label}: {value}"));
+    }
+
+    /// A button, which returns true on the frame it's clicked
+    pub fn button(&mut self, label: &str) -> bool {
+        let row = self.row();
+        let hovered = row.contains(self.mouse);
+        let color = match (hovered, self.mouse_down) {
+            (true, true) => PRESSED,
+            (true, false) => HOVERED,
+            (false, _) => BUTTON,
+        };
+        self.venus.gfx.push_rect(row, color, None);
+        self.text(row, 0.0, label);
+        hovered && self.clicked
+    }
+
+    /// A checkbox which flips the value when clicked, returning true if it changed
+    pub fn checkbox(&mut self, label: &str, value: &mut bool) -> bool {
+        let row = self.row();
+        let changed = row.contains(self.mouse) && self.clicked;
+        if changed {
+            *value = !*value;
+        }
+        self.venus.gfx.push_rect(row, PANEL, None);
+        let top = row.y + (ROW_HEIGHT - TEXT_HEIGHT) / 2.0;
+        let check = Rect::new(row.x + PADDING, top, TEXT_HEIGHT, TEXT_HEIGHT);
+        self.venus.gfx.push_rect(check, Color::WHITE, None);
+        if !*value {
+            let inner = Rect::new(check.x + 2.0, check.y + 2.0, 6.0, 6.0);
+            self.venus.gfx.push_rect(inner, PANEL.with_alpha(1.0), None);
+        }
+        self.text(row, TEXT_HEIGHT + PADDING, label);
+        changed
+    }
+
+    /// A slider which sets the value by dragging across it, returning true if it changed
+    pub fn slider(&mut self, label: &str, value: &mut f32, range: RangeInclusive<f32>) -> bool {
+        let row = self.row();
+        let state = &mut self.venus.debug_ui_state;
+        if self.clicked && row.contains(self.mouse) {
+            state.dragging = Some(label.to_string());
+        }
+        let previous = *value;
+        if self.mouse_down && state.dragging.as_deref() == Some(label) {
+            *value = slider_value(self.mouse.x, row, &range);
+        }
+        self.venus.gfx.push_rect(row, PANEL, None);
+        let filled = Rect::new(
+            row.x,
+            row.y,
+            row.width * fraction(*value, &range),
+            row.height,
+        );
+        self.venus.gfx.push_rect(filled, BUTTON, None);
+        self.text(row, 0.0, &format!("{label}: {value:.2}"));
+        *value != previous
+    }
+
+    fn row(&mut self) -> Rect {
+        let state = &mut self.venus.debug_ui_state;
+        let y = MARGIN + state.next_row;
+        state.next_row += ROW_HEIGHT + ROW_GAP;
+        Rect::new(
+            self.venus.logical_size.x - PANEL_WIDTH - MARGIN,
+            y,
+            PANEL_WIDTH,
+            ROW_HEIGHT,
+        )
+    }
+
+    fn text(&mut self, row: Rect, indent: f32, text: &str) {
+        let position = Vec2::new(
+            row.x + PADDING + indent,
+            row.y + (ROW_HEIGHT - TEXT_HEIGHT) / 2.0,
+        );
+        self.venus.draw_debug_text(position, text, Color::WHITE);
+    }
+}
+
+impl Drop for DebugUi<'_> {
+    fn drop(&mut self) {
+        self.venus.end_screen_space(&self.previous_camera);
+    }
+}
+
+// Where a value sits within a range, from 0 to 1
+fn fraction(value: f32, range: &RangeInclusive<f32>) -> f32 {
+    let width = range.end() - range.start();
+    if width <= 0.0 {
+        return 0.0;
+    }
+    ((value - range.start()) / width).clamp(0.0, 1.0)
+}
+
+fn slider_value(mouse_x: f32, row: Rect, range: &RangeInclusive<f32>) -> f32 {
+    let fraction = ((mouse_x - row.x) / row.width).clamp(0.0, 1.0);
+    range.start() + (range.end() - range.start()) * fraction
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ROW_GAP, ROW_HEIGHT, fraction, slider_value};
+    use crate::{Rect, Settings, Venus};
+
+    #[test]
+    fn slider_math() {
+        let row = Rect::new(100.0, 0.0, 200.0, 20.0);
+        assert_eq!(slider_value(150.0, row, &(0.0..=8.0)), 2.0);
+        assert_eq!(slider_value(0.0, row, &(-1.0..=1.0)), -1.0);
+        assert_eq!(slider_value(500.0, row, &(-1.0..=1.0)), 1.0);
+        assert_eq!(fraction(5.0, &(0.0..=10.0)), 0.5);
+        assert_eq!(fraction(5.0, &(1.0..=1.0)), 0.0);
+    }
+
+    #[test]
+    fn layout() {
+        let mut venus = Venus::headless(Settings::default());
+        {
+            let mut ui = venus.debug_ui();
+            let mut speed = 2.0;
+            let mut enabled = false;
+            // Without a mouse, nothing is ever clicked
+            assert!(!ui.button("spawn"));
+            assert!(!ui.checkbox("enabled", &mut enabled));
+            assert!(!ui.slider("speed", &mut speed, 0.0..=10.0));
+            ui.value("entities", 12);
+        }
+        assert_eq!(venus.debug_ui_state.next_row, (ROW_HEIGHT + ROW_GAP) * 4.0);
+        assert!(!venus.take_draw_commands().is_empty());
+        venus.debug_ui_state.end_frame(false);
+        assert_eq!(venus.debug_ui_state.next_row, 0.0);
+    }
+}
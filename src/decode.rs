@@ -0,0 +1,209 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    future::Future,
+    panic::{AssertUnwindSafe, catch_unwind},
+    pin::Pin,
+    sync::{
+        Arc, Mutex, OnceLock,
+        mpsc::{self, Receiver, Sender},
+    },
+    task::{Context, Poll, Waker},
+};
+
+use crate::Error;
+
+/// An image decoded into RGBA pixels, ready to upload
+pub(crate) struct DecodedImage {
+    pub pixels: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Decode an image without blocking the main thread
+///
+/// On the web there are no threads to decode on, so it happens right away.
+pub(crate) async fn decode_image(path: String, bytes: Vec<u8>) -> Result<DecodedImage, Error> {
+    let image_path = path.clone();
+    in_background(move || decode_image_now(&image_path, &bytes))
+        .await
+        .unwrap_or_else(|| {
+            Err(Error::ImageDecodeError {
+                path,
+                error: "the decoder panicked".into(),
+            })
+        })
+}
+
+/// Run slow work on a worker thread, or right away on the web where there are no threads
+///
+/// Returns None if the work panicked.
+pub(crate) async fn in_background<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+) -> Option<T> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return on_worker(work).await;
+    #[cfg(target_arch = "wasm32")]
+    Some(work())
+}
+
+#[cfg(feature = "images")]
+pub(crate) fn decode_image_now(path: &str, bytes: &[u8]) -> Result<DecodedImage, Error> {
+    let image = image::load_from_memory(bytes).map_err(|error| Error::ImageDecodeError {
+        path: path.to_string(),
+        error: Box::new(error),
+    })?;
+    let image = image.into_rgba8();
+    Ok(DecodedImage {
+        width: image.width(),
+        height: image.height(),
+        pixels: image.into_raw(),
+    })
+}
+
+#[cfg(not(feature = "images"))]
+pub(crate) fn decode_image_now(path: &str, _bytes: &[u8]) -> Result<DecodedImage, Error> {
+    Err(Error::ImageDecodeError {
+        path: path.to_string(),
+        error: "image decoding is turned off, enable the images feature".into(),
+    })
+}
+
+// The most threads work is spread across, however many cores there are
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_WORKERS: usize = 4;
+
+#[cfg(not(target_arch = "wasm32"))]
+type Job = Box<dyn FnOnce() + Send>;
+
+// Started the first time there's work to do
+#[cfg(not(target_arch = "wasm32"))]
+static POOL: OnceLock<Sender<Job>> = OnceLock::new();
+
+#[cfg(not(target_arch = "wasm32"))]
+fn start_pool() -> Sender<Job> {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |count| count.get())
+        .min(MAX_WORKERS);
+    for index in 0..workers {
+        let receiver = receiver.clone();
+        let spawned = std::thread::Builder::new()
+            .name(format!("venus-worker-{index}"))
+            .spawn(move || run_jobs(&receiver));
+        if let Err(error) = spawned {
+            log::error!("Can't start a worker thread: {error}");
+        }
+    }
+    sender
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run_jobs(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = receiver.lock().unwrap().recv();
+        match job {
+            Ok(job) => job(),
+            Err(_) => break,
+        }
+    }
+}
+
+// Run a closure on the worker pool, finishing when it returns or panics
+#[cfg(not(target_arch = "wasm32"))]
+fn on_worker<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Worker<T> {
+    let shared = Arc::new(Mutex::new(WorkerState {
+        result: None,
+        waker: None,
+    }));
+    let worker = Worker {
+        shared: shared.clone(),
+    };
+    let job: Job = Box::new(move || {
+        let result = catch_unwind(AssertUnwindSafe(work)).ok();
+        let mut state = shared.lock().unwrap();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+    // If no worker could be started, the work is done right away instead
+    if let Err(mpsc::SendError(job)) = POOL.get_or_init(start_pool).send(job) {
+        job();
+    }
+    worker
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Worker<T> {
+    shared: Arc<Mutex<WorkerState<T>>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct WorkerState<T> {
+    // None inside once the work has finished, if it panicked
+    result: Option<Option<T>>,
+    waker: Option<Waker>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T> Future for Worker<T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.shared.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "images"))]
+mod test {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::{decode_image, decode_image_now, in_background};
+
+    #[test]
+    fn decodes_on_worker() {
+        let mut png = Vec::new();
+        image::RgbImage::new(3, 2)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let mut future = pin!(decode_image("small.png".to_string(), png));
+        let mut context = Context::from_waker(Waker::noop());
+        let decoded = loop {
+            if let Poll::Ready(result) = future.as_mut().poll(&mut context) {
+                break result.unwrap();
+            }
+            std::thread::yield_now();
+        };
+        // Images without alpha are expanded to RGBA for the atlas
+        assert_eq!((decoded.width, decoded.height), (3, 2));
+        assert_eq!(decoded.pixels.len(), 3 * 2 * 4);
+
+        assert!(decode_image_now("broken.png", &[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn worker_panics() {
+        let mut future = pin!(in_background(|| -> u32 { panic!("broken decoder") }));
+        let mut context = Context::from_waker(Waker::noop());
+        let result = loop {
+            if let Poll::Ready(result) = future.as_mut().poll(&mut context) {
+                break result;
+            }
+            std::thread::yield_now();
+        };
+        assert_eq!(result, None);
+    }
+}
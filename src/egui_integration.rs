@@ -0,0 +1,246 @@
+use blinds::{Event, MouseButton, ScrollDelta};
+use egui::{
+    ClippedPrimitive, Color32, ImageData, Modifiers, MouseWheelUnit, PlatformOutput, PointerButton,
+    Pos2, RawInput, TextureId, epaint::Primitive,
+};
+use glam::Vec2;
+use rustc_hash::FxHashMap as HashMap;
+use web_time::Instant;
+
+use crate::{Color, Key, TextureHandle, Venus};
+
+// Leaves plenty of room on a texture atlas page for egui's font texture
+const MAX_TEXTURE_SIDE: usize = 1024;
+
+/// Draws an egui interface over the game, fed by the window's input
+///
+/// Keep one around for the whole game, since egui remembers what's open and where, and run it
+/// once a frame after drawing the scene:
+///
+/// ```ignore
+/// egui.run(&mut venus, |ctx| {
+///     egui::Window::new("Settings").show(ctx, |ui| {
+///         ui.add(egui::Slider::new(&mut volume, 0.0..=1.0).text("volume"));
+///     });
+/// });
+/// ```
+///
+/// The interface is laid out in the same units as the settings' width and height. Clip
+/// rectangles aren't applied, so scrolled content can draw outside of its area.
+pub struct Egui {
+    ctx: egui::Context,
+    textures: HashMap<TextureId, TextureHandle>,
+    start: Instant,
+}
+
+impl Egui {
+    pub fn new() -> Egui {
+        Egui {
+            ctx: egui::Context::default(),
+            textures: HashMap::default(),
+            start: Instant::now(),
+        }
+    }
+
+    pub fn context(&self) -> &egui::Context {
+        &self.ctx
+    }
+
+    /// Run the interface with the input since the last frame and draw it
+    ///
+    /// The platform output says what egui wants from the window, like a cursor or copied text.
+    pub fn run(&mut self, venus: &mut Venus, ui: impl FnMut(&egui::Context)) -> PlatformOutput {
+        let screen = egui::Rect::from_min_size(
+            Pos2::ZERO,
+            egui::vec2(venus.logical_size.x, venus.logical_size.y),
+        );
+        let input = RawInput {
+            screen_rect: Some(screen),
+            time: Some(self.start.elapsed().as_secs_f64()),
+            modifiers: modifiers(venus),
+            events: std::mem::take(&mut venus.egui_events),
+            focused: venus.focused,
+            max_texture_side: Some(MAX_TEXTURE_SIDE),
+            ..RawInput::default()
+        };
+        let output = self.ctx.run(input, ui);
+
+        for (id, delta) in &output.textures_delta.set {
+            let ImageData::Color(image) = &delta.image;
+            let pixels: Vec<u8> = image
+                .pixels
+                .iter()
+                .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+                .collect();
+            let [width, height] = image.size.map(|side| side as u32);
+            match (delta.pos, self.textures.get(id)) {
+                (Some([x, y]), Some(texture)) => {
+                    venus
+                        .gfx
+                        .update_texture(*texture, x as u32, y as u32, &pixels, width, height);
+                }
+                // TODO-someday: textures which are replaced keep their space in the atlas
+                _ => match venus.gfx.new_texture_from_bytes(&pixels, width, height) {
+                    Ok(texture) => {
+                        self.textures.insert(*id, texture);
+                    }
+                    Err(error) => log::error!("Can't upload an egui texture: {error}"),
+                },
+            }
+        }
+
+        let primitives = self.ctx.tessellate(output.shapes, output.pixels_per_point);
+        let previous_camera = venus.begin_screen_space();
+        for ClippedPrimitive { primitive, .. } in primitives {
+            // Paint callbacks need direct access to the GPU, which Venus doesn't give out
+            let Primitive::Mesh(mesh) = primitive else {
+                continue;
+            };
+            let vertices: Vec<_> = mesh
+                .vertices
+                .iter()
+                .map(|vertex| {
+                    (
+                        Vec2::new(vertex.pos.x, vertex.pos.y),
+                        Vec2::new(vertex.uv.x, vertex.uv.y),
+                        color(vertex.color),
+                    )
+                })
+                .collect();
+            let texture = self.textures.get(&mesh.texture_id).copied();
+            venus.gfx.push_triangles(&vertices, &mesh.indices, texture);
+        }
+        venus.end_screen_space(&previous_camera);
+
+        for id in &output.textures_delta.free {
+            self.textures.remove(id);
+        }
+        output.platform_output
+    }
+}
+
+impl Default for Egui {
+    fn default() -> Egui {
+        Egui::new()
+    }
+}
+
+// Converts a window event into egui's terms, with the mouse in logical coordinates
+pub(crate) fn translate_event(event: &Event, mouse: Vec2) -> Option<egui::Event> {
+    let pos = Pos2::new(mouse.x, mouse.y);
+    match event {
+        Event::PointerMoved(_) => Some(egui::Event::PointerMoved(pos)),
+        Event::PointerLeft(_) => Some(egui::Event::PointerGone),
+        Event::PointerInput(e) => Some(egui::Event::PointerButton {
+            pos,
+            button: match e.button() {
+                MouseButton::Left => PointerButton::Primary,
+                MouseButton::Right => PointerButton::Secondary,
+                MouseButton::Middle => PointerButton::Middle,
+                _ => return None,
+            },
+            pressed: e.is_down(),
+            modifiers: Modifiers::default(),
+        }),
+        Event::ScrollInput(delta) => {
+            let (unit, delta) = match delta {
+                ScrollDelta::Lines(delta) => (MouseWheelUnit::Line, delta),
+                ScrollDelta::Pixels(delta) => (MouseWheelUnit::Point, delta),
+            };
+            Some(egui::Event::MouseWheel {
+                unit,
+                delta: egui::vec2(delta.x, delta.y),
+                modifiers: Modifiers::default(),
+            })
+        }
+        Event::ReceivedCharacter(e) if !e.result().is_control() => {
+            Some(egui::Event::Text(e.result().to_string()))
+        }
+        Event::KeyboardInput(e) => Some(egui::Event::Key {
+            key: key(e.key())?,
+            physical_key: None,
+            pressed: e.is_presed(),
+            repeat: false,
+            modifiers: Modifiers::default(),
+        }),
+        _ => None,
+    }
+}
+
+fn key(key: Key) -> Option<egui::Key> {
+    Some(match key {
+        Key::Back => egui::Key::Backspace,
+        Key::Delete => egui::Key::Delete,
+        Key::Return => egui::Key::Enter,
+        Key::Tab => egui::Key::Tab,
+        Key::Escape => egui::Key::Escape,
+        Key::Space => egui::Key::Space,
+        Key::Left => egui::Key::ArrowLeft,
+        Key::Right => egui::Key::ArrowRight,
+        Key::Up => egui::Key::ArrowUp,
+        Key::Down => egui::Key::ArrowDown,
+        Key::Home => egui::Key::Home,
+        Key::End => egui::Key::End,
+        Key::A => egui::Key::A,
+        Key::C => egui::Key::C,
+        Key::V => egui::Key::V,
+        Key::X => egui::Key::X,
+        Key::Z => egui::Key::Z,
+        _ => return None,
+    })
+}
+
+fn modifiers(venus: &Venus) -> Modifiers {
+    let either = |left, right| venus.is_key_down(left) || venus.is_key_down(right);
+    let ctrl = either(Key::LControl, Key::RControl);
+    let mac_cmd = cfg!(target_os = "macos") && either(Key::LWin, Key::RWin);
+    Modifiers {
+        alt: either(Key::LAlt, Key::RAlt),
+        ctrl,
+        shift: either(Key::LShift, Key::RShift),
+        mac_cmd,
+        command: if cfg!(target_os = "macos") {
+            mac_cmd
+        } else {
+            ctrl
+        },
+    }
+}
+
+// egui's colors have their alpha multiplied in, but Venus blends them without
+fn color(color: Color32) -> Color {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    Color::from_rgba(r, g, b, a as f32 / 255.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Egui, color};
+    use crate::{DrawCommand, Settings, Venus};
+
+    #[test]
+    fn colors() {
+        let half_red = egui::Color32::from_rgba_unmultiplied(255, 0, 0, 128);
+        let converted = color(half_red);
+        assert!(converted.r > 0.99);
+        assert!((converted.a - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn draws_headless() {
+        let mut venus = Venus::headless(Settings::default());
+        let mut egui = Egui::new();
+        egui.run(&mut venus, |ctx| {
+            egui::Window::new("Debug").show(ctx, |ui| {
+                ui.label("hello");
+            });
+        });
+        let commands = venus.take_draw_commands();
+        assert!(
+            commands
+                .iter()
+                .any(|command| matches!(command, DrawCommand::Triangles { .. }))
+        );
+        assert!(!egui.textures.is_empty());
+    }
+}
@@ -1,11 +1,44 @@
+use std::{
+    hash::BuildHasher,
+    ops::RangeInclusive,
+    sync::{Arc, Mutex},
+};
+
 use fontdue::Metrics;
-use rustc_hash::FxHashMap as HashMap;
+use glam::Vec2;
+use rustc_hash::{FxBuildHasher, FxHashMap as HashMap, FxHashSet as HashSet};
+
+use crate::{Error, Rect, Texture, graphics::Graphics, profiling::profile_scope};
 
-use crate::{Error, Rect, Texture, graphics::Graphics};
+// Glyphs are packed into images this size at most, so each batch is one upload
+const BATCH_SIZE: u32 = 512;
+// Empty space between packed glyphs, so they never bleed into each other
+const GLYPH_PADDING: u32 = 1;
+// Measured widths are forgotten past this many, so measuring ever-changing text can't leak
+const MAX_CACHED_WIDTHS: usize = 4096;
 
+/// The printable ASCII characters, for `Venus::prewarm_glyphs`
+pub const ASCII_GLYPHS: RangeInclusive<char> = ' '..='~';
+/// The printable characters of Latin-1, which covers most Western European languages, for
+/// `Venus::prewarm_glyphs`
+pub const LATIN_1_GLYPHS: RangeInclusive<char> = ' '..='\u{ff}';
+
+/// A loaded font, which can lay out text without a window, see `Font::layout`
+///
+/// Fonts loaded into a `Venus` are used through their `FontHandle` instead.
 pub struct Font {
-    font: fontdue::Font,
+    font: Arc<fontdue::Font>,
     characters: HashMap<(char, u32), (Texture, Metrics)>,
+    // Text widths by a hash of the text and the size, since UIs measure the same labels each frame
+    widths: Mutex<HashMap<(u64, u32), f32>>,
+}
+
+/// A character rasterized into RGBA pixels, waiting to be uploaded
+pub(crate) struct RasterizedGlyph {
+    ch: char,
+    size: u32,
+    metrics: Metrics,
+    pixels: Vec<u8>,
 }
 
 impl Font {
@@ -13,62 +46,173 @@ impl Font {
         let font = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())
             .map_err(Error::FontError)?;
         Ok(Font {
-            font,
+            font: Arc::new(font),
             characters: HashMap::default(),
+            widths: Mutex::default(),
         })
     }
 
-    pub fn metrics(&self, ch: char, size: u32) -> Metrics {
+    pub(crate) fn metrics(&self, ch: char, size: u32) -> Metrics {
         match self.characters.get(&(ch, size)) {
             Some((_texture, size)) => *size,
             _ => self.font.metrics(ch, size as f32),
         }
     }
 
-    pub fn rasterize(
+    pub(crate) fn rasterize(
         &mut self,
         ch: char,
         size: u32,
         graphics: &mut Graphics,
-    ) -> &(Texture, Metrics) {
-        self.characters.entry((ch, size)).or_insert_with(|| {
-            let (metrics, buffer) = self.font.rasterize(ch, size as f32);
-            let buffer: Vec<_> = buffer
+    ) -> Option<&(Texture, Metrics)> {
+        if !self.characters.contains_key(&(ch, size)) {
+            let glyph = rasterize_glyph(&self.font, ch, size);
+            self.upload(vec![glyph], graphics);
+        }
+        // Missing if the glyph couldn't be uploaded
+        self.characters.get(&(ch, size))
+    }
+
+    /// Rasterize every character of the text that hasn't been drawn at this size yet, uploading
+    /// them together instead of one at a time
+    pub(crate) fn prepare(&mut self, text: &str, size: u32, graphics: &mut Graphics) {
+        let missing = self.missing(text, size);
+        if !missing.is_empty() {
+            let glyphs = missing
                 .into_iter()
-                .flat_map(|coverage| [255, 255, 255, coverage])
+                .map(|ch| rasterize_glyph(&self.font, ch, size))
                 .collect();
-            let width = metrics.width as u32;
-            let height = metrics.height as u32;
-            let handle = graphics.new_texture_from_bytes(&buffer, width, height);
+            self.upload(glyphs, graphics);
+        }
+    }
+
+    /// The characters of the text that haven't been rasterized at this size, without repeats
+    pub(crate) fn missing(&self, text: &str, size: u32) -> Vec<char> {
+        let mut missing: Vec<char> = text
+            .chars()
+            .filter(|ch| !self.characters.contains_key(&(*ch, size)))
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+        missing
+    }
+
+    /// A handle to the font's outlines, which can rasterize characters on another thread
+    pub(crate) fn rasterizer(&self) -> Arc<fontdue::Font> {
+        self.font.clone()
+    }
+
+    /// Give the atlas space of every glyph back, for when the font is unloaded or replaced
+    pub(crate) fn free_glyphs(&mut self, graphics: &mut Graphics) {
+        // Glyphs uploaded together share one image, which only needs freeing once
+        let handles: HashSet<_> = self
+            .characters
+            .drain()
+            .map(|(_, (texture, _))| texture.handle)
+            .collect();
+        for handle in handles {
+            graphics.free_texture(handle);
+        }
+    }
+
+    /// Pack glyphs into as few images as possible and upload them to the atlas
+    pub(crate) fn upload(&mut self, mut glyphs: Vec<RasterizedGlyph>, graphics: &mut Graphics) {
+        profile_scope!("glyph upload");
+        // Another draw may have needed the same glyphs while these were being rasterized
+        glyphs.retain(|glyph| !self.characters.contains_key(&(glyph.ch, glyph.size)));
+        let mut glyphs = glyphs.into_iter().peekable();
+        while glyphs.peek().is_some() {
+            let mut batch = Vec::new();
+            let (mut x, mut y, mut row_height, mut width) = (0, 0, 0, 0);
+            while let Some(glyph) = glyphs.peek() {
+                let (glyph_width, glyph_height) =
+                    (glyph.metrics.width as u32, glyph.metrics.height as u32);
+                if x > 0 && x + glyph_width > BATCH_SIZE {
+                    x = 0;
+                    y += row_height + GLYPH_PADDING;
+                    row_height = 0;
+                }
+                // Glyphs too large for a batch still get one to themselves
+                if !batch.is_empty() && y + glyph_height > BATCH_SIZE {
+                    break;
+                }
+                batch.push((glyphs.next().unwrap(), x, y));
+                x += glyph_width + GLYPH_PADDING;
+                width = width.max(x - GLYPH_PADDING);
+                row_height = row_height.max(glyph_height);
+            }
+            self.upload_batch(batch, width, y + row_height, graphics);
+        }
+    }
+
+    fn upload_batch(
+        &mut self,
+        batch: Vec<(RasterizedGlyph, u32, u32)>,
+        width: u32,
+        height: u32,
+        graphics: &mut Graphics,
+    ) {
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        for (glyph, x, y) in &batch {
+            let row_length = glyph.metrics.width * 4;
+            for (row, source) in glyph.pixels.chunks_exact(row_length.max(1)).enumerate() {
+                let start = ((y + row as u32) * width + x) as usize * 4;
+                pixels[start..start + row_length].copy_from_slice(source);
+            }
+        }
+        let handle = match graphics.new_texture_from_bytes(&pixels, width, height) {
+            Ok(handle) => handle,
+            Err(error) => {
+                log::error!("Can't upload glyphs: {error}");
+                return;
+            }
+        };
+        let scale = |value: u32, total: u32| value as f32 / total.max(1) as f32;
+        for (glyph, x, y) in batch {
+            let glyph_width = glyph.metrics.width as u32;
+            let glyph_height = glyph.metrics.height as u32;
             let texture = Texture {
                 handle,
                 uv: Rect {
-                    x: 0.,
-                    y: 0.,
-                    width: 1.,
-                    height: 1.,
+                    x: scale(x, width),
+                    y: scale(y, height),
+                    width: scale(glyph_width, width),
+                    height: scale(glyph_height, height),
                 },
-                width,
-                height,
+                width: glyph_width,
+                height: glyph_height,
             };
-            (texture, metrics)
-        })
+            self.characters
+                .insert((glyph.ch, glyph.size), (texture, glyph.metrics));
+        }
     }
 
     pub fn text_width(&self, text: &str, size: u32) -> f32 {
-        let mut width = 0.0;
+        let key = (FxBuildHasher.hash_one(text), size);
+        let mut widths = self.widths.lock().unwrap();
+        if let Some(width) = widths.get(&key) {
+            return *width;
+        }
+        let width = self.measure(text, size);
+        if widths.len() >= MAX_CACHED_WIDTHS {
+            widths.clear();
+        }
+        widths.insert(key, width);
+        width
+    }
 
-        let prev_ch = None;
+    fn measure(&self, text: &str, size: u32) -> f32 {
+        let mut width = 0.0;
+        let mut prev_ch = None;
         for ch in text.chars() {
-            if let Some(prev_ch) = prev_ch {
-                if let Some(kern) = self.font.horizontal_kern(prev_ch, ch, size as f32) {
-                    width += kern;
-                }
+            if let Some(prev_ch) = prev_ch
+                && let Some(kern) = self.font.horizontal_kern(prev_ch, ch, size as f32)
+            {
+                width += kern;
             }
-            let metrics = self.metrics(ch, size);
-            width += metrics.advance_width;
+            width += self.metrics(ch, size).advance_width;
+            prev_ch = Some(ch);
         }
-
         width
     }
 
@@ -78,11 +222,139 @@ impl Font {
             .map(|metrics| metrics.new_line_size)
             .unwrap_or(size as f32)
     }
+
+    /// Work out where each character of some text goes, the same way it would be drawn
+    ///
+    /// This only needs the font's measurements, not a window or graphics context, so it works
+    /// in tools and tests. Words wrap onto a new line when they would go past
+    /// `max_line_length`.
+    pub fn layout(&self, text: &str, size: u32, max_line_length: f32) -> TextLayout {
+        let mut layout = LayoutState {
+            font: self,
+            size,
+            line_height: self.line_height(size),
+            layout: TextLayout {
+                glyphs: Vec::new(),
+                lines: 1,
+                size: Vec2::ZERO,
+            },
+            cursor_x: 0.0,
+            topline: 0.0,
+            prev_ch: None,
+        };
+        let mut word = String::new();
+        for ch in text.chars() {
+            // TODO: also break on other characters like '-'
+            if !ch.is_whitespace() {
+                word.push(ch);
+                continue;
+            }
+
+            // Handle one word at a time
+            layout.push_word(&word, max_line_length);
+            word.clear();
+
+            if ch == '\n' {
+                layout.new_line();
+            } else {
+                layout.push(ch);
+            }
+        }
+        layout.push_word(&word, max_line_length);
+        let mut layout = layout.layout;
+        layout.size.y = layout.lines as f32 * self.line_height(size);
+        layout
+    }
+}
+
+/// Where the characters of some text go, from `Font::layout` or `Venus::text_layout`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextLayout {
+    pub glyphs: Vec<LaidOutGlyph>,
+    pub lines: usize,
+    /// The width of the longest line and the height of all the lines together
+    pub size: Vec2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LaidOutGlyph {
+    pub ch: char,
+    /// Which line the character is on, counting from 0
+    pub line: usize,
+    /// The top left of the character's image, relative to where the text starts
+    pub position: Vec2,
+    /// The size of the character's image, which is empty for whitespace
+    pub size: Vec2,
+    /// How far the next character starts after this one, before kerning
+    pub advance: f32,
+}
+
+struct LayoutState<'a> {
+    font: &'a Font,
+    size: u32,
+    line_height: f32,
+    layout: TextLayout,
+    cursor_x: f32,
+    topline: f32,
+    prev_ch: Option<char>,
+}
+
+impl LayoutState<'_> {
+    fn new_line(&mut self) {
+        self.cursor_x = 0.0;
+        self.topline += self.line_height;
+        self.layout.lines += 1;
+        self.prev_ch = None;
+    }
+
+    // Start a new line first if the word would go past the end of this one
+    fn push_word(&mut self, word: &str, max_line_length: f32) {
+        if self.cursor_x + self.font.text_width(word, self.size) > max_line_length {
+            self.new_line();
+        }
+        for ch in word.chars() {
+            self.push(ch);
+        }
+    }
+
+    fn push(&mut self, ch: char) {
+        let size = self.size as f32;
+        if let Some(prev_ch) = self.prev_ch
+            && let Some(kern) = self.font.font.horizontal_kern(prev_ch, ch, size)
+        {
+            self.cursor_x += kern;
+        }
+        let metrics = self.font.metrics(ch, self.size);
+        let y = self.topline + ((size - metrics.height as f32) - (metrics.ymin as f32));
+        self.layout.glyphs.push(LaidOutGlyph {
+            ch,
+            line: self.layout.lines - 1,
+            position: Vec2::new(self.cursor_x, y),
+            size: Vec2::new(metrics.width as f32, metrics.height as f32),
+            advance: metrics.advance_width,
+        });
+        self.cursor_x += metrics.advance_width;
+        self.layout.size.x = self.layout.size.x.max(self.cursor_x);
+        self.prev_ch = Some(ch);
+    }
+}
+
+pub(crate) fn rasterize_glyph(font: &fontdue::Font, ch: char, size: u32) -> RasterizedGlyph {
+    let (metrics, coverage) = font.rasterize(ch, size as f32);
+    let pixels = coverage
+        .into_iter()
+        .flat_map(|coverage| [255, 255, 255, coverage])
+        .collect();
+    RasterizedGlyph {
+        ch,
+        size,
+        metrics,
+        pixels,
+    }
 }
 
 #[derive(Default)]
 pub struct TextRenderer {
-    word_buffer: String,
     character_buffer: Vec<(Texture, char, f32, f32)>,
 }
 
@@ -97,96 +369,166 @@ impl TextRenderer {
         size: u32,
         max_line_length: f32,
     ) {
-        let mut cursor_x = x;
-        let mut topline = y;
-
-        let line_height = font.line_height(size);
-
-        let mut prev_ch = None;
-        for ch in text.chars() {
-            // TODO: also break on other characters like '-'
-            if !ch.is_whitespace() {
-                self.word_buffer.push(ch);
+        profile_scope!("text layout");
+        // Upload every new glyph in the text at once, rather than one by one as they're reached
+        font.prepare(text, size, gfx);
+        for glyph in font.layout(text, size, max_line_length).glyphs {
+            let Some((texture, _)) = font.rasterize(glyph.ch, size, gfx) else {
                 continue;
-            }
+            };
+            self.character_buffer.push((
+                texture.clone(),
+                glyph.ch,
+                x + glyph.position.x,
+                y + glyph.position.y,
+            ));
+        }
+    }
 
-            // Handle one word at a time
-            let word_length = font.text_width(&self.word_buffer, size);
+    pub fn characters(&mut self) -> impl Iterator<Item = (Texture, char, f32, f32)> {
+        self.character_buffer.drain(..)
+    }
+}
 
-            if cursor_x + word_length > x + max_line_length {
-                cursor_x = x;
-                topline += line_height;
-                prev_ch = None;
-            }
+#[cfg(test)]
+mod test {
+    use glam::Vec2;
 
-            for ch in self.word_buffer.drain(..) {
-                Self::push_character(
-                    ch,
-                    &mut cursor_x,
-                    topline,
-                    size,
-                    &mut prev_ch,
-                    gfx,
-                    font,
-                    &mut self.character_buffer,
-                );
-            }
+    use super::Font;
+    use crate::{Settings, Venus};
 
-            if ch == '\n' {
-                cursor_x = x;
-                topline += line_height;
-                prev_ch = None;
-            } else {
-                Self::push_character(
-                    ch,
-                    &mut cursor_x,
-                    topline,
-                    size,
-                    &mut prev_ch,
-                    gfx,
-                    font,
-                    &mut self.character_buffer,
-                );
+    // A TrueType font with a box for each printable ASCII character, so tests don't need a file
+    //
+    // At 1000 units per em every glyph is 500 units wide, and the box covers 100 to 400 across and
+    // 0 to 600 up. The ascent is 800 and the descent 200.
+    fn test_font() -> Vec<u8> {
+        // Big-endian numbers, each with its own size in bytes
+        fn numbers(values: &[(i32, usize)]) -> Vec<u8> {
+            let mut bytes = Vec::new();
+            for (value, size) in values {
+                bytes.extend_from_slice(&value.to_be_bytes()[4 - size..]);
             }
+            bytes
         }
-
-        for ch in self.word_buffer.drain(..) {
-            Self::push_character(
-                ch,
-                &mut cursor_x,
-                topline,
-                size,
-                &mut prev_ch,
-                gfx,
-                font,
-                &mut self.character_buffer,
-            );
+        let words =
+            |values: &[i32]| numbers(&values.iter().map(|value| (*value, 2)).collect::<Vec<_>>());
+        let head = [
+            numbers(&[(0x10000, 4), (0x10000, 4), (0, 4), (0x5F0F3CF5, 4)]),
+            words(&[0, 1000]),
+            vec![0; 16],
+            words(&[0, 0, 500, 600, 0, 8, 2, 1, 0]),
+        ]
+        .concat();
+        let hhea = [
+            numbers(&[(0x10000, 4)]),
+            words(&[800, -200, 0, 500, 0, 0, 500, 1, 0, 0, 0, 0, 0, 0, 0, 3]),
+        ]
+        .concat();
+        let maxp = numbers(&[(0x5000, 4), (3, 2)]);
+        // Glyph 0 is missing characters, 1 is a space, and 2 is the box
+        let hmtx = words(&[500, 0, 500, 0, 500, 100]);
+        let glyf = [
+            words(&[1, 100, 0, 400, 600, 3, 0]),
+            vec![1; 4],
+            words(&[100, 0, 300, 0, 0, 600, 0, -600]),
+        ]
+        .concat();
+        let loca = numbers(&[(0, 4), (0, 4), (0, 4), (glyf.len() as i32, 4)]);
+        let glyphs: Vec<i32> = (' '..='~')
+            .map(|ch| if ch == ' ' { 1 } else { 2 })
+            .collect();
+        let cmap = [
+            words(&[0, 1, 3, 1]),
+            numbers(&[(12, 4)]),
+            words(&[
+                6,
+                10 + 2 * glyphs.len() as i32,
+                0,
+                ' ' as i32,
+                glyphs.len() as i32,
+            ]),
+            words(&glyphs),
+        ]
+        .concat();
+        let tables = [
+            (b"cmap", cmap),
+            (b"glyf", glyf),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"loca", loca),
+            (b"maxp", maxp),
+        ];
+        let directory_size = 12 + 16 * tables.len();
+        let mut font = numbers(&[
+            (0x10000, 4),
+            (tables.len() as i32, 2),
+            (0, 2),
+            (0, 2),
+            (0, 2),
+        ]);
+        let mut data = Vec::new();
+        for (tag, table) in &tables {
+            let offset = (directory_size + data.len()) as i32;
+            font.extend_from_slice(*tag);
+            font.extend(numbers(&[(0, 4), (offset, 4), (table.len() as i32, 4)]));
+            data.extend_from_slice(table);
+            data.resize(data.len().next_multiple_of(4), 0);
         }
+        font.extend(data);
+        font
     }
 
-    pub fn characters(&mut self) -> impl Iterator<Item = (Texture, char, f32, f32)> {
-        self.character_buffer.drain(..)
+    #[test]
+    fn layout() {
+        let font = Font::from_bytes(&test_font()).unwrap();
+        // Each character is 10 wide at size 20, and its box starts 8 below the top of the line
+        let layout = font.layout("ab cd ef", 20, 45.0);
+        assert_eq!(layout.lines, 3);
+        assert_eq!(layout.size, Vec2::new(30.0, 60.0));
+        let placed: Vec<_> = layout
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.ch != ' ')
+            .map(|glyph| (glyph.ch, glyph.line, glyph.position))
+            .collect();
+        assert_eq!(
+            placed,
+            [
+                ('a', 0, Vec2::new(0.0, 8.0)),
+                ('b', 0, Vec2::new(10.0, 8.0)),
+                ('c', 1, Vec2::new(0.0, 28.0)),
+                ('d', 1, Vec2::new(10.0, 28.0)),
+                ('e', 2, Vec2::new(0.0, 48.0)),
+                ('f', 2, Vec2::new(10.0, 48.0)),
+            ]
+        );
+        assert_eq!(layout.glyphs[0].size, Vec2::new(6.0, 12.0));
+        assert_eq!(layout.glyphs[0].advance, 10.0);
+
+        let lines = font.layout("a\nb", 20, f32::MAX);
+        assert_eq!(lines.lines, 2);
+        assert_eq!(lines.glyphs.len(), 2);
+        assert_eq!(lines.glyphs[1].position, Vec2::new(0.0, 28.0));
     }
 
-    fn push_character(
-        ch: char,
-        cursor_x: &mut f32,
-        topline: f32,
-        size: u32,
-        prev_ch: &mut Option<char>,
-        gfx: &mut Graphics,
-        font: &mut Font,
-        character_buffer: &mut Vec<(Texture, char, f32, f32)>,
-    ) {
-        if let Some(prev_ch) = prev_ch {
-            if let Some(kern) = font.font.horizontal_kern(*prev_ch, ch, size as f32) {
-                *cursor_x += kern;
-            }
-        }
-        let (texture, metrics) = font.rasterize(ch, size, gfx);
-        let y = topline + ((size as f32 - metrics.height as f32) - (metrics.ymin as f32));
-        character_buffer.push((texture.clone(), ch, *cursor_x, y));
-        *cursor_x += metrics.advance_width;
-        *prev_ch = Some(ch);
+    #[test]
+    fn freeing_glyphs() {
+        let mut venus = Venus::headless(Settings::default());
+        let mut font = Font::from_bytes(&test_font()).unwrap();
+        font.prepare("Hi!", 20, &mut venus.gfx);
+        let handle = font.characters[&('H', 20)].0.handle;
+        assert_eq!(font.characters[&('!', 20)].0.handle, handle);
+        assert!(venus.gfx.read_texture(handle).is_some());
+
+        font.free_glyphs(&mut venus.gfx);
+        assert!(font.characters.is_empty());
+        assert!(venus.gfx.read_texture(handle).is_none());
+    }
+
+    #[test]
+    fn shareable_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Font>();
     }
 }
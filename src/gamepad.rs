@@ -0,0 +1,263 @@
+use blinds::Event;
+pub use blinds::{GamepadAxis, GamepadButton, GamepadId};
+use glam::Vec2;
+use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
+
+use crate::Venus;
+
+/// One of a controller's two analog sticks
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+impl Stick {
+    fn axes(self) -> (GamepadAxis, GamepadAxis) {
+        match self {
+            Stick::Left => (GamepadAxis::LeftStickX, GamepadAxis::LeftStickY),
+            Stick::Right => (GamepadAxis::RightStickX, GamepadAxis::RightStickY),
+        }
+    }
+}
+
+/// Which stick positions count as inside the deadzone
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DeadzoneShape {
+    /// A circle around the center, which keeps diagonals smooth
+    Radial,
+    /// A cross along each axis, which makes it easy to push in exactly one direction
+    Axial,
+}
+
+/// How the distance past the deadzone maps to the reported value, from 0 to 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResponseCurve {
+    Linear,
+    /// Raise to a power, where powers above 1 give more precision near the center
+    Power(f32),
+}
+
+impl ResponseCurve {
+    fn apply(self, amount: f32) -> f32 {
+        match self {
+            ResponseCurve::Linear => amount,
+            ResponseCurve::Power(power) => amount.powf(power),
+        }
+    }
+}
+
+/// How raw stick positions are cleaned up before being reported, see `Venus::gamepad_stick`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StickSettings {
+    pub shape: DeadzoneShape,
+    /// Positions closer to the center than this are reported as 0
+    pub deadzone: f32,
+    /// Positions further from the center than this are reported as fully pushed
+    pub saturation: f32,
+    pub curve: ResponseCurve,
+}
+
+impl Default for StickSettings {
+    fn default() -> StickSettings {
+        StickSettings {
+            shape: DeadzoneShape::Radial,
+            deadzone: 0.15,
+            saturation: 0.95,
+            curve: ResponseCurve::Linear,
+        }
+    }
+}
+
+impl StickSettings {
+    /// Process a single axis, like a trigger or one direction of a stick
+    pub fn apply_axis(&self, raw: f32) -> f32 {
+        raw.signum() * self.rescale(raw.abs())
+    }
+
+    /// Process a stick position, returning a vector no longer than 1
+    pub fn apply(&self, raw: Vec2) -> Vec2 {
+        match self.shape {
+            DeadzoneShape::Radial => {
+                let length = raw.length();
+                if length == 0.0 {
+                    return Vec2::ZERO;
+                }
+                raw / length * self.rescale(length)
+            }
+            DeadzoneShape::Axial => {
+                Vec2::new(self.apply_axis(raw.x), self.apply_axis(raw.y)).clamp_length_max(1.0)
+            }
+        }
+    }
+
+    // Map the distance from the center between the deadzone and saturation onto 0 to 1
+    fn rescale(&self, distance: f32) -> f32 {
+        let range = (self.saturation - self.deadzone).max(f32::EPSILON);
+        let amount = ((distance - self.deadzone) / range).clamp(0.0, 1.0);
+        self.curve.apply(amount)
+    }
+}
+
+/// A controller being plugged in or unplugged, see `Venus::gamepad_events`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+}
+
+#[derive(Default)]
+pub(crate) struct Gamepads {
+    // In the order they were connected, so the first is a reasonable choice for player one
+    connected: Vec<GamepadId>,
+    // Since the input cache was last cleared
+    events: Vec<GamepadEvent>,
+    buttons: HashSet<(GamepadId, GamepadButton)>,
+    just_pressed: HashSet<(GamepadId, GamepadButton)>,
+    axes: HashMap<(GamepadId, GamepadAxis), f32>,
+    stick_settings: [StickSettings; 2],
+}
+
+impl Gamepads {
+    pub fn handle(&mut self, event: &Event) {
+        match event {
+            Event::GamepadConnected(e) => {
+                let id = e.id();
+                if !self.connected.contains(&id) {
+                    self.connected.push(id);
+                }
+                self.events.push(GamepadEvent::Connected(id));
+                log::debug!("Gamepad connected: {id:?}");
+            }
+            Event::GamepadDisconnected(e) => {
+                let id = e.id();
+                self.connected.retain(|pad| *pad != id);
+                self.buttons.retain(|(pad, _)| *pad != id);
+                self.axes.retain(|(pad, _), _| *pad != id);
+                self.events.push(GamepadEvent::Disconnected(id));
+                log::debug!("Gamepad disconnected: {id:?}");
+            }
+            Event::GamepadButton(e) => {
+                let key = (e.id(), e.button());
+                if e.is_pressed() {
+                    if self.buttons.insert(key) {
+                        self.just_pressed.insert(key);
+                    }
+                } else {
+                    self.buttons.remove(&key);
+                }
+            }
+            Event::GamepadAxis(e) => {
+                self.axes.insert((e.id(), e.axis()), e.value());
+            }
+            _ => {}
+        }
+    }
+
+    pub fn clear_events(&mut self) {
+        self.events.clear();
+        self.just_pressed.clear();
+    }
+
+    pub fn connected(&self) -> &[GamepadId] {
+        &self.connected
+    }
+
+    pub fn events(&self) -> &[GamepadEvent] {
+        &self.events
+    }
+
+    pub fn is_button_down(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.buttons.contains(&(id, button))
+    }
+
+    pub fn is_button_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.just_pressed.contains(&(id, button))
+    }
+
+    pub fn axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.axes.get(&(id, axis)).copied().unwrap_or(0.0)
+    }
+
+    pub fn stick(&self, id: GamepadId, stick: Stick) -> Vec2 {
+        let (x, y) = stick.axes();
+        let raw = Vec2::new(self.axis(id, x), self.axis(id, y));
+        self.stick_settings[stick as usize].apply(raw)
+    }
+
+    pub fn set_stick_settings(&mut self, stick: Stick, settings: StickSettings) {
+        self.stick_settings[stick as usize] = settings;
+    }
+}
+
+impl Venus {
+    /// The controllers currently plugged in, in the order they were connected
+    pub fn gamepads(&self) -> &[GamepadId] {
+        self.gamepads.connected()
+    }
+
+    /// Controllers connected or disconnected since the input cache was last cleared
+    ///
+    /// Pads that are already plugged in when the game starts show up as connected on the first
+    /// frame.
+    pub fn gamepad_events(&self) -> &[GamepadEvent] {
+        self.gamepads.events()
+    }
+
+    pub fn is_gamepad_button_down(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads.is_button_down(id, button)
+    }
+
+    pub fn is_gamepad_button_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads.is_button_pressed(id, button)
+    }
+
+    /// The raw position of a stick or trigger, from -1 to 1, or 0 if the pad isn't connected
+    pub fn gamepad_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.gamepads.axis(id, axis)
+    }
+
+    /// The position of a stick with its deadzone, saturation, and response curve applied
+    pub fn gamepad_stick(&self, id: GamepadId, stick: Stick) -> Vec2 {
+        self.gamepads.stick(id, stick)
+    }
+
+    /// Change how a stick's raw position is processed, for every pad
+    pub fn set_stick_settings(&mut self, stick: Stick, settings: StickSettings) {
+        self.gamepads.set_stick_settings(stick, settings);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use glam::Vec2;
+
+    use super::{DeadzoneShape, ResponseCurve, StickSettings};
+
+    #[test]
+    fn deadzones() {
+        let radial = StickSettings {
+            shape: DeadzoneShape::Radial,
+            deadzone: 0.2,
+            saturation: 0.8,
+            curve: ResponseCurve::Linear,
+        };
+        assert_eq!(radial.apply(Vec2::new(0.1, -0.1)), Vec2::ZERO);
+        assert!((radial.apply(Vec2::new(0.5, 0.0)).x - 0.5).abs() < 1e-5);
+        assert_eq!(radial.apply(Vec2::new(0.0, -0.9)), Vec2::new(0.0, -1.0));
+        // Pushing along one axis with a little drift on the other stays off the other axis
+        let axial = StickSettings {
+            shape: DeadzoneShape::Axial,
+            ..radial
+        };
+        assert_eq!(axial.apply(Vec2::new(0.9, 0.15)), Vec2::new(1.0, 0.0));
+        assert!(axial.apply(Vec2::splat(0.9)).length() <= 1.0);
+
+        let curved = StickSettings {
+            curve: ResponseCurve::Power(2.0),
+            ..radial
+        };
+        assert!((curved.apply_axis(-0.5) + 0.25).abs() < 1e-5);
+        assert_eq!(curved.apply_axis(0.0), 0.0);
+    }
+}
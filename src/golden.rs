@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use image::RgbaImage;
+
+use crate::Venus;
+
+impl Venus {
+    /// What has been drawn since the last clear, when running headless
+    ///
+    /// Headless drawing happens on the CPU, and follows the same rules as the GPU closely enough
+    /// to compare frames against reference images with `assert_golden`. Always None when running
+    /// in a window.
+    pub fn snapshot(&self) -> Option<RgbaImage> {
+        self.gfx.snapshot()
+    }
+}
+
+/// How different two images are, see `compare_images`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageDiff {
+    /// The number of pixels with a channel that differs by more than the tolerance
+    pub differing_pixels: usize,
+    /// The largest difference in any channel of any pixel
+    pub max_difference: u8,
+}
+
+impl ImageDiff {
+    pub fn is_match(&self) -> bool {
+        self.differing_pixels == 0
+    }
+}
+
+/// Compare two images channel by channel, allowing each channel to differ by the tolerance
+///
+/// Images of different sizes never match.
+pub fn compare_images(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> ImageDiff {
+    if actual.dimensions() != expected.dimensions() {
+        return ImageDiff {
+            differing_pixels: actual.len().max(expected.len()) / 4,
+            max_difference: u8::MAX,
+        };
+    }
+    let mut diff = ImageDiff {
+        differing_pixels: 0,
+        max_difference: 0,
+    };
+    for (actual, expected) in actual.pixels().zip(expected.pixels()) {
+        let difference = actual
+            .0
+            .iter()
+            .zip(expected.0)
+            .map(|(actual, expected)| actual.abs_diff(expected))
+            .max()
+            .unwrap_or(0);
+        diff.max_difference = diff.max_difference.max(difference);
+        if difference > tolerance {
+            diff.differing_pixels += 1;
+        }
+    }
+    diff
+}
+
+/// Assert that an image matches the reference image at a path, within a tolerance per channel
+///
+/// If the reference doesn't exist yet, or the `VENUS_BLESS` environment variable is set, the
+/// image is saved as the new reference instead. On a mismatch, the image is saved next to the
+/// reference with `.actual.png` on the end, to compare by eye.
+///
+/// ```ignore
+/// let mut venus = Venus::headless(Settings::default());
+/// draw_menu(&mut venus);
+/// assert_golden(&venus.snapshot().unwrap(), "tests/golden/menu.png", 2);
+/// ```
+#[track_caller]
+pub fn assert_golden(image: &RgbaImage, reference: impl AsRef<Path>, tolerance: u8) {
+    let reference = reference.as_ref();
+    if std::env::var_os("VENUS_BLESS").is_some() || !reference.exists() {
+        if let Some(parent) = reference.parent() {
+            std::fs::create_dir_all(parent).expect("create the reference image's directory");
+        }
+        image.save(reference).expect("save the reference image");
+        return;
+    }
+    let expected = image::open(reference)
+        .expect("open the reference image")
+        .into_rgba8();
+    let diff = compare_images(image, &expected, tolerance);
+    if !diff.is_match() {
+        let actual = reference.with_extension("actual.png");
+        image.save(&actual).expect("save the mismatched image");
+        panic!(
+            "{} pixels differ from {} by up to {}; the rendered image is at {}",
+            diff.differing_pixels,
+            reference.display(),
+            diff.max_difference,
+            actual.display(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use image::{Rgba, RgbaImage};
+
+    use super::{assert_golden, compare_images};
+    use crate::{Color, Settings, Venus};
+
+    #[test]
+    fn comparison() {
+        let expected = RgbaImage::from_pixel(2, 2, Rgba([100, 100, 100, 255]));
+        let mut actual = expected.clone();
+        actual.put_pixel(0, 0, Rgba([103, 100, 100, 255]));
+        assert!(compare_images(&actual, &expected, 3).is_match());
+        let diff = compare_images(&actual, &expected, 2);
+        assert_eq!(diff.differing_pixels, 1);
+        assert_eq!(diff.max_difference, 3);
+        assert!(!compare_images(&RgbaImage::new(1, 2), &expected, 255).is_match());
+    }
+
+    #[test]
+    fn headless_snapshot() {
+        let mut venus = Venus::headless(Settings {
+            width: 8.0,
+            height: 4.0,
+            ..Settings::default()
+        });
+        venus.clear(Color::BLACK);
+        venus.draw_rect((0.0, 0.0), (4.0, 4.0), Color::RED);
+        let image = venus.snapshot().unwrap();
+        assert_eq!(image.dimensions(), (8, 4));
+        assert_eq!(image.get_pixel(1, 1).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(6, 1).0, [0, 0, 0, 255]);
+
+        let reference = std::env::temp_dir().join("venus-golden-test/rect.png");
+        let _ = std::fs::remove_file(&reference);
+        // The first run saves the reference, and the second compares against it
+        assert_golden(&image, &reference, 0);
+        assert_golden(&image, &reference, 0);
+    }
+}
@@ -0,0 +1,308 @@
+use glam::Vec2;
+
+use crate::{Circle, Color, Rect, Venus};
+
+// Radial gradients are split into cells this many times smaller than their radius, since colors
+// are only blended in straight lines between corners
+const RADIAL_STEPS: f32 = 8.0;
+// The most cells a shape is split into along each side, however small the radius
+const MAX_CELLS: f32 = 64.0;
+
+/// Colors blended across a shape, for skies, UI backgrounds, and glows
+///
+/// Positions are in the same coordinates as the shapes being filled. Past the first and last
+/// stops, their colors carry on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    shape: GradientShape,
+    // Sorted by offset
+    stops: Vec<(f32, Color)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientShape {
+    /// Blends along the line from one point to the other
+    Linear { from: Vec2, to: Vec2 },
+    /// Blends outwards from the center, reaching the end at the radius
+    Radial { center: Vec2, radius: f32 },
+}
+
+impl Gradient {
+    /// Blend from one color at one point to another color at another
+    pub fn linear(
+        from: impl Into<Vec2>,
+        to: impl Into<Vec2>,
+        start: Color,
+        end: Color,
+    ) -> Gradient {
+        Gradient {
+            shape: GradientShape::Linear {
+                from: from.into(),
+                to: to.into(),
+            },
+            stops: vec![(0.0, start), (1.0, end)],
+        }
+    }
+
+    /// Blend from one color at the center to another color at the radius
+    pub fn radial(center: impl Into<Vec2>, radius: f32, inner: Color, outer: Color) -> Gradient {
+        Gradient {
+            shape: GradientShape::Radial {
+                center: center.into(),
+                radius,
+            },
+            stops: vec![(0.0, inner), (1.0, outer)],
+        }
+    }
+
+    /// Add a color partway through, where 0 is the start and 1 is the end
+    pub fn with_stop(mut self, offset: f32, color: Color) -> Gradient {
+        let index = self.stops.partition_point(|(other, _)| *other <= offset);
+        self.stops.insert(index, (offset, color));
+        self
+    }
+
+    pub fn shape(&self) -> GradientShape {
+        self.shape
+    }
+
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    /// The color at an offset along the gradient, where 0 is the start and 1 is the end
+    pub fn color_at(&self, offset: f32) -> Color {
+        let next = self.stops.partition_point(|(other, _)| *other <= offset);
+        if next == 0 {
+            return self.stops[0].1;
+        }
+        let (start, from) = self.stops[next - 1];
+        let Some((end, to)) = self.stops.get(next) else {
+            return from;
+        };
+        from.lerp(*to, (offset - start) / (end - start))
+    }
+
+    /// The color at a point
+    pub fn sample(&self, point: impl Into<Vec2>) -> Color {
+        self.color_at(self.offset(point.into()))
+    }
+
+    fn offset(&self, point: Vec2) -> f32 {
+        match self.shape {
+            GradientShape::Linear { from, to } => {
+                let direction = to - from;
+                let length_squared = direction.length_squared();
+                if length_squared > 0.0 {
+                    (point - from).dot(direction) / length_squared
+                } else {
+                    0.0
+                }
+            }
+            GradientShape::Radial { center, radius } => {
+                if radius > 0.0 {
+                    point.distance(center) / radius
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+
+    /// Split a convex polygon into triangles with a color at each corner
+    ///
+    /// Linear gradients are cut at each stop, so blending between corners matches exactly.
+    /// Radial gradients aren't straight, so they're cut into a grid of small cells instead.
+    pub(crate) fn triangulate(&self, points: &[Vec2]) -> (Vec<(Vec2, Vec2, Color)>, Vec<u32>) {
+        let mut pieces = Vec::new();
+        match self.shape {
+            GradientShape::Linear { from, to } => {
+                let direction = to - from;
+                let length_squared = direction.length_squared();
+                if length_squared > 0.0 {
+                    // Offsets along the gradient are measured in the same units as dot products
+                    let axis = direction / length_squared;
+                    let base = from.dot(axis);
+                    let mut start = f32::NEG_INFINITY;
+                    for (offset, _) in &self.stops {
+                        pieces.push(clip(points, axis, base + start, base + offset));
+                        start = *offset;
+                    }
+                    pieces.push(clip(points, axis, base + start, f32::INFINITY));
+                } else {
+                    pieces.push(points.to_vec());
+                }
+            }
+            GradientShape::Radial { radius, .. } => {
+                let bounds = bounds(points);
+                let cell = (radius / RADIAL_STEPS)
+                    .max(bounds.width / MAX_CELLS)
+                    .max(bounds.height / MAX_CELLS);
+                if cell > 0.0 {
+                    let columns = (bounds.width / cell).ceil().max(1.0) as u32;
+                    let rows = (bounds.height / cell).ceil().max(1.0) as u32;
+                    for row in 0..rows {
+                        let top = bounds.y + row as f32 * cell;
+                        let band = clip(points, Vec2::Y, top, top + cell);
+                        for column in 0..columns {
+                            let left = bounds.x + column as f32 * cell;
+                            pieces.push(clip(&band, Vec2::X, left, left + cell));
+                        }
+                    }
+                } else {
+                    pieces.push(points.to_vec());
+                }
+            }
+        }
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for piece in pieces
+            .into_iter()
+            .filter(|piece| area(piece) > f32::EPSILON)
+        {
+            let first = vertices.len() as u32;
+            vertices.extend(
+                piece
+                    .iter()
+                    .map(|point| (*point, Vec2::ZERO, self.sample(*point))),
+            );
+            indices.extend(
+                (1..piece.len() as u32 - 1).flat_map(|i| [first, first + i, first + i + 1]),
+            );
+        }
+        (vertices, indices)
+    }
+}
+
+// The part of a convex polygon where the dot product with the axis is between min and max
+fn clip(points: &[Vec2], axis: Vec2, min: f32, max: f32) -> Vec<Vec2> {
+    let above = clip_half(points, |point| point.dot(axis) - min);
+    clip_half(&above, |point| max - point.dot(axis))
+}
+
+// The part of a convex polygon where the distance is at least 0
+fn clip_half(points: &[Vec2], distance: impl Fn(Vec2) -> f32) -> Vec<Vec2> {
+    let mut clipped = Vec::with_capacity(points.len() + 1);
+    for (index, point) in points.iter().enumerate() {
+        let next = points[(index + 1) % points.len()];
+        let (here, there) = (distance(*point), distance(next));
+        if here >= 0.0 {
+            clipped.push(*point);
+        }
+        // Corners exactly on the line are kept as they are, rather than repeated
+        if (here > 0.0 && there < 0.0) || (here < 0.0 && there > 0.0) {
+            clipped.push(point.lerp(next, here / (here - there)));
+        }
+    }
+    clipped
+}
+
+fn area(points: &[Vec2]) -> f32 {
+    let doubled: f32 = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(point, next)| point.perp_dot(*next))
+        .sum();
+    doubled.abs() / 2.0
+}
+
+fn bounds(points: &[Vec2]) -> Rect {
+    let min = points.iter().copied().reduce(Vec2::min).unwrap_or_default();
+    let max = points.iter().copied().reduce(Vec2::max).unwrap_or_default();
+    Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+}
+
+impl Venus {
+    pub fn draw_rect_gradient(
+        &mut self,
+        position: impl Into<Vec2>,
+        size: impl Into<Vec2>,
+        gradient: &Gradient,
+    ) {
+        let position = position.into();
+        let size = size.into();
+        let rect = Rect::new(position.x, position.y, size.x, size.y);
+        let corners = [
+            rect.top_left(),
+            Vec2::new(rect.x + rect.width, rect.y),
+            rect.bottom_right(),
+            Vec2::new(rect.x, rect.y + rect.height),
+        ];
+        self.draw_polygon_gradient(&corners, gradient);
+    }
+
+    pub fn draw_circle_gradient(
+        &mut self,
+        center: impl Into<Vec2>,
+        radius: f32,
+        gradient: &Gradient,
+    ) {
+        let points: Vec<Vec2> = Circle::new(center.into(), radius).outline().collect();
+        self.draw_polygon_gradient(&points, gradient);
+    }
+
+    /// Fill a convex polygon, with points in order around it, with a gradient
+    pub fn draw_polygon_gradient(&mut self, points: &[Vec2], gradient: &Gradient) {
+        let (vertices, indices) = gradient.triangulate(points);
+        if !indices.is_empty() {
+            self.gfx.push_triangles(&vertices, &indices, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+    use glam::Vec2;
+
+    use super::Gradient;
+    use crate::{Color, DrawCommand, Settings, Venus};
+
+    #[test]
+    fn stops() {
+        let sky = Gradient::linear((0.0, 0.0), (0.0, 100.0), Color::BLUE, Color::BLACK)
+            .with_stop(0.5, Color::WHITE);
+        assert_eq!(sky.sample((50.0, -10.0)), Color::BLUE);
+        assert_eq!(sky.sample((0.0, 50.0)), Color::WHITE);
+        assert_eq!(sky.sample((0.0, 200.0)), Color::BLACK);
+        assert_abs_diff_eq!(sky.sample((0.0, 75.0)).r, 0.5, epsilon = 1e-5);
+
+        let glow = Gradient::radial((0.0, 0.0), 10.0, Color::WHITE, Color::BLACK);
+        assert_abs_diff_eq!(glow.sample((0.0, 5.0)).r, 0.5, epsilon = 1e-5);
+        assert_eq!(glow.sample((20.0, 0.0)), Color::BLACK);
+    }
+
+    #[test]
+    fn fills() {
+        let sky = Gradient::linear((0.0, 0.0), (0.0, 100.0), Color::BLUE, Color::BLACK)
+            .with_stop(0.5, Color::WHITE);
+        let square = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(100.0, 0.0),
+            Vec2::new(100.0, 100.0),
+            Vec2::new(0.0, 100.0),
+        ];
+        let (vertices, indices) = sky.triangulate(&square);
+        // One band between each pair of stops, each made of two triangles
+        assert_eq!(indices.len(), 2 * 2 * 3);
+        assert!(
+            vertices
+                .iter()
+                .any(|(point, _, color)| point.y == 50.0 && *color == Color::WHITE)
+        );
+
+        let mut venus = Venus::headless(Settings::default());
+        let glow = Gradient::radial((50.0, 50.0), 50.0, Color::WHITE, Color::BLACK);
+        venus.draw_rect_gradient((0.0, 0.0), (100.0, 100.0), &glow);
+        let commands = venus.take_draw_commands();
+        let [DrawCommand::Triangles { vertices, .. }] = &commands[..] else {
+            panic!("expected triangles, got {commands:?}");
+        };
+        let center = vertices
+            .iter()
+            .find(|(point, _)| *point == Vec2::new(50.0, 50.0))
+            .unwrap();
+        assert_eq!(center.1, Color::WHITE);
+    }
+}
@@ -1,31 +1,104 @@
 use std::num::NonZeroU32;
 
-use glam::Mat3;
+use glam::{Mat3, Vec2};
 use golem::{
     Attribute, AttributeType, ElementBuffer, GeometryMode, ShaderDescription, ShaderProgram,
     Uniform, UniformType, UniformValue, VertexBuffer,
 };
+#[cfg(feature = "images")]
+use image::RgbaImage;
 
 use crate::{
-    Color,
+    Color, ColorLut, Error,
+    capture::{CapturedCommand, FrameCapture},
+    post_process::PostProcess,
+    profiling::profile_scope,
     shape::Rect,
-    texture_atlas::{TextureAtlas, TextureHandle},
+    software::{SoftwareTarget, SoftwareVertex},
+    texture_atlas::{TextureAtlas, TextureHandle, bind_point_for_page},
+    transform::Transform,
 };
 
 pub struct Graphics {
-    ctx: golem::Context,
-    vb: VertexBuffer,
-    eb: ElementBuffer,
-    shader: ShaderProgram,
+    // None when running headless, in which case draws are recorded instead
+    gpu: Option<Gpu>,
+    recorded: Vec<DrawCommand>,
+    // What the recorded draws would look like, when running headless
+    software: Option<SoftwareTarget>,
+    viewport: Rect,
+    // The viewport in OpenGL's terms, from the bottom left
+    gl_viewport: [u32; 4],
+    window_size: [u32; 2],
+    // Applied to the whole frame once it's finished, by drawing it off screen first
+    color_filter: Option<Mat3>,
+    // Applied after the color filter, and uploaded when the next frame begins if it changed
+    color_lut: Option<ColorLut>,
+    color_lut_changed: bool,
+    color_lut_strength: f32,
     vertex_data: Vec<f32>,
+    // Left empty while the batch is only quads, which draw from a shared index buffer instead
     index_data: Vec<u32>,
+    quads_only: bool,
     vertices: u32,
+    // The most vertex and index data flushed at once, which every GPU buffer is grown to fit
+    largest_batch: (usize, usize),
     atlas: TextureAtlas,
     bound_texture: Option<NonZeroU32>,
+    projection: Mat3,
+    transform: Transform,
+    culling: bool,
+    // The logical resolution, when quads are snapped to its pixels
+    pixel_snap: Option<Vec2>,
+    flushes: u32,
+    // Some while capturing a frame, see `Venus::capture_frame`
+    capture: Option<Vec<CapturedCommand>>,
+    finished_capture: Option<FrameCapture>,
+}
+
+struct Gpu {
+    ctx: golem::Context,
+    // Flushes take turns between buffers, so writing a batch never waits for the GPU to finish
+    // drawing the one before it
+    buffers: Vec<(VertexBuffer, ElementBuffer)>,
+    next_buffer: usize,
+    // Indices for drawing quads from four vertices each, which never change
+    quad_indices: ElementBuffer,
+    quad_capacity: usize,
+    shader: ShaderProgram,
+    // Created the first time a color filter is used
+    post_process: Option<PostProcess>,
+}
+
+const BUFFER_COUNT: usize = 3;
+// How many atlas pages the shader can sample without switching which is bound
+const SAMPLED_PAGES: u32 = 4;
+
+/// Something drawn while running headless or capturing a frame, in world coordinates after any
+/// pushed transforms
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCommand {
+    Clear(Color),
+    /// A quad with corners in clockwise order, starting from the one matching the top left of
+    /// the texture
+    Quad {
+        corners: [Vec2; 4],
+        color: Color,
+        texture: Option<TextureHandle>,
+    },
+    Polygon {
+        points: Vec<Vec2>,
+        color: Color,
+    },
+    Triangles {
+        vertices: Vec<(Vec2, Color)>,
+        indices: Vec<u32>,
+        texture: Option<TextureHandle>,
+    },
 }
 
 impl Graphics {
-    pub fn new(ctx: golem::Context) -> Graphics {
+    /// Graphics drawing through a context, which can sample from a number of texture units
+    pub fn new(ctx: golem::Context, texture_units: u32) -> Result<Graphics, Error> {
         use golem::Dimension::*;
         let mut shader = ShaderProgram::new(
             &ctx,
@@ -34,13 +107,19 @@ impl Graphics {
                     Attribute::new("vert_color", AttributeType::Vector(D4)),
                     Attribute::new("vert_position", AttributeType::Vector(D2)),
                     Attribute::new("vert_uv", AttributeType::Vector(D2)),
+                    Attribute::new("vert_page", AttributeType::Scalar),
                 ],
                 fragment_input: &[
                     Attribute::new("frag_color", AttributeType::Vector(D4)),
                     Attribute::new("frag_uv", AttributeType::Vector(D2)),
+                    Attribute::new("frag_page", AttributeType::Scalar),
                 ],
                 uniforms: &[
                     Uniform::new("image", UniformType::Sampler2D),
+                    Uniform::new("page0", UniformType::Sampler2D),
+                    Uniform::new("page1", UniformType::Sampler2D),
+                    Uniform::new("page2", UniformType::Sampler2D),
+                    Uniform::new("page3", UniformType::Sampler2D),
                     Uniform::new("projection", UniformType::Matrix(D3)),
                 ],
                 vertex_shader: r#" void main() {
@@ -48,17 +127,30 @@ impl Graphics {
                 gl_Position = vec4(transformed.xy, 0, 1);
                 frag_uv = vert_uv;
                 frag_color = vert_color;
+                frag_page = vert_page;
             }"#,
+                // Samplers can't be indexed by a varying, so each page gets a branch, and pages
+                // past the first few use whichever is bound to image
                 fragment_shader: r#" void main() {
                 vec4 tex = vec4(1);
                 if(frag_uv.x >= 0.0 && frag_uv.y >= 0.0) {
-                    tex = texture(image, frag_uv);
+                    if(frag_page < 0.5) {
+                        tex = texture(page0, frag_uv);
+                    } else if(frag_page < 1.5) {
+                        tex = texture(page1, frag_uv);
+                    } else if(frag_page < 2.5) {
+                        tex = texture(page2, frag_uv);
+                    } else if(frag_page < 3.5) {
+                        tex = texture(page3, frag_uv);
+                    } else {
+                        tex = texture(image, frag_uv);
+                    }
                 }
                 gl_FragColor = tex * frag_color;
             }"#,
             },
         )
-        .expect("compiling shaders");
+        .map_err(graphics_error("compiling shaders"))?;
         shader.bind();
         shader
             .set_uniform(
@@ -68,36 +160,317 @@ impl Graphics {
                     [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
                 ),
             )
-            .expect("setting projection matrix");
-        let vb = VertexBuffer::new(&ctx).expect("creating vertex buffer");
-        let eb = ElementBuffer::new(&ctx).expect("create element buffer");
+            .map_err(graphics_error("setting the projection matrix"))?;
+        for page in 0..SAMPLED_PAGES {
+            let bind_point = bind_point_for_page(page).get() as i32;
+            shader
+                .set_uniform(&format!("page{page}"), UniformValue::Int(bind_point))
+                .map_err(graphics_error("assigning texture pages"))?;
+        }
+        let buffers = (0..BUFFER_COUNT)
+            .map(|_| {
+                let vb = VertexBuffer::new(&ctx)
+                    .map_err(graphics_error("creating the vertex buffer"))?;
+                let eb = ElementBuffer::new(&ctx)
+                    .map_err(graphics_error("creating the element buffer"))?;
+                Ok((vb, eb))
+            })
+            .collect::<Result<_, Error>>()?;
+        let quad_indices =
+            ElementBuffer::new(&ctx).map_err(graphics_error("creating the element buffer"))?;
+        let atlas = TextureAtlas::new(&ctx, texture_units)
+            .map_err(graphics_error("allocating a texture page"))?;
         shader.bind();
         ctx.set_blend_mode(Some(Default::default()));
 
+        Ok(Graphics {
+            gpu: Some(Gpu {
+                ctx,
+                buffers,
+                next_buffer: 0,
+                quad_indices,
+                quad_capacity: 0,
+                post_process: None,
+                shader,
+            }),
+            recorded: Vec::new(),
+            software: None,
+            viewport: Rect::new(0.0, 0.0, 0.0, 0.0),
+            gl_viewport: [0; 4],
+            window_size: [0; 2],
+            color_filter: None,
+            color_lut: None,
+            color_lut_changed: false,
+            color_lut_strength: 1.0,
+            vertex_data: Vec::new(),
+            index_data: Vec::new(),
+            quads_only: true,
+            vertices: 0,
+            largest_batch: (0, 0),
+            atlas,
+            bound_texture: None,
+            projection: Mat3::IDENTITY,
+            transform: Transform::IDENTITY,
+            culling: false,
+            pixel_snap: None,
+            flushes: 0,
+            capture: None,
+            finished_capture: None,
+        })
+    }
+
+    /// Graphics with no GPU behind them, which record what would have been drawn
+    pub fn headless() -> Graphics {
         Graphics {
-            ctx,
-            vb,
-            eb,
-            shader,
+            gpu: None,
+            recorded: Vec::new(),
+            software: Some(SoftwareTarget::new()),
+            viewport: Rect::new(0.0, 0.0, 0.0, 0.0),
+            gl_viewport: [0; 4],
+            window_size: [0; 2],
+            color_filter: None,
+            color_lut: None,
+            color_lut_changed: false,
+            color_lut_strength: 1.0,
             vertex_data: Vec::new(),
             index_data: Vec::new(),
+            quads_only: true,
             vertices: 0,
-            atlas: TextureAtlas::new(),
+            largest_batch: (0, 0),
+            atlas: TextureAtlas::headless(),
             bound_texture: None,
+            projection: Mat3::IDENTITY,
+            transform: Transform::IDENTITY,
+            culling: false,
+            pixel_snap: None,
+            flushes: 0,
+            capture: None,
+            finished_capture: None,
         }
     }
 
-    pub fn clear(&self, color: Color) {
-        self.ctx.set_clear_color(color.r, color.g, color.b, color.a);
-        self.ctx.clear();
+    /// Everything drawn since the last call, if running headless
+    pub fn take_recorded(&mut self) -> Vec<DrawCommand> {
+        std::mem::take(&mut self.recorded)
+    }
+
+    pub fn recorded(&self) -> &[DrawCommand] {
+        &self.recorded
+    }
+
+    /// What has been drawn so far, if running headless
+    #[cfg(feature = "images")]
+    pub fn snapshot(&self) -> Option<RgbaImage> {
+        self.software.as_ref().map(SoftwareTarget::to_image)
+    }
+
+    pub fn start_capture(&mut self) {
+        self.capture = Some(Vec::new());
+    }
+
+    /// The last frame captured, once it has finished
+    pub fn take_capture(&mut self) -> Option<FrameCapture> {
+        self.finished_capture.take()
+    }
+
+    // Add to the frame being captured, if there is one
+    pub(crate) fn capture(&mut self, command: impl FnOnce(&Graphics) -> CapturedCommand) {
+        if self.capture.is_none() {
+            return;
+        }
+        let command = command(self);
+        if let Some(capture) = &mut self.capture {
+            capture.push(command);
+        }
+    }
+
+    /// How full each texture atlas page is, from 0 to 1
+    pub fn atlas_usage(&self) -> Vec<f32> {
+        self.atlas.usage()
+    }
+
+    /// How many batches were sent to the GPU since the last call
+    pub fn take_flush_count(&mut self) -> u32 {
+        std::mem::take(&mut self.flushes)
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        self.capture(|_| CapturedCommand::Draw(DrawCommand::Clear(color)));
+        match &self.gpu {
+            Some(gpu) => {
+                gpu.ctx.set_clear_color(color.r, color.g, color.b, color.a);
+                gpu.ctx.clear();
+            }
+            None => {
+                self.recorded.push(DrawCommand::Clear(color));
+                if let Some(software) = &mut self.software {
+                    software.clear(color);
+                }
+            }
+        }
+    }
+
+    /// Restrict drawing to a region of the window, in physical pixels from the top left
+    pub fn set_viewport(&mut self, region: Rect, window_size: Vec2) {
+        self.flush();
+        self.viewport = region;
+        self.capture(|_| CapturedCommand::SetViewport(region));
+        let window_height = window_size.y;
+        self.window_size = [window_size.x.round() as u32, window_size.y.round() as u32];
+        if let Some(software) = &mut self.software {
+            let right = region.x + region.width;
+            software.grow(right.max(0.0).round() as u32, window_height.round() as u32);
+        }
+        let Some(gpu) = &self.gpu else {
+            return;
+        };
+        // OpenGL measures the viewport from the bottom left
+        let bottom = window_height - (region.y + region.height);
+        self.gl_viewport = [
+            region.x.max(0.0).round() as u32,
+            bottom.max(0.0).round() as u32,
+            region.width.round() as u32,
+            region.height.round() as u32,
+        ];
+        let [x, y, width, height] = self.gl_viewport;
+        gpu.ctx.set_viewport(x, y, width, height);
+    }
+
+    /// Run custom rendering code between batches, returning None when running headless
+    ///
+    /// Everything pushed so far is flushed first, and the state the batcher relies on (its
+    /// shader, projection, blend mode, viewport, and texture bindings) is restored afterwards.
+    pub fn with_context<T>(&mut self, f: impl FnOnce(&golem::Context) -> T) -> Option<T> {
+        self.flush();
+        let gpu = self.gpu.as_ref()?;
+        let result = f(&gpu.ctx);
+        if let Some(post_process) = &gpu.post_process
+            && post_process.is_active()
+        {
+            post_process.rebind();
+        }
+        self.restore_state();
+        Some(result)
+    }
+
+    /// Apply a color matrix to every frame, starting with the next one
+    ///
+    /// Has no effect when running headless.
+    pub fn set_color_filter(&mut self, filter: Option<Mat3>) {
+        self.color_filter = filter;
+    }
+
+    /// Grade every frame through a lookup table, starting with the next one
+    ///
+    /// Has no effect when running headless.
+    pub fn set_color_lut(&mut self, lut: Option<&ColorLut>) {
+        self.color_lut = lut.cloned();
+        self.color_lut_changed = true;
+    }
+
+    pub fn set_color_lut_strength(&mut self, strength: f32) {
+        self.color_lut_strength = strength;
+    }
+
+    /// Draw the finished frame to the window, through the color filter if it was drawn off screen
+    pub fn finish_frame(&mut self) {
+        self.flush();
+        if let Some(commands) = self.capture.take() {
+            self.finished_capture = Some(FrameCapture { commands });
+        }
+        let Some(gpu) = &mut self.gpu else {
+            return;
+        };
+        if let Some(post_process) = &mut gpu.post_process
+            && post_process.is_active()
+        {
+            // The filter could have been removed partway through the frame
+            let filter = self.color_filter.unwrap_or(Mat3::IDENTITY);
+            let strength = if self.color_lut.is_some() {
+                self.color_lut_strength
+            } else {
+                0.0
+            };
+            post_process.finish(&gpu.ctx, filter, strength);
+            self.restore_state();
+        }
+    }
+
+    /// Start drawing a frame, off screen if there's a color filter to apply at the end
+    pub fn begin_frame(&mut self) {
+        let Some(gpu) = &mut self.gpu else {
+            return;
+        };
+        if self.color_filter.is_none() && self.color_lut.is_none() {
+            return;
+        }
+        if gpu.post_process.is_none() {
+            match PostProcess::new(&gpu.ctx, self.atlas.max_pages()) {
+                Ok(post_process) => gpu.post_process = Some(post_process),
+                Err(error) => {
+                    log::error!("Can't apply color filters: {error}");
+                    self.color_filter = None;
+                    self.color_lut = None;
+                    return;
+                }
+            }
+        }
+        if let Some(post_process) = &mut gpu.post_process {
+            if std::mem::take(&mut self.color_lut_changed)
+                && let Some(lut) = &self.color_lut
+                && let Err(error) = post_process.set_lut(&gpu.ctx, lut)
+            {
+                log::error!("Can't apply color grading: {error}");
+                self.color_lut = None;
+            }
+            post_process.begin(self.window_size);
+        }
+    }
+
+    // Put back the state the batcher relies on, after other rendering code has changed it
+    fn restore_state(&mut self) {
+        let Some(gpu) = &self.gpu else {
+            return;
+        };
+        gpu.shader.bind();
+        let mut projection = [0.0; 9];
+        self.projection.write_cols_to_slice(&mut projection);
+        gpu.shader
+            .set_uniform("projection", UniformValue::Matrix3(projection))
+            .expect("set projection matrix");
+        gpu.ctx.set_blend_mode(Some(Default::default()));
+        let [x, y, width, height] = self.gl_viewport;
+        gpu.ctx.set_viewport(x, y, width, height);
+        self.atlas.reactivate();
+        // Make the next textured draw set which page to sample from
+        self.bound_texture = None;
+    }
+
+    /// Set the transform applied to everything pushed from now on
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+        self.capture(|_| CapturedCommand::SetTransform(transform));
+    }
+
+    pub fn projection_matrix(&self) -> Mat3 {
+        self.projection
     }
 
     pub fn set_projection_matrix(&mut self, matrix: Mat3) {
+        // Cameras are often set every frame without changing
+        if matrix == self.projection {
+            return;
+        }
         self.flush();
-        self.shader.bind();
+        self.projection = matrix;
+        self.capture(|_| CapturedCommand::SetProjection(matrix));
+        let Some(gpu) = &self.gpu else {
+            return;
+        };
+        gpu.shader.bind();
         let mut data = [0.0; 9];
         matrix.write_cols_to_slice(&mut data);
-        self.shader
+        gpu.shader
             .set_uniform("projection", UniformValue::Matrix3(data))
             .expect("set projection matrix");
     }
@@ -107,9 +480,53 @@ impl Graphics {
         image_data: &[u8],
         width: u32,
         height: u32,
-    ) -> TextureHandle {
+    ) -> Result<TextureHandle, Error> {
+        self.atlas.upload_image(
+            self.gpu.as_ref().map(|gpu| &gpu.ctx),
+            image_data,
+            width,
+            height,
+        )
+    }
+
+    /// Replace part of an uploaded texture, in pixels from its top left
+    pub fn update_texture(
+        &mut self,
+        texture: TextureHandle,
+        x: u32,
+        y: u32,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+    ) {
+        // Anything already batched was drawn with the old pixels
+        self.flush();
         self.atlas
-            .upload_image(&self.ctx, image_data, width, height)
+            .update_image(texture, x, y, image_data, width, height);
+    }
+
+    /// Give an uploaded image's atlas space back, see `TextureAtlas::free_image`
+    pub fn free_texture(&mut self, texture: TextureHandle) {
+        // Anything already batched could be drawn with whatever is uploaded in its place
+        self.flush();
+        self.atlas.free_image(texture);
+    }
+
+    /// The RGBA pixels of a whole uploaded image, with its width and height
+    pub fn read_texture(&mut self, texture: TextureHandle) -> Option<(Vec<u8>, u32, u32)> {
+        self.flush();
+        let pixels = self
+            .atlas
+            .read_image(self.gpu.as_ref().map(|gpu| &gpu.ctx), texture);
+        if let Some(gpu) = &self.gpu {
+            if let Some(post_process) = &gpu.post_process
+                && post_process.is_active()
+            {
+                post_process.rebind();
+            }
+            self.restore_state();
+        }
+        pixels
     }
 
     pub fn push_rect(
@@ -118,87 +535,367 @@ impl Graphics {
         color: Color,
         texture: Option<(TextureHandle, Rect)>,
     ) {
-        let uv = if let Some((texture, uv)) = texture {
-            let bind_point = texture.bind_point();
-            if let Some(currently_bound) = self.bound_texture {
-                if bind_point != currently_bound {
-                    self.flush();
-                }
-            }
-            self.shader
-                .set_uniform("image", UniformValue::Int(bind_point.get() as i32))
-                .expect("change active image");
-            self.bound_texture = Some(bind_point);
-            self.atlas.uv(texture, uv)
-        } else {
-            Rect {
-                x: -1.0,
-                y: -1.0,
-                width: 0.0,
-                height: 0.0,
-            }
+        let corners = [
+            region.top_left(),
+            Vec2::new(region.x + region.width, region.y),
+            region.bottom_right(),
+            Vec2::new(region.x, region.y + region.height),
+        ];
+        self.push_quad(corners, color, texture);
+    }
+
+    /// Skip quads that are entirely outside the projection, rather than sending them to the GPU
+    pub fn set_culling(&mut self, culling: bool) {
+        self.culling = culling;
+    }
+
+    /// Move quads so their first corner lands on a whole pixel of a resolution, or stop with None
+    pub fn set_pixel_snap(&mut self, resolution: Option<Vec2>) {
+        self.pixel_snap = resolution;
+    }
+
+    /// Push a quad with corners in clockwise order, starting from the one matching the top left
+    /// of the texture
+    pub fn push_quad(
+        &mut self,
+        corners: [Vec2; 4],
+        color: Color,
+        texture: Option<(TextureHandle, Rect)>,
+    ) {
+        if self.culling && self.is_off_screen(&corners) {
+            return;
+        }
+        let corners = self.snap_to_pixels(corners);
+        self.capture(|gfx| {
+            CapturedCommand::Draw(DrawCommand::Quad {
+                corners: corners.map(|corner| gfx.transform.transform_point(corner)),
+                color,
+                texture: texture.map(|(texture, _)| texture),
+            })
+        });
+        if self.gpu.is_none() {
+            let uv = texture.map_or(Rect::new(0.0, 0.0, 1.0, 1.0), |(_, uv)| uv);
+            let uvs = [
+                uv.top_left(),
+                Vec2::new(uv.x + uv.width, uv.y),
+                uv.bottom_right(),
+                Vec2::new(uv.x, uv.y + uv.height),
+            ];
+            let vertices: Vec<_> = corners
+                .into_iter()
+                .zip(uvs)
+                .map(|(corner, uv)| (corner, uv, color))
+                .collect();
+            let texture = texture.map(|(texture, _)| texture);
+            self.draw_software(&vertices, &[0, 1, 2, 0, 2, 3], texture);
+            let corners = corners.map(|corner| self.transform.transform_point(corner));
+            self.recorded.push(DrawCommand::Quad {
+                corners,
+                color,
+                texture,
+            });
+            return;
+        }
+        let (uv, page) = match texture {
+            Some((texture, uv)) => (self.atlas.uv(texture, uv), self.bind_texture(texture)),
+            None => (
+                Rect {
+                    x: -1.0,
+                    y: -1.0,
+                    width: 0.0,
+                    height: 0.0,
+                },
+                0.0,
+            ),
         };
         let index = self.vertices;
-        self.push_vertex(region.x, region.y, color, uv.x, uv.y);
-        self.push_vertex(
-            region.x + region.width,
-            region.y,
-            color,
-            uv.x + uv.width,
-            uv.y,
-        );
-        self.push_vertex(
-            region.x + region.width,
-            region.y + region.height,
-            color,
-            uv.x + uv.width,
-            uv.y + uv.height,
-        );
-        self.push_vertex(
-            region.x,
-            region.y + region.height,
-            color,
-            uv.x,
-            uv.y + uv.height,
-        );
-        self.index_data.extend_from_slice(&[
-            index,
-            index + 1,
-            index + 2,
-            index,
-            index + 2,
-            index + 3,
-        ]);
+        self.push_vertex(corners[0], color, uv.x, uv.y, page);
+        self.push_vertex(corners[1], color, uv.x + uv.width, uv.y, page);
+        self.push_vertex(corners[2], color, uv.x + uv.width, uv.y + uv.height, page);
+        self.push_vertex(corners[3], color, uv.x, uv.y + uv.height, page);
+        if !self.quads_only {
+            self.index_data.extend(quad_indices(index));
+        }
+    }
+
+    /// Push a convex polygon, as a fan of triangles around the first point
+    pub fn push_convex_polygon(&mut self, points: &[Vec2], color: Color) {
+        if points.len() < 3 {
+            return;
+        }
+        self.capture(|gfx| {
+            CapturedCommand::Draw(DrawCommand::Polygon {
+                points: points
+                    .iter()
+                    .map(|point| gfx.transform.transform_point(*point))
+                    .collect(),
+                color,
+            })
+        });
+        if self.gpu.is_none() {
+            let vertices: Vec<_> = points
+                .iter()
+                .map(|point| (*point, Vec2::ZERO, color))
+                .collect();
+            let indices: Vec<_> = (1..(points.len() as u32 - 1))
+                .flat_map(|i| [0, i, i + 1])
+                .collect();
+            self.draw_software(&vertices, &indices, None);
+            let points = points
+                .iter()
+                .map(|point| self.transform.transform_point(*point))
+                .collect();
+            self.recorded.push(DrawCommand::Polygon { points, color });
+            return;
+        }
+        self.stop_quads_only();
+        let index = self.vertices;
+        for point in points {
+            self.push_vertex(*point, color, -1.0, -1.0, 0.0);
+        }
+        for i in 1..(points.len() as u32 - 1) {
+            self.index_data
+                .extend_from_slice(&[index, index + i, index + i + 1]);
+        }
+    }
+
+    /// Push triangles from a list of vertices, each with a position, texture coordinates from 0
+    /// to 1 within the texture, and a color
+    pub fn push_triangles(
+        &mut self,
+        vertices: &[(Vec2, Vec2, Color)],
+        indices: &[u32],
+        texture: Option<TextureHandle>,
+    ) {
+        self.capture(|gfx| {
+            CapturedCommand::Draw(DrawCommand::Triangles {
+                vertices: vertices
+                    .iter()
+                    .map(|(position, _, color)| (gfx.transform.transform_point(*position), *color))
+                    .collect(),
+                indices: indices.to_vec(),
+                texture,
+            })
+        });
+        if self.gpu.is_none() {
+            self.draw_software(vertices, indices, texture);
+            let vertices = vertices
+                .iter()
+                .map(|(position, _, color)| (self.transform.transform_point(*position), *color))
+                .collect();
+            self.recorded.push(DrawCommand::Triangles {
+                vertices,
+                indices: indices.to_vec(),
+                texture,
+            });
+            return;
+        }
+        let page = texture.map_or(0.0, |texture| self.bind_texture(texture));
+        self.stop_quads_only();
+        let index = self.vertices;
+        for (position, uv, color) in vertices {
+            let uv = match texture {
+                Some(texture) => self
+                    .atlas
+                    .uv(texture, Rect::new(uv.x, uv.y, 0.0, 0.0))
+                    .position(),
+                None => Vec2::new(-1.0, -1.0),
+            };
+            self.push_vertex(*position, *color, uv.x, uv.y, page);
+        }
+        self.index_data
+            .extend(indices.iter().map(|vertex| index + vertex));
     }
 
     pub fn flush(&mut self) {
+        let Some(gpu) = &mut self.gpu else {
+            return;
+        };
         if self.vertices == 0 {
             return;
         }
+        profile_scope!("flush");
 
-        self.vb.set_data(&self.vertex_data);
-        self.eb.set_data(&self.index_data);
+        let vertices = self.vertices;
+        self.largest_batch.0 = self.largest_batch.0.max(self.vertex_data.len());
+        self.largest_batch.1 = self.largest_batch.1.max(self.index_data.len());
+        let (vb, eb) = &mut gpu.buffers[gpu.next_buffer];
+        gpu.next_buffer = (gpu.next_buffer + 1) % BUFFER_COUNT;
+        // Buffers only ever grow, with room to spare, so most flushes just overwrite the start
+        // instead of reallocating
+        vb.ensure_capacity(buffer_capacity(self.largest_batch.0));
+        vb.set_sub_data(0, &self.vertex_data);
+        let (eb, index_count) = if self.quads_only {
+            let quads = self.vertices / 4;
+            if gpu.quad_capacity < quads as usize {
+                gpu.quad_capacity = buffer_capacity(quads as usize);
+                let indices: Vec<u32> = (0..gpu.quad_capacity as u32)
+                    .flat_map(|quad| quad_indices(quad * 4))
+                    .collect();
+                gpu.quad_indices.set_data(&indices);
+            }
+            (&gpu.quad_indices, quads as usize * 6)
+        } else {
+            eb.ensure_capacity(buffer_capacity(self.largest_batch.1));
+            eb.set_sub_data(0, &self.index_data);
+            (&*eb, self.index_data.len())
+        };
         // TODO-someday: maybe switch to draw_prepared, which requires more care to be taken with
         // safety but incurs less overhead
-        // SAFETY: index data is only pushed to valid vertex indices above
+        // SAFETY: index data is only pushed to valid vertex indices above, and the quad indices
+        // only go up to the number of quads pushed
         unsafe {
-            self.shader
-                .draw(
-                    &self.vb,
-                    &self.eb,
-                    0..self.index_data.len(),
-                    GeometryMode::Triangles,
-                )
+            gpu.shader
+                .draw(vb, eb, 0..index_count, GeometryMode::Triangles)
                 .expect("flush to the GPU");
         }
         self.vertex_data.clear();
         self.index_data.clear();
+        self.quads_only = true;
         self.vertices = 0;
+        self.flushes += 1;
+        self.capture(|_| CapturedCommand::Flush {
+            vertices,
+            indices: index_count,
+        });
+    }
+
+    // Draw triangles into the software target, the same way the GPU would
+    fn draw_software(
+        &mut self,
+        vertices: &[(Vec2, Vec2, Color)],
+        indices: &[u32],
+        texture: Option<TextureHandle>,
+    ) {
+        let Some(software) = &mut self.software else {
+            return;
+        };
+        let to_pixels = |position: Vec2| {
+            let ndc = self
+                .projection
+                .transform_point2(self.transform.transform_point(position));
+            Vec2::new(
+                self.viewport.x + (ndc.x + 1.0) / 2.0 * self.viewport.width,
+                self.viewport.y + (1.0 - ndc.y) / 2.0 * self.viewport.height,
+            )
+        };
+        let vertices: Vec<_> = vertices
+            .iter()
+            .map(|(position, uv, color)| SoftwareVertex {
+                position: to_pixels(*position),
+                uv: *uv,
+                color: *color,
+            })
+            .collect();
+        let atlas = &self.atlas;
+        let sample = |uv| match texture {
+            Some(texture) => atlas.sample(texture, uv).unwrap_or(Color::WHITE),
+            None => Color::WHITE,
+        };
+        for triangle in indices.chunks_exact(3) {
+            let corners = [0, 1, 2].map(|corner| &vertices[triangle[corner] as usize]);
+            software.fill_triangle(corners, &sample);
+        }
+    }
+
+    // Write out the indices of the quads so far, so other shapes can be batched after them
+    fn stop_quads_only(&mut self) {
+        if self.quads_only {
+            self.quads_only = false;
+            self.index_data
+                .extend((0..self.vertices).step_by(4).flat_map(quad_indices));
+        }
     }
 
-    fn push_vertex(&mut self, x: f32, y: f32, color: Color, u: f32, v: f32) {
-        self.vertex_data
-            .extend_from_slice(&[color.r, color.g, color.b, color.a, x, y, u, v]);
+    // Shift the whole quad by the same amount, so it keeps its size while its first corner
+    // moves to the nearest pixel once projected
+    fn snap_to_pixels(&self, corners: [Vec2; 4]) -> [Vec2; 4] {
+        let Some(resolution) = self.pixel_snap else {
+            return corners;
+        };
+        let world = corners.map(|corner| self.transform.transform_point(corner));
+        let ndc = self.projection.transform_point2(world[0]);
+        let pixel = (ndc + Vec2::ONE) / 2.0 * resolution;
+        let offset = (pixel.round() - pixel) / resolution * 2.0;
+        let offset = self.projection.inverse().transform_vector2(offset);
+        let inverse = self.transform.inverse();
+        world.map(|corner| inverse.transform_point(corner + offset))
+    }
+
+    // Whether every corner is past the same edge of the projection
+    fn is_off_screen(&self, corners: &[Vec2; 4]) -> bool {
+        let ndc = corners.map(|corner| {
+            self.projection
+                .transform_point2(self.transform.transform_point(corner))
+        });
+        ndc.iter().all(|corner| corner.x < -1.0)
+            || ndc.iter().all(|corner| corner.x > 1.0)
+            || ndc.iter().all(|corner| corner.y < -1.0)
+            || ndc.iter().all(|corner| corner.y > 1.0)
+    }
+
+    // Make a texture's page available to the shader, returning the page for its vertices
+    //
+    // The first few pages are always available, so only textures on later pages need to switch
+    // what's bound, flushing everything drawn with the previous one.
+    fn bind_texture(&mut self, texture: TextureHandle) -> f32 {
+        if texture.page() < SAMPLED_PAGES {
+            return texture.page() as f32;
+        }
+        let bind_point = texture.bind_point();
+        if self.bound_texture == Some(bind_point) {
+            return SAMPLED_PAGES as f32;
+        }
+        if self.bound_texture.is_some() {
+            self.flush();
+        }
+        self.capture(|_| CapturedCommand::BindPage(texture.page()));
+        if let Some(gpu) = &self.gpu {
+            gpu.shader
+                .set_uniform("image", UniformValue::Int(bind_point.get() as i32))
+                .expect("change active image");
+        }
+        self.bound_texture = Some(bind_point);
+        SAMPLED_PAGES as f32
+    }
+
+    fn push_vertex(&mut self, position: Vec2, color: Color, u: f32, v: f32, page: f32) {
+        let position = self.transform.transform_point(position);
+        self.vertex_data.extend_from_slice(&[
+            color.r, color.g, color.b, color.a, position.x, position.y, u, v, page,
+        ]);
         self.vertices += 1;
     }
 }
+
+// The two triangles of a quad, from the index of its first vertex
+fn quad_indices(index: u32) -> [u32; 6] {
+    [index, index + 1, index + 2, index, index + 2, index + 3]
+}
+
+// Leave headroom past the largest batch, so a slightly larger one doesn't reallocate
+fn buffer_capacity(largest_batch: usize) -> usize {
+    (largest_batch + 1).next_power_of_two()
+}
+
+/// How many texture units a window's fragment shaders can sample from
+///
+/// golem can't query the context, so this asks through a second handle to the same one.
+pub(crate) fn texture_units(window: &blinds::Window) -> u32 {
+    use glow::HasContext;
+    #[cfg(not(target_arch = "wasm32"))]
+    let gl =
+        unsafe { glow::Context::from_loader_function_cstr(|func| window.get_proc_address(func)) };
+    #[cfg(target_arch = "wasm32")]
+    let gl = glow::Context::from_webgl2_context(window.webgl2_context());
+    let units = unsafe { gl.get_parameter_i32(glow::MAX_TEXTURE_IMAGE_UNITS) };
+    log::debug!("Fragment shaders can sample {units} texture units");
+    u32::try_from(units).unwrap_or(0)
+}
+
+fn graphics_error(action: &'static str) -> impl FnOnce(golem::GolemError) -> Error {
+    move |error| Error::GraphicsError {
+        action,
+        error: format!("{error:?}"),
+    }
+}
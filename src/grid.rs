@@ -0,0 +1,117 @@
+use glam::{IVec2, Vec2};
+
+use crate::shape::Rect;
+
+/// Converts between world positions and the coordinates of tiles in a regular grid
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Grid {
+    pub tile_size: Vec2,
+    /// The world position of the top left corner of tile (0, 0)
+    pub origin: Vec2,
+}
+
+impl Grid {
+    pub fn new(tile_size: Vec2) -> Grid {
+        Grid {
+            tile_size,
+            origin: Vec2::ZERO,
+        }
+    }
+
+    pub fn with_origin(self, origin: Vec2) -> Grid {
+        Grid { origin, ..self }
+    }
+
+    /// The tile containing a world position
+    pub fn world_to_tile(&self, position: Vec2) -> IVec2 {
+        ((position - self.origin) / self.tile_size)
+            .floor()
+            .as_ivec2()
+    }
+
+    /// The world position of the top left corner of a tile
+    pub fn tile_to_world(&self, tile: IVec2) -> Vec2 {
+        self.origin + tile.as_vec2() * self.tile_size
+    }
+
+    pub fn tile_center(&self, tile: IVec2) -> Vec2 {
+        self.tile_to_world(tile) + self.tile_size / 2.0
+    }
+
+    pub fn tile_rect(&self, tile: IVec2) -> Rect {
+        let position = self.tile_to_world(tile);
+        Rect::new(position.x, position.y, self.tile_size.x, self.tile_size.y)
+    }
+
+    /// Every tile that overlaps the rect, row by row
+    ///
+    /// A rect whose edge lies exactly on a tile boundary doesn't include the tile on the far side
+    pub fn tiles_in_rect(&self, rect: &Rect) -> impl Iterator<Item = IVec2> + use<> {
+        let min = self.world_to_tile(rect.top_left());
+        let max = ((rect.bottom_right() - self.origin) / self.tile_size)
+            .ceil()
+            .as_ivec2()
+            - IVec2::ONE;
+        let max = max.max(min);
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+    }
+
+    /// The tiles directly above, right, below, and left of a tile
+    pub fn neighbors(&self, tile: IVec2) -> [IVec2; 4] {
+        [IVec2::NEG_Y, IVec2::X, IVec2::Y, IVec2::NEG_X].map(|offset| tile + offset)
+    }
+
+    /// All eight tiles surrounding a tile, clockwise from the one above
+    pub fn neighbors_with_diagonals(&self, tile: IVec2) -> [IVec2; 8] {
+        [
+            IVec2::new(0, -1),
+            IVec2::new(1, -1),
+            IVec2::new(1, 0),
+            IVec2::new(1, 1),
+            IVec2::new(0, 1),
+            IVec2::new(-1, 1),
+            IVec2::new(-1, 0),
+            IVec2::new(-1, -1),
+        ]
+        .map(|offset| tile + offset)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use glam::{IVec2, Vec2};
+
+    use super::Grid;
+    use crate::shape::Rect;
+
+    #[test]
+    fn conversions() {
+        let grid = Grid::new(Vec2::splat(16.0)).with_origin(Vec2::new(8.0, 0.0));
+        assert_eq!(grid.world_to_tile(Vec2::new(8.0, 0.0)), IVec2::ZERO);
+        assert_eq!(grid.world_to_tile(Vec2::new(7.9, 15.9)), IVec2::new(-1, 0));
+        assert_eq!(grid.tile_to_world(IVec2::new(2, 1)), Vec2::new(40.0, 16.0));
+        assert_eq!(grid.tile_center(IVec2::ZERO), Vec2::new(16.0, 8.0));
+    }
+
+    #[test]
+    fn tiles_in_rect() {
+        let grid = Grid::new(Vec2::splat(16.0));
+        let tiles: Vec<_> = grid
+            .tiles_in_rect(&Rect::new(0.0, 0.0, 32.0, 16.0))
+            .collect();
+        assert_eq!(tiles, vec![IVec2::new(0, 0), IVec2::new(1, 0)]);
+        let tiles: Vec<_> = grid
+            .tiles_in_rect(&Rect::new(-1.0, 15.0, 2.0, 2.0))
+            .collect();
+        assert_eq!(
+            tiles,
+            vec![
+                IVec2::new(-1, 0),
+                IVec2::new(0, 0),
+                IVec2::new(-1, 1),
+                IVec2::new(0, 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::{Duration, SystemTime};
+
+use rustc_hash::FxHashMap as HashMap;
+use web_time::Instant;
+
+// Checking every frame would mean hundreds of filesystem calls a second for no benefit
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Polls files for changes to their modification time
+///
+/// Files are watched under the asset path they were loaded with, which can differ from where
+/// they are on disk
+pub(crate) struct FileWatcher {
+    watched: HashMap<String, (String, Option<SystemTime>)>,
+    last_check: Instant,
+}
+
+impl FileWatcher {
+    pub fn new() -> FileWatcher {
+        FileWatcher {
+            watched: HashMap::default(),
+            last_check: Instant::now(),
+        }
+    }
+
+    pub fn watch(&mut self, path: &str, file: String) {
+        let modified = modified_time(&file);
+        self.watched.insert(path.to_string(), (file, modified));
+    }
+
+    pub fn unwatch(&mut self, path: &str) {
+        self.watched.remove(path);
+    }
+
+    pub fn changed_files(&mut self) -> Vec<String> {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return Vec::new();
+        }
+        self.last_check = Instant::now();
+        let mut changed = Vec::new();
+        for (path, (file, modified)) in self.watched.iter_mut() {
+            let current = modified_time(file);
+            if current != *modified {
+                *modified = current;
+                // A missing file is usually an editor in the middle of replacing it
+                if current.is_some() {
+                    changed.push(path.clone());
+                }
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn modified_time(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// There's no filesystem to watch on the web
+#[cfg(target_arch = "wasm32")]
+fn modified_time(_path: &str) -> Option<SystemTime> {
+    None
+}
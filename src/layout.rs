@@ -0,0 +1,195 @@
+use glam::Vec2;
+
+use crate::{Venus, shape::Rect};
+
+/// A point on a rectangle, for placing things relative to its edges instead of at fixed
+/// positions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far across and down the rectangle the anchor is, from 0 to 1
+    pub fn fraction(self) -> Vec2 {
+        match self {
+            Anchor::TopLeft => Vec2::new(0.0, 0.0),
+            Anchor::Top => Vec2::new(0.5, 0.0),
+            Anchor::TopRight => Vec2::new(1.0, 0.0),
+            Anchor::Left => Vec2::new(0.0, 0.5),
+            Anchor::Center => Vec2::new(0.5, 0.5),
+            Anchor::Right => Vec2::new(1.0, 0.5),
+            Anchor::BottomLeft => Vec2::new(0.0, 1.0),
+            Anchor::Bottom => Vec2::new(0.5, 1.0),
+            Anchor::BottomRight => Vec2::new(1.0, 1.0),
+        }
+    }
+
+    /// The anchor's position on a rectangle
+    pub fn point(self, container: Rect) -> Vec2 {
+        container.position() + container.size() * self.fraction()
+    }
+
+    /// A rectangle of some size lined up with the same anchor of a container
+    ///
+    /// E.g. `Anchor::BottomRight` puts its bottom right corner on the container's.
+    pub fn place(self, container: Rect, size: Vec2) -> Rect {
+        let position = self.point(container) - size * self.fraction();
+        Rect::new(position.x, position.y, size.x, size.y)
+    }
+}
+
+/// Space to leave around the inside edges of a rectangle
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Margins {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Margins {
+    pub fn uniform(margin: f32) -> Margins {
+        Margins {
+            left: margin,
+            top: margin,
+            right: margin,
+            bottom: margin,
+        }
+    }
+
+    pub fn symmetric(horizontal: f32, vertical: f32) -> Margins {
+        Margins {
+            left: horizontal,
+            top: vertical,
+            right: horizontal,
+            bottom: vertical,
+        }
+    }
+
+    /// What's left of a rectangle inside the margins, never smaller than nothing
+    pub fn inset(&self, rect: Rect) -> Rect {
+        let width = (rect.width - self.left - self.right).max(0.0);
+        let height = (rect.height - self.top - self.bottom).max(0.0);
+        Rect::new(rect.x + self.left, rect.y + self.top, width, height)
+    }
+}
+
+/// Hands out rectangles one after another across or down an area, like a row of buttons
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stack {
+    area: Rect,
+    horizontal: bool,
+    spacing: f32,
+    // How far along the area the next rectangle starts
+    offset: f32,
+}
+
+impl Stack {
+    /// Rectangles from left to right, each as tall as the area
+    pub fn row(area: Rect, spacing: f32) -> Stack {
+        Stack {
+            area,
+            horizontal: true,
+            spacing,
+            offset: 0.0,
+        }
+    }
+
+    /// Rectangles from top to bottom, each as wide as the area
+    pub fn column(area: Rect, spacing: f32) -> Stack {
+        Stack {
+            area,
+            horizontal: false,
+            spacing,
+            offset: 0.0,
+        }
+    }
+
+    /// The next rectangle, with a width in a row or a height in a column
+    pub fn next(&mut self, length: f32) -> Rect {
+        let rect = if self.horizontal {
+            Rect::new(
+                self.area.x + self.offset,
+                self.area.y,
+                length,
+                self.area.height,
+            )
+        } else {
+            Rect::new(
+                self.area.x,
+                self.area.y + self.offset,
+                self.area.width,
+                length,
+            )
+        };
+        self.offset += length + self.spacing;
+        rect
+    }
+
+    /// Split the rest of the area evenly between some number of rectangles
+    pub fn split(&mut self, count: usize) -> Vec<Rect> {
+        let total = if self.horizontal {
+            self.area.width
+        } else {
+            self.area.height
+        };
+        let gaps = self.spacing * count.saturating_sub(1) as f32;
+        let length = ((total - self.offset - gaps) / count.max(1) as f32).max(0.0);
+        (0..count).map(|_| self.next(length)).collect()
+    }
+}
+
+impl Venus {
+    /// The whole logical resolution, in the same units as the settings' width and height
+    ///
+    /// Anchoring HUD elements to this keeps them in place when the resolution changes.
+    pub fn screen_rect(&self) -> Rect {
+        Rect::new(0.0, 0.0, self.logical_size.x, self.logical_size.y)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use glam::Vec2;
+
+    use super::{Anchor, Margins, Stack};
+    use crate::shape::Rect;
+
+    #[test]
+    fn anchors_and_margins() {
+        let screen = Rect::new(0.0, 0.0, 320.0, 180.0);
+        let hud = Margins::uniform(8.0).inset(screen);
+        assert_eq!(
+            Anchor::TopRight.place(hud, Vec2::new(50.0, 10.0)),
+            Rect::new(262.0, 8.0, 50.0, 10.0)
+        );
+        assert_eq!(
+            Anchor::Center.place(screen, Vec2::new(100.0, 20.0)),
+            Rect::new(110.0, 80.0, 100.0, 20.0)
+        );
+        assert_eq!(Anchor::Bottom.point(screen), Vec2::new(160.0, 180.0));
+        assert_eq!(Margins::symmetric(200.0, 0.0).inset(screen).width, 0.0);
+    }
+
+    #[test]
+    fn stacking() {
+        let mut column = Stack::column(Rect::new(10.0, 10.0, 100.0, 100.0), 5.0);
+        assert_eq!(column.next(20.0), Rect::new(10.0, 10.0, 100.0, 20.0));
+        assert_eq!(column.next(10.0), Rect::new(10.0, 35.0, 100.0, 10.0));
+
+        let buttons = Stack::row(Rect::new(0.0, 0.0, 100.0, 20.0), 5.0).split(3);
+        assert_eq!(buttons.len(), 3);
+        assert_eq!(buttons[1], Rect::new(35.0, 0.0, 30.0, 20.0));
+        assert_eq!(buttons[2].x + buttons[2].width, 100.0);
+    }
+}
@@ -0,0 +1,385 @@
+use glam::Vec2;
+use rustc_hash::FxHashMap as HashMap;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    Color, Error, Venus,
+    shape::Rect,
+    tilemap::{
+        IntGridLayer, Layer, MapObject, ObjectLayer, Properties, Property, TileLayer, Tilemap,
+        Tileset, resolve_path,
+    },
+};
+
+/// The levels of a project made in the LDtk editor
+pub struct LdtkProject {
+    pub levels: Vec<LdtkLevel>,
+}
+
+impl LdtkProject {
+    pub fn level(&self, name: &str) -> Option<&LdtkLevel> {
+        self.levels.iter().find(|level| level.name == name)
+    }
+}
+
+pub struct LdtkLevel {
+    pub name: String,
+    /// Where the level sits in the project's world, in pixels
+    pub world_position: Vec2,
+    /// The level's layers, with the level's custom fields as the map properties
+    ///
+    /// Entities become objects whose `name` is their unique iid and whose `class` is the entity
+    /// identifier. Int grid layers with auto-tiling produce both an `IntGridLayer` and a
+    /// `TileLayer` of the same name.
+    pub map: Tilemap,
+}
+
+impl Venus {
+    /// Load a project saved by the LDtk editor (a `.ldtk` file)
+    ///
+    /// Tileset images and levels saved as separate files are loaded relative to the project,
+    /// through `load_file` and `load_texture`. Where several tiles are stacked in one cell, only
+    /// the top one is kept, and flipped tiles are drawn unflipped.
+    pub async fn load_ldtk_project(&mut self, path: &str) -> Result<LdtkProject, Error> {
+        let bytes = self.load_file(path).await?;
+        let project: ProjectJson = parse_json(path, &bytes)?;
+
+        let mut tilesets = Vec::new();
+        let mut first_gids = HashMap::default();
+        let mut next_gid = 1;
+        for tileset in project.defs.tilesets {
+            // Tilesets without an image are LDtk's built-in icons, which aren't available
+            let Some(image) = &tileset.rel_path else {
+                continue;
+            };
+            let texture = self.load_texture(&resolve_path(path, image)).await?;
+            first_gids.insert(tileset.uid, next_gid);
+            let tile_count = tileset.columns * tileset.rows;
+            tilesets.push(Tileset {
+                name: tileset.identifier,
+                first_gid: next_gid,
+                texture,
+                tile_width: tileset.tile_grid_size,
+                tile_height: tileset.tile_grid_size,
+                columns: tileset.columns,
+                tile_count,
+                margin: tileset.padding,
+                spacing: tileset.spacing,
+                tile_properties: HashMap::default(),
+            });
+            next_gid += tile_count;
+        }
+
+        let mut levels = Vec::new();
+        for mut level in project.levels {
+            if level.layer_instances.is_none()
+                && let Some(external) = &level.external_rel_path
+            {
+                let level_path = resolve_path(path, external);
+                let bytes = self.load_file(&level_path).await?;
+                level = parse_json(&level_path, &bytes)?;
+            }
+            let mut map = build_level(&level, project.default_grid_size, &first_gids);
+            map.tilesets = tilesets.clone();
+            levels.push(LdtkLevel {
+                name: level.identifier,
+                world_position: Vec2::new(level.world_x, level.world_y),
+                map,
+            });
+        }
+
+        Ok(LdtkProject { levels })
+    }
+}
+
+fn parse_json<'a, T: Deserialize<'a>>(path: &str, bytes: &'a [u8]) -> Result<T, Error> {
+    serde_json::from_slice(bytes).map_err(|error| Error::ParseError {
+        path: path.to_string(),
+        error: error.to_string(),
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectJson {
+    default_grid_size: u32,
+    defs: DefsJson,
+    levels: Vec<LevelJson>,
+}
+
+#[derive(Deserialize)]
+struct DefsJson {
+    tilesets: Vec<TilesetJson>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TilesetJson {
+    uid: i64,
+    identifier: String,
+    rel_path: Option<String>,
+    tile_grid_size: u32,
+    spacing: u32,
+    padding: u32,
+    #[serde(rename = "__cWid")]
+    columns: u32,
+    #[serde(rename = "__cHei")]
+    rows: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LevelJson {
+    identifier: String,
+    world_x: f32,
+    world_y: f32,
+    px_wid: u32,
+    px_hei: u32,
+    #[serde(default)]
+    field_instances: Vec<FieldJson>,
+    layer_instances: Option<Vec<LayerJson>>,
+    external_rel_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LayerJson {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__type")]
+    kind: String,
+    #[serde(rename = "__cWid")]
+    width: u32,
+    #[serde(rename = "__cHei")]
+    height: u32,
+    #[serde(rename = "__gridSize")]
+    grid_size: u32,
+    #[serde(rename = "__pxTotalOffsetX")]
+    offset_x: f32,
+    #[serde(rename = "__pxTotalOffsetY")]
+    offset_y: f32,
+    #[serde(rename = "__opacity")]
+    opacity: f32,
+    #[serde(rename = "__tilesetDefUid")]
+    tileset: Option<i64>,
+    visible: bool,
+    #[serde(default)]
+    int_grid_csv: Vec<i32>,
+    #[serde(default)]
+    grid_tiles: Vec<TileJson>,
+    #[serde(default)]
+    auto_layer_tiles: Vec<TileJson>,
+    #[serde(default)]
+    entity_instances: Vec<EntityJson>,
+}
+
+#[derive(Deserialize)]
+struct TileJson {
+    px: [i32; 2],
+    t: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EntityJson {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    iid: String,
+    px: [f32; 2],
+    #[serde(rename = "__pivot")]
+    pivot: [f32; 2],
+    width: f32,
+    height: f32,
+    #[serde(default)]
+    field_instances: Vec<FieldJson>,
+}
+
+#[derive(Deserialize)]
+struct FieldJson {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__type")]
+    kind: String,
+    #[serde(rename = "__value")]
+    value: Value,
+}
+
+fn build_level(level: &LevelJson, grid_size: u32, first_gids: &HashMap<i64, u32>) -> Tilemap {
+    let mut layers = Vec::new();
+    let mut next_id = 1;
+    // LDtk lists layers from top to bottom
+    for layer in level.layer_instances.iter().flatten().rev() {
+        let tile_size = Vec2::splat(layer.grid_size as f32);
+        let offset = Vec2::new(layer.offset_x, layer.offset_y);
+        if layer.kind == "IntGrid" {
+            layers.push(Layer::IntGrid(IntGridLayer {
+                name: layer.identifier.clone(),
+                width: layer.width,
+                height: layer.height,
+                tile_size,
+                offset,
+                visible: layer.visible,
+                values: layer.int_grid_csv.clone(),
+                properties: Properties::default(),
+            }));
+        }
+        if layer.kind == "Entities" {
+            let objects = layer
+                .entity_instances
+                .iter()
+                .map(|entity| {
+                    let size = Vec2::new(entity.width, entity.height);
+                    let position = Vec2::from(entity.px) - Vec2::from(entity.pivot) * size;
+                    next_id += 1;
+                    MapObject {
+                        id: next_id - 1,
+                        name: entity.iid.clone(),
+                        class: entity.identifier.clone(),
+                        rect: Rect::new(position.x, position.y, size.x, size.y),
+                        rotation: 0.0,
+                        gid: None,
+                        properties: fields(&entity.field_instances),
+                    }
+                })
+                .collect();
+            layers.push(Layer::Objects(ObjectLayer {
+                name: layer.identifier.clone(),
+                offset,
+                visible: layer.visible,
+                objects,
+                properties: Properties::default(),
+            }));
+        }
+        let tiles = layer.grid_tiles.iter().chain(&layer.auto_layer_tiles);
+        if let Some(first_gid) = layer.tileset.and_then(|uid| first_gids.get(&uid))
+            && tiles.clone().next().is_some()
+        {
+            let mut gids = vec![0; (layer.width * layer.height) as usize];
+            for tile in tiles {
+                let x = tile.px[0] / layer.grid_size as i32;
+                let y = tile.px[1] / layer.grid_size as i32;
+                if (0..layer.width as i32).contains(&x) && (0..layer.height as i32).contains(&y) {
+                    gids[(y as u32 * layer.width + x as u32) as usize] = first_gid + tile.t;
+                }
+            }
+            layers.push(Layer::Tiles(TileLayer {
+                name: layer.identifier.clone(),
+                width: layer.width,
+                height: layer.height,
+                tile_size,
+                offset,
+                opacity: layer.opacity,
+                visible: layer.visible,
+                tiles: gids,
+                properties: Properties::default(),
+            }));
+        }
+    }
+
+    Tilemap {
+        width: level.px_wid / grid_size,
+        height: level.px_hei / grid_size,
+        tile_size: Vec2::splat(grid_size as f32),
+        tilesets: Vec::new(),
+        layers,
+        properties: fields(&level.field_instances),
+    }
+}
+
+// Fields without a value, and points and arrays, which have no matching property type, are skipped
+fn fields(fields: &[FieldJson]) -> Properties {
+    fields
+        .iter()
+        .filter_map(|field| {
+            let value = &field.value;
+            let property = match field.kind.as_str() {
+                "Int" => Property::Int(value.as_i64()?),
+                "Float" => Property::Float(value.as_f64()? as f32),
+                "Bool" => Property::Bool(value.as_bool()?),
+                "String" | "Multilines" => Property::String(value.as_str()?.to_string()),
+                "FilePath" => Property::File(value.as_str()?.to_string()),
+                "Color" => Property::Color(parse_color(value.as_str()?)?),
+                "EntityRef" => Property::String(value.get("entityIid")?.as_str()?.to_string()),
+                kind if kind.starts_with("LocalEnum.") || kind.starts_with("ExternEnum.") => {
+                    Property::String(value.as_str()?.to_string())
+                }
+                _ => return None,
+            };
+            Some((field.identifier.clone(), property))
+        })
+        .collect()
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    let [_, red, green, blue] = u32::from_str_radix(hex, 16).ok()?.to_be_bytes();
+    Some(Color::from_rgba(red, green, blue, 1.0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LevelJson, build_level};
+    use crate::tilemap::{Layer, Property};
+
+    const LEVEL: &str = r##"{
+        "identifier": "Level_0", "worldX": 256, "worldY": 0, "pxWid": 48, "pxHei": 32,
+        "fieldInstances": [{ "__identifier": "music", "__type": "String", "__value": "cave" }],
+        "externalRelPath": null,
+        "layerInstances": [
+            {
+                "__identifier": "Entities", "__type": "Entities", "__cWid": 3, "__cHei": 2,
+                "__gridSize": 16, "__pxTotalOffsetX": 0, "__pxTotalOffsetY": 0, "__opacity": 1,
+                "__tilesetDefUid": null, "visible": true,
+                "entityInstances": [{
+                    "__identifier": "Player", "iid": "a1b2", "px": [24, 32], "__pivot": [0.5, 1],
+                    "width": 16, "height": 24,
+                    "fieldInstances": [
+                        { "__identifier": "health", "__type": "Int", "__value": 3 },
+                        { "__identifier": "tint", "__type": "Color", "__value": "#FF0000" },
+                        { "__identifier": "spawn", "__type": "Point", "__value": { "cx": 1, "cy": 1 } }
+                    ]
+                }]
+            },
+            {
+                "__identifier": "Collisions", "__type": "IntGrid", "__cWid": 3, "__cHei": 2,
+                "__gridSize": 16, "__pxTotalOffsetX": 0, "__pxTotalOffsetY": 0, "__opacity": 1,
+                "__tilesetDefUid": 7, "visible": true,
+                "intGridCsv": [0, 0, 0, 1, 1, 2],
+                "autoLayerTiles": [
+                    { "px": [0, 16], "src": [0, 0], "f": 0, "t": 4, "a": 1 },
+                    { "px": [32, 16], "src": [0, 0], "f": 1, "t": 5, "a": 1 }
+                ]
+            }
+        ]
+    }"##;
+
+    #[test]
+    fn build() {
+        let level: LevelJson = serde_json::from_str(LEVEL).unwrap();
+        let map = build_level(&level, 16, &[(7, 10)].into_iter().collect());
+        assert_eq!((map.width, map.height), (3, 2));
+        assert_eq!(
+            map.properties["music"],
+            Property::String("cave".to_string())
+        );
+
+        let [
+            Layer::IntGrid(collisions),
+            Layer::Tiles(tiles),
+            Layer::Objects(entities),
+        ] = &map.layers[..]
+        else {
+            panic!("wrong layers");
+        };
+        assert_eq!(collisions.value(glam::IVec2::new(2, 1)), Some(2));
+        assert_eq!(tiles.tiles, [0, 0, 0, 14, 0, 15]);
+
+        let player = &entities.objects[0];
+        assert_eq!(player.class, "Player");
+        assert_eq!((player.rect.x, player.rect.y), (16.0, 8.0));
+        assert_eq!(player.properties["health"], Property::Int(3));
+        assert!(matches!(player.properties["tint"], Property::Color(tint) if tint.r == 1.0));
+        assert!(!player.properties.contains_key("spawn"));
+    }
+}
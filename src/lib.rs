@@ -1,46 +1,299 @@
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display, time::Duration};
 
-use audio::AudioPlayer;
+use accessibility::AccessibilityQuery;
+#[cfg(feature = "audio")]
+use audio::{AudioPlayer, Generated};
 use blinds::{CachedEventStream, Event, Window};
-use font::{Font, TextRenderer};
-use rodio::Source;
+use cursor::CursorImage;
+use debug_overlay::DebugOverlay;
+use debug_ui::DebugUiState;
+#[cfg(feature = "text")]
+use font::TextRenderer;
+#[cfg(feature = "gamepad")]
+use gamepad::Gamepads;
 use rustc_hash::FxHashSet as HashSet;
+use slotmap::SlotMap;
+use web_time::Instant;
 
+pub use accessibility::Accessibility;
+pub use animation::{Animation, AnimationPlayer, PlayMode};
+#[cfg(feature = "aseprite")]
+pub use aseprite::{AsepriteDirection, AsepriteFrame, AsepriteSheet, AsepriteTag};
+#[cfg(feature = "audio")]
+pub use assets::AudioAsset;
+#[cfg(feature = "text")]
+pub use assets::FontAsset;
+pub use assets::{Assets, TextureAsset};
+#[cfg(feature = "audio")]
 pub use audio::{Audio, PlayingAudio};
-pub use blinds::Key;
+#[cfg(feature = "audio")]
+pub use audio_tap::{AudioTap, Tapped};
+pub use blinds::{CursorIcon, Key};
+pub use camera::Camera;
+pub use capture::{CapturedCommand, FrameCapture};
+pub use collision::{Collision, move_and_collide};
 pub use color::Color;
-pub use shape::Rect;
+pub use color_filter::{ColorBlindness, ColorFilter};
+pub use color_grading::ColorLut;
+pub use debug_ui::DebugUi;
+#[cfg(feature = "egui")]
+pub use egui;
+#[cfg(feature = "egui")]
+pub use egui_integration::Egui;
+#[cfg(feature = "text")]
+pub use font::{ASCII_GLYPHS, Font, LATIN_1_GLYPHS, LaidOutGlyph, TextLayout};
+#[cfg(feature = "gamepad")]
+pub use gamepad::{
+    DeadzoneShape, GamepadAxis, GamepadButton, GamepadEvent, GamepadId, ResponseCurve, Stick,
+    StickSettings,
+};
+pub use glam::{IVec2, Mat3, Vec2};
+#[cfg(feature = "images")]
+pub use golden::{ImageDiff, assert_golden, compare_images};
+pub use golem;
+pub use gradient::{Gradient, GradientShape};
+pub use graphics::DrawCommand;
+pub use grid::Grid;
+pub use layout::{Anchor, Margins, Stack};
+#[cfg(feature = "ldtk")]
+pub use ldtk::{LdtkLevel, LdtkProject};
+pub use loader::{BatchLoader, LoadedBatch};
+#[cfg(feature = "audio")]
+pub use music::MusicTransition;
+pub use pack::{AssetPack, AssetPackBuilder};
+pub use palette::Palette;
+pub use parallax::ParallaxLayer;
+pub use pathfinding::{Diagonals, Pathfinder, smooth_path};
+pub use physics::{Body, BodyHandle, BodyKind, Collider, Contact, PhysicsWorld};
+pub use procedural::Pixels;
+#[cfg(feature = "gamepad")]
+pub use prompt::button_name;
+pub use prompt::{Binding, ButtonIcons, InputDevice, Prompt, key_name};
+pub use scene::{Scene, SceneStack, Transition};
+pub use shader::{ShaderHandle, ShaderLayout};
+pub use shape::{Circle, Polygon, Rect};
+#[cfg(feature = "spine")]
+pub use spine::{Bone, Skeleton, SkeletonAnimation, SkeletonPose, Slot};
+#[cfg(feature = "sprite-sheets")]
+pub use sprite_sheet::{SpriteFrame, SpriteSheet};
+pub use subsystems::{Canvas, Input, Mixer};
+pub use texture_atlas::TextureHandle;
+pub use tilemap::{
+    IntGridLayer, Layer, MapObject, ObjectLayer, Properties, Property, TileLayer, Tilemap, Tileset,
+};
+pub use timeline::{Easing, Lerp, Timeline, Track};
+pub use timestep::{FixedTimestep, Interpolated};
+pub use transform::Transform;
+pub use typewriter::Typewriter;
+#[cfg(feature = "text")]
+pub use ui::{Navigation, NineSlice, Ui, UiEvent, UiStyle, Widget, WidgetId};
+pub use video::{Video, VideoDecoder, VideoFrame};
+pub use viewport::ScaleMode;
 
-use shape::orthographic_projection;
-use texture_atlas::TextureHandle;
+use shape::{ndc_to_screen, screen_to_ndc};
+use viewport::fit_viewport;
 
+use decode::{DecodedImage, decode_image};
 use graphics::Graphics;
+use hot_reload::FileWatcher;
+use loader::{AssetKind, LoadFuture, LoadedFile};
+#[cfg(feature = "audio")]
+use music::Music;
+use profiling::profile_scope;
+use shader::CustomShader;
+use vfs::{Mounts, load_sources};
 
+mod accessibility;
+mod animation;
+#[cfg(feature = "aseprite")]
+mod aseprite;
+mod assets;
+#[cfg(feature = "audio")]
 mod audio;
+#[cfg(feature = "audio")]
+mod audio_tap;
+mod camera;
+mod capture;
+mod collision;
 mod color;
+mod color_filter;
+mod color_grading;
+#[cfg(feature = "toml")]
+mod config;
+mod cursor;
+mod debug_overlay;
+mod debug_ui;
+mod decode;
+#[cfg(feature = "egui")]
+mod egui_integration;
+#[cfg(feature = "text")]
 mod font;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+#[cfg(feature = "images")]
+mod golden;
+mod gradient;
 mod graphics;
+mod grid;
+mod hot_reload;
+mod layout;
+#[cfg(feature = "ldtk")]
+mod ldtk;
+mod loader;
+mod manifest;
+#[cfg(feature = "audio")]
+mod music;
+mod noise;
+mod pack;
+mod palette;
+mod panic_screen;
+mod parallax;
+mod pathfinding;
+mod physics;
+mod post_process;
+mod procedural;
+mod profiling;
+mod prompt;
+mod save;
+mod scene;
+mod shader;
 mod shape;
+mod software;
+#[cfg(feature = "spine")]
+mod spine;
+#[cfg(feature = "sprite-sheets")]
+mod sprite_sheet;
+mod subsystems;
+#[cfg(feature = "svg")]
+mod svg;
 mod texture_atlas;
+#[cfg(feature = "tiled")]
+mod tiled;
+mod tilemap;
+mod timeline;
+mod timestep;
+mod transform;
+mod typewriter;
+#[cfg(feature = "text")]
+mod ui;
+mod vfs;
+mod video;
+mod viewport;
 
 pub struct Venus {
-    window: Window,
-    event_stream: CachedEventStream,
+    window: Option<Window>,
+    event_stream: Option<CachedEventStream>,
     gfx: Graphics,
     just_pressed: HashSet<Key>,
-    fonts: Vec<Font>,
+    input_device: InputDevice,
+    #[cfg(feature = "gamepad")]
+    gamepads: Gamepads,
+    #[cfg(feature = "text")]
+    fonts: SlotMap<FontHandle, Font>,
+    assets: Assets,
+    watcher: Option<FileWatcher>,
+    shaders: SlotMap<ShaderHandle, CustomShader>,
+    mounts: Mounts,
+    save_name: String,
+    #[cfg(feature = "text")]
     text_renderer: TextRenderer,
+    #[cfg(feature = "audio")]
     audio: AudioPlayer,
+    #[cfg(feature = "audio")]
+    music: Music,
+    camera: Camera,
+    pixel_snapping: bool,
+    transform: Transform,
+    transform_stack: Vec<Transform>,
     auto_clear_input_cache: bool,
+    focused: bool,
+    window_size: Vec2,
+    logical_size: Vec2,
+    scale_mode: ScaleMode,
+    viewport: Rect,
+    mouse_position: Vec2,
+    // Since the input cache was last cleared, or None before the mouse has first moved
+    mouse_delta: Option<Vec2>,
+    cursor_image: Option<CursorImage>,
+    unfocused_behavior: UnfocusedBehavior,
+    accessibility: Accessibility,
+    accessibility_changed: bool,
+    // Once the game sets its own preferences, the platform's are ignored
+    accessibility_overridden: bool,
+    accessibility_query: Option<AccessibilityQuery>,
+    min_size: Option<Vec2>,
+    max_size: Option<Vec2>,
+    last_frame: Instant,
+    // The length of the last frame in seconds, before the time scale
+    unscaled_delta: f32,
+    time_scale: f32,
+    debug_overlay: DebugOverlay,
+    debug_ui_state: DebugUiState,
+    #[cfg(feature = "egui")]
+    egui_events: Vec<egui::Event>,
 }
 
 pub struct Settings {
     pub width: f32,
     pub height: f32,
     pub fullscreen: bool,
-    pub title: &'static str,
+    /// The window title, which can be built at runtime, e.g. to include the version
+    ///
+    /// Saves are named after `app_id` instead, so changing the title doesn't lose them.
+    pub title: Cow<'static, str>,
+    /// A name for the game that stays the same between releases, like `my-game`, which names
+    /// the folder saves are kept in
+    ///
+    /// Defaults to the name of the executable, or `venus` on the web.
+    pub app_id: Option<Cow<'static, str>>,
     pub resizable: bool,
+    /// The smallest size the window can be resized to
+    pub min_size: Option<Vec2>,
+    /// The largest size the window can be resized to
+    pub max_size: Option<Vec2>,
+    /// The path of an image to use as the window's icon
+    ///
+    /// Has no effect on the web, where the page's favicon is used instead
+    pub icon: Option<Cow<'static, str>>,
     pub auto_clear_input_cache: bool,
+    pub unfocused_behavior: UnfocusedBehavior,
+    /// How to fit `width` x `height` into the window when it's a different size
+    pub scale_mode: ScaleMode,
+    /// Watch assets loaded through the asset manager and reload them when their files change
+    ///
+    /// Meant for development; has no effect on the web
+    pub hot_reload: bool,
+    /// The directory relative asset paths are loaded from, instead of the working directory (or
+    /// the page's address on the web)
+    ///
+    /// Files in mounted asset packs are still looked up by their original paths
+    pub asset_root: Option<String>,
+    /// A key which shows and hides the debug overlay, see `Venus::set_debug_overlay`
+    pub debug_overlay_key: Option<Key>,
+    /// A key which freezes and unfreezes the game, see `Venus::set_frozen`
+    pub freeze_key: Option<Key>,
+    /// A key which moves a frozen game forward by one frame, see `Venus::step_frame`
+    pub step_key: Option<Key>,
+}
+
+/// What the game loop should do while the window doesn't have focus
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum UnfocusedBehavior {
+    /// Keep running at the full frame rate
+    #[default]
+    Continue,
+    /// Limit the game loop to the given number of frames per second
+    ///
+    /// On the web, browsers already throttle background tabs, so this only takes effect on top of
+    /// that. A rate of zero or less pauses instead.
+    Throttle { frames_per_second: f32 },
+    /// Don't return from `end_frame` until the window regains focus
+    Pause,
+}
+
+// How long each frame takes at a throttled rate, or None to pause if the rate isn't positive
+fn throttled_frame_time(frames_per_second: f32) -> Option<Duration> {
+    Duration::try_from_secs_f32(1.0 / frames_per_second).ok()
 }
 
 impl Default for Settings {
@@ -49,35 +302,118 @@ impl Default for Settings {
             width: 1024.0,
             height: 768.0,
             fullscreen: false,
-            title: "My Venus Game",
+            title: Cow::Borrowed("My Venus Game"),
+            app_id: None,
             resizable: false,
+            min_size: None,
+            max_size: None,
+            icon: None,
             auto_clear_input_cache: true,
+            unfocused_behavior: UnfocusedBehavior::Continue,
+            scale_mode: ScaleMode::Stretch,
+            hot_reload: false,
+            asset_root: None,
+            debug_overlay_key: None,
+            freeze_key: None,
+            step_key: None,
         }
     }
 }
 
-impl Venus {
-    pub fn run<T: Future<Output = ()>, F: FnOnce(Venus) -> T + 'static>(f: F, settings: Settings) {
-        let Settings {
+impl Settings {
+    pub fn with_title(self, title: impl Into<Cow<'static, str>>) -> Settings {
+        Settings {
+            title: title.into(),
+            ..self
+        }
+    }
+
+    pub fn with_app_id(self, app_id: impl Into<Cow<'static, str>>) -> Settings {
+        Settings {
+            app_id: Some(app_id.into()),
+            ..self
+        }
+    }
+
+    pub fn with_size(self, width: f32, height: f32) -> Settings {
+        Settings {
             width,
             height,
-            fullscreen,
-            title,
-            resizable,
-            auto_clear_input_cache,
-        } = settings;
+            ..self
+        }
+    }
+
+    pub fn with_fullscreen(self, fullscreen: bool) -> Settings {
+        Settings { fullscreen, ..self }
+    }
+
+    pub fn with_resizable(self, resizable: bool) -> Settings {
+        Settings { resizable, ..self }
+    }
+
+    pub fn with_min_size(self, width: f32, height: f32) -> Settings {
+        Settings {
+            min_size: Some(Vec2::new(width, height)),
+            ..self
+        }
+    }
+
+    pub fn with_max_size(self, width: f32, height: f32) -> Settings {
+        Settings {
+            max_size: Some(Vec2::new(width, height)),
+            ..self
+        }
+    }
+
+    pub fn with_icon(self, path: impl Into<Cow<'static, str>>) -> Settings {
+        Settings {
+            icon: Some(path.into()),
+            ..self
+        }
+    }
+
+    pub fn with_scale_mode(self, scale_mode: ScaleMode) -> Settings {
+        Settings { scale_mode, ..self }
+    }
+}
+
+impl Venus {
+    /// Open a window and run a game in it
+    ///
+    /// Panics if graphics or audio can't be initialized, see `try_run` to handle that instead.
+    pub fn run<T: Future<Output = ()>, F: FnOnce(Venus) -> T + 'static>(f: F, settings: Settings) {
+        Venus::try_run(
+            async move |venus| match venus {
+                Ok(venus) => f(venus).await,
+                Err(error) => panic!("{error}"),
+            },
+            settings,
+        );
+    }
+
+    /// Open a window and run a game in it, passing along any error from setting up graphics or
+    /// audio
+    ///
+    /// The window stays open until the game returns, so it can report the error to the player
+    /// (e.g. with a native message box) instead of crashing. If the game panics, the window
+    /// shows the panic message and backtrace until it's closed.
+    pub fn try_run<T: Future<Output = ()>, F: FnOnce(Result<Venus, Error>) -> T + 'static>(
+        f: F,
+        settings: Settings,
+    ) {
+        panic_screen::install_hook();
         blinds::run(
             blinds::Settings {
                 size: mint::Vector2 {
-                    x: width,
-                    y: height,
+                    x: settings.width,
+                    y: settings.height,
                 },
                 cursor_icon: Some(blinds::CursorIcon::Default),
-                icon_path: None,
-                fullscreen,
+                icon_path: settings.icon.clone().map(leak),
+                fullscreen: settings.fullscreen,
                 multisampling: None,
-                resizable,
-                title,
+                resizable: settings.resizable,
+                title: leak(settings.title.clone()),
             },
             async move |window, event_stream| {
                 #[cfg(not(target_arch = "wasm32"))]
@@ -86,46 +422,227 @@ impl Venus {
                 };
                 #[cfg(target_arch = "wasm32")]
                 let golem = golem::Context::from_webgl2_context(window.webgl2_context());
-                let golem = golem.expect("graphics initialization");
-                let mut venus = Venus {
-                    window,
-                    event_stream: CachedEventStream::new(event_stream),
-                    gfx: Graphics::new(golem),
-                    just_pressed: HashSet::default(),
-                    fonts: Vec::new(),
-                    text_renderer: TextRenderer::default(),
-                    audio: AudioPlayer::new(),
-                    auto_clear_input_cache,
+                let golem = golem.map_err(|error| Error::GraphicsError {
+                    action: "creating the context",
+                    error: format!("{error:?}"),
+                });
+                let texture_units = graphics::texture_units(&window);
+                let gfx = match golem.and_then(|golem| Graphics::new(golem, texture_units)) {
+                    Ok(gfx) => gfx,
+                    Err(error) => return fail_to_start(f, error).await,
                 };
-                venus
-                    .gfx
-                    .set_projection_matrix(orthographic_projection(0.0, 0.0, width, height));
-
-                f(venus).await
+                #[cfg(feature = "audio")]
+                let audio = match AudioPlayer::new() {
+                    Ok(audio) => audio,
+                    Err(error) => return fail_to_start(f, error).await,
+                };
+                log::info!("Opened a {}x{} window", settings.width, settings.height);
+                let events = CachedEventStream::new(event_stream);
+                #[allow(unused_mut)]
+                let mut venus = Venus::new(settings, Some((window, events)), gfx);
+                // Asked once up front, so the first frame already has the preferences
+                venus.update_accessibility(Accessibility::query());
+                #[cfg(feature = "audio")]
+                {
+                    venus.audio = audio;
+                }
+                panic_screen::catch_panics(f(Ok(venus))).await
             },
         );
     }
 
+    /// A Venus with no window, which draws nothing and plays no sound
+    ///
+    /// Meant for testing game logic and drawing code without a display. Draws are recorded
+    /// instead, see `take_draw_commands`. No input ever arrives, so every key reads as up, and
+    /// the window size stays at the size in the settings.
+    pub fn headless(settings: Settings) -> Venus {
+        log::info!("Running headless");
+        Venus::new(settings, None, Graphics::headless())
+    }
+
+    fn new(
+        settings: Settings,
+        window: Option<(Window, CachedEventStream)>,
+        gfx: Graphics,
+    ) -> Venus {
+        let Settings {
+            width,
+            height,
+            fullscreen: _,
+            title,
+            app_id,
+            resizable: _,
+            min_size,
+            max_size,
+            icon: _,
+            auto_clear_input_cache,
+            unfocused_behavior,
+            scale_mode,
+            hot_reload,
+            asset_root,
+            debug_overlay_key,
+            freeze_key,
+            step_key,
+        } = settings;
+        let camera = Camera::from_rect(Rect::new(0.0, 0.0, width, height));
+        let (window, event_stream) = window.unzip();
+        let mut venus = Venus {
+            window,
+            event_stream,
+            gfx,
+            just_pressed: HashSet::default(),
+            input_device: InputDevice::Keyboard,
+            #[cfg(feature = "gamepad")]
+            gamepads: Gamepads::default(),
+            #[cfg(feature = "text")]
+            fonts: SlotMap::with_key(),
+            assets: Assets::new(),
+            watcher: hot_reload.then(FileWatcher::new),
+            shaders: SlotMap::with_key(),
+            mounts: Mounts::new(asset_root),
+            save_name: save::save_name(app_id.as_deref()),
+            #[cfg(feature = "text")]
+            text_renderer: TextRenderer::default(),
+            #[cfg(feature = "audio")]
+            audio: AudioPlayer::null(),
+            #[cfg(feature = "audio")]
+            music: Music::new(),
+            camera: camera.clone(),
+            pixel_snapping: false,
+            transform: Transform::IDENTITY,
+            transform_stack: Vec::new(),
+            auto_clear_input_cache,
+            focused: true,
+            window_size: Vec2::new(width, height),
+            logical_size: Vec2::new(width, height),
+            scale_mode,
+            viewport: Rect::new(0.0, 0.0, width, height),
+            mouse_position: Vec2::ZERO,
+            mouse_delta: None,
+            cursor_image: None,
+            unfocused_behavior,
+            accessibility: Accessibility::default(),
+            accessibility_changed: false,
+            accessibility_overridden: false,
+            accessibility_query: None,
+            min_size,
+            max_size,
+            last_frame: Instant::now(),
+            unscaled_delta: 0.0,
+            time_scale: 1.0,
+            debug_overlay: DebugOverlay::new(debug_overlay_key, freeze_key, step_key),
+            debug_ui_state: DebugUiState::default(),
+            #[cfg(feature = "egui")]
+            egui_events: Vec::new(),
+        };
+        venus.gfx.set_projection_matrix(camera.projection());
+        venus.update_viewport();
+        venus
+    }
+
+    /// Everything drawn since the last call, when running headless
+    ///
+    /// Positions are in world coordinates, after any pushed transforms but before the camera.
+    /// Always empty when running in a window.
+    pub fn take_draw_commands(&mut self) -> Vec<DrawCommand> {
+        self.gfx.take_recorded()
+    }
+
     pub fn is_key_down(&self, key: Key) -> bool {
-        self.event_stream.cache().key(key)
+        self.event_stream
+            .as_ref()
+            .is_some_and(|events| events.cache().key(key))
     }
 
     pub fn is_key_pressed(&self, key: Key) -> bool {
         self.just_pressed.contains(&key)
     }
 
-    pub fn clear(&self, c: Color) {
+    /// The position of the mouse in window coordinates, see `screen_to_world`
+    pub fn mouse_position(&self) -> Vec2 {
+        self.mouse_position
+    }
+
+    /// How far the mouse has moved since the input cache was last cleared, in window coordinates
+    ///
+    /// This is for aiming and dragging the camera, where motion matters more than position.
+    /// The cursor isn't locked to the window, so motion stops at the window's edges; hiding the
+    /// cursor with `set_cursor_icon(None)` makes that less noticeable.
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_delta.unwrap_or(Vec2::ZERO)
+    }
+
+    pub fn window_size(&self) -> Vec2 {
+        self.window_size
+    }
+
+    /// How long the last frame took in seconds, multiplied by the time scale
+    ///
+    /// Pass this to everything that moves with time, like animation players, physics, cameras,
+    /// and timelines, so slow motion and hit-stop apply to all of them at once. While the game
+    /// is frozen this is 0, except on frames stepped with `step_frame`.
+    pub fn delta(&self) -> f32 {
+        if !self.is_simulating() {
+            return 0.0;
+        }
+        self.unscaled_delta * self.time_scale
+    }
+
+    /// How long the last frame took in seconds, ignoring the time scale
+    ///
+    /// This is for things that should keep moving in slow motion or while paused, like menus.
+    pub fn unscaled_delta(&self) -> f32 {
+        self.unscaled_delta
+    }
+
+    /// Speed up or slow down `delta`, where 1.0 is normal speed and 0.0 freezes time
+    ///
+    /// Time can't run backwards, so negative scales are treated as 0.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// The region of the window the game is drawn into, see `Settings::scale_mode`
+    pub fn viewport(&self) -> Rect {
+        self.viewport
+    }
+
+    /// Convert a point in window coordinates into the world coordinates of the current camera
+    pub fn screen_to_world(&self, point: impl Into<Vec2>) -> Vec2 {
+        let ndc = screen_to_ndc(
+            point.into() - self.viewport.position(),
+            self.viewport.size(),
+        );
+        self.gfx.projection_matrix().inverse().transform_point2(ndc)
+    }
+
+    /// Convert a point in the world coordinates of the current camera into window coordinates
+    pub fn world_to_screen(&self, point: impl Into<Vec2>) -> Vec2 {
+        let ndc = self.gfx.projection_matrix().transform_point2(point.into());
+        ndc_to_screen(ndc, self.viewport.size()) + self.viewport.position()
+    }
+
+    pub fn clear(&mut self, c: Color) {
         self.gfx.clear(c);
     }
 
+    /// Upload RGBA pixels into the texture atlas
+    ///
+    /// Fails if the atlas needs another page and the GPU can't allocate one, or if the image is
+    /// too large to fit on a page.
     pub fn new_texture_from_bytes(
         &mut self,
         image_data: &[u8],
         width: u32,
         height: u32,
-    ) -> Texture {
-        let handle = self.gfx.new_texture_from_bytes(image_data, width, height);
-        Texture {
+    ) -> Result<Texture, Error> {
+        let handle = self.gfx.new_texture_from_bytes(image_data, width, height)?;
+        Ok(Texture {
             handle,
             uv: Rect {
                 x: 0.,
@@ -135,9 +652,10 @@ impl Venus {
             },
             width,
             height,
-        }
+        })
     }
 
+    #[cfg(feature = "audio")]
     pub fn new_audio_from_bytes(&self, bytes: &[u8]) -> Result<Audio, Error> {
         Audio::new(bytes.into()).map_err(|error| Error::AudioDecodeError {
             path: None,
@@ -145,188 +663,955 @@ impl Venus {
         })
     }
 
-    pub async fn load_texture(&mut self, path: &str) -> Result<Texture, Error> {
+    #[cfg(feature = "audio")]
+    /// Create audio from samples between -1 and 1, with the channels of each frame interleaved
+    ///
+    /// For sounds made in code, like retro bleeps, without encoding them to a WAV first.
+    pub fn new_audio_from_samples(
+        &self,
+        samples: &[f32],
+        sample_rate: u32,
+        channels: u16,
+    ) -> Audio {
+        Audio::from_samples(samples.into(), sample_rate, channels)
+    }
+
+    #[cfg(feature = "text")]
+    /// Create a font from the contents of a TTF or OTF file
+    pub fn new_font_from_bytes(&mut self, bytes: &[u8]) -> Result<FontHandle, Error> {
+        let font = Font::from_bytes(bytes)?;
+        Ok(self.fonts.insert(font))
+    }
+
+    /// Make the files in an asset pack available to every `load_*` method
+    ///
+    /// Packs and directories mounted later take priority over earlier ones, and every mount
+    /// takes priority over loose files under the asset root
+    pub fn mount_pack(&mut self, pack: AssetPack) {
+        self.mounts.mount_pack("", pack);
+    }
+
+    /// Mount an asset pack under a prefix, so `prefix/file` loads `file` from the pack
+    ///
+    /// Like every mount, it takes priority over earlier mounts that cover the same paths.
+    pub fn mount_pack_at(&mut self, prefix: &str, pack: AssetPack) {
+        self.mounts.mount_pack(prefix, pack);
+    }
+
+    /// Load the files under a prefix from a directory, before anything mounted earlier
+    ///
+    /// Files missing from the directory fall through to earlier mounts and then the asset root,
+    /// so a mod can mount a directory with only the files it replaces. For example,
+    /// `mount_directory("sprites", "mods/hd")` loads `sprites/hero.png` from `mods/hd/hero.png`
+    /// if it exists. Mount with an empty prefix to overlay every path.
+    pub fn mount_directory(&mut self, prefix: &str, directory: &str) {
+        self.mounts.mount_directory(prefix, directory);
+    }
+
+    /// Remove every pack and directory mounted at a prefix
+    ///
+    /// An empty prefix does nothing, so packs mounted with `mount_pack` stay mounted.
+    pub fn unmount(&mut self, prefix: &str) {
+        self.mounts.unmount(prefix);
+    }
+
+    /// Load an asset pack from a file and mount it, see `mount_pack`
+    pub async fn load_pack(&mut self, path: &str) -> Result<(), Error> {
         let bytes = load_file(path).await?;
-        let image = image::load_from_memory(&bytes).map_err(|error| Error::ImageDecodeError {
-            path: path.to_string(),
-            error: Box::new(error),
-        })?;
-        Ok(self.new_texture_from_bytes(image.as_bytes(), image.width(), image.height()))
+        self.mount_pack(AssetPack::from_bytes(bytes)?);
+        Ok(())
+    }
+
+    /// Load the contents of a file, checking mounted packs and directories first
+    pub async fn load_file(&self, path: &str) -> Result<Vec<u8>, Error> {
+        let result = load_sources(self.mounts.sources(path)).await;
+        match &result {
+            Ok(bytes) => log::debug!("Loaded {path} ({} bytes)", bytes.len()),
+            Err(error) => log::warn!("Failed to load {path}: {error}"),
+        }
+        result
+    }
+
+    pub async fn load_texture(&mut self, path: &str) -> Result<Texture, Error> {
+        let bytes = self.load_file(path).await?;
+        // Decoding happens off the main thread, so the window stays responsive
+        let image = decode_image(path.to_string(), bytes).await?;
+        self.upload_image(image)
+    }
+
+    /// Rasterize the characters of some text ahead of time, on another thread where possible
+    ///
+    /// New characters are otherwise rasterized the first time they're drawn, which can cause a
+    /// hitch when a line introduces lots of them at once, such as a new size or alphabet.
+    #[cfg(feature = "text")]
+    pub async fn prewarm_text(&mut self, font: FontHandle, text: &str, size: u32) {
+        let Some(loaded) = self.fonts.get(font) else {
+            return;
+        };
+        let missing = loaded.missing(text, size);
+        if missing.is_empty() {
+            return;
+        }
+        let rasterizer = loaded.rasterizer();
+        let glyphs = decode::in_background(move || {
+            missing
+                .into_iter()
+                .map(|ch| font::rasterize_glyph(&rasterizer, ch, size))
+                .collect()
+        })
+        .await;
+        let Some(glyphs) = glyphs else {
+            log::error!("Rasterizing glyphs panicked");
+            return;
+        };
+        // The font could have been unloaded in the meantime
+        if let Some(loaded) = self.fonts.get_mut(font) {
+            loaded.upload(glyphs, &mut self.gfx);
+        }
     }
 
+    /// Rasterize a set of characters ahead of time, such as `ASCII_GLYPHS`, during a loading
+    /// screen
+    ///
+    /// This is `prewarm_text` for every character that could come up, rather than the text
+    /// about to be shown. Control characters are skipped, since they're never drawn.
+    #[cfg(feature = "text")]
+    pub async fn prewarm_glyphs(
+        &mut self,
+        font: FontHandle,
+        size: u32,
+        chars: impl IntoIterator<Item = char>,
+    ) {
+        let text: String = chars.into_iter().filter(|ch| !ch.is_control()).collect();
+        self.prewarm_text(font, &text, size).await;
+    }
+
+    #[cfg(feature = "text")]
     pub async fn load_font(&mut self, path: &str) -> Result<FontHandle, Error> {
-        let bytes = load_file(path).await?;
-        let font = Font::from_bytes(&bytes)?;
-        let idx = self.fonts.len();
-        self.fonts.push(font);
-        Ok(FontHandle(idx as u32))
+        let bytes = self.load_file(path).await?;
+        self.new_font_from_bytes(&bytes)
     }
 
+    #[cfg(feature = "audio")]
     pub async fn load_audio(&mut self, path: &str) -> Result<Audio, Error> {
-        let bytes = load_file(path).await?;
-        Audio::new(bytes.into()).map_err(|error| Error::AudioDecodeError {
-            path: Some(path.to_string()),
-            error: Box::new(error),
-        })
+        let bytes = self.load_file(path).await?;
+        decode_audio(path, bytes)
+    }
+
+    fn upload_image(&mut self, image: DecodedImage) -> Result<Texture, Error> {
+        self.new_texture_from_bytes(&image.pixels, image.width, image.height)
+    }
+
+    /// Start loading many files at once, see `BatchLoader`
+    ///
+    /// Images, audio, and fonts are recognized by their file extension and added to the asset
+    /// manager as they finish loading
+    pub fn load_batch(&self, paths: &[&str]) -> BatchLoader {
+        let pending = paths
+            .iter()
+            .map(|path| {
+                let sources: Vec<_> = self
+                    .mounts
+                    .sources(path)
+                    .into_iter()
+                    .map(vfs::Source::into_owned)
+                    .collect();
+                let kind = AssetKind::from_path(path);
+                let image_path = path.to_string();
+                let future: LoadFuture = Box::pin(async move {
+                    let bytes = load_sources(sources).await?;
+                    match kind {
+                        AssetKind::Texture => {
+                            decode_image(image_path, bytes).await.map(LoadedFile::Image)
+                        }
+                        _ => Ok(LoadedFile::Bytes(bytes)),
+                    }
+                });
+                (path.to_string(), future)
+            })
+            .collect();
+        BatchLoader::new(pending)
+    }
+
+    /// Make progress on a `BatchLoader`; call once a frame until it's finished
+    pub fn update_batch(&mut self, loader: &mut BatchLoader) {
+        for (path, result) in loader.poll_files() {
+            if let Err(error) = self.store_batch_file(loader, path, result) {
+                loader.fail(error);
+            }
+        }
+    }
+
+    fn store_batch_file(
+        &mut self,
+        loader: &mut BatchLoader,
+        path: String,
+        result: Result<LoadedFile, Error>,
+    ) -> Result<(), Error> {
+        let loaded = loader.loaded_mut();
+        let bytes = match result? {
+            LoadedFile::Image(image) => {
+                let handle = match self.assets.textures.retain_path(&path) {
+                    Some(handle) => handle,
+                    None => {
+                        let texture = self.upload_image(image)?;
+                        self.watch(&path);
+                        self.assets.textures.insert(&path, texture)
+                    }
+                };
+                loaded.textures.insert(path, handle);
+                return Ok(());
+            }
+            LoadedFile::Bytes(bytes) => bytes,
+        };
+        match AssetKind::from_path(&path) {
+            #[cfg(feature = "audio")]
+            AssetKind::Audio => {
+                let handle = match self.assets.audio.retain_path(&path) {
+                    Some(handle) => handle,
+                    None => {
+                        let audio = decode_audio(&path, bytes)?;
+                        self.watch(&path);
+                        self.assets.audio.insert(&path, audio)
+                    }
+                };
+                loaded.audio.insert(path, handle);
+            }
+            #[cfg(feature = "text")]
+            AssetKind::Font => {
+                let handle = match self.assets.fonts.retain_path(&path) {
+                    Some(handle) => handle,
+                    None => {
+                        let font = self.new_font_from_bytes(&bytes)?;
+                        self.watch(&path);
+                        self.assets.fonts.insert(&path, font)
+                    }
+                };
+                loaded.fonts.insert(path, handle);
+            }
+            // Textures always arrive decoded, and disabled kinds are kept as plain files
+            _ => {
+                loaded.files.insert(path, bytes);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn assets(&self) -> &Assets {
+        &self.assets
     }
 
-    pub fn set_camera(&mut self, x: f32, y: f32, width: f32, height: f32) {
-        self.gfx.flush();
+    /// Load a texture through the asset manager, reusing it if the path is already loaded
+    pub async fn load_texture_asset(&mut self, path: &str) -> Result<TextureAsset, Error> {
+        if let Some(handle) = self.assets.textures.retain_path(path) {
+            return Ok(handle);
+        }
+        let texture = self.load_texture(path).await?;
+        self.watch(path);
+        Ok(self.assets.textures.insert(path, texture))
+    }
+
+    #[cfg(feature = "audio")]
+    /// Load audio through the asset manager, reusing it if the path is already loaded
+    pub async fn load_audio_asset(&mut self, path: &str) -> Result<AudioAsset, Error> {
+        if let Some(handle) = self.assets.audio.retain_path(path) {
+            return Ok(handle);
+        }
+        let audio = self.load_audio(path).await?;
+        self.watch(path);
+        Ok(self.assets.audio.insert(path, audio))
+    }
+
+    #[cfg(feature = "text")]
+    /// Load a font through the asset manager, reusing it if the path is already loaded
+    pub async fn load_font_asset(&mut self, path: &str) -> Result<FontAsset, Error> {
+        if let Some(handle) = self.assets.fonts.retain_path(path) {
+            return Ok(handle);
+        }
+        let font = self.load_font(path).await?;
+        self.watch(path);
+        Ok(self.assets.fonts.insert(path, font))
+    }
+
+    /// Give up a reference to a texture, freeing its atlas space once the last one is gone
+    ///
+    /// Textures cut from it, e.g. with `Texture::slice`, mustn't be drawn after it's freed.
+    pub fn unload_texture(&mut self, handle: TextureAsset) {
+        let path = self.assets.textures.path(handle).map(str::to_string);
+        if let Some(texture) = self.assets.textures.release(handle) {
+            self.gfx.free_texture(texture.handle);
+            self.unwatch(path);
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn unload_audio(&mut self, handle: AudioAsset) {
+        let path = self.assets.audio.path(handle).map(str::to_string);
+        if self.assets.audio.release(handle).is_some() {
+            self.unwatch(path);
+        }
+    }
+
+    #[cfg(feature = "text")]
+    pub fn unload_font(&mut self, handle: FontAsset) {
+        let path = self.assets.fonts.path(handle).map(str::to_string);
+        if let Some(font) = self.assets.fonts.release(handle) {
+            if let Some(mut font) = self.fonts.remove(font) {
+                font.free_glyphs(&mut self.gfx);
+            }
+            self.unwatch(path);
+        }
+    }
+
+    fn watch(&mut self, path: &str) {
+        if let Some(watcher) = &mut self.watcher
+            && let Some(file) = self.mounts.file_path(path)
+        {
+            watcher.watch(path, file);
+        }
+    }
+
+    fn unwatch(&mut self, path: Option<String>) {
+        if let (Some(watcher), Some(path)) = (&mut self.watcher, path) {
+            watcher.unwatch(&path);
+        }
+    }
+
+    async fn reload_changed_assets(&mut self) {
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        // Failed reloads keep the previous version, since editors often save files in stages
+        for path in watcher.changed_files() {
+            log::info!("Reloading {path}");
+            if let Some(handle) = self.assets.textures.find(&path)
+                && let Err(error) = self.reload_texture(handle, &path).await
+            {
+                log::warn!("Keeping the previous version of {path}: {error}");
+            }
+            #[cfg(feature = "audio")]
+            if let Some(handle) = self.assets.audio.find(&path) {
+                match self.load_audio(&path).await {
+                    Ok(audio) => self.assets.audio.replace(handle, audio),
+                    Err(error) => log::warn!("Keeping the previous version of {path}: {error}"),
+                }
+            }
+            #[cfg(feature = "text")]
+            if let Some(handle) = self.assets.fonts.find(&path)
+                && let Some(font) = self.assets.fonts.get(handle).copied()
+            {
+                let reloaded = self
+                    .load_file(&path)
+                    .await
+                    .and_then(|bytes| Font::from_bytes(&bytes));
+                match reloaded {
+                    Ok(reloaded) => {
+                        if let Some(loaded) = self.fonts.get_mut(font) {
+                            std::mem::replace(loaded, reloaded).free_glyphs(&mut self.gfx);
+                        }
+                    }
+                    Err(error) => log::warn!("Keeping the previous version of {path}: {error}"),
+                }
+            }
+            self.reload_shaders(&path).await;
+        }
+    }
+
+    // Put a changed image in place of the old one, in the same atlas space if it's the same size
+    async fn reload_texture(&mut self, handle: TextureAsset, path: &str) -> Result<(), Error> {
+        let bytes = self.load_file(path).await?;
+        let image = decode_image(path.to_string(), bytes).await?;
+        let Some(old) = self.assets.textures.get(handle).cloned() else {
+            return Ok(());
+        };
+        if (old.width, old.height) == (image.width, image.height) {
+            self.gfx
+                .update_texture(old.handle, 0, 0, &image.pixels, image.width, image.height);
+        } else {
+            let texture = self.upload_image(image)?;
+            self.gfx.free_texture(old.handle);
+            self.assets.textures.replace(handle, texture);
+        }
+        Ok(())
+    }
+
+    pub fn set_camera(&mut self, position: impl Into<Vec2>, size: impl Into<Vec2>) {
+        let position = position.into();
+        let size = size.into();
+        self.use_camera(&Camera::from_rect(Rect::new(
+            position.x, position.y, size.x, size.y,
+        )));
+    }
+
+    pub fn use_camera(&mut self, camera: &Camera) {
+        self.camera = camera.clone();
+        if self.pixel_snapping {
+            // Line the edge of the view up with the pixel grid, rather than the center, since
+            // odd resolutions put the center half way across a pixel
+            let pixel = self.camera.visible_size() / self.logical_size;
+            let top_left = self.camera.center - self.camera.visible_size() / 2.0;
+            self.camera.center += (top_left / pixel).round() * pixel - top_left;
+        }
+        self.gfx.set_projection_matrix(self.camera.projection());
+    }
+
+    /// Snap images, shapes, and the camera to whole pixels of the logical resolution
+    ///
+    /// Pixel art drawn at fractional positions shimmers as it moves and can show seams between
+    /// tiles. With snapping on, every rectangle and image is moved so its top left corner lands
+    /// on a pixel, keeping its size, and the camera only moves a pixel at a time. Circles,
+    /// polygons, and triangles aren't snapped.
+    pub fn set_pixel_snapping(&mut self, pixel_snapping: bool) {
+        self.pixel_snapping = pixel_snapping;
         self.gfx
-            .set_projection_matrix(orthographic_projection(x, y, width, height));
+            .set_pixel_snap(pixel_snapping.then_some(self.logical_size));
+        let camera = self.camera.clone();
+        self.use_camera(&camera);
     }
 
-    pub fn draw_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
-        self.gfx.push_rect(
-            Rect {
-                x,
-                y,
-                width,
-                height,
-            },
-            color,
-            None,
-        );
+    pub fn camera(&self) -> &Camera {
+        &self.camera
     }
 
-    pub fn draw_image(&mut self, texture: &Texture, x: f32, y: f32) {
-        draw_image(
-            &mut self.gfx,
-            texture,
-            Rect {
-                x,
-                y,
-                width: texture.width as f32,
-                height: texture.height as f32,
-            },
-        );
+    /// Skip drawing rectangles and images that are entirely outside the camera's view
+    ///
+    /// This saves sending large scrolling worlds to the GPU a sprite at a time, at the cost of
+    /// a little math per draw, so it's off by default. Circles, polygons, and triangles are
+    /// always drawn.
+    pub fn set_culling(&mut self, culling: bool) {
+        self.gfx.set_culling(culling);
     }
 
-    pub fn draw_image_sized(&mut self, texture: &Texture, x: f32, y: f32, width: f32, height: f32) {
-        draw_image(
-            &mut self.gfx,
-            texture,
-            Rect {
-                x,
-                y,
-                width,
-                height,
-            },
-        );
+    /// Filter the colors of everything drawn, starting with the next frame
+    ///
+    /// This draws each frame off screen first, which costs an extra full-screen pass. The
+    /// filter works on the sRGB values drawn, and is ignored when running headless.
+    pub fn set_color_filter(&mut self, filter: ColorFilter) {
+        self.gfx.set_color_filter(filter.matrix());
+    }
+
+    /// Draw with an arbitrary world-to-screen transform, mapping into normalized device coordinates
+    ///
+    /// This replaces the current camera's projection until the next call to `use_camera`
+    pub fn set_projection(&mut self, projection: Mat3) {
+        self.gfx.set_projection_matrix(projection);
+    }
+
+    /// Render with the golem context directly, such as for a 3D background or another library
+    ///
+    /// Everything drawn before the call is on screen before the closure runs, and everything
+    /// drawn after it goes on top. Venus restores its own GPU state afterwards, so the closure
+    /// can bind its own shaders, buffers, and textures and change the blend mode or viewport
+    /// freely. Returns None without calling the closure when running headless.
+    pub fn with_gl<T>(&mut self, f: impl FnOnce(&golem::Context) -> T) -> Option<T> {
+        self.gfx.with_context(f)
     }
 
-    pub fn draw_text(&mut self, font: FontHandle, x: f32, y: f32, text: &str, size: u32) {
-        self.draw_text_wrap(font, x, y, text, size, f32::MAX);
+    pub fn draw_rect(&mut self, position: impl Into<Vec2>, size: impl Into<Vec2>, color: Color) {
+        self.canvas().draw_rect(position, size, color);
     }
 
+    pub fn draw_circle(&mut self, center: impl Into<Vec2>, radius: f32, color: Color) {
+        self.canvas().draw_circle(center, radius, color);
+    }
+
+    pub fn draw_image(&mut self, texture: &Texture, position: impl Into<Vec2>) {
+        self.canvas().draw_image(texture, position);
+    }
+
+    pub fn draw_image_sized(
+        &mut self,
+        texture: &Texture,
+        position: impl Into<Vec2>,
+        size: impl Into<Vec2>,
+    ) {
+        self.canvas().draw_image_sized(texture, position, size);
+    }
+
+    /// Draw a texture with its top left corner at the origin, then moved by the transform
+    pub fn draw_image_transformed(&mut self, texture: &Texture, transform: Transform) {
+        self.canvas().draw_image_transformed(texture, transform);
+    }
+
+    /// Apply a transform to everything drawn until the matching `pop_transform`
+    ///
+    /// Transforms pushed while another is active are applied before it, so nested transforms
+    /// behave like nested coordinate spaces
+    pub fn push_transform(&mut self, transform: Transform) {
+        self.transform_stack.push(self.transform);
+        self.transform = self.transform * transform;
+        self.gfx.set_transform(self.transform);
+    }
+
+    pub fn pop_transform(&mut self) {
+        self.transform = self.transform_stack.pop().unwrap_or(Transform::IDENTITY);
+        self.gfx.set_transform(self.transform);
+    }
+
+    #[cfg(feature = "text")]
+    pub fn draw_text(
+        &mut self,
+        font: FontHandle,
+        position: impl Into<Vec2>,
+        text: &str,
+        size: u32,
+    ) {
+        self.draw_text_wrap(font, position, text, size, f32::MAX);
+    }
+
+    #[cfg(feature = "text")]
     pub fn draw_text_wrap(
         &mut self,
         font: FontHandle,
-        x: f32,
-        y: f32,
+        position: impl Into<Vec2>,
         text: &str,
         size: u32,
         max_line_length: f32,
     ) {
-        let font = &mut self.fonts[font.0 as usize];
-        self.text_renderer
-            .layout_text(&mut self.gfx, font, x, y, text, size, max_line_length);
-        for (texture, _, x, y) in self.text_renderer.characters() {
-            draw_image(
-                &mut self.gfx,
-                &texture,
-                Rect {
-                    x,
-                    y,
-                    width: texture.width as f32,
-                    height: texture.height as f32,
-                },
-            );
-        }
+        self.canvas()
+            .draw_text_wrap(font, position, text, size, max_line_length);
+    }
+
+    #[cfg(feature = "text")]
+    /// Draw only the first characters of some text, see `Canvas::draw_text_revealed`
+    pub fn draw_text_revealed(
+        &mut self,
+        font: FontHandle,
+        position: impl Into<Vec2>,
+        text: &str,
+        size: u32,
+        max_line_length: f32,
+        characters: usize,
+    ) {
+        self.canvas()
+            .draw_text_revealed(font, position, text, size, max_line_length, characters);
     }
 
+    #[cfg(feature = "text")]
     pub fn layout_text(
         &mut self,
         font: FontHandle,
-        x: f32,
-        y: f32,
+        position: impl Into<Vec2>,
         text: &str,
         size: u32,
         max_line_length: f32,
         character_buffer: &mut Vec<(Texture, char, f32, f32)>,
     ) {
-        let font = &mut self.fonts[font.0 as usize];
-        self.text_renderer
-            .layout_text(&mut self.gfx, font, x, y, text, size, max_line_length);
-        character_buffer.extend(self.text_renderer.characters());
+        self.canvas().layout_text(
+            font,
+            position,
+            text,
+            size,
+            max_line_length,
+            character_buffer,
+        );
     }
 
+    #[cfg(feature = "text")]
+    /// How wide some text would be, or 0 if the font has been unloaded
     pub fn text_width(&self, font: FontHandle, text: &str, size: u32) -> f32 {
-        let font = &self.fonts[font.0 as usize];
-        font.text_width(text, size)
+        self.fonts
+            .get(font)
+            .map_or(0.0, |font| font.text_width(text, size))
     }
 
+    #[cfg(feature = "text")]
     pub fn line_height(&self, font: FontHandle, size: u32) -> f32 {
-        let font = &self.fonts[font.0 as usize];
-        font.line_height(size)
+        self.fonts
+            .get(font)
+            .map_or(0.0, |font| font.line_height(size))
+    }
+
+    #[cfg(feature = "text")]
+    /// Where each character of some text would be drawn, without drawing it, see `Font::layout`
+    pub fn text_layout(
+        &self,
+        font: FontHandle,
+        text: &str,
+        size: u32,
+        max_line_length: f32,
+    ) -> TextLayout {
+        self.fonts
+            .get(font)
+            .map(|font| font.layout(text, size, max_line_length))
+            .unwrap_or_default()
     }
 
     pub fn set_title(&self, title: &str) {
-        self.window.set_title(title);
+        if let Some(window) = &self.window {
+            window.set_title(title);
+        }
     }
 
+    #[cfg(feature = "audio")]
     pub fn play_audio(&mut self, audio: &Audio) -> PlayingAudio {
-        self.audio.start(audio.source().unwrap().pausable(false))
+        self.mixer().play_audio(audio)
     }
 
+    #[cfg(feature = "audio")]
     pub fn loop_audio(&mut self, audio: &Audio) -> PlayingAudio {
-        self.audio
-            .start(audio.source().unwrap().pausable(false).repeat_infinite())
+        self.mixer().loop_audio(audio)
+    }
+
+    #[cfg(feature = "audio")]
+    /// Play audio from any rodio source, such as a decoder for a format venus doesn't support
+    ///
+    /// The source has to implement `Source` from the same version of rodio venus uses. The
+    /// returned handle can be paused, resumed, and stopped like any other.
+    pub fn play_source(&mut self, source: impl rodio::Source + Send + 'static) -> PlayingAudio {
+        self.mixer().play_source(source)
     }
 
+    #[cfg(feature = "audio")]
+    /// Play samples as they're made, one at a time, until the function returns None
+    ///
+    /// For sound that changes as the game runs, like an engine hum following its speed. The
+    /// function is called on the audio thread, with the channels of each frame interleaved, so
+    /// share state with it through atomics or a channel.
+    pub fn play_generated(
+        &mut self,
+        sample_rate: u32,
+        channels: u16,
+        generate: impl FnMut() -> Option<f32> + Send + 'static,
+    ) -> PlayingAudio {
+        self.mixer()
+            .play_source(Generated::new(sample_rate, channels, generate))
+    }
+
+    #[cfg(feature = "audio")]
+    /// Play audio while watching its waveform and spectrum, e.g. for a visualizer
+    ///
+    /// To tap other sources, wrap them with `AudioTap::wrap` before `play_source`.
+    pub fn play_audio_tapped(&mut self, audio: &Audio) -> (PlayingAudio, AudioTap) {
+        self.mixer().play_audio_tapped(audio)
+    }
+
+    #[cfg(feature = "audio")]
     pub fn pause_audio(&self, handle: PlayingAudio) {
         self.audio.pause(handle);
     }
 
+    #[cfg(feature = "audio")]
     pub fn resume_audio(&self, handle: PlayingAudio) {
         self.audio.play(handle);
     }
 
+    #[cfg(feature = "audio")]
     pub fn stop_audio(&self, handle: PlayingAudio) {
         self.audio.stop(handle);
     }
 
     pub fn clear_input_cache(&mut self) {
         self.just_pressed.clear();
+        self.accessibility_changed = false;
+        if let Some(delta) = &mut self.mouse_delta {
+            *delta = Vec2::ZERO;
+        }
+        #[cfg(feature = "gamepad")]
+        self.gamepads.clear_events();
+        #[cfg(feature = "audio")]
+        self.music.changed = false;
     }
 
     pub async fn end_frame(&mut self) {
-        self.gfx.flush();
-        self.window.present();
-        if self.auto_clear_input_cache {
-            self.clear_input_cache();
+        {
+            profile_scope!("end_frame");
+            self.draw_debug_overlay();
+            self.draw_cursor_image();
+            self.gfx.finish_frame();
+            let flushes = self.gfx.take_flush_count();
+            log::trace!("Frame drawn in {flushes} flushes");
+            self.debug_overlay
+                .record_frame(self.last_frame.elapsed().as_secs_f32(), flushes);
+            if let Some(window) = &self.window {
+                window.present();
+            }
+            self.gfx.begin_frame();
+            if self.auto_clear_input_cache {
+                self.clear_input_cache();
+            }
+            let mouse_down = self.is_left_mouse_down();
+            self.debug_ui_state.end_frame(mouse_down);
+            // Events from frames where egui didn't run would otherwise pile up
+            #[cfg(feature = "egui")]
+            self.egui_events.clear();
+            #[cfg(feature = "audio")]
+            self.audio.gc();
+            #[cfg(feature = "audio")]
+            self.update_music(self.unscaled_delta);
         }
-        self.audio.gc();
+        // Spans can't be held across an await, since other work runs in the meantime
+        self.reload_changed_assets().await;
+        self.process_events().await;
+        self.poll_accessibility();
+        while !self.focused {
+            let frame_time = match self.unfocused_behavior {
+                UnfocusedBehavior::Continue => break,
+                UnfocusedBehavior::Throttle { frames_per_second } => {
+                    throttled_frame_time(frames_per_second)
+                }
+                UnfocusedBehavior::Pause => None,
+            };
+            match frame_time {
+                Some(frame_time) => {
+                    let elapsed = self.last_frame.elapsed();
+                    if elapsed >= frame_time {
+                        break;
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    std::thread::sleep(frame_time - elapsed);
+                }
+                None => {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    std::thread::sleep(PAUSED_POLL_INTERVAL);
+                }
+            }
+            self.process_events().await;
+        }
+        profiling::finish_frame();
+        let now = Instant::now();
+        self.unscaled_delta = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+        self.debug_overlay.start_frame_step();
+    }
+
+    async fn process_events(&mut self) {
         loop {
-            let event = self.event_stream.next_event().await;
-            match event {
-                None => break,
-                Some(Event::KeyboardInput(e)) if e.is_presed() => {
+            let Some(event_stream) = &mut self.event_stream else {
+                break;
+            };
+            let Some(event) = event_stream.next_event().await else {
+                break;
+            };
+            match &event {
+                Event::KeyboardInput(e) if e.is_presed() => {
                     self.just_pressed.insert(e.key());
+                    self.debug_key_pressed(e.key());
+                }
+                Event::FocusChanged(e) => {
+                    self.focused = e.is_focused();
+                    log::debug!("Window focused: {}", self.focused);
+                    if self.focused {
+                        self.refresh_accessibility();
+                    }
+                }
+                Event::Resized(e) => {
+                    self.window_size = e.size().into();
+                    log::debug!("Window resized to {}", self.window_size);
+                    self.enforce_size_limits();
+                    self.update_viewport();
+                }
+                Event::PointerMoved(e) => {
+                    let position = e.location().into();
+                    // The first position isn't relative to anything, so it isn't motion
+                    if let Some(delta) = &mut self.mouse_delta {
+                        *delta += position - self.mouse_position;
+                    } else {
+                        self.mouse_delta = Some(Vec2::ZERO);
+                    }
+                    self.mouse_position = position;
                 }
                 _ => {}
             }
+            self.track_input_device(&event);
+            #[cfg(feature = "gamepad")]
+            self.gamepads.handle(&event);
+            #[cfg(feature = "egui")]
+            if let Some(event) =
+                egui_integration::translate_event(&event, self.logical_mouse_position())
+            {
+                self.egui_events.push(event);
+            }
+        }
+    }
+
+    // The mouse position in the same units as the settings' width and height
+    pub(crate) fn logical_mouse_position(&self) -> Vec2 {
+        (self.mouse_position - self.viewport.position()) * self.logical_size / self.viewport.size()
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Draw with the current camera adjusted for a layer with the given scroll factor
+    ///
+    /// See `Camera::parallax`; the camera is restored afterwards
+    pub fn draw_parallax_layer(
+        &mut self,
+        scroll_factor: impl Into<Vec2>,
+        draw: impl FnOnce(&mut Venus),
+    ) {
+        let camera = self.camera.clone();
+        self.use_camera(&camera.parallax(scroll_factor.into()));
+        draw(self);
+        self.use_camera(&camera);
+    }
+
+    /// Draw background layers in order, from furthest to nearest
+    pub fn draw_parallax(&mut self, layers: &[ParallaxLayer]) {
+        for layer in layers {
+            self.draw_parallax_layer(layer.scroll_factor, |venus| {
+                let bounds = venus.camera.visible_bounds();
+                let size = Vec2::new(layer.texture.width as f32, layer.texture.height as f32);
+                for y in parallax::tile_positions(
+                    layer.offset.y,
+                    size.y,
+                    bounds.y,
+                    bounds.y + bounds.height,
+                    layer.repeat_y,
+                ) {
+                    for x in parallax::tile_positions(
+                        layer.offset.x,
+                        size.x,
+                        bounds.x,
+                        bounds.x + bounds.width,
+                        layer.repeat_x,
+                    ) {
+                        venus.draw_image(&layer.texture, (x, y));
+                    }
+                }
+            });
+        }
+    }
+
+    /// Draw every visible tile layer of a map, from bottom to top
+    pub fn draw_tilemap(&mut self, map: &Tilemap) {
+        for layer in map.tile_layers().filter(|layer| layer.visible) {
+            self.draw_tile_layer(map, layer);
         }
     }
+
+    /// Draw the tiles of one layer of a map that are inside the camera's view
+    pub fn draw_tile_layer(&mut self, map: &Tilemap, layer: &TileLayer) {
+        let grid = layer.grid();
+        let layer_bounds = Rect::new(
+            layer.offset.x,
+            layer.offset.y,
+            layer.width as f32 * layer.tile_size.x,
+            layer.height as f32 * layer.tile_size.y,
+        );
+        let Some(visible) = self.camera.visible_bounds().intersection(&layer_bounds) else {
+            return;
+        };
+        let color = Color::WHITE.with_alpha(layer.opacity);
+        for tile in grid.tiles_in_rect(&visible) {
+            let Some(texture) = layer.tile(tile).and_then(|gid| map.tile_texture(gid)) else {
+                continue;
+            };
+            // Tiles larger than the grid are anchored to the bottom left of their cell
+            let cell = grid.tile_rect(tile);
+            let target = Rect::new(
+                cell.x,
+                cell.y + cell.height - texture.height as f32,
+                texture.width as f32,
+                texture.height as f32,
+            );
+            self.gfx
+                .push_rect(target, color, Some((texture.handle, texture.uv)));
+        }
+    }
+
+    /// Draw part of the scene into a region of the screen with its own camera, e.g. for split-screen
+    ///
+    /// The region is in the same units as `Settings::width` and `Settings::height`. Anything drawn
+    /// by `draw` is clipped to the region, but note that `clear` always clears the whole window.
+    /// The previous viewport and camera are restored afterwards.
+    pub fn draw_in_viewport(
+        &mut self,
+        region: Rect,
+        camera: &Camera,
+        draw: impl FnOnce(&mut Venus),
+    ) {
+        let previous_viewport = self.viewport;
+        let previous_camera = self.camera.clone();
+        let scale = previous_viewport.size() / self.logical_size;
+        let position = previous_viewport.position() + region.position() * scale;
+        let size = region.size() * scale;
+        self.viewport = Rect::new(position.x, position.y, size.x, size.y);
+        self.apply_viewport();
+        self.use_camera(camera);
+
+        draw(self);
+
+        self.viewport = previous_viewport;
+        self.apply_viewport();
+        self.use_camera(&previous_camera);
+    }
+
+    fn scale_factor(&self) -> f32 {
+        self.window.as_ref().map_or(1.0, Window::scale_factor)
+    }
+
+    // Windowing doesn't support size limits directly, so resize the window back within them
+    fn enforce_size_limits(&mut self) {
+        let min = self.min_size.unwrap_or(Vec2::ZERO);
+        let max = self.max_size.unwrap_or(Vec2::INFINITY);
+        let limited = self.window_size.max(min).min(max);
+        if limited != self.window_size
+            && let Some(window) = &self.window
+        {
+            window.set_size(mint::Vector2 {
+                x: limited.x,
+                y: limited.y,
+            });
+            self.window_size = limited;
+        }
+    }
+
+    fn update_viewport(&mut self) {
+        let scale_factor = self.scale_factor();
+        let physical_size = self.window_size * scale_factor;
+        let physical_viewport = fit_viewport(self.logical_size, physical_size, self.scale_mode);
+        self.viewport = Rect::new(
+            physical_viewport.x / scale_factor,
+            physical_viewport.y / scale_factor,
+            physical_viewport.width / scale_factor,
+            physical_viewport.height / scale_factor,
+        );
+        self.apply_viewport();
+    }
+
+    fn apply_viewport(&mut self) {
+        let scale_factor = self.scale_factor();
+        let physical_viewport = Rect::new(
+            self.viewport.x * scale_factor,
+            self.viewport.y * scale_factor,
+            self.viewport.width * scale_factor,
+            self.viewport.height * scale_factor,
+        );
+        self.gfx
+            .set_viewport(physical_viewport, self.window_size * scale_factor);
+    }
+}
+
+// Window settings are made once per run, so leaking their strings costs almost nothing
+fn leak(text: Cow<'static, str>) -> &'static str {
+    match text {
+        Cow::Borrowed(text) => text,
+        Cow::Owned(text) => text.leak(),
+    }
 }
 
-// Required because otherwise draw_text mutably borrows Venus twice
-fn draw_image(gfx: &mut Graphics, texture: &Texture, target: Rect) {
-    gfx.push_rect(
-        target,
-        Color::WHITE,
-        Some((texture.handle, texture.uv.clone())),
-    );
+// Let the game handle a subsystem that failed to start
+async fn fail_to_start<T: Future<Output = ()>>(
+    f: impl FnOnce(Result<Venus, Error>) -> T,
+    error: Error,
+) {
+    log::error!("Failed to start: {error}");
+    f(Err(error)).await
+}
+
+// How long to sleep between checking for events while paused in the background
+#[cfg(not(target_arch = "wasm32"))]
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[cfg(feature = "audio")]
+fn decode_audio(path: &str, bytes: Vec<u8>) -> Result<Audio, Error> {
+    Audio::new(bytes.into()).map_err(|error| Error::AudioDecodeError {
+        path: Some(path.to_string()),
+        error: Box::new(error),
+    })
 }
 
 #[derive(Clone, Debug)]
@@ -338,6 +1623,29 @@ pub struct Texture {
 }
 
 impl Texture {
+    /// Which uploaded image the texture is part of
+    pub fn handle(&self) -> TextureHandle {
+        self.handle
+    }
+
+    /// The texture's pixels as bytes, four per pixel in RGBA order, row by row from the top left
+    ///
+    /// This reads back from the GPU, which stalls until everything drawn so far is finished, so
+    /// it's meant for loading time and tools rather than every frame. Returns `None` if the
+    /// texture has been unloaded or can't be read back.
+    pub fn read_pixels(&self, venus: &mut Venus) -> Option<Vec<u8>> {
+        let (image, image_width, image_height) = venus.gfx.read_texture(self.handle)?;
+        let mut pixels = vec![0; (self.width * self.height * 4) as usize];
+        let left = (self.uv.x * image_width as f32).round() as usize;
+        let top = (self.uv.y * image_height as f32).round() as usize;
+        let row_length = (self.width * 4) as usize;
+        for (row, target) in pixels.chunks_exact_mut(row_length.max(1)).enumerate() {
+            let start = ((top + row) * image_width as usize + left) * 4;
+            target.copy_from_slice(&image[start..start + row_length]);
+        }
+        Some(pixels)
+    }
+
     pub fn sub_texture(&self, x: u32, y: u32, width: u32, height: u32) -> Texture {
         assert!(
             x + width <= self.width && y + height <= self.height,
@@ -357,6 +1665,44 @@ impl Texture {
             height,
         }
     }
+
+    /// Split the texture into an even grid of frames, in reading order
+    pub fn grid_frames(&self, columns: u32, rows: u32) -> Vec<Texture> {
+        assert!(columns > 0 && rows > 0, "a grid needs at least one cell");
+        self.slice(self.width / columns, self.height / rows, 0, 0)
+    }
+
+    /// Cut the texture into frames of a fixed size, in reading order
+    ///
+    /// The margin is the space around the edge of the texture, and the spacing is the space
+    /// between frames, both in pixels. Partial frames at the right and bottom edges are skipped.
+    pub fn slice(
+        &self,
+        frame_width: u32,
+        frame_height: u32,
+        margin: u32,
+        spacing: u32,
+    ) -> Vec<Texture> {
+        assert!(
+            frame_width > 0 && frame_height > 0,
+            "frames must be at least one pixel wide and tall"
+        );
+        let count =
+            |size: u32, frame: u32| (size.saturating_sub(2 * margin) + spacing) / (frame + spacing);
+        let columns = count(self.width, frame_width);
+        let rows = count(self.height, frame_height);
+        (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                self.sub_texture(
+                    margin + column * (frame_width + spacing),
+                    margin + row * (frame_height + spacing),
+                    frame_width,
+                    frame_height,
+                )
+            })
+            .collect()
+    }
 }
 
 type OpaqueError = Box<dyn std::error::Error + Send + Sync + 'static>;
@@ -376,6 +1722,30 @@ pub enum Error {
         error: std::io::Error,
     },
     FontError(&'static str),
+    /// The graphics context couldn't be set up, usually because a GL feature is missing
+    GraphicsError {
+        action: &'static str,
+        error: String,
+    },
+    /// No audio output device could be opened
+    AudioDeviceError(OpaqueError),
+    PackError(&'static str),
+    ParseError {
+        path: String,
+        error: String,
+    },
+    SaveError {
+        slot: String,
+        error: std::io::Error,
+    },
+    /// A color string wasn't in any of the hex formats `Color::try_from_hex` accepts
+    HexColorError(String),
+    /// A custom shader failed to compile, with the log from the driver
+    ShaderError {
+        vertex: String,
+        fragment: String,
+        error: String,
+    },
 }
 
 impl Display for Error {
@@ -386,6 +1756,19 @@ impl Display for Error {
             }
             Error::FileLoadError { path, error: _ } => write!(f, "Error loading file: {path}"),
             Error::FontError(error) => write!(f, "Error in font: {error}"),
+            Error::GraphicsError { action, error } => {
+                write!(f, "Graphics error while {action}: {error}")
+            }
+            Error::AudioDeviceError(error) => write!(f, "Error opening audio device: {error}"),
+            Error::PackError(error) => write!(f, "Error reading asset pack: {error}"),
+            Error::ParseError { path, error } => write!(f, "Error parsing {path}: {error}"),
+            Error::SaveError { slot, error } => write!(f, "Error accessing save {slot}: {error}"),
+            Error::HexColorError(hex) => write!(f, "Malformed hex color: {hex:?}"),
+            Error::ShaderError {
+                vertex,
+                fragment,
+                error,
+            } => write!(f, "Error compiling shader {vertex} and {fragment}: {error}"),
             Error::AudioDecodeError { path, error } => {
                 write!(f, "Error decoding audio from ")?;
                 match &path {
@@ -402,15 +1785,25 @@ impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Error::ImageDecodeError { path: _, error }
-            | Error::AudioDecodeError { path: _, error } => Some(error.as_ref()),
-            Error::FileLoadError { path: _, error } => Some(error),
-            Error::FontError(_) => None,
+            | Error::AudioDecodeError { path: _, error }
+            | Error::AudioDeviceError(error) => Some(error.as_ref()),
+            Error::FileLoadError { path: _, error } | Error::SaveError { slot: _, error } => {
+                Some(error)
+            }
+            Error::FontError(_)
+            | Error::GraphicsError { .. }
+            | Error::PackError(_)
+            | Error::ParseError { .. }
+            | Error::HexColorError(_)
+            | Error::ShaderError { .. } => None,
         }
     }
 }
 
-#[derive(Copy, Clone)]
-pub struct FontHandle(u32);
+slotmap::new_key_type! {
+    #[cfg(feature = "text")]
+    pub struct FontHandle;
+}
 
 pub async fn load_file(path: &str) -> Result<Vec<u8>, Error> {
     let bytes = platter::load_file(path)
@@ -422,27 +1815,10 @@ pub async fn load_file(path: &str) -> Result<Vec<u8>, Error> {
     Ok(bytes)
 }
 
-pub struct Animation {
-    frames: Vec<Texture>,
-    ticks_per_frame: u32,
-}
-
-impl Animation {
-    pub fn new(frames: Vec<Texture>, ticks_per_frame: u32) -> Animation {
-        Animation {
-            frames,
-            ticks_per_frame,
-        }
-    }
-
-    pub fn frame(&self, tick: u32) -> &Texture {
-        let frame = (tick / self.ticks_per_frame) % (self.frames.len() as u32);
-        &self.frames[frame as usize]
-    }
-}
-
 #[cfg(test)]
 mod test {
+    use approx::assert_abs_diff_eq;
+
     use super::*;
 
     #[test]
@@ -464,4 +1840,272 @@ mod test {
             sub_texture.uv.height / 2.0
         );
     }
+
+    #[test]
+    fn slice() {
+        let texture = Texture {
+            handle: TextureHandle::mock(),
+            uv: Rect::new(0.0, 0.0, 1.0, 1.0),
+            width: 64,
+            height: 32,
+        };
+        let frames = texture.grid_frames(4, 2);
+        assert_eq!(frames.len(), 8);
+        assert_eq!((frames[5].width, frames[5].height), (16, 16));
+        assert_eq!((frames[5].uv.x, frames[5].uv.y), (0.25, 0.5));
+
+        // 1 pixel of margin and 2 of spacing leaves room for three 18 pixel frames across
+        let frames = texture.slice(18, 12, 1, 2);
+        assert_eq!(frames.len(), 6);
+        assert_eq!(frames[4].uv.x, 21.0 / 64.0);
+        assert_eq!(frames[4].uv.y, 15.0 / 32.0);
+    }
+
+    #[test]
+    fn settings_builder() {
+        let version = 3;
+        let settings = Settings::default()
+            .with_title(format!("Game v{version}"))
+            .with_size(640.0, 480.0)
+            .with_min_size(320.0, 240.0);
+        assert_eq!(settings.title, "Game v3");
+        assert_eq!((settings.width, settings.height), (640.0, 480.0));
+        assert_eq!(settings.min_size, Some(Vec2::new(320.0, 240.0)));
+        assert_eq!(settings.max_size, None);
+    }
+
+    #[test]
+    fn headless() {
+        let mut venus = Venus::headless(Settings::default());
+        assert!(!venus.is_key_down(Key::Space));
+        venus.clear(Color::BLACK);
+        venus.push_transform(Transform::translate(Vec2::new(10.0, 0.0)));
+        venus.draw_rect(Vec2::ZERO, Vec2::new(4.0, 2.0), Color::RED);
+        venus.pop_transform();
+        let texture = venus.new_texture_from_bytes(&[255; 16], 2, 2).unwrap();
+        venus.draw_image(&texture, Vec2::ZERO);
+
+        let commands = venus.take_draw_commands();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[0], DrawCommand::Clear(Color::BLACK));
+        assert_eq!(
+            commands[1],
+            DrawCommand::Quad {
+                corners: [
+                    Vec2::new(10.0, 0.0),
+                    Vec2::new(14.0, 0.0),
+                    Vec2::new(14.0, 2.0),
+                    Vec2::new(10.0, 2.0),
+                ],
+                color: Color::RED,
+                texture: None,
+            }
+        );
+        assert!(matches!(
+            commands[2],
+            DrawCommand::Quad { texture: Some(handle), .. } if handle == texture.handle()
+        ));
+        assert!(venus.take_draw_commands().is_empty());
+    }
+
+    #[test]
+    fn culling() {
+        let mut venus = Venus::headless(Settings::default());
+        venus.set_culling(true);
+        venus.set_camera(Vec2::ZERO, Vec2::new(100.0, 100.0));
+        venus.draw_rect(Vec2::new(150.0, 10.0), Vec2::new(10.0, 10.0), Color::RED);
+        // Partly on screen
+        venus.draw_rect(Vec2::new(95.0, -5.0), Vec2::new(10.0, 10.0), Color::RED);
+        venus.push_transform(Transform::translate(Vec2::new(-200.0, 0.0)));
+        venus.draw_rect(Vec2::new(150.0, 10.0), Vec2::new(10.0, 10.0), Color::RED);
+        venus.pop_transform();
+        assert_eq!(venus.take_draw_commands().len(), 1);
+
+        venus.set_culling(false);
+        venus.draw_rect(Vec2::new(150.0, 10.0), Vec2::new(10.0, 10.0), Color::RED);
+        assert_eq!(venus.take_draw_commands().len(), 1);
+    }
+
+    #[test]
+    fn frame_capture() {
+        let mut venus = Venus::headless(Settings::default());
+        venus.draw_rect(Vec2::ZERO, Vec2::new(10.0, 10.0), Color::RED);
+        venus.capture_frame();
+        venus.push_transform(Transform::translate(Vec2::new(5.0, 0.0)));
+        venus.draw_rect(Vec2::ZERO, Vec2::new(10.0, 10.0), Color::BLUE);
+        venus.pop_transform();
+        assert_eq!(venus.take_frame_capture(), None);
+        venus.gfx.finish_frame();
+
+        let capture = venus.take_frame_capture().unwrap();
+        assert_eq!(capture.batches(), 0);
+        assert!(matches!(
+            capture.commands[..],
+            [
+                CapturedCommand::SetTransform(_),
+                CapturedCommand::Draw(DrawCommand::Quad { corners, color, .. }),
+                CapturedCommand::SetTransform(_),
+            ] if color == Color::BLUE && corners[0] == Vec2::new(5.0, 0.0)
+        ));
+        assert!(capture.to_string().starts_with("batch 0    SetTransform"));
+        assert_eq!(venus.take_frame_capture(), None);
+    }
+
+    #[test]
+    fn cursor_image() {
+        let mut venus = Venus::headless(Settings::default());
+        let cursor = Texture {
+            handle: TextureHandle::mock(),
+            uv: Rect::new(0.0, 0.0, 1.0, 1.0),
+            width: 16,
+            height: 16,
+        };
+        venus.set_cursor_image(&cursor, Vec2::new(8.0, 8.0));
+        venus.draw_cursor_image();
+        let commands = venus.take_draw_commands();
+        assert!(matches!(
+            commands[..],
+            [DrawCommand::Quad { corners, .. }] if corners[0] == Vec2::new(-8.0, -8.0)
+        ));
+
+        venus.set_cursor_icon(Some(CursorIcon::Crosshair));
+        venus.draw_cursor_image();
+        assert!(venus.take_draw_commands().is_empty());
+    }
+
+    #[test]
+    fn pixel_snapping() {
+        let mut venus = Venus::headless(Settings::default().with_size(320.0, 180.0));
+        venus.set_pixel_snapping(true);
+        venus.use_camera(&Camera::new((160.3, 90.6), (320.0, 180.0)));
+        assert_abs_diff_eq!(venus.camera().center.x, 160.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(venus.camera().center.y, 91.0, epsilon = 1e-3);
+
+        venus.set_camera((0.0, 0.0), (320.0, 180.0));
+        venus.draw_rect((10.3, 20.6), (5.0, 5.0), Color::WHITE);
+        let commands = venus.take_draw_commands();
+        let [DrawCommand::Quad { corners, .. }] = &commands[..] else {
+            panic!("expected a single quad, got {commands:?}");
+        };
+        assert_abs_diff_eq!(corners[0].x, 10.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(corners[0].y, 21.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(corners[2].x, 15.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn time_scale() {
+        let mut venus = Venus::headless(Settings::default());
+        assert_eq!(venus.delta(), 0.0);
+        venus.unscaled_delta = 0.1;
+        venus.set_time_scale(0.5);
+        assert_eq!(venus.delta(), 0.05);
+        assert_eq!(venus.unscaled_delta(), 0.1);
+        venus.set_time_scale(-1.0);
+        assert_eq!(venus.delta(), 0.0);
+    }
+
+    #[test]
+    fn read_pixels() {
+        let mut venus = Venus::headless(Settings::default());
+        let pixels: Vec<u8> = (0..4 * 2 * 4).collect();
+        let texture = venus.new_texture_from_bytes(&pixels, 4, 2).unwrap();
+        assert_eq!(texture.read_pixels(&mut venus).as_ref(), Some(&pixels));
+        let corner = texture.sub_texture(2, 1, 2, 1);
+        assert_eq!(corner.read_pixels(&mut venus).unwrap(), &pixels[24..32]);
+        venus.gfx.free_texture(texture.handle());
+        assert_eq!(texture.read_pixels(&mut venus), None);
+    }
+
+    #[test]
+    fn freezing() {
+        let mut venus = Venus::headless(Settings::default());
+        venus.unscaled_delta = 0.1;
+        venus.set_frozen(true);
+        assert!(!venus.is_simulating());
+        assert_eq!(venus.delta(), 0.0);
+        assert_eq!(venus.unscaled_delta(), 0.1);
+
+        venus.step_frame();
+        venus.debug_overlay.start_frame_step();
+        assert!(venus.is_simulating());
+        assert!(venus.delta() > 0.0);
+        venus.debug_overlay.start_frame_step();
+        assert!(!venus.is_simulating());
+
+        venus.set_frozen(false);
+        assert!(venus.is_simulating());
+    }
+
+    #[test]
+    fn throttling() {
+        assert_eq!(throttled_frame_time(4.0), Some(Duration::from_millis(250)));
+        assert_eq!(throttled_frame_time(0.0), None);
+        assert_eq!(throttled_frame_time(-30.0), None);
+        assert_eq!(throttled_frame_time(f32::NAN), None);
+    }
+
+    #[test]
+    fn oversized_texture() {
+        let mut venus = Venus::headless(Settings::default());
+        assert!(
+            venus
+                .new_texture_from_bytes(&[0; 4096 * 4], 4096, 1)
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "images")]
+    #[test]
+    fn reload_texture() {
+        use std::{
+            pin::pin,
+            task::{Context, Poll, Waker},
+        };
+
+        fn block_on<T>(future: impl Future<Output = T>) -> T {
+            let mut future = pin!(future);
+            let mut context = Context::from_waker(Waker::noop());
+            loop {
+                if let Poll::Ready(result) = future.as_mut().poll(&mut context) {
+                    return result;
+                }
+                std::thread::yield_now();
+            }
+        }
+
+        let directory =
+            std::env::temp_dir().join(format!("venus-reload-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let write = |width, height, color| {
+            image::RgbaImage::from_pixel(width, height, image::Rgba(color))
+                .save(directory.join("sprite.png"))
+                .unwrap();
+        };
+        let mut venus = Venus::headless(Settings {
+            asset_root: Some(directory.to_string_lossy().into_owned()),
+            ..Settings::default()
+        });
+        write(2, 2, [255, 0, 0, 255]);
+        let handle = block_on(venus.load_texture_asset("sprite.png")).unwrap();
+        let original = venus.assets().texture(handle).unwrap().clone();
+
+        // The same size is drawn over the old image, in the same place
+        write(2, 2, [0, 0, 255, 255]);
+        block_on(venus.reload_texture(handle, "sprite.png")).unwrap();
+        let reloaded = venus.assets().texture(handle).unwrap().clone();
+        assert_eq!(reloaded.handle, original.handle);
+        assert_eq!(
+            &reloaded.read_pixels(&mut venus).unwrap()[..4],
+            [0, 0, 255, 255]
+        );
+
+        // A new size needs new space
+        write(3, 1, [0, 255, 0, 255]);
+        block_on(venus.reload_texture(handle, "sprite.png")).unwrap();
+        let resized = venus.assets().texture(handle).unwrap().clone();
+        assert_ne!(resized.handle, original.handle);
+        assert_eq!((resized.width, resized.height), (3, 1));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
 }
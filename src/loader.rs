@@ -0,0 +1,188 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use rustc_hash::FxHashMap as HashMap;
+
+#[cfg(feature = "audio")]
+use crate::AudioAsset;
+#[cfg(feature = "text")]
+use crate::FontAsset;
+use crate::{Error, TextureAsset, decode::DecodedImage};
+
+pub(crate) type LoadFuture = Pin<Box<dyn Future<Output = Result<LoadedFile, Error>>>>;
+
+/// A file from a batch, with images already decoded off the main thread
+pub(crate) enum LoadedFile {
+    Image(DecodedImage),
+    Bytes(Vec<u8>),
+}
+
+/// A set of files loading at the same time, created by `Venus::load_batch`
+///
+/// Call `Venus::update_batch` once a frame to make progress, and draw a loading screen from
+/// `progress` in the meantime.
+pub struct BatchLoader {
+    pending: Vec<(String, LoadFuture)>,
+    total: usize,
+    loaded: LoadedBatch,
+    error: Option<Error>,
+}
+
+impl BatchLoader {
+    pub(crate) fn new(pending: Vec<(String, LoadFuture)>) -> BatchLoader {
+        BatchLoader {
+            total: pending.len(),
+            pending,
+            loaded: LoadedBatch::default(),
+            error: None,
+        }
+    }
+
+    /// How many files have finished loading, and how many there are in total
+    pub fn progress(&self) -> (usize, usize) {
+        (self.total - self.pending.len(), self.total)
+    }
+
+    /// How much of the batch has loaded, from 0 to 1
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (self.total - self.pending.len()) as f32 / self.total as f32
+        }
+    }
+
+    /// One of the files still loading, to show on a loading screen
+    pub fn current(&self) -> Option<&str> {
+        self.pending.first().map(|(path, _)| path.as_str())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// The loaded assets, or the first error encountered while loading
+    pub fn finish(self) -> Result<LoadedBatch, Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.loaded),
+        }
+    }
+
+    // Poll every file that's still loading once, returning the ones which finished
+    pub(crate) fn poll_files(&mut self) -> Vec<(String, Result<LoadedFile, Error>)> {
+        // Each load is driven by the platform (a thread or a browser request), so there's
+        // nothing to wake; checking once a frame is enough
+        let mut context = Context::from_waker(Waker::noop());
+        let mut finished = Vec::new();
+        self.pending
+            .retain_mut(|(path, future)| match future.as_mut().poll(&mut context) {
+                Poll::Ready(result) => {
+                    finished.push((path.clone(), result));
+                    false
+                }
+                Poll::Pending => true,
+            });
+        finished
+    }
+
+    pub(crate) fn loaded_mut(&mut self) -> &mut LoadedBatch {
+        &mut self.loaded
+    }
+
+    pub(crate) fn fail(&mut self, error: Error) {
+        self.error.get_or_insert(error);
+    }
+}
+
+/// The assets from a finished `BatchLoader`, stored in the asset manager
+///
+/// Files which aren't a recognized image, audio, or font format are kept as raw bytes
+#[derive(Default)]
+pub struct LoadedBatch {
+    pub(crate) textures: HashMap<String, TextureAsset>,
+    #[cfg(feature = "audio")]
+    pub(crate) audio: HashMap<String, AudioAsset>,
+    #[cfg(feature = "text")]
+    pub(crate) fonts: HashMap<String, FontAsset>,
+    pub(crate) files: HashMap<String, Vec<u8>>,
+}
+
+impl LoadedBatch {
+    pub fn texture(&self, path: &str) -> Option<TextureAsset> {
+        self.textures.get(path).copied()
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn audio(&self, path: &str) -> Option<AudioAsset> {
+        self.audio.get(path).copied()
+    }
+
+    #[cfg(feature = "text")]
+    pub fn font(&self, path: &str) -> Option<FontAsset> {
+        self.fonts.get(path).copied()
+    }
+
+    pub fn file(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path).map(Vec::as_slice)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum AssetKind {
+    Texture,
+    Audio,
+    Font,
+    Other,
+}
+
+impl AssetKind {
+    pub fn from_path(path: &str) -> AssetKind {
+        let extension = path
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "tga" => AssetKind::Texture,
+            "ogg" | "wav" | "mp3" | "flac" => AssetKind::Audio,
+            "ttf" | "otf" => AssetKind::Font,
+            _ => AssetKind::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::future::ready;
+
+    use super::{AssetKind, BatchLoader, LoadedFile};
+
+    #[test]
+    fn progress() {
+        let mut loader = BatchLoader::new(vec![
+            (
+                "a.png".to_string(),
+                Box::pin(ready(Ok(LoadedFile::Bytes(vec![1])))),
+            ),
+            ("b.png".to_string(), Box::pin(std::future::pending())),
+        ]);
+        assert_eq!(loader.progress(), (0, 2));
+        let finished = loader.poll_files();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].0, "a.png");
+        assert_eq!(loader.progress(), (1, 2));
+        assert_eq!(loader.current(), Some("b.png"));
+        assert!(!loader.is_finished());
+    }
+
+    #[test]
+    fn kinds() {
+        assert_eq!(AssetKind::from_path("sprites/hero.PNG"), AssetKind::Texture);
+        assert_eq!(AssetKind::from_path("music.ogg"), AssetKind::Audio);
+        assert_eq!(AssetKind::from_path("fonts/body.ttf"), AssetKind::Font);
+        assert_eq!(AssetKind::from_path("levels/1"), AssetKind::Other);
+    }
+}
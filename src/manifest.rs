@@ -0,0 +1,112 @@
+#[cfg(feature = "audio")]
+use crate::AudioAsset;
+#[cfg(feature = "text")]
+use crate::FontAsset;
+use crate::{Error, TextureAsset, Venus, assets::Named, loader::AssetKind};
+
+impl Venus {
+    /// Give a texture a name to look it up by with `Assets::named_texture`
+    ///
+    /// Names don't keep assets loaded, so a name for an unloaded asset finds nothing.
+    pub fn name_texture(&mut self, name: &str, handle: TextureAsset) {
+        self.assets.set_name(name, Named::Texture(handle));
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn name_audio(&mut self, name: &str, handle: AudioAsset) {
+        self.assets.set_name(name, Named::Audio(handle));
+    }
+
+    #[cfg(feature = "text")]
+    pub fn name_font(&mut self, name: &str, handle: FontAsset) {
+        self.assets.set_name(name, Named::Font(handle));
+    }
+
+    /// Load every asset in a manifest through the asset manager, named so they can be looked
+    /// up with `Assets::named_texture` and friends
+    ///
+    /// Each line of a manifest is a name and a path separated by `=`, like
+    /// `player = sprites/player.png`, and lines starting with `#` are comments. The kind of
+    /// asset is worked out from the file extension, as with `load_batch`.
+    pub async fn load_manifest(&mut self, path: &str) -> Result<(), Error> {
+        let bytes = self.load_file(path).await?;
+        let text = String::from_utf8_lossy(&bytes);
+        let parse_error = |error: String| Error::ParseError {
+            path: path.to_string(),
+            error,
+        };
+        for (name, asset_path) in parse_manifest(&text).map_err(parse_error)? {
+            match AssetKind::from_path(&asset_path) {
+                AssetKind::Texture => {
+                    let handle = self.load_texture_asset(&asset_path).await?;
+                    self.name_texture(&name, handle);
+                }
+                #[cfg(feature = "audio")]
+                AssetKind::Audio => {
+                    let handle = self.load_audio_asset(&asset_path).await?;
+                    self.name_audio(&name, handle);
+                }
+                #[cfg(feature = "text")]
+                AssetKind::Font => {
+                    let handle = self.load_font_asset(&asset_path).await?;
+                    self.name_font(&name, handle);
+                }
+                _ => {
+                    return Err(parse_error(format!(
+                        "{name}: can't load {asset_path} as a texture, sound, or font"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_manifest(text: &str) -> Result<Vec<(String, String)>, String> {
+    let mut entries = Vec::new();
+    for (index, line) in text.lines().map(str::trim).enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = line
+            .split_once('=')
+            .map(|(name, path)| (name.trim(), path.trim()))
+            .filter(|(name, path)| !name.is_empty() && !path.is_empty());
+        let Some((name, path)) = entry else {
+            return Err(format!("line {}: expected a name = path", index + 1));
+        };
+        entries.push((name.to_string(), path.to_string()));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_manifest;
+    use crate::{Settings, Venus};
+
+    #[test]
+    fn manifests() {
+        let manifest = "# Characters\nplayer = sprites/player.png\n\n  jump=sfx/jump.ogg  \n";
+        assert_eq!(
+            parse_manifest(manifest).unwrap(),
+            [
+                ("player".to_string(), "sprites/player.png".to_string()),
+                ("jump".to_string(), "sfx/jump.ogg".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_manifest("player = a.png\nenemy").unwrap_err(),
+            "line 2: expected a name = path"
+        );
+
+        let mut venus = Venus::headless(Settings::default());
+        let texture = venus.new_texture_from_bytes(&[255; 4], 1, 1).unwrap();
+        let handle = venus.assets.textures.insert("sprites/player.png", texture);
+        venus.name_texture("player", handle);
+        assert!(venus.assets().named_texture("player").is_some());
+        assert!(venus.assets().named_texture("enemy").is_none());
+        venus.unload_texture(handle);
+        assert!(venus.assets().named_texture("player").is_none());
+    }
+}
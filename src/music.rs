@@ -0,0 +1,316 @@
+use std::{collections::VecDeque, time::Duration};
+
+use rodio::Source;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Audio, PlayingAudio, Venus, audio::AudioPlayer, noise::hash};
+
+/// How one queued track leads into the next, see `Venus::set_music_transition`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MusicTransition {
+    /// Start the next track the moment the last one ends
+    #[default]
+    Gapless,
+    /// Fade the next track in over the end of the last one, for a number of seconds
+    ///
+    /// Only tracks whose length is known ahead of time can be faded out of; the rest are
+    /// followed as soon as they end.
+    Crossfade(f32),
+}
+
+struct Track {
+    name: String,
+    audio: Audio,
+    duration: Option<Duration>,
+}
+
+pub(crate) struct Music {
+    queue: VecDeque<Track>,
+    // The track playing, and where it's playing
+    current: Option<(Track, PlayingAudio)>,
+    // Already queued on the same sink as the current track, so it follows without a gap
+    following: Option<Track>,
+    // A track fading out under the current one, and how many seconds it's been fading
+    fading: Option<(PlayingAudio, f32)>,
+    transition: MusicTransition,
+    shuffle: bool,
+    seed: u32,
+    picks: i32,
+    // Since the input cache was last cleared
+    pub changed: bool,
+}
+
+impl Music {
+    pub fn new() -> Music {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.subsec_nanos());
+        Music {
+            queue: VecDeque::new(),
+            current: None,
+            following: None,
+            fading: None,
+            transition: MusicTransition::default(),
+            shuffle: false,
+            seed,
+            picks: 0,
+            changed: false,
+        }
+    }
+
+    pub fn update(&mut self, player: &mut AudioPlayer, delta: f32) {
+        if let Some((old, elapsed)) = &mut self.fading {
+            *elapsed += delta;
+            let progress = match self.transition {
+                MusicTransition::Crossfade(seconds) if seconds > 0.0 => {
+                    (*elapsed / seconds).min(1.0)
+                }
+                _ => 1.0,
+            };
+            player.set_volume(*old, 1.0 - progress);
+            if let Some((_, sink)) = &self.current {
+                player.set_volume(*sink, progress);
+            }
+            if progress >= 1.0 {
+                player.stop(*old);
+                self.fading = None;
+            }
+        }
+
+        if let Some((track, sink)) = &mut self.current {
+            if self.following.is_some() && player.queued(*sink) <= 1 {
+                *track = self.following.take().unwrap();
+                self.changed = true;
+            } else if player.is_finished(*sink) {
+                self.current = None;
+                self.changed = true;
+            }
+        }
+
+        let Some((track, sink)) = &self.current else {
+            if let Some(track) = self.pick() {
+                let sink = player.start(track.audio.source().unwrap());
+                self.current = Some((track, sink));
+                self.changed = true;
+            }
+            return;
+        };
+        match self.transition {
+            MusicTransition::Gapless => {
+                if self.following.is_none() {
+                    let sink = *sink;
+                    if let Some(next) = self.pick() {
+                        player.append(sink, next.audio.source().unwrap());
+                        self.following = Some(next);
+                    }
+                }
+            }
+            MusicTransition::Crossfade(seconds) => {
+                let remaining = track
+                    .duration
+                    .map(|duration| duration.saturating_sub(player.position(*sink)));
+                if self.fading.is_none()
+                    && remaining.is_some_and(|remaining| remaining.as_secs_f32() <= seconds)
+                    && !self.queue.is_empty()
+                {
+                    let old = *sink;
+                    let next = self.pick().unwrap();
+                    let sink = player.start(next.audio.source().unwrap());
+                    player.set_volume(sink, 0.0);
+                    self.current = Some((next, sink));
+                    self.fading = Some((old, 0.0));
+                    self.changed = true;
+                }
+            }
+        }
+    }
+
+    pub fn set_transition(&mut self, player: &mut AudioPlayer, transition: MusicTransition) {
+        self.transition = transition;
+        if transition == MusicTransition::Gapless {
+            return;
+        }
+        // The following track is already queued behind the current one, which can't be undone,
+        // so the current track moves to a sink of its own and the following one is picked again
+        if let Some(following) = self.following.take() {
+            self.queue.push_front(following);
+            if let Some((track, sink)) = &mut self.current {
+                let position = player.position(*sink);
+                let moved = player.start(track.audio.source().unwrap());
+                player.seek(moved, position);
+                player.stop(*sink);
+                *sink = moved;
+            }
+        }
+    }
+
+    /// Stop the current track, leaving the rest of the queue to play
+    pub fn skip(&mut self, player: &mut AudioPlayer) {
+        if let Some((_, sink)) = self.current.take() {
+            player.stop(sink);
+            self.changed = true;
+        }
+        if let Some(following) = self.following.take() {
+            self.queue.push_front(following);
+        }
+        if let Some((old, _)) = self.fading.take() {
+            player.stop(old);
+        }
+        self.update(player, 0.0);
+    }
+
+    pub fn stop(&mut self, player: &mut AudioPlayer) {
+        self.queue.clear();
+        self.following = None;
+        self.skip(player);
+    }
+
+    // The next track to play, at random when shuffling
+    fn pick(&mut self) -> Option<Track> {
+        let index = if self.shuffle && !self.queue.is_empty() {
+            self.picks += 1;
+            hash(self.picks, self.seed) as usize % self.queue.len()
+        } else {
+            0
+        };
+        self.queue.remove(index)
+    }
+}
+
+impl Venus {
+    /// Add a track to the end of the music queue, with a name to show what's playing
+    ///
+    /// The queue plays through on its own, one track after another. Tracks are forgotten once
+    /// they've been played, so queue them again to repeat them.
+    pub fn queue_music(&mut self, name: &str, audio: &Audio) {
+        let duration = audio
+            .source()
+            .ok()
+            .and_then(|source| source.total_duration());
+        self.music.queue.push_back(Track {
+            name: name.to_string(),
+            audio: audio.clone(),
+            duration,
+        });
+        if self.music.current.is_none() {
+            self.music.update(&mut self.audio, 0.0);
+        }
+    }
+
+    /// Stop the track that's playing and move on to the next one
+    pub fn skip_music(&mut self) {
+        self.music.skip(&mut self.audio);
+    }
+
+    /// Stop the music and empty the queue
+    pub fn stop_music(&mut self) {
+        self.music.stop(&mut self.audio);
+    }
+
+    pub fn set_music_transition(&mut self, transition: MusicTransition) {
+        self.music.set_transition(&mut self.audio, transition);
+    }
+
+    /// Play the queue in a random order instead of the order it was queued in
+    pub fn set_music_shuffle(&mut self, shuffle: bool) {
+        self.music.shuffle = shuffle;
+    }
+
+    /// The name of the track playing
+    pub fn now_playing(&self) -> Option<&str> {
+        self.music
+            .current
+            .as_ref()
+            .map(|(track, _)| track.name.as_str())
+    }
+
+    /// The names of the tracks still to play, in order unless shuffling
+    pub fn upcoming_music(&self) -> impl Iterator<Item = &str> {
+        self.music
+            .following
+            .iter()
+            .chain(&self.music.queue)
+            .map(|track| track.name.as_str())
+    }
+
+    pub(crate) fn update_music(&mut self, delta: f32) {
+        self.music.update(&mut self.audio, delta);
+    }
+
+    /// Whether the music moved to another track, or stopped, since the input cache was last
+    /// cleared
+    pub fn music_changed(&self) -> bool {
+        self.music.changed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::MusicTransition;
+    use crate::{Audio, Settings, Venus};
+
+    // A valid WAV file with a single silent sample
+    fn silence() -> Audio {
+        let mut wav = Vec::new();
+        wav.extend(b"RIFF");
+        wav.extend(38u32.to_le_bytes());
+        wav.extend(b"WAVEfmt ");
+        wav.extend(16u32.to_le_bytes());
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(44100u32.to_le_bytes());
+        wav.extend((44100u32 * 2).to_le_bytes());
+        wav.extend(2u16.to_le_bytes());
+        wav.extend(16u16.to_le_bytes());
+        wav.extend(b"data");
+        wav.extend(2u32.to_le_bytes());
+        wav.extend([0, 0]);
+        Audio::new(Arc::from(wav)).unwrap()
+    }
+
+    #[test]
+    fn queue() {
+        let mut venus = Venus::headless(Settings::default());
+        let track = silence();
+        venus.queue_music("title", &track);
+        venus.queue_music("overworld", &track);
+        venus.queue_music("boss", &track);
+        assert_eq!(venus.now_playing(), Some("title"));
+        assert!(venus.music_changed());
+
+        // Nothing plays when headless, so each track ends straight away
+        venus.clear_input_cache();
+        venus.update_music(0.0);
+        assert_eq!(venus.now_playing(), Some("overworld"));
+        assert_eq!(venus.upcoming_music().collect::<Vec<_>>(), ["boss"]);
+        assert!(venus.music_changed());
+
+        // Switching away from gapless takes back the track queued behind the current one
+        venus.set_music_transition(MusicTransition::Crossfade(2.0));
+        assert!(venus.music.following.is_none());
+        assert_eq!(venus.now_playing(), Some("overworld"));
+        assert_eq!(venus.upcoming_music().collect::<Vec<_>>(), ["boss"]);
+        venus.set_music_transition(MusicTransition::Gapless);
+
+        venus.skip_music();
+        assert_eq!(venus.now_playing(), Some("boss"));
+        venus.stop_music();
+        assert_eq!(venus.now_playing(), None);
+        assert_eq!(venus.upcoming_music().count(), 0);
+
+        venus.set_music_transition(MusicTransition::Crossfade(2.0));
+        venus.set_music_shuffle(true);
+        for name in ["a", "b", "c", "d"] {
+            venus.queue_music(name, &track);
+        }
+        let mut played = Vec::new();
+        while let Some(name) = venus.now_playing() {
+            played.push(name.to_string());
+            venus.update_music(0.0);
+        }
+        played.sort();
+        assert_eq!(played, ["a", "b", "c", "d"]);
+    }
+}
@@ -0,0 +1,88 @@
+// Deterministic integer hash, so noise is stable for a given seed across platforms
+pub(crate) fn hash(x: i32, seed: u32) -> u32 {
+    let mut hash = (x as u32).wrapping_mul(0x27d4_eb2d) ^ seed.wrapping_mul(0x1656_67b1);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^ (hash >> 16)
+}
+
+fn gradient(x: i32, seed: u32) -> f32 {
+    hash(x, seed) as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+/// Smooth one-dimensional gradient noise in the range [-1, 1]
+pub fn perlin_1d(x: f32, seed: u32) -> f32 {
+    let cell = x.floor();
+    let t = x - cell;
+    let cell = cell as i32;
+    let left = gradient(cell, seed) * t;
+    let right = gradient(cell + 1, seed) * (t - 1.0);
+    // One-dimensional gradient noise only reaches +/- 0.5, so stretch it to the full range
+    (left + (right - left) * fade(t)) * 2.0
+}
+
+// Mix both coordinates into one hash, so nearby cells don't line up
+fn hash_2d(x: i32, y: i32, seed: u32) -> u32 {
+    hash(x ^ hash(y, seed) as i32, seed)
+}
+
+/// Blocky-but-smooth noise from random values at each whole coordinate, in the range [0, 1]
+pub fn value_2d(x: f32, y: f32, seed: u32) -> f32 {
+    let (cell_x, cell_y) = (x.floor(), y.floor());
+    let (tx, ty) = (fade(x - cell_x), fade(y - cell_y));
+    let (cell_x, cell_y) = (cell_x as i32, cell_y as i32);
+    let value = |dx: i32, dy: i32| hash_2d(cell_x + dx, cell_y + dy, seed) as f32 / u32::MAX as f32;
+    let top = value(0, 0) + (value(1, 0) - value(0, 0)) * tx;
+    let bottom = value(0, 1) + (value(1, 1) - value(0, 1)) * tx;
+    top + (bottom - top) * ty
+}
+
+/// Smooth two-dimensional gradient noise in the range [-1, 1]
+pub fn perlin_2d(x: f32, y: f32, seed: u32) -> f32 {
+    let (cell_x, cell_y) = (x.floor(), y.floor());
+    let (fx, fy) = (x - cell_x, y - cell_y);
+    let (cell_x, cell_y) = (cell_x as i32, cell_y as i32);
+    let dot = |dx: i32, dy: i32| {
+        let angle = hash_2d(cell_x + dx, cell_y + dy, seed) as f32 / u32::MAX as f32
+            * std::f32::consts::TAU;
+        angle.cos() * (fx - dx as f32) + angle.sin() * (fy - dy as f32)
+    };
+    let (tx, ty) = (fade(fx), fade(fy));
+    let top = dot(0, 0) + (dot(1, 0) - dot(0, 0)) * tx;
+    let bottom = dot(0, 1) + (dot(1, 1) - dot(0, 1)) * tx;
+    // Unit gradients only reach +/- sqrt(1/2), so stretch it to the full range
+    ((top + (bottom - top) * ty) * std::f32::consts::SQRT_2).clamp(-1.0, 1.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{perlin_1d, perlin_2d, value_2d};
+
+    #[test]
+    fn perlin_1d_range() {
+        for i in 0..1000 {
+            let x = i as f32 * 0.037;
+            let value = perlin_1d(x, 7);
+            assert!((-1.0..=1.0).contains(&value));
+        }
+        assert_eq!(perlin_1d(3.0, 7), 0.0);
+        assert_eq!(perlin_1d(1.5, 7), perlin_1d(1.5, 7));
+    }
+
+    #[test]
+    fn noise_2d_range() {
+        for i in 0..1000 {
+            let (x, y) = (i as f32 * 0.037, i as f32 * 0.061);
+            assert!((-1.0..=1.0).contains(&perlin_2d(x, y, 7)));
+            assert!((0.0..=1.0).contains(&value_2d(x, y, 7)));
+        }
+        assert_eq!(perlin_2d(3.0, -2.0, 7), 0.0);
+        assert_ne!(value_2d(0.5, 0.5, 7), value_2d(0.5, 0.5, 8));
+    }
+}
@@ -0,0 +1,230 @@
+use rustc_hash::FxHashMap as HashMap;
+
+use crate::Error;
+
+const MAGIC: &[u8; 4] = b"VPAK";
+const VERSION: u32 = 1;
+
+/// A single file containing many assets, looked up by their original paths
+///
+/// Mount one with `Venus::mount_pack` and every `Venus::load_*` call checks it before going to
+/// the filesystem or network. Build packs with `AssetPackBuilder`, usually from a build script
+/// or a small tool.
+///
+/// The format is a header of `VPAK`, a version number, and an entry count, then a table of
+/// entries (path, offset, length), then the contents of every file back to back. All integers
+/// are little-endian.
+///
+/// Packs can also be embedded straight into the executable with `include_assets!`.
+pub struct AssetPack {
+    data: Vec<u8>,
+    entries: HashMap<String, Entry>,
+}
+
+enum Entry {
+    Packed { offset: usize, length: usize },
+    Embedded(&'static [u8]),
+}
+
+impl AssetPack {
+    pub fn from_bytes(data: Vec<u8>) -> Result<AssetPack, Error> {
+        let mut reader = Reader { data: &data, at: 0 };
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(Error::PackError("not an asset pack"));
+        }
+        if reader.u32()? != VERSION {
+            return Err(Error::PackError("unsupported asset pack version"));
+        }
+        let count = reader.u32()?;
+        let mut entries = HashMap::default();
+        for _ in 0..count {
+            let path_length = reader.u32()? as usize;
+            let path = std::str::from_utf8(reader.bytes(path_length)?)
+                .map_err(|_| Error::PackError("asset path is not UTF-8"))?
+                .to_string();
+            let offset = reader.u64()? as usize;
+            let length = reader.u64()? as usize;
+            if offset
+                .checked_add(length)
+                .is_none_or(|end| end > data.len())
+            {
+                return Err(Error::PackError("asset extends past the end of the pack"));
+            }
+            entries.insert(path, Entry::Packed { offset, length });
+        }
+
+        Ok(AssetPack { data, entries })
+    }
+
+    /// A pack of files compiled into the executable, usually created with `include_assets!`
+    pub fn embedded(files: &[(&str, &'static [u8])]) -> AssetPack {
+        let entries = files
+            .iter()
+            .map(|(path, contents)| (path.to_string(), Entry::Embedded(contents)))
+            .collect();
+        AssetPack {
+            data: Vec::new(),
+            entries,
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        match *self.entries.get(path)? {
+            Entry::Packed { offset, length } => Some(&self.data[offset..offset + length]),
+            Entry::Embedded(contents) => Some(contents),
+        }
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], Error> {
+        let bytes = self
+            .data
+            .get(self.at..self.at + count)
+            .ok_or(Error::PackError("asset pack is truncated"))?;
+        self.at += count;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        let bytes = self.bytes(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+/// Embed files into the executable as an `AssetPack`
+///
+/// Paths are relative to the crate root (the directory with `Cargo.toml`) and are stored as
+/// written, so mounting the result with `Venus::mount_pack` lets the same `load_*` calls work
+/// without any files next to the executable:
+///
+/// ```ignore
+/// venus.mount_pack(venus::include_assets!["assets/player.png", "assets/music.ogg"]);
+/// let player = venus.load_texture("assets/player.png").await?;
+/// ```
+#[macro_export]
+macro_rules! include_assets {
+    ($($path:literal),* $(,)?) => {
+        $crate::AssetPack::embedded(&[$(
+            (
+                $path,
+                ::std::include_bytes!(::std::concat!(::std::env!("CARGO_MANIFEST_DIR"), "/", $path))
+                    as &'static [u8],
+            ),
+        )*])
+    };
+}
+
+#[derive(Default)]
+pub struct AssetPackBuilder {
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl AssetPackBuilder {
+    pub fn new() -> AssetPackBuilder {
+        AssetPackBuilder::default()
+    }
+
+    pub fn add(&mut self, path: &str, contents: Vec<u8>) -> &mut AssetPackBuilder {
+        self.files.push((path.to_string(), contents));
+        self
+    }
+
+    /// Add every file under a directory, with paths relative to `prefix`
+    ///
+    /// For example, adding the directory `assets` with the prefix `assets` stores
+    /// `assets/player.png` under that same path, so game code doesn't need to change.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_directory(
+        &mut self,
+        directory: impl AsRef<std::path::Path>,
+        prefix: &str,
+    ) -> std::io::Result<&mut AssetPackBuilder> {
+        for entry in std::fs::read_dir(directory)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let path = format!("{prefix}/{}", name.to_string_lossy());
+            if entry.file_type()?.is_dir() {
+                self.add_directory(entry.path(), &path)?;
+            } else {
+                let contents = std::fs::read(entry.path())?;
+                self.add(&path, contents);
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let table_size: usize = self
+            .files
+            .iter()
+            .map(|(path, _)| 4 + path.len() + 8 + 8)
+            .sum();
+        let mut offset = MAGIC.len() + 4 + 4 + table_size;
+
+        let mut pack = Vec::new();
+        pack.extend_from_slice(MAGIC);
+        pack.extend_from_slice(&VERSION.to_le_bytes());
+        pack.extend_from_slice(&(self.files.len() as u32).to_le_bytes());
+        for (path, contents) in self.files.iter() {
+            pack.extend_from_slice(&(path.len() as u32).to_le_bytes());
+            pack.extend_from_slice(path.as_bytes());
+            pack.extend_from_slice(&(offset as u64).to_le_bytes());
+            pack.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+            offset += contents.len();
+        }
+        for (_, contents) in self.files.iter() {
+            pack.extend_from_slice(contents);
+        }
+
+        pack
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AssetPack, AssetPackBuilder};
+
+    #[test]
+    fn round_trip() {
+        let bytes = AssetPackBuilder::new()
+            .add("a.txt", b"hello".to_vec())
+            .add("dir/b.bin", vec![1, 2, 3])
+            .add("empty", Vec::new())
+            .build();
+        let pack = AssetPack::from_bytes(bytes.clone()).unwrap();
+        assert_eq!(pack.get("a.txt"), Some(&b"hello"[..]));
+        assert_eq!(pack.get("dir/b.bin"), Some(&[1, 2, 3][..]));
+        assert_eq!(pack.get("empty"), Some(&[][..]));
+        assert_eq!(pack.get("missing"), None);
+
+        assert!(AssetPack::from_bytes(bytes[..bytes.len() - 1].to_vec()).is_err());
+        assert!(AssetPack::from_bytes(b"nope".to_vec()).is_err());
+    }
+
+    #[test]
+    fn embedded() {
+        let pack = AssetPack::embedded(&[("a.txt", b"hello"), ("dir/b.bin", &[1, 2, 3])]);
+        assert_eq!(pack.get("dir/b.bin"), Some(&[1, 2, 3][..]));
+        assert!(pack.contains("a.txt"));
+        assert!(!pack.contains("b.bin"));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{Color, Error, Venus};
+
+/// An ordered list of colors, each with an optional name, like the colors picked for a game's art
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Palette {
+    entries: Vec<(Option<String>, Color)>,
+}
+
+impl Palette {
+    pub fn new() -> Palette {
+        Palette::default()
+    }
+
+    pub fn push(&mut self, name: Option<&str>, color: Color) {
+        self.entries.push((name.map(str::to_string), color));
+    }
+
+    pub fn get(&self, index: usize) -> Option<Color> {
+        self.entries.get(index).map(|(_, color)| *color)
+    }
+
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.entries.get(index)?.0.as_deref()
+    }
+
+    /// The first color with a name, ignoring case
+    pub fn by_name(&self, name: &str) -> Option<Color> {
+        self.entries
+            .iter()
+            .find(|(entry, _)| {
+                entry
+                    .as_ref()
+                    .is_some_and(|entry| entry.eq_ignore_ascii_case(name))
+            })
+            .map(|(_, color)| *color)
+    }
+
+    /// The index of the color closest to another, comparing red, green, and blue
+    pub fn nearest(&self, color: Color) -> Option<usize> {
+        let distance = |other: &Color| {
+            let (r, g, b) = (other.r - color.r, other.g - color.g, other.b - color.b);
+            r * r + g * g + b * b
+        };
+        (0..self.entries.len())
+            .min_by(|a, b| distance(&self.entries[*a].1).total_cmp(&distance(&self.entries[*b].1)))
+    }
+
+    pub fn nearest_color(&self, color: Color) -> Option<Color> {
+        self.nearest(color).and_then(|index| self.get(index))
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = Color> + '_ {
+        self.entries.iter().map(|(_, color)| *color)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl FromIterator<Color> for Palette {
+    fn from_iter<T: IntoIterator<Item = Color>>(colors: T) -> Palette {
+        Palette {
+            entries: colors.into_iter().map(|color| (None, color)).collect(),
+        }
+    }
+}
+
+impl Venus {
+    /// Load a palette from a GIMP palette (`.gpl`) or a list of hex colors, one per line
+    pub async fn load_palette(&mut self, path: &str) -> Result<Palette, Error> {
+        let bytes = self.load_file(path).await?;
+        let text = String::from_utf8_lossy(&bytes);
+        parse_palette(&text).map_err(|error| Error::ParseError {
+            path: path.to_string(),
+            error,
+        })
+    }
+}
+
+fn parse_palette(text: &str) -> Result<Palette, String> {
+    let mut lines = text.lines().map(str::trim).enumerate();
+    let is_gpl = text.trim_start().starts_with("GIMP Palette");
+    if is_gpl {
+        lines.next();
+    }
+    let mut palette = Palette::new();
+    let comment = if is_gpl { '#' } else { ';' };
+    for (index, line) in lines {
+        if line.is_empty() || line.starts_with(comment) {
+            continue;
+        }
+        let line_error = |message: String| format!("line {}: {message}", index + 1);
+        if !is_gpl {
+            let color = Color::try_from_hex(line).map_err(|error| line_error(error.to_string()))?;
+            palette.push(None, color);
+            continue;
+        }
+        // Header fields like Name and Columns come before the colors
+        if line.contains(':') && !line.starts_with(|c: char| c.is_ascii_digit()) {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let mut channel = || {
+            parts
+                .next()
+                .and_then(|part| part.parse::<u8>().ok())
+                .ok_or_else(|| line_error("expected red, green, and blue from 0 to 255".into()))
+        };
+        let (r, g, b) = (channel()?, channel()?, channel()?);
+        let name = parts.collect::<Vec<_>>().join(" ");
+        let name = (!name.is_empty()).then_some(name.as_str());
+        palette.push(name, Color::from_rgba(r, g, b, 1.0));
+    }
+    Ok(palette)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Palette, parse_palette};
+    use crate::Color;
+
+    #[test]
+    fn gpl() {
+        let palette = parse_palette(
+            "GIMP Palette\nName: Primary\nColumns: 3\n#\n255   0   0\tBright Red\n  0 255   0\n0 0 255 Blue\n",
+        )
+        .unwrap();
+        assert_eq!(palette.len(), 3);
+        assert_eq!(palette.name(0), Some("Bright Red"));
+        assert_eq!(palette.name(1), None);
+        assert_eq!(palette.by_name("blue"), Some(Color::BLUE));
+        assert_eq!(palette.get(1), Some(Color::GREEN));
+
+        assert!(parse_palette("GIMP Palette\n255 0\n").is_err());
+    }
+
+    #[test]
+    fn hex_list_and_nearest() {
+        let palette = parse_palette("000000\n#ffffff\n\n; comment\nff0000\n").unwrap();
+        assert_eq!(
+            palette.colors().collect::<Vec<_>>(),
+            [Color::BLACK, Color::WHITE, Color::RED]
+        );
+        assert_eq!(palette.nearest(Color::from_rgba(200, 30, 20, 1.0)), Some(2));
+        assert_eq!(
+            palette.nearest_color(Color::from_hex("#ccc")),
+            Some(Color::WHITE)
+        );
+        assert_eq!(Palette::new().nearest(Color::WHITE), None);
+
+        assert!(parse_palette("ff00zz\n").is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    cell::{Cell, RefCell},
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use blinds::{CachedEventStream, Window};
+use glam::Vec2;
+
+use crate::{
+    Color, Settings, Venus,
+    debug_overlay::{LINE_HEIGHT, PADDING, text_width},
+    graphics::Graphics,
+};
+
+const BACKGROUND: Color = Color {
+    r: 0.2,
+    g: 0.05,
+    b: 0.05,
+    a: 1.0,
+};
+
+thread_local! {
+    // The message and backtrace of the last panic
+    static REPORT: RefCell<Option<String>> = const { RefCell::new(None) };
+    // Set while the game's future is dropped after a panic, so Venus hands over its window
+    static RESCUING: Cell<bool> = const { Cell::new(false) };
+    static RESCUED: RefCell<Option<Rescued>> = const { RefCell::new(None) };
+}
+
+// What's needed to keep drawing to the window after the game's Venus is gone
+struct Rescued {
+    window: Window,
+    events: CachedEventStream,
+    gfx: Graphics,
+    size: Vec2,
+}
+
+/// Record every panic's message and backtrace, on top of the existing panic hook
+pub(crate) fn install_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let report = format!("{info}\n\n{}", Backtrace::force_capture());
+        // On the web, panics abort instead of unwinding, so the log is the only trace
+        log::error!("The game panicked: {report}");
+        REPORT.with_borrow_mut(|slot| *slot = Some(report));
+        previous(info);
+    }));
+}
+
+/// Run the game, replacing the window's contents with an error screen if it panics
+///
+/// Only works where panics unwind, so not on the web.
+pub(crate) async fn catch_panics(game: impl Future<Output = ()>) {
+    let mut caught = CatchPanic {
+        future: Box::pin(game),
+    };
+    let Err(payload) = (&mut caught).await else {
+        return;
+    };
+    RESCUING.set(true);
+    drop(caught);
+    RESCUING.set(false);
+    let Some(rescued) = RESCUED.take() else {
+        // The window went down with the game, so there's nowhere to show the error
+        panic::resume_unwind(payload);
+    };
+    let report = REPORT
+        .take()
+        .unwrap_or_else(|| panic_message(payload.as_ref()));
+    show_error_screen(rescued, &report).await;
+}
+
+impl Drop for Venus {
+    fn drop(&mut self) {
+        if !std::thread::panicking() && !RESCUING.get() {
+            return;
+        }
+        if let (Some(window), Some(events)) = (self.window.take(), self.event_stream.take()) {
+            let gfx = std::mem::replace(&mut self.gfx, Graphics::headless());
+            RESCUED.set(Some(Rescued {
+                window,
+                events,
+                gfx,
+                size: self.window_size,
+            }));
+        }
+    }
+}
+
+async fn show_error_screen(rescued: Rescued, report: &str) {
+    let settings = Settings {
+        width: rescued.size.x,
+        height: rescued.size.y,
+        ..Settings::default()
+    };
+    let window = Some((rescued.window, rescued.events));
+    let mut venus = Venus::new(settings, window, rescued.gfx);
+    let lines = wrap_lines(
+        &format!("The game crashed\n\n{report}"),
+        venus.logical_size.x - PADDING * 2.0,
+    );
+    loop {
+        venus.clear(BACKGROUND);
+        for (index, line) in lines.iter().enumerate() {
+            let position = Vec2::new(PADDING, PADDING + index as f32 * LINE_HEIGHT);
+            if position.y > venus.logical_size.y {
+                break;
+            }
+            venus.draw_debug_text(position, line, Color::WHITE);
+        }
+        venus.end_frame().await;
+    }
+}
+
+// Break text into lines that fit in a width, cutting long lines wherever they overflow
+fn wrap_lines(text: &str, width: f32) -> Vec<String> {
+    let per_line = ((width / text_width("M")) as usize).max(1);
+    text.lines()
+        .flat_map(|line| {
+            let chars: Vec<char> = line.chars().collect();
+            if chars.is_empty() {
+                return vec![String::new()];
+            }
+            chars
+                .chunks(per_line)
+                .map(|chunk| chunk.iter().collect())
+                .collect()
+        })
+        .collect()
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "The game panicked".to_string()
+    }
+}
+
+struct CatchPanic<F> {
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for CatchPanic<F> {
+    type Output = Result<F::Output, Box<dyn Any + Send>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.future.as_mut();
+        match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::{CatchPanic, panic_message, wrap_lines};
+
+    #[test]
+    fn wrapping() {
+        // Each character of the debug font is 8 units wide
+        let lines = wrap_lines("abcdef\n\nab", 24.0);
+        assert_eq!(lines, ["abc", "def", "", "ab"]);
+    }
+
+    #[test]
+    fn catches() {
+        let mut caught = pin!(CatchPanic {
+            future: Box::pin(async { panic!("out of bananas") }),
+        });
+        let mut context = Context::from_waker(Waker::noop());
+        let Poll::Ready(Err(payload)) = caught.as_mut().poll(&mut context) else {
+            panic!("the panic should be caught");
+        };
+        assert_eq!(panic_message(payload.as_ref()), "out of bananas");
+    }
+}
@@ -0,0 +1,59 @@
+use glam::Vec2;
+
+use crate::Texture;
+
+/// A background image that scrolls at a different rate than the rest of the world
+#[derive(Clone, Debug)]
+pub struct ParallaxLayer {
+    pub texture: Texture,
+    /// How fast the layer moves relative to the camera, see `Camera::parallax`
+    pub scroll_factor: Vec2,
+    /// Where the layer's image sits in the layer's own coordinates
+    pub offset: Vec2,
+    /// Tile the image horizontally to cover the whole view
+    pub repeat_x: bool,
+    /// Tile the image vertically to cover the whole view
+    pub repeat_y: bool,
+}
+
+impl ParallaxLayer {
+    pub fn new(texture: Texture, scroll_factor: Vec2) -> ParallaxLayer {
+        ParallaxLayer {
+            texture,
+            scroll_factor,
+            offset: Vec2::ZERO,
+            repeat_x: false,
+            repeat_y: false,
+        }
+    }
+}
+
+// The positions along one axis to draw copies of a tile so they cover [start, end)
+pub(crate) fn tile_positions(
+    offset: f32,
+    tile_size: f32,
+    start: f32,
+    end: f32,
+    repeat: bool,
+) -> impl Iterator<Item = f32> {
+    let (first, count) = if repeat && tile_size > 0.0 {
+        let first = offset + ((start - offset) / tile_size).floor() * tile_size;
+        (first, ((end - first) / tile_size).ceil().max(0.0) as u32)
+    } else {
+        (offset, 1)
+    };
+    (0..count).map(move |i| first + i as f32 * tile_size)
+}
+
+#[cfg(test)]
+mod test {
+    use super::tile_positions;
+
+    #[test]
+    fn tiling() {
+        let positions: Vec<_> = tile_positions(10.0, 100.0, -30.0, 250.0, true).collect();
+        assert_eq!(positions, vec![-90.0, 10.0, 110.0, 210.0]);
+        let positions: Vec<_> = tile_positions(10.0, 100.0, -30.0, 250.0, false).collect();
+        assert_eq!(positions, vec![10.0]);
+    }
+}
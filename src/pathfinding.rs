@@ -0,0 +1,302 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use glam::IVec2;
+use rustc_hash::FxHashMap as HashMap;
+
+/// Whether paths can move diagonally between tiles
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Diagonals {
+    Never,
+    /// Only when both tiles beside the move can be walked on, so paths don't cut corners
+    NoCornerCutting,
+    Always,
+}
+
+/// Finds paths across tile grids with A*, see `find_path`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pathfinder {
+    pub diagonals: Diagonals,
+    /// Give up after looking at this many tiles, so searching an open world for an unreachable
+    /// tile doesn't go on forever
+    pub max_explored: usize,
+}
+
+impl Default for Pathfinder {
+    fn default() -> Pathfinder {
+        Pathfinder {
+            diagonals: Diagonals::NoCornerCutting,
+            max_explored: 10_000,
+        }
+    }
+}
+
+impl Pathfinder {
+    /// The cheapest path from one tile to another, including both, or None if there isn't one
+    ///
+    /// `cost` is the cost of stepping onto a tile, or None if it can't be walked on, e.g. from a
+    /// tilemap layer's values. Diagonal steps cost √2 times as much. Costs should be at least
+    /// 1, otherwise the path found may not be the cheapest.
+    pub fn find_path(
+        &self,
+        start: IVec2,
+        goal: IVec2,
+        cost: impl Fn(IVec2) -> Option<f32>,
+    ) -> Option<Vec<IVec2>> {
+        cost(goal)?;
+        let mut open = BinaryHeap::new();
+        // The cheapest known cost to reach each tile, and the tile it was reached from
+        let mut visited: HashMap<IVec2, (f32, IVec2)> = HashMap::default();
+        open.push(Open {
+            estimate: 0.0,
+            cost: 0.0,
+            tile: start,
+        });
+        visited.insert(start, (0.0, start));
+        let mut explored = 0;
+        while let Some(Open {
+            cost: so_far, tile, ..
+        }) = open.pop()
+        {
+            if tile == goal {
+                let mut path = vec![goal];
+                let mut tile = goal;
+                while tile != start {
+                    tile = visited[&tile].1;
+                    path.push(tile);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            // A cheaper way here was found after this one was queued
+            if so_far > visited[&tile].0 {
+                continue;
+            }
+            explored += 1;
+            if explored > self.max_explored {
+                return None;
+            }
+            for (offset, distance) in STEPS {
+                let diagonal = offset.x != 0 && offset.y != 0;
+                if diagonal {
+                    let beside = [IVec2::new(offset.x, 0), IVec2::new(0, offset.y)];
+                    let allowed = match self.diagonals {
+                        Diagonals::Never => false,
+                        Diagonals::NoCornerCutting => {
+                            beside.iter().all(|beside| cost(tile + *beside).is_some())
+                        }
+                        Diagonals::Always => true,
+                    };
+                    if !allowed {
+                        continue;
+                    }
+                }
+                let next = tile + offset;
+                let Some(step_cost) = cost(next) else {
+                    continue;
+                };
+                let next_cost = so_far + step_cost * distance;
+                if visited
+                    .get(&next)
+                    .is_some_and(|(known, _)| *known <= next_cost)
+                {
+                    continue;
+                }
+                visited.insert(next, (next_cost, tile));
+                open.push(Open {
+                    estimate: next_cost + self.heuristic(next, goal),
+                    cost: next_cost,
+                    tile: next,
+                });
+            }
+        }
+        None
+    }
+
+    // The distance left if every tile cost 1, which never overestimates
+    fn heuristic(&self, from: IVec2, to: IVec2) -> f32 {
+        let delta = (to - from).abs();
+        let (long, short) = (delta.max_element() as f32, delta.min_element() as f32);
+        match self.diagonals {
+            Diagonals::Never => long + short,
+            Diagonals::NoCornerCutting | Diagonals::Always => {
+                long + (std::f32::consts::SQRT_2 - 1.0) * short
+            }
+        }
+    }
+}
+
+const STEPS: [(IVec2, f32); 8] = [
+    (IVec2::new(0, -1), 1.0),
+    (IVec2::new(1, 0), 1.0),
+    (IVec2::new(0, 1), 1.0),
+    (IVec2::new(-1, 0), 1.0),
+    (IVec2::new(1, -1), std::f32::consts::SQRT_2),
+    (IVec2::new(1, 1), std::f32::consts::SQRT_2),
+    (IVec2::new(-1, 1), std::f32::consts::SQRT_2),
+    (IVec2::new(-1, -1), std::f32::consts::SQRT_2),
+];
+
+struct Open {
+    estimate: f32,
+    cost: f32,
+    tile: IVec2,
+}
+
+// Reversed, so the heap pops the lowest estimate first
+impl Ord for Open {
+    fn cmp(&self, other: &Open) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Open) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Open) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+/// Cut a path down to the tiles where it turns, skipping any it can go straight past
+///
+/// A straight line between two kept tiles only crosses tiles that can be walked on, including
+/// both tiles beside it where it passes exactly through a corner.
+pub fn smooth_path(path: &[IVec2], walkable: impl Fn(IVec2) -> bool) -> Vec<IVec2> {
+    let Some(first) = path.first() else {
+        return Vec::new();
+    };
+    let mut smoothed = vec![*first];
+    let mut anchor = 0;
+    while anchor < path.len() - 1 {
+        // The furthest tile that can be seen from the anchor, which is at least the next one
+        let furthest = (anchor + 1..path.len())
+            .rev()
+            .find(|index| line_of_sight(path[anchor], path[*index], &walkable))
+            .unwrap_or(anchor + 1);
+        smoothed.push(path[furthest]);
+        anchor = furthest;
+    }
+    smoothed
+}
+
+// Walk every tile the line between two tile centers touches
+fn line_of_sight(from: IVec2, to: IVec2, walkable: &impl Fn(IVec2) -> bool) -> bool {
+    let delta = to - from;
+    let (steps, direction) = (delta.abs(), delta.signum());
+    let mut tile = from;
+    let (mut x, mut y) = (0, 0);
+    while x < steps.x || y < steps.y {
+        // Which tile boundary the line crosses next, comparing in whole numbers
+        let next = (1 + 2 * x) * steps.y - (1 + 2 * y) * steps.x;
+        match next.cmp(&0) {
+            Ordering::Equal => {
+                let beside = [IVec2::new(direction.x, 0), IVec2::new(0, direction.y)];
+                if !beside.iter().all(|beside| walkable(tile + *beside)) {
+                    return false;
+                }
+                tile += direction;
+                x += 1;
+                y += 1;
+            }
+            Ordering::Less => {
+                tile.x += direction.x;
+                x += 1;
+            }
+            Ordering::Greater => {
+                tile.y += direction.y;
+                y += 1;
+            }
+        }
+        if !walkable(tile) {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use glam::IVec2;
+
+    use super::{Diagonals, Pathfinder, smooth_path};
+
+    // A 10 by 10 room with a wall down the middle, open only at the bottom
+    fn room(tile: IVec2) -> Option<f32> {
+        let inside = tile.cmpge(IVec2::ZERO).all() && tile.cmplt(IVec2::splat(10)).all();
+        let wall = tile.x == 5 && tile.y < 9;
+        (inside && !wall).then_some(1.0)
+    }
+
+    #[test]
+    fn around_a_wall() {
+        let pathfinder = Pathfinder {
+            diagonals: Diagonals::Never,
+            ..Pathfinder::default()
+        };
+        let path = pathfinder
+            .find_path(IVec2::new(4, 0), IVec2::new(6, 0), room)
+            .unwrap();
+        assert_eq!(path.first(), Some(&IVec2::new(4, 0)));
+        assert_eq!(path.last(), Some(&IVec2::new(6, 0)));
+        assert!(path.contains(&IVec2::new(5, 9)));
+        // Down 9, across 2, and back up 9
+        assert_eq!(path.len(), 21);
+        assert!(
+            path.windows(2)
+                .all(|step| (step[1] - step[0]).abs().element_sum() == 1)
+        );
+
+        let blocked = |tile: IVec2| room(tile).filter(|_| tile != IVec2::new(5, 9));
+        assert_eq!(
+            pathfinder.find_path(IVec2::new(4, 0), IVec2::new(6, 0), blocked),
+            None
+        );
+        // With no edges to the world, an unreachable tile is given up on
+        let walled_in = |tile: IVec2| (tile.abs().max_element() != 1).then_some(1.0);
+        assert_eq!(
+            Pathfinder::default().find_path(IVec2::new(5, 5), IVec2::ZERO, walled_in),
+            None
+        );
+    }
+
+    #[test]
+    fn diagonals_and_smoothing() {
+        let pathfinder = Pathfinder::default();
+        let path = pathfinder
+            .find_path(IVec2::new(4, 0), IVec2::new(6, 0), room)
+            .unwrap();
+        // Diagonals can't cut around the end of the wall, unless they're always allowed
+        assert!(path.contains(&IVec2::new(4, 9)) && path.contains(&IVec2::new(6, 9)));
+        let cutting = Pathfinder {
+            diagonals: Diagonals::Always,
+            ..pathfinder
+        }
+        .find_path(IVec2::new(4, 0), IVec2::new(6, 0), room)
+        .unwrap();
+        assert_eq!(cutting.len(), 19);
+        assert!(!cutting.contains(&IVec2::new(4, 9)));
+
+        let smoothed = smooth_path(&path, |tile| room(tile).is_some());
+        assert_eq!(
+            smoothed,
+            [
+                IVec2::new(4, 0),
+                IVec2::new(4, 9),
+                IVec2::new(6, 9),
+                IVec2::new(6, 0)
+            ]
+        );
+
+        let straight: Vec<_> = (0..5).map(|x| IVec2::new(x, 2)).collect();
+        assert_eq!(
+            smooth_path(&straight, |_| true),
+            [IVec2::new(0, 2), IVec2::new(4, 2)]
+        );
+    }
+}
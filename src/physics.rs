@@ -0,0 +1,335 @@
+use glam::Vec2;
+use slotmap::SlotMap;
+
+use crate::{
+    FixedTimestep,
+    collision::move_and_collide,
+    shape::{Circle, Polygon, Rect},
+};
+
+slotmap::new_key_type! {
+    pub struct BodyHandle;
+}
+
+// How far above a one-way platform a body's bottom can start and still land on it
+const ONE_WAY_TOLERANCE: f32 = 0.01;
+
+/// The shape of a body, centered on its position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Collider {
+    /// An axis-aligned box with a width and height
+    Rect(Vec2),
+    Circle(f32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BodyKind {
+    /// Moved by its velocity and gravity, and stopped by static bodies and platforms
+    Dynamic,
+    /// Never moves on its own, like walls and floors
+    Static,
+    /// A static platform that only blocks bodies landing on it from above, so they can jump up
+    /// through it
+    OneWay,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Body {
+    pub kind: BodyKind,
+    /// The center of the collider
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub collider: Collider,
+    /// How much of the world's gravity applies, e.g. 0 for flying enemies
+    pub gravity_scale: f32,
+    on_ground: bool,
+    // Where the body was before the last step, for drawing between steps
+    previous_position: Vec2,
+}
+
+impl Body {
+    pub fn new(kind: BodyKind, position: Vec2, collider: Collider) -> Body {
+        Body {
+            kind,
+            position,
+            velocity: Vec2::ZERO,
+            collider,
+            gravity_scale: 1.0,
+            on_ground: false,
+            previous_position: position,
+        }
+    }
+
+    /// Whether the body was stopped by something below it during the last step
+    pub fn on_ground(&self) -> bool {
+        self.on_ground
+    }
+
+    /// Where to draw the body between the last two steps, see `PhysicsWorld::alpha`
+    pub fn interpolated_position(&self, alpha: f32) -> Vec2 {
+        self.previous_position.lerp(self.position, alpha)
+    }
+
+    pub fn bounds(&self) -> Rect {
+        match self.collider {
+            Collider::Rect(size) => Rect::from_center_size(self.position, size),
+            Collider::Circle(radius) => Circle::new(self.position, radius).bounding_rect(),
+        }
+    }
+
+    fn is_box(&self) -> bool {
+        matches!(self.collider, Collider::Rect(_))
+    }
+
+    // The smallest translation that moves this body out of another, if they overlap
+    fn penetration(&self, other: &Body) -> Option<Vec2> {
+        match (self.collider, other.collider) {
+            (Collider::Rect(_), Collider::Rect(_)) => {
+                Polygon::from_rect(&self.bounds()).collide_rect(&other.bounds())
+            }
+            (Collider::Rect(_), Collider::Circle(radius)) => Polygon::from_rect(&self.bounds())
+                .collide_circle(&Circle::new(other.position, radius)),
+            (Collider::Circle(radius), Collider::Rect(_)) => Polygon::from_rect(&other.bounds())
+                .collide_circle(&Circle::new(self.position, radius))
+                .map(|translation| -translation),
+            (Collider::Circle(radius), Collider::Circle(other_radius)) => {
+                let offset = self.position - other.position;
+                let overlap = radius + other_radius - offset.length();
+                (overlap > 0.0).then(|| offset.normalize_or(Vec2::NEG_Y) * overlap)
+            }
+        }
+    }
+}
+
+/// Two bodies touching during a step, see `PhysicsWorld::contacts`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    pub body: BodyHandle,
+    pub other: BodyHandle,
+    /// The direction `other` pushes `body` in, e.g. `(0, -1)` when landing on a floor
+    ///
+    /// Dynamic bodies don't push each other, so their overlaps have a normal of zero.
+    pub normal: Vec2,
+}
+
+/// Simple platformer physics: bodies with velocity and gravity, stopped by walls and platforms
+///
+/// Dynamic boxes are swept against static boxes, so they can't tunnel through thin walls.
+/// Anything involving a circle is pushed apart after moving instead, so fast circles can pass
+/// through thin walls. Dynamic bodies report when they overlap each other but don't collide.
+pub struct PhysicsWorld {
+    bodies: SlotMap<BodyHandle, Body>,
+    /// In units per second per second, where positive y is down the screen
+    pub gravity: Vec2,
+    timestep: FixedTimestep,
+    contacts: Vec<Contact>,
+}
+
+impl PhysicsWorld {
+    /// A world that steps 60 times per second
+    pub fn new(gravity: Vec2) -> PhysicsWorld {
+        PhysicsWorld {
+            bodies: SlotMap::with_key(),
+            gravity,
+            timestep: FixedTimestep::new(60.0),
+            contacts: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, body: Body) -> BodyHandle {
+        self.bodies.insert(body)
+    }
+
+    pub fn remove(&mut self, handle: BodyHandle) -> Option<Body> {
+        self.bodies.remove(handle)
+    }
+
+    pub fn get(&self, handle: BodyHandle) -> Option<&Body> {
+        self.bodies.get(handle)
+    }
+
+    pub fn get_mut(&mut self, handle: BodyHandle) -> Option<&mut Body> {
+        self.bodies.get_mut(handle)
+    }
+
+    pub fn bodies(&self) -> impl Iterator<Item = (BodyHandle, &Body)> {
+        self.bodies.iter()
+    }
+
+    /// Advance by the seconds since the last frame, in as many fixed steps as fit
+    pub fn update(&mut self, delta: f32) {
+        self.contacts.clear();
+        for _ in 0..self.timestep.advance(delta) {
+            self.step(self.timestep.step());
+        }
+    }
+
+    /// How far the world is between its last step and the next, for drawing bodies smoothly
+    /// with `Body::interpolated_position`
+    pub fn alpha(&self) -> f32 {
+        self.timestep.alpha()
+    }
+
+    /// Everything that touched during the last update, in the order it happened
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// Advance by exactly one step of any length, without the fixed timestep
+    pub fn step(&mut self, delta: f32) {
+        let solids: Vec<(BodyHandle, Body)> = self
+            .bodies
+            .iter()
+            .filter(|(_, body)| body.kind != BodyKind::Dynamic)
+            .map(|(handle, body)| (handle, *body))
+            .collect();
+        for (handle, body) in self
+            .bodies
+            .iter_mut()
+            .filter(|(_, body)| body.kind == BodyKind::Dynamic)
+        {
+            body.previous_position = body.position;
+            body.velocity += self.gravity * body.gravity_scale * delta;
+            body.on_ground = false;
+            let start = body.bounds();
+            let motion = body.velocity * delta;
+            // One-way platforms only count if the body starts above them and is heading down
+            let blocking: Vec<&(BodyHandle, Body)> = solids
+                .iter()
+                .filter(|(_, solid)| {
+                    solid.kind == BodyKind::Static
+                        || (motion.y > 0.0
+                            && start.y + start.height <= solid.bounds().y + ONE_WAY_TOLERANCE)
+                })
+                .collect();
+            let mut touch = |body: &mut Body, other: BodyHandle, normal: Vec2| {
+                if body.velocity.dot(normal) < 0.0 {
+                    body.velocity -= normal * body.velocity.dot(normal);
+                }
+                if normal.y < 0.0 {
+                    body.on_ground = true;
+                }
+                self.contacts.push(Contact {
+                    body: handle,
+                    other,
+                    normal,
+                });
+            };
+
+            if body.is_box() {
+                let (boxes, rects): (Vec<BodyHandle>, Vec<Rect>) = blocking
+                    .iter()
+                    .filter(|(_, solid)| solid.is_box())
+                    .map(|(handle, solid)| (*handle, solid.bounds()))
+                    .unzip();
+                let (position, collisions) = move_and_collide(start, motion, &rects);
+                body.position = position + start.size() / 2.0;
+                for collision in collisions {
+                    touch(body, boxes[collision.index], collision.normal);
+                }
+            } else {
+                body.position += motion;
+            }
+            for (other, solid) in &blocking {
+                if body.is_box() && solid.is_box() {
+                    continue;
+                }
+                if let Some(translation) = body.penetration(solid) {
+                    body.position += translation;
+                    touch(body, *other, translation.normalize_or_zero());
+                }
+            }
+        }
+
+        let dynamic: Vec<(BodyHandle, Body)> = self
+            .bodies
+            .iter()
+            .filter(|(_, body)| body.kind == BodyKind::Dynamic)
+            .map(|(handle, body)| (handle, *body))
+            .collect();
+        for (index, (handle, body)) in dynamic.iter().enumerate() {
+            for (other, other_body) in &dynamic[index + 1..] {
+                if body.penetration(other_body).is_some() {
+                    self.contacts.push(Contact {
+                        body: *handle,
+                        other: *other,
+                        normal: Vec2::ZERO,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+    use glam::Vec2;
+
+    use super::{Body, BodyKind, Collider, PhysicsWorld};
+
+    fn world_with_floor() -> PhysicsWorld {
+        let mut world = PhysicsWorld::new(Vec2::new(0.0, 1000.0));
+        world.insert(Body::new(
+            BodyKind::Static,
+            Vec2::new(0.0, 100.0),
+            Collider::Rect(Vec2::new(1000.0, 20.0)),
+        ));
+        world
+    }
+
+    #[test]
+    fn landing() {
+        let mut world = world_with_floor();
+        let player = world.insert(Body::new(
+            BodyKind::Dynamic,
+            Vec2::ZERO,
+            Collider::Rect(Vec2::new(10.0, 20.0)),
+        ));
+        let ball = world.insert(Body::new(
+            BodyKind::Dynamic,
+            Vec2::new(100.0, 0.0),
+            Collider::Circle(5.0),
+        ));
+        for _ in 0..60 {
+            world.update(1.0 / 60.0);
+        }
+        let player = world.get(player).unwrap();
+        assert!(player.on_ground());
+        assert_abs_diff_eq!(player.position.y, 80.0, epsilon = 1e-3);
+        assert_eq!(player.velocity.y, 0.0);
+        let ball = world.get(ball).unwrap();
+        assert!(ball.on_ground());
+        assert_abs_diff_eq!(ball.position.y, 85.0, epsilon = 0.1);
+        assert!(
+            world
+                .contacts()
+                .iter()
+                .all(|contact| contact.normal == Vec2::new(0.0, -1.0))
+        );
+    }
+
+    #[test]
+    fn one_way_platforms() {
+        let mut world = world_with_floor();
+        world.insert(Body::new(
+            BodyKind::OneWay,
+            Vec2::new(0.0, 50.0),
+            Collider::Rect(Vec2::new(100.0, 4.0)),
+        ));
+        // Jumping up from the floor passes through the platform, then lands on it
+        let mut body = Body::new(
+            BodyKind::Dynamic,
+            Vec2::new(0.0, 85.0),
+            Collider::Rect(Vec2::new(10.0, 10.0)),
+        );
+        body.velocity.y = -600.0;
+        let player = world.insert(body);
+        for _ in 0..120 {
+            world.update(1.0 / 60.0);
+        }
+        let player = world.get(player).unwrap();
+        assert!(player.on_ground());
+        assert_abs_diff_eq!(player.position.y, 43.0, epsilon = 1e-3);
+    }
+}
@@ -0,0 +1,224 @@
+use std::num::NonZeroU32;
+
+use glam::Mat3;
+use golem::{
+    Attribute, AttributeType, ColorFormat, ElementBuffer, GeometryMode, NumberType,
+    ShaderDescription, ShaderProgram, Surface, Texture, TextureFilter, TextureWrap, Uniform,
+    UniformType, UniformValue, VertexBuffer,
+};
+
+use crate::{ColorLut, Error, texture_atlas::bind_point_for_page};
+
+/// Draws the frame off screen, then onto the window through a final full-screen pass
+pub(crate) struct PostProcess {
+    surface: Surface,
+    size: [u32; 2],
+    // Whether the current frame is being drawn off screen
+    active: bool,
+    shader: ShaderProgram,
+    vb: VertexBuffer,
+    eb: ElementBuffer,
+    // The color grading table and how many levels it has, once one has been used
+    lut: Option<(Texture, u32)>,
+    // The texture units the color grading table and frame are sampled from, just past the ones
+    // used by atlas pages
+    lut_bind_point: NonZeroU32,
+    frame_bind_point: NonZeroU32,
+}
+
+impl PostProcess {
+    pub fn new(ctx: &golem::Context, max_pages: u32) -> Result<PostProcess, Error> {
+        use golem::Dimension::*;
+        let shader = ShaderProgram::new(
+            ctx,
+            ShaderDescription {
+                vertex_input: &[
+                    Attribute::new("vert_position", AttributeType::Vector(D2)),
+                    Attribute::new("vert_uv", AttributeType::Vector(D2)),
+                ],
+                fragment_input: &[Attribute::new("frag_uv", AttributeType::Vector(D2))],
+                uniforms: &[
+                    Uniform::new("frame", UniformType::Sampler2D),
+                    Uniform::new("color_matrix", UniformType::Matrix(D3)),
+                    Uniform::new("lut", UniformType::Sampler2D),
+                    Uniform::new("lut_size", UniformType::Scalar(NumberType::Float)),
+                    Uniform::new("lut_strength", UniformType::Scalar(NumberType::Float)),
+                ],
+                vertex_shader: r#" void main() {
+                gl_Position = vec4(vert_position, 0, 1);
+                frag_uv = vert_uv;
+            }"#,
+                fragment_shader: r#"
+            // Look up a color in a strip of slices, blending between the two nearest slices of
+            // blue, while filtering blends red and green within each slice
+            vec3 grade(vec3 color) {
+                float last = lut_size - 1.0;
+                float blue = color.b * last;
+                float slice = floor(blue);
+                float next = min(slice + 1.0, last);
+                float x = (color.r * last + 0.5) / (lut_size * lut_size);
+                float y = (color.g * last + 0.5) / lut_size;
+                vec3 low = texture(lut, vec2(x + slice / lut_size, y)).rgb;
+                vec3 high = texture(lut, vec2(x + next / lut_size, y)).rgb;
+                return mix(low, high, blue - slice);
+            }
+
+            void main() {
+                vec4 color = texture(frame, frag_uv);
+                vec3 filtered = clamp(color_matrix * color.rgb, 0.0, 1.0);
+                vec3 graded = lut_strength > 0.0 ? grade(filtered) : filtered;
+                gl_FragColor = vec4(mix(filtered, graded, lut_strength), color.a);
+            }"#,
+            },
+        )
+        .map_err(post_process_error("compiling post-processing shaders"))?;
+        let mut vb = VertexBuffer::new(ctx)
+            .map_err(post_process_error("creating post-processing buffers"))?;
+        let mut eb = ElementBuffer::new(ctx)
+            .map_err(post_process_error("creating post-processing buffers"))?;
+        // The whole screen, where the bottom left of the frame texture is the bottom left of NDC
+        vb.set_data(&[
+            -1.0, -1.0, 0.0, 0.0, //
+            1.0, -1.0, 1.0, 0.0, //
+            1.0, 1.0, 1.0, 1.0, //
+            -1.0, 1.0, 0.0, 1.0,
+        ]);
+        eb.set_data(&[0, 1, 2, 0, 2, 3]);
+        let mut texture =
+            Texture::new(ctx).map_err(post_process_error("creating the off-screen frame"))?;
+        texture.set_image(None, 1, 1, ColorFormat::RGBA);
+        let frame_error = post_process_error("creating the off-screen frame");
+        texture
+            .set_wrap_h(TextureWrap::ClampToEdge)
+            .map_err(frame_error)?;
+        texture
+            .set_wrap_v(TextureWrap::ClampToEdge)
+            .map_err(frame_error)?;
+        texture
+            .set_minification(TextureFilter::Nearest)
+            .map_err(frame_error)?;
+        texture
+            .set_magnification(TextureFilter::Nearest)
+            .map_err(frame_error)?;
+        let surface = Surface::new(ctx, texture).map_err(frame_error)?;
+        Ok(PostProcess {
+            surface,
+            size: [1, 1],
+            active: false,
+            shader,
+            vb,
+            eb,
+            lut: None,
+            lut_bind_point: bind_point_for_page(max_pages),
+            frame_bind_point: bind_point_for_page(max_pages + 1),
+        })
+    }
+
+    /// Upload a new color grading table
+    pub fn set_lut(&mut self, ctx: &golem::Context, lut: &ColorLut) -> Result<(), Error> {
+        let lut_error = post_process_error("uploading the color grading table");
+        let mut texture = match self.lut.take() {
+            Some((texture, _)) => texture,
+            None => {
+                let mut texture = Texture::new(ctx).map_err(lut_error)?;
+                texture
+                    .set_wrap_h(TextureWrap::ClampToEdge)
+                    .map_err(lut_error)?;
+                texture
+                    .set_wrap_v(TextureWrap::ClampToEdge)
+                    .map_err(lut_error)?;
+                // Blends between levels of red and green, where the slices of blue don't meet
+                texture
+                    .set_minification(TextureFilter::Linear)
+                    .map_err(lut_error)?;
+                texture
+                    .set_magnification(TextureFilter::Linear)
+                    .map_err(lut_error)?;
+                texture
+            }
+        };
+        let size = lut.size();
+        texture.set_image(Some(lut.pixels()), size * size, size, ColorFormat::RGBA);
+        self.lut = Some((texture, size));
+        Ok(())
+    }
+
+    /// Send everything drawn from now on to the off-screen frame, resized to the window
+    pub fn begin(&mut self, size: [u32; 2]) {
+        if size != self.size
+            && let Some(mut texture) = self.surface.take_texture()
+        {
+            texture.set_image(None, size[0], size[1], ColorFormat::RGBA);
+            self.surface.put_texture(texture);
+            self.size = size;
+        }
+        self.surface.bind();
+        self.active = true;
+    }
+
+    /// Stop drawing off screen, and draw the frame to the window through a color matrix, then
+    /// the color grading table at a strength from 0 to 1
+    ///
+    /// This leaves a different shader bound, without blending, and with a viewport covering the
+    /// whole window.
+    pub fn finish(&mut self, ctx: &golem::Context, color_matrix: Mat3, lut_strength: f32) {
+        Surface::unbind(ctx);
+        self.active = false;
+        let [width, height] = self.size;
+        ctx.set_viewport(0, 0, width, height);
+        ctx.set_blend_mode(None);
+        if let Some(texture) = self.surface.borrow_texture() {
+            texture.set_active(self.frame_bind_point);
+        }
+        let (lut_size, lut_strength) = match &self.lut {
+            Some((texture, size)) => {
+                texture.set_active(self.lut_bind_point);
+                (*size as f32, lut_strength)
+            }
+            None => (2.0, 0.0),
+        };
+        self.shader.bind();
+        let mut matrix = [0.0; 9];
+        color_matrix.write_cols_to_slice(&mut matrix);
+        self.shader
+            .set_uniform(
+                "frame",
+                UniformValue::Int(self.frame_bind_point.get() as i32),
+            )
+            .expect("set the frame texture");
+        self.shader
+            .set_uniform("color_matrix", UniformValue::Matrix3(matrix))
+            .expect("set the color matrix");
+        self.shader
+            .set_uniform("lut", UniformValue::Int(self.lut_bind_point.get() as i32))
+            .expect("set the color grading table");
+        self.shader
+            .set_uniform("lut_size", UniformValue::Float(lut_size))
+            .expect("set the color grading table size");
+        self.shader
+            .set_uniform("lut_strength", UniformValue::Float(lut_strength))
+            .expect("set the color grading strength");
+        // SAFETY: the indices only refer to the four vertices of the quad
+        unsafe {
+            self.shader
+                .draw(&self.vb, &self.eb, 0..6, GeometryMode::Triangles)
+                .expect("draw the frame to the window");
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Draw to the off-screen frame again, after other code may have bound something else
+    pub fn rebind(&self) {
+        self.surface.bind();
+    }
+}
+
+fn post_process_error(action: &'static str) -> impl Fn(golem::GolemError) -> Error + Copy {
+    move |error| Error::GraphicsError {
+        action,
+        error: format!("{error:?}"),
+    }
+}
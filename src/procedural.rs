@@ -0,0 +1,187 @@
+use glam::Vec2;
+
+use crate::{
+    Color, Error, Texture, Venus,
+    noise::{perlin_2d, value_2d},
+};
+
+/// An image made on the CPU, to upload with `Venus::new_texture_from_pixels`
+///
+/// The generators are for placeholder art before the real art exists, and for noise and
+/// gradient inputs to shaders.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pixels {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Pixels {
+    /// Work out each pixel's color from its position, with (0, 0) in the top left
+    pub fn from_fn(width: u32, height: u32, mut f: impl FnMut(u32, u32) -> Color) -> Pixels {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| f(x, y))
+            .collect();
+        Pixels {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn solid(width: u32, height: u32, color: Color) -> Pixels {
+        Pixels::from_fn(width, height, |_, _| color)
+    }
+
+    /// Alternating squares of two colors, each a number of pixels across
+    pub fn checkerboard(
+        width: u32,
+        height: u32,
+        square: u32,
+        first: Color,
+        second: Color,
+    ) -> Pixels {
+        let square = square.max(1);
+        Pixels::from_fn(width, height, |x, y| {
+            if (x / square + y / square) % 2 == 0 {
+                first
+            } else {
+                second
+            }
+        })
+    }
+
+    /// Blend from one color to another across the image, at an angle in radians
+    ///
+    /// An angle of 0 goes from left to right, and turning clockwise from there, PI / 2 goes
+    /// from top to bottom.
+    pub fn linear_gradient(width: u32, height: u32, from: Color, to: Color, angle: f32) -> Pixels {
+        let direction = Vec2::from_angle(angle);
+        let size = Vec2::new(width as f32, height as f32);
+        // How far the corners reach along the direction, so both ends of the gradient fit
+        let reach = (size * direction.abs()).element_sum() / 2.0;
+        Pixels::from_fn(width, height, |x, y| {
+            let offset = pixel_center(x, y) - size / 2.0;
+            let t = if reach > 0.0 {
+                offset.dot(direction) / (reach * 2.0) + 0.5
+            } else {
+                0.0
+            };
+            from.lerp(to, t.clamp(0.0, 1.0))
+        })
+    }
+
+    /// Blend from one color in the middle to another at the edges, reaching it at the middle of
+    /// each side
+    pub fn radial_gradient(width: u32, height: u32, inner: Color, outer: Color) -> Pixels {
+        let half = Vec2::new(width as f32, height as f32) / 2.0;
+        Pixels::from_fn(width, height, |x, y| {
+            let t = ((pixel_center(x, y) - half) / half.max(Vec2::splat(f32::EPSILON))).length();
+            inner.lerp(outer, t.min(1.0))
+        })
+    }
+
+    /// Grey value noise, where random values are placed every `cell` pixels and blended
+    pub fn value_noise(width: u32, height: u32, cell: f32, seed: u32) -> Pixels {
+        Pixels::from_fn(width, height, |x, y| {
+            let point = pixel_center(x, y) / cell;
+            grey(value_2d(point.x, point.y, seed))
+        })
+    }
+
+    /// Grey Perlin noise, which is smoother and less blocky than value noise, with features
+    /// about `cell` pixels across
+    pub fn perlin_noise(width: u32, height: u32, cell: f32, seed: u32) -> Pixels {
+        Pixels::from_fn(width, height, |x, y| {
+            let point = pixel_center(x, y) / cell;
+            grey(perlin_2d(point.x, point.y, seed) * 0.5 + 0.5)
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<Color> {
+        (x < self.width && y < self.height).then(|| self.pixels[(y * self.width + x) as usize])
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, color: Color) {
+        if x < self.width && y < self.height {
+            self.pixels[(y * self.width + x) as usize] = color;
+        }
+    }
+
+    /// The pixels as bytes, four per pixel in RGBA order
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|color| color.to_array())
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect()
+    }
+}
+
+impl Venus {
+    /// Upload generated pixels into the texture atlas
+    pub fn new_texture_from_pixels(&mut self, pixels: &Pixels) -> Result<Texture, Error> {
+        self.new_texture_from_bytes(&pixels.to_bytes(), pixels.width, pixels.height)
+    }
+}
+
+fn pixel_center(x: u32, y: u32) -> Vec2 {
+    Vec2::new(x as f32 + 0.5, y as f32 + 0.5)
+}
+
+fn grey(value: f32) -> Color {
+    Color {
+        r: value,
+        g: value,
+        b: value,
+        a: 1.0,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::Pixels;
+    use crate::{Color, Settings, Venus};
+
+    #[test]
+    fn generators() {
+        let checkers = Pixels::checkerboard(4, 4, 2, Color::WHITE, Color::BLACK);
+        assert_eq!(checkers.get(1, 1), Some(Color::WHITE));
+        assert_eq!(checkers.get(2, 0), Some(Color::BLACK));
+        assert_eq!(checkers.get(3, 3), Some(Color::WHITE));
+        assert_eq!(checkers.get(4, 0), None);
+
+        let across = Pixels::linear_gradient(100, 1, Color::BLACK, Color::WHITE, 0.0);
+        assert!(across.get(0, 0).unwrap().r < 0.01);
+        assert!(across.get(99, 0).unwrap().r > 0.99);
+        let down = Pixels::linear_gradient(1, 100, Color::BLACK, Color::WHITE, FRAC_PI_2);
+        assert!(down.get(0, 0).unwrap().r < 0.01);
+        assert!(down.get(0, 99).unwrap().r > 0.99);
+
+        let radial = Pixels::radial_gradient(10, 10, Color::WHITE, Color::BLACK);
+        assert!(radial.get(5, 5).unwrap().r > 0.8);
+        assert_eq!(radial.get(0, 0), Some(Color::BLACK));
+
+        let noise = Pixels::perlin_noise(16, 16, 4.0, 3);
+        assert_eq!(noise, Pixels::perlin_noise(16, 16, 4.0, 3));
+        assert_ne!(noise, Pixels::perlin_noise(16, 16, 4.0, 4));
+
+        let mut venus = Venus::headless(Settings::default());
+        let texture = venus
+            .new_texture_from_pixels(&Pixels::solid(3, 2, Color::RED))
+            .unwrap();
+        assert_eq!((texture.width, texture.height), (3, 2));
+        assert_eq!(Pixels::solid(1, 1, Color::RED).to_bytes(), [255, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,19 @@
+// Profiler spans around the engine's own work, which compile to nothing unless the puffin or
+// tracing feature is enabled
+
+macro_rules! profile_scope {
+    ($name:literal) => {
+        #[cfg(feature = "puffin")]
+        puffin::profile_scope!($name);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!($name).entered();
+    };
+}
+
+pub(crate) use profile_scope;
+
+// Tell the profiler a frame has ended
+pub(crate) fn finish_frame() {
+    #[cfg(feature = "puffin")]
+    puffin::GlobalProfiler::lock().new_frame();
+}
@@ -0,0 +1,264 @@
+use blinds::Event;
+use rustc_hash::FxHashMap as HashMap;
+
+#[cfg(feature = "gamepad")]
+use crate::{GamepadButton, GamepadId};
+use crate::{Key, Texture, Venus};
+
+/// What the player last pressed a button on, to show prompts for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    #[cfg(feature = "gamepad")]
+    Gamepad(GamepadId),
+}
+
+/// The inputs that trigger one action in a game, like jumping or interacting
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub key: Option<Key>,
+    #[cfg(feature = "gamepad")]
+    pub button: Option<GamepadButton>,
+}
+
+impl Binding {
+    pub fn key(key: Key) -> Binding {
+        Binding {
+            key: Some(key),
+            ..Binding::default()
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn button(button: GamepadButton) -> Binding {
+        Binding {
+            button: Some(button),
+            ..Binding::default()
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn with_button(self, button: GamepadButton) -> Binding {
+        Binding {
+            button: Some(button),
+            ..self
+        }
+    }
+
+    /// Whether any of the inputs was pressed since the input cache was last cleared
+    pub fn is_pressed(&self, venus: &Venus) -> bool {
+        let pressed = self.key.is_some_and(|key| venus.is_key_pressed(key));
+        #[cfg(feature = "gamepad")]
+        let pressed = pressed
+            || self.button.is_some_and(|button| {
+                venus
+                    .gamepads()
+                    .iter()
+                    .any(|id| venus.is_gamepad_button_pressed(*id, button))
+            });
+        pressed
+    }
+
+    pub fn is_down(&self, venus: &Venus) -> bool {
+        let down = self.key.is_some_and(|key| venus.is_key_down(key));
+        #[cfg(feature = "gamepad")]
+        let down = down
+            || self.button.is_some_and(|button| {
+                venus
+                    .gamepads()
+                    .iter()
+                    .any(|id| venus.is_gamepad_button_down(*id, button))
+            });
+        down
+    }
+}
+
+/// Icons for keys and buttons, e.g. cut from a button prompt sheet with `Texture::slice`
+#[derive(Clone, Debug, Default)]
+pub struct ButtonIcons {
+    keys: HashMap<Key, Texture>,
+    #[cfg(feature = "gamepad")]
+    buttons: HashMap<GamepadButton, Texture>,
+}
+
+impl ButtonIcons {
+    pub fn new() -> ButtonIcons {
+        ButtonIcons::default()
+    }
+
+    pub fn set_key(&mut self, key: Key, icon: Texture) {
+        self.keys.insert(key, icon);
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn set_button(&mut self, button: GamepadButton, icon: Texture) {
+        self.buttons.insert(button, icon);
+    }
+
+    pub fn key(&self, key: Key) -> Option<&Texture> {
+        self.keys.get(&key)
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn button(&self, button: GamepadButton) -> Option<&Texture> {
+        self.buttons.get(&button)
+    }
+}
+
+/// How to show the player which input to press
+#[derive(Clone, Debug)]
+pub enum Prompt {
+    Icon(Texture),
+    /// The input's name in brackets, like `[Space]`, when there's no icon for it
+    Text(String),
+    /// The action has nothing bound on the device in use
+    Unbound,
+}
+
+impl Venus {
+    /// What the player last pressed a key or button on, so prompts can match what they're
+    /// holding
+    pub fn input_device(&self) -> InputDevice {
+        self.input_device
+    }
+
+    /// How to show the input for an action on the device the player is using
+    ///
+    /// Icons are used where the set has one, falling back to the name of the key or button.
+    pub fn prompt(&self, binding: &Binding, icons: Option<&ButtonIcons>) -> Prompt {
+        match self.input_device {
+            InputDevice::Keyboard => match binding.key {
+                Some(key) => icons.and_then(|icons| icons.key(key)).map_or_else(
+                    || Prompt::Text(format!("[{}]", key_name(key))),
+                    |icon| Prompt::Icon(icon.clone()),
+                ),
+                None => Prompt::Unbound,
+            },
+            #[cfg(feature = "gamepad")]
+            InputDevice::Gamepad(_) => match binding.button {
+                Some(button) => icons.and_then(|icons| icons.button(button)).map_or_else(
+                    || Prompt::Text(format!("[{}]", button_name(button))),
+                    |icon| Prompt::Icon(icon.clone()),
+                ),
+                None => Prompt::Unbound,
+            },
+        }
+    }
+
+    pub(crate) fn track_input_device(&mut self, event: &Event) {
+        match event {
+            Event::KeyboardInput(e) if e.is_presed() => {
+                self.input_device = InputDevice::Keyboard;
+            }
+            #[cfg(feature = "gamepad")]
+            Event::GamepadButton(e) if e.is_pressed() => {
+                self.input_device = InputDevice::Gamepad(e.id());
+            }
+            #[cfg(feature = "gamepad")]
+            Event::GamepadDisconnected(e) if self.input_device == InputDevice::Gamepad(e.id()) => {
+                self.input_device = InputDevice::Keyboard;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A short name for a key, as it's labelled on most keyboards
+pub fn key_name(key: Key) -> String {
+    let name = match key {
+        Key::Key0 => "0",
+        Key::Key1 => "1",
+        Key::Key2 => "2",
+        Key::Key3 => "3",
+        Key::Key4 => "4",
+        Key::Key5 => "5",
+        Key::Key6 => "6",
+        Key::Key7 => "7",
+        Key::Key8 => "8",
+        Key::Key9 => "9",
+        Key::Escape => "Esc",
+        Key::Back => "Backspace",
+        Key::Return => "Enter",
+        Key::Left => "Left",
+        Key::Right => "Right",
+        Key::Up => "Up",
+        Key::Down => "Down",
+        Key::LShift | Key::RShift => "Shift",
+        Key::LControl | Key::RControl => "Ctrl",
+        Key::LAlt | Key::RAlt => "Alt",
+        _ => return format!("{key:?}"),
+    };
+    name.to_string()
+}
+
+#[cfg(feature = "gamepad")]
+/// A short name for a button, using the labels on an Xbox controller, which most PC gamepads
+/// copy
+pub fn button_name(button: GamepadButton) -> String {
+    let name = match button {
+        GamepadButton::South => "A",
+        GamepadButton::East => "B",
+        GamepadButton::West => "X",
+        GamepadButton::North => "Y",
+        GamepadButton::LeftShoulder => "LB",
+        GamepadButton::RightShoulder => "RB",
+        GamepadButton::LeftTrigger => "LT",
+        GamepadButton::RightTrigger => "RT",
+        GamepadButton::LeftStick => "LS",
+        GamepadButton::RightStick => "RS",
+        GamepadButton::DPadUp => "Up",
+        GamepadButton::DPadDown => "Down",
+        GamepadButton::DPadLeft => "Left",
+        GamepadButton::DPadRight => "Right",
+        _ => return format!("{button:?}"),
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Binding, ButtonIcons, InputDevice, Prompt};
+    use crate::{Key, Rect, Settings, Texture, TextureHandle, Venus};
+
+    #[test]
+    fn prompts() {
+        let mut venus = Venus::headless(Settings::default());
+        assert_eq!(venus.input_device(), InputDevice::Keyboard);
+
+        let jump = Binding::key(Key::Space);
+        let interact = Binding::key(Key::Return);
+        let text = |prompt| match prompt {
+            Prompt::Text(text) => Some(text),
+            _ => None,
+        };
+        assert_eq!(text(venus.prompt(&jump, None)).as_deref(), Some("[Space]"));
+        assert_eq!(
+            text(venus.prompt(&interact, None)).as_deref(),
+            Some("[Enter]")
+        );
+        assert!(matches!(
+            venus.prompt(&Binding::default(), None),
+            Prompt::Unbound
+        ));
+
+        let icon = Texture {
+            handle: TextureHandle::mock(),
+            uv: Rect::new(0.0, 0.0, 1.0, 1.0),
+            width: 16,
+            height: 16,
+        };
+        let mut icons = ButtonIcons::new();
+        icons.set_key(Key::Space, icon);
+        assert!(matches!(
+            venus.prompt(&jump, Some(&icons)),
+            Prompt::Icon(icon) if icon.width == 16
+        ));
+        assert_eq!(
+            text(venus.prompt(&interact, Some(&icons))).as_deref(),
+            Some("[Enter]")
+        );
+        venus.clear_input_cache();
+        assert!(!jump.is_pressed(&venus));
+    }
+}
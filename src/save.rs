@@ -0,0 +1,223 @@
+use std::io;
+
+use crate::{Error, Venus};
+
+impl Venus {
+    /// Store save data under a slot name, replacing anything already saved there
+    ///
+    /// On desktop, saves are files in the platform's data directory (`~/.local/share` on Linux,
+    /// `~/Library/Application Support` on macOS, and `%APPDATA%` on Windows), in a folder named
+    /// after `Settings::app_id`. On the web, they're kept in the browser's local storage. Slot
+    /// names may contain letters, numbers, `-`, and `_`.
+    pub fn save(&self, slot: &str, data: &[u8]) -> Result<(), Error> {
+        check_slot(slot)?;
+        platform::write(&self.save_name, slot, data).map_err(|error| save_error(slot, error))
+    }
+
+    /// The data saved under a slot name, or None if nothing has been saved there
+    pub fn load_save(&self, slot: &str) -> Result<Option<Vec<u8>>, Error> {
+        check_slot(slot)?;
+        platform::read(&self.save_name, slot).map_err(|error| save_error(slot, error))
+    }
+
+    pub fn delete_save(&self, slot: &str) -> Result<(), Error> {
+        check_slot(slot)?;
+        platform::delete(&self.save_name, slot).map_err(|error| save_error(slot, error))
+    }
+}
+
+fn save_error(slot: &str, error: io::Error) -> Error {
+    Error::SaveError {
+        slot: slot.to_string(),
+        error,
+    }
+}
+
+// Slots become file names, so anything that could escape the save directory is rejected
+fn check_slot(slot: &str) -> Result<(), Error> {
+    let valid = !slot.is_empty()
+        && slot
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(save_error(
+            slot,
+            io::Error::new(io::ErrorKind::InvalidInput, "invalid save slot name"),
+        ))
+    }
+}
+
+/// The name of the folder saves are kept in, based on the game's app id
+pub(crate) fn save_name(app_id: Option<&str>) -> String {
+    let name = app_id
+        .map(str::to_string)
+        .or_else(executable_name)
+        .unwrap_or_default()
+        .chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || ch == ' ' || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    match name.trim() {
+        "" => "venus".to_string(),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn executable_name() -> Option<String> {
+    let path = std::env::current_exe().ok()?;
+    Some(path.file_stem()?.to_str()?.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn executable_name() -> Option<String> {
+    None
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod platform {
+    use std::{
+        io,
+        path::{Path, PathBuf},
+    };
+
+    fn data_directory() -> io::Result<PathBuf> {
+        let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+        let directory = if cfg!(target_os = "windows") {
+            var("APPDATA").map(PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            var("HOME").map(|home| Path::new(&home).join("Library/Application Support"))
+        } else {
+            var("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .or_else(|| var("HOME").map(|home| Path::new(&home).join(".local/share")))
+        };
+        directory.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))
+    }
+
+    pub fn write(name: &str, slot: &str, data: &[u8]) -> io::Result<()> {
+        write_in(&data_directory()?.join(name), slot, data)
+    }
+
+    pub fn read(name: &str, slot: &str) -> io::Result<Option<Vec<u8>>> {
+        read_in(&data_directory()?.join(name), slot)
+    }
+
+    pub fn delete(name: &str, slot: &str) -> io::Result<()> {
+        delete_in(&data_directory()?.join(name), slot)
+    }
+
+    pub(super) fn write_in(directory: &Path, slot: &str, data: &[u8]) -> io::Result<()> {
+        std::fs::create_dir_all(directory)?;
+        // Writing to a temporary file first means a crash mid-save can't corrupt the old save
+        let temporary = directory.join(format!("{slot}.tmp"));
+        std::fs::write(&temporary, data)?;
+        std::fs::rename(temporary, directory.join(format!("{slot}.sav")))
+    }
+
+    pub(super) fn read_in(directory: &Path, slot: &str) -> io::Result<Option<Vec<u8>>> {
+        match std::fs::read(directory.join(format!("{slot}.sav"))) {
+            Ok(data) => Ok(Some(data)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub(super) fn delete_in(directory: &Path, slot: &str) -> io::Result<()> {
+        match std::fs::remove_file(directory.join(format!("{slot}.sav"))) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod platform {
+    use std::io;
+
+    use web_sys::Storage;
+
+    fn storage() -> io::Result<Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "no local storage"))
+    }
+
+    fn storage_error(_: web_sys::wasm_bindgen::JsValue) -> io::Error {
+        // Usually the storage quota being exceeded, or storage being disabled
+        io::Error::other("local storage refused the request")
+    }
+
+    fn key(name: &str, slot: &str) -> String {
+        format!("{name}/{slot}")
+    }
+
+    // Local storage only holds strings, so every byte is stored as the character with that code
+    pub fn write(name: &str, slot: &str, data: &[u8]) -> io::Result<()> {
+        let text: String = data.iter().map(|byte| char::from(*byte)).collect();
+        storage()?
+            .set_item(&key(name, slot), &text)
+            .map_err(storage_error)
+    }
+
+    pub fn read(name: &str, slot: &str) -> io::Result<Option<Vec<u8>>> {
+        let Some(text) = storage()?
+            .get_item(&key(name, slot))
+            .map_err(storage_error)?
+        else {
+            return Ok(None);
+        };
+        text.chars()
+            .map(|ch| u8::try_from(ch).map_err(|_| io::Error::from(io::ErrorKind::InvalidData)))
+            .collect::<io::Result<_>>()
+            .map(Some)
+    }
+
+    pub fn delete(name: &str, slot: &str) -> io::Result<()> {
+        storage()?
+            .remove_item(&key(name, slot))
+            .map_err(storage_error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_slot, platform, save_name};
+
+    #[test]
+    fn slot_names() {
+        assert!(check_slot("slot_1").is_ok());
+        assert!(check_slot("").is_err());
+        assert!(check_slot("../escape").is_err());
+        assert_eq!(
+            save_name(Some("Super Game: Deluxe!")),
+            "Super Game_ Deluxe_"
+        );
+        assert_eq!(save_name(Some(" ")), "venus");
+        assert!(!save_name(None).is_empty());
+    }
+
+    #[test]
+    fn round_trip() {
+        let directory =
+            std::env::temp_dir().join(format!("venus-save-test-{}", std::process::id()));
+        assert_eq!(platform::read_in(&directory, "slot").unwrap(), None);
+        platform::write_in(&directory, "slot", b"first").unwrap();
+        platform::write_in(&directory, "slot", b"second").unwrap();
+        assert_eq!(
+            platform::read_in(&directory, "slot").unwrap().as_deref(),
+            Some(&b"second"[..])
+        );
+        platform::delete_in(&directory, "slot").unwrap();
+        platform::delete_in(&directory, "slot").unwrap();
+        assert_eq!(platform::read_in(&directory, "slot").unwrap(), None);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,356 @@
+use web_time::Instant;
+
+use crate::{BatchLoader, Error, LoadedBatch, Venus};
+
+/// One screen of the game, like a title menu, a level, or a pause screen
+///
+/// Scenes live on a `SceneStack`, and only the one on top is updated. Every hook besides `update`
+/// and `draw` does nothing unless it's implemented.
+pub trait Scene {
+    /// Files to load in a batch when the scene is added to the stack, before `load` is called
+    fn assets(&self) -> &[&'static str] {
+        &[]
+    }
+
+    /// Called once the scene's assets have loaded, or with the first error that stopped them
+    fn load(&mut self, _venus: &mut Venus, _assets: Result<LoadedBatch, Error>) {}
+
+    /// Drawn instead of `draw` while the scene's assets are loading, e.g. as a progress bar
+    fn draw_loading(&mut self, _venus: &mut Venus, _loader: &BatchLoader) {}
+
+    /// Advance the scene by a number of seconds, returning what should happen to the stack
+    fn update(&mut self, venus: &mut Venus, delta: f32) -> Transition;
+
+    fn draw(&mut self, venus: &mut Venus);
+
+    /// Called when the scene is removed from the stack, by being popped or replaced
+    ///
+    /// A scene removed before its assets finished loading isn't loaded, so it isn't unloaded.
+    fn unload(&mut self, _venus: &mut Venus) {}
+
+    /// Called when another scene is pushed on top of this one
+    fn pause(&mut self, _venus: &mut Venus) {}
+
+    /// Called when this scene is on top again, after the one above it was popped
+    fn resume(&mut self, _venus: &mut Venus) {}
+
+    /// Whether the scene below should still be drawn underneath this one, like for a pause menu
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+/// A change to the scene stack, returned from `Scene::update`
+#[derive(Default)]
+pub enum Transition {
+    /// Stay on the current scene
+    #[default]
+    None,
+    /// Pause the current scene and put a new one on top of it
+    Push(Box<dyn Scene>),
+    /// Remove the current scene and resume the one below it
+    Pop,
+    /// Remove the current scene and put a new one in its place
+    Replace(Box<dyn Scene>),
+    /// Remove every scene, which ends `SceneStack::run`
+    Quit,
+}
+
+/// Scenes layered on top of each other, such as a pause menu over the level it paused
+#[derive(Default)]
+pub struct SceneStack {
+    scenes: Vec<Entry>,
+}
+
+struct Entry {
+    scene: Box<dyn Scene>,
+    // The scene's assets, until they're finished and it's loaded
+    loading: Option<BatchLoader>,
+}
+
+impl Entry {
+    fn unload(mut self, venus: &mut Venus) -> Box<dyn Scene> {
+        if self.loading.is_none() {
+            self.scene.unload(venus);
+        }
+        self.scene
+    }
+}
+
+impl SceneStack {
+    pub fn new() -> SceneStack {
+        SceneStack::default()
+    }
+
+    /// Pause the scene on top, if any, and add a new one above it
+    pub fn push(&mut self, venus: &mut Venus, scene: impl Scene + 'static) {
+        self.push_boxed(venus, Box::new(scene));
+    }
+
+    /// Remove the scene on top and resume the one below it
+    pub fn pop(&mut self, venus: &mut Venus) -> Option<Box<dyn Scene>> {
+        let scene = self.scenes.pop()?.unload(venus);
+        if let Some(below) = self.scenes.last_mut() {
+            below.scene.resume(venus);
+        }
+        Some(scene)
+    }
+
+    /// Swap out the scene on top for a new one, without pausing or resuming the ones below
+    pub fn replace(&mut self, venus: &mut Venus, scene: impl Scene + 'static) {
+        self.replace_boxed(venus, Box::new(scene));
+    }
+
+    /// Remove every scene, from the top down
+    pub fn clear(&mut self, venus: &mut Venus) {
+        while let Some(entry) = self.scenes.pop() {
+            entry.unload(venus);
+        }
+    }
+
+    pub fn apply(&mut self, venus: &mut Venus, transition: Transition) {
+        match transition {
+            Transition::None => {}
+            Transition::Push(scene) => self.push_boxed(venus, scene),
+            Transition::Pop => {
+                self.pop(venus);
+            }
+            Transition::Replace(scene) => self.replace_boxed(venus, scene),
+            Transition::Quit => self.clear(venus),
+        }
+    }
+
+    /// Update the scene on top and apply the transition it returns
+    ///
+    /// If the scene on top is still loading, its assets make progress instead.
+    pub fn update(&mut self, venus: &mut Venus, delta: f32) {
+        let Some(entry) = self.scenes.last_mut() else {
+            return;
+        };
+        if let Some(loader) = &mut entry.loading {
+            venus.update_batch(loader);
+            if let Some(loader) = entry.loading.take_if(|loader| loader.is_finished()) {
+                entry.scene.load(venus, loader.finish());
+            }
+            return;
+        }
+        let transition = entry.scene.update(venus, delta);
+        self.apply(venus, transition);
+    }
+
+    /// Draw the scene on top, along with the scenes below it that it's an overlay for
+    pub fn draw(&mut self, venus: &mut Venus) {
+        let first_visible = self
+            .scenes
+            .iter()
+            .rposition(|entry| !entry.scene.is_overlay())
+            .unwrap_or(0);
+        for entry in &mut self.scenes[first_visible..] {
+            match &entry.loading {
+                Some(loader) => entry.scene.draw_loading(venus, loader),
+                None => entry.scene.draw(venus),
+            }
+        }
+    }
+
+    /// Update and draw the scenes once a frame until the stack is empty
+    pub async fn run(&mut self, venus: &mut Venus) {
+        let mut last_update = Instant::now();
+        while !self.is_empty() {
+            let now = Instant::now();
+            let delta = (now - last_update).as_secs_f32();
+            last_update = now;
+            self.update(venus, delta);
+            self.draw(venus);
+            venus.end_frame().await;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    fn push_boxed(&mut self, venus: &mut Venus, scene: Box<dyn Scene>) {
+        if let Some(top) = self.scenes.last_mut() {
+            top.scene.pause(venus);
+        }
+        self.start(venus, scene);
+    }
+
+    fn replace_boxed(&mut self, venus: &mut Venus, scene: Box<dyn Scene>) {
+        if let Some(previous) = self.scenes.pop() {
+            previous.unload(venus);
+        }
+        self.start(venus, scene);
+    }
+
+    // Add a scene on top, loading it straight away if it has no assets to wait for
+    fn start(&mut self, venus: &mut Venus, mut scene: Box<dyn Scene>) {
+        let loading = if scene.assets().is_empty() {
+            scene.load(venus, Ok(LoadedBatch::default()));
+            None
+        } else {
+            Some(venus.load_batch(scene.assets()))
+        };
+        self.scenes.push(Entry { scene, loading });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::{Scene, SceneStack, Transition};
+    use crate::{BatchLoader, Error, LoadedBatch, Settings, Venus};
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    struct Logged {
+        name: &'static str,
+        log: Log,
+        overlay: bool,
+        assets: &'static [&'static str],
+        next: Option<Transition>,
+    }
+
+    impl Logged {
+        fn new(name: &'static str, log: &Log) -> Logged {
+            Logged {
+                name,
+                log: log.clone(),
+                overlay: false,
+                assets: &[],
+                next: None,
+            }
+        }
+
+        fn record(&self, hook: &str) {
+            self.log.borrow_mut().push(format!("{} {hook}", self.name));
+        }
+    }
+
+    impl Scene for Logged {
+        fn assets(&self) -> &[&'static str] {
+            self.assets
+        }
+
+        fn load(&mut self, _venus: &mut Venus, assets: Result<LoadedBatch, Error>) {
+            self.record(if assets.is_ok() {
+                "load"
+            } else {
+                "load failed"
+            });
+        }
+
+        fn draw_loading(&mut self, _venus: &mut Venus, _loader: &BatchLoader) {
+            self.record("draw loading");
+        }
+
+        fn update(&mut self, _venus: &mut Venus, _delta: f32) -> Transition {
+            self.record("update");
+            self.next.take().unwrap_or_default()
+        }
+
+        fn draw(&mut self, _venus: &mut Venus) {
+            self.record("draw");
+        }
+
+        fn unload(&mut self, _venus: &mut Venus) {
+            self.record("unload");
+        }
+
+        fn pause(&mut self, _venus: &mut Venus) {
+            self.record("pause");
+        }
+
+        fn resume(&mut self, _venus: &mut Venus) {
+            self.record("resume");
+        }
+
+        fn is_overlay(&self) -> bool {
+            self.overlay
+        }
+    }
+
+    #[test]
+    fn transitions() {
+        let mut venus = Venus::headless(Settings::default());
+        let log = Log::default();
+        let mut stack = SceneStack::new();
+
+        let mut level = Logged::new("level", &log);
+        let mut pause = Logged::new("pause", &log);
+        pause.overlay = true;
+        pause.next = Some(Transition::Pop);
+        level.next = Some(Transition::Push(Box::new(pause)));
+        stack.push(&mut venus, level);
+
+        stack.update(&mut venus, 0.1);
+        stack.draw(&mut venus);
+        stack.update(&mut venus, 0.1);
+        stack.draw(&mut venus);
+        assert_eq!(
+            *log.borrow(),
+            [
+                "level load",
+                "level update",
+                "level pause",
+                "pause load",
+                "level draw",
+                "pause draw",
+                "pause update",
+                "pause unload",
+                "level resume",
+                "level draw",
+            ]
+        );
+
+        log.borrow_mut().clear();
+        stack.replace(&mut venus, Logged::new("menu", &log));
+        stack.apply(&mut venus, Transition::Quit);
+        assert_eq!(*log.borrow(), ["level unload", "menu load", "menu unload"]);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn loading() {
+        let mut venus = Venus::headless(Settings::default());
+        let log = Log::default();
+        let mut stack = SceneStack::new();
+
+        let mut level = Logged::new("level", &log);
+        level.assets = &["missing/level.png"];
+        stack.push(&mut venus, level);
+        stack.draw(&mut venus);
+        for _ in 0..100 {
+            if log.borrow().len() > 1 {
+                break;
+            }
+            stack.update(&mut venus, 0.1);
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        stack.update(&mut venus, 0.1);
+        stack.draw(&mut venus);
+        assert_eq!(
+            *log.borrow(),
+            [
+                "level draw loading",
+                "level load failed",
+                "level update",
+                "level draw"
+            ]
+        );
+
+        // A scene that's removed while loading was never loaded, so it isn't unloaded
+        log.borrow_mut().clear();
+        let mut next = Logged::new("next", &log);
+        next.assets = &["missing/next.png"];
+        stack.replace(&mut venus, next);
+        stack.clear(&mut venus);
+        assert_eq!(*log.borrow(), ["level unload"]);
+    }
+}
@@ -0,0 +1,142 @@
+use golem::{Attribute, ShaderDescription, ShaderProgram, Uniform};
+
+use crate::{Error, Venus};
+
+slotmap::new_key_type! {
+    pub struct ShaderHandle;
+}
+
+/// The inputs and uniforms of a custom shader, which golem declares for it when compiling
+#[derive(Clone, Copy)]
+pub struct ShaderLayout {
+    pub vertex_input: &'static [Attribute],
+    pub fragment_input: &'static [Attribute],
+    pub uniforms: &'static [Uniform],
+}
+
+pub(crate) struct CustomShader {
+    vertex_path: String,
+    fragment_path: String,
+    layout: ShaderLayout,
+    // None when running headless
+    program: Option<ShaderProgram>,
+    // Why the last reload failed, while the previous version is still in use
+    pub error: Option<String>,
+}
+
+impl CustomShader {
+    pub fn uses(&self, path: &str) -> bool {
+        self.vertex_path == path || self.fragment_path == path
+    }
+}
+
+impl Venus {
+    /// Load and compile a shader to use with `with_shader`
+    ///
+    /// With `Settings::hot_reload`, the shader is recompiled whenever either file changes. If
+    /// that fails, the error is logged and shown on the debug overlay, and the last version
+    /// that compiled keeps being used.
+    pub async fn load_shader(
+        &mut self,
+        vertex_path: &str,
+        fragment_path: &str,
+        layout: ShaderLayout,
+    ) -> Result<ShaderHandle, Error> {
+        let program = self
+            .compile_shader(vertex_path, fragment_path, layout)
+            .await?;
+        self.watch(vertex_path);
+        self.watch(fragment_path);
+        Ok(self.shaders.insert(CustomShader {
+            vertex_path: vertex_path.to_string(),
+            fragment_path: fragment_path.to_string(),
+            layout,
+            program,
+            error: None,
+        }))
+    }
+
+    /// Use a custom shader through golem, see `with_gl`
+    ///
+    /// The shader isn't bound, since binding it means setting its uniforms too. Returns None
+    /// without calling the closure when running headless or for an unknown handle.
+    pub fn with_shader<T>(
+        &mut self,
+        handle: ShaderHandle,
+        f: impl FnOnce(&golem::Context, &mut ShaderProgram) -> T,
+    ) -> Option<T> {
+        let program = self.shaders.get_mut(handle)?.program.as_mut()?;
+        self.gfx.with_context(|ctx| f(ctx, program))
+    }
+
+    /// Why the last reload of a shader failed, or None if it's up to date
+    pub fn shader_error(&self, handle: ShaderHandle) -> Option<&str> {
+        self.shaders.get(handle)?.error.as_deref()
+    }
+
+    pub(crate) async fn reload_shaders(&mut self, path: &str) {
+        let handles: Vec<ShaderHandle> = self
+            .shaders
+            .iter()
+            .filter(|(_, shader)| shader.uses(path))
+            .map(|(handle, _)| handle)
+            .collect();
+        for handle in handles {
+            let shader = &self.shaders[handle];
+            let (vertex_path, fragment_path, layout) = (
+                shader.vertex_path.clone(),
+                shader.fragment_path.clone(),
+                shader.layout,
+            );
+            let result = self
+                .compile_shader(&vertex_path, &fragment_path, layout)
+                .await;
+            let shader = &mut self.shaders[handle];
+            match result {
+                Ok(program) => {
+                    shader.program = program;
+                    shader.error = None;
+                }
+                Err(error) => {
+                    log::error!("Keeping the previous version of the shader: {error}");
+                    shader.error = Some(error.to_string());
+                }
+            }
+        }
+    }
+
+    async fn compile_shader(
+        &mut self,
+        vertex_path: &str,
+        fragment_path: &str,
+        layout: ShaderLayout,
+    ) -> Result<Option<ShaderProgram>, Error> {
+        let vertex_shader = self.load_source(vertex_path).await?;
+        let fragment_shader = self.load_source(fragment_path).await?;
+        let compiled = self.gfx.with_context(|ctx| {
+            ShaderProgram::new(
+                ctx,
+                ShaderDescription {
+                    vertex_input: layout.vertex_input,
+                    fragment_input: layout.fragment_input,
+                    uniforms: layout.uniforms,
+                    vertex_shader: &vertex_shader,
+                    fragment_shader: &fragment_shader,
+                },
+            )
+        });
+        compiled.transpose().map_err(|error| Error::ShaderError {
+            vertex: vertex_path.to_string(),
+            fragment: fragment_path.to_string(),
+            error: format!("{error:?}"),
+        })
+    }
+
+    async fn load_source(&self, path: &str) -> Result<String, Error> {
+        let bytes = self.load_file(path).await?;
+        String::from_utf8(bytes).map_err(|error| Error::ParseError {
+            path: path.to_string(),
+            error: error.to_string(),
+        })
+    }
+}
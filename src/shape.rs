@@ -1,6 +1,9 @@
 use glam::{Mat3, Vec2};
 
-#[derive(Clone, Debug)]
+use crate::transform::Transform;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rect {
     pub x: f32,
     pub y: f32,
@@ -18,6 +21,11 @@ impl Rect {
         }
     }
 
+    pub fn from_center_size(center: Vec2, size: Vec2) -> Rect {
+        let position = center - size / 2.0;
+        Rect::new(position.x, position.y, size.x, size.y)
+    }
+
     pub fn position(&self) -> Vec2 {
         Vec2 {
             x: self.x,
@@ -32,15 +40,264 @@ impl Rect {
         }
     }
 
+    pub fn center(&self) -> Vec2 {
+        self.position() + self.size() / 2.0
+    }
+
+    pub fn top_left(&self) -> Vec2 {
+        self.position()
+    }
+
+    pub fn bottom_right(&self) -> Vec2 {
+        self.position() + self.size()
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x >= self.x
+            && point.y >= self.y
+            && point.x < self.x + self.width
+            && point.y < self.y + self.height
+    }
+
+    pub fn intersects_circle(&self, circle: &Circle) -> bool {
+        circle.intersects_rect(self)
+    }
+
     pub fn intersects(&self, other: &Rect) -> bool {
         self.x < other.x + other.width
             && self.x + self.width > other.x
             && self.y < other.y + other.height
             && self.y + self.height > other.y
     }
+
+    /// The overlapping region of two rectangles, if they overlap at all
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let top_left = self.top_left().max(other.top_left());
+        let bottom_right = self.bottom_right().min(other.bottom_right());
+        let size = bottom_right - top_left;
+        Some(Rect::new(top_left.x, top_left.y, size.x, size.y))
+    }
+
+    /// The smallest rectangle containing both rectangles
+    pub fn union(&self, other: &Rect) -> Rect {
+        let top_left = self.top_left().min(other.top_left());
+        let bottom_right = self.bottom_right().max(other.bottom_right());
+        let size = bottom_right - top_left;
+        Rect::new(top_left.x, top_left.y, size.x, size.y)
+    }
+
+    pub fn translate(&self, offset: Vec2) -> Rect {
+        Rect {
+            x: self.x + offset.x,
+            y: self.y + offset.y,
+            ..*self
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Circle {
+    pub center: Vec2,
+    pub radius: f32,
 }
 
-#[derive(Clone, Debug)]
+impl Circle {
+    pub fn new(center: Vec2, radius: f32) -> Circle {
+        Circle { center, radius }
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.center.distance_squared(point) <= self.radius * self.radius
+    }
+
+    pub fn intersects(&self, other: &Circle) -> bool {
+        let radii = self.radius + other.radius;
+        self.center.distance_squared(other.center) < radii * radii
+    }
+
+    pub fn intersects_rect(&self, rect: &Rect) -> bool {
+        let closest = self.center.clamp(rect.top_left(), rect.bottom_right());
+        self.center.distance_squared(closest) < self.radius * self.radius
+    }
+
+    pub fn bounding_rect(&self) -> Rect {
+        Rect::from_center_size(self.center, Vec2::splat(self.radius * 2.0))
+    }
+
+    pub fn translate(&self, offset: Vec2) -> Circle {
+        Circle {
+            center: self.center + offset,
+            ..*self
+        }
+    }
+
+    // Points around the edge, with enough of them that the circle looks smooth at its size
+    pub(crate) fn outline(&self) -> impl Iterator<Item = Vec2> {
+        let segments = ((self.radius * std::f32::consts::TAU / 4.0).ceil() as u32).clamp(12, 64);
+        let center = self.center;
+        let radius = self.radius;
+        (0..segments).map(move |i| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            center + Vec2::from_angle(angle) * radius
+        })
+    }
+}
+
+/// A convex polygon, with points in either winding order
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Polygon {
+    pub points: Vec<Vec2>,
+}
+
+impl Polygon {
+    pub fn new(points: Vec<Vec2>) -> Polygon {
+        Polygon { points }
+    }
+
+    pub fn from_rect(rect: &Rect) -> Polygon {
+        Polygon::new(vec![
+            rect.top_left(),
+            Vec2::new(rect.x + rect.width, rect.y),
+            rect.bottom_right(),
+            Vec2::new(rect.x, rect.y + rect.height),
+        ])
+    }
+
+    pub fn translate(&self, offset: Vec2) -> Polygon {
+        Polygon::new(self.points.iter().map(|point| *point + offset).collect())
+    }
+
+    pub fn transform(&self, transform: &Transform) -> Polygon {
+        Polygon::new(
+            self.points
+                .iter()
+                .map(|point| transform.transform_point(*point))
+                .collect(),
+        )
+    }
+
+    pub fn centroid(&self) -> Vec2 {
+        self.points.iter().copied().sum::<Vec2>() / self.points.len().max(1) as f32
+    }
+
+    pub fn bounding_rect(&self) -> Rect {
+        let Some(first) = self.points.first() else {
+            return Rect::default();
+        };
+        let (min, max) = self
+            .points
+            .iter()
+            .fold((*first, *first), |(min, max), point| {
+                (min.min(*point), max.max(*point))
+            });
+        let size = max - min;
+        Rect::new(min.x, min.y, size.x, size.y)
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.points.len() >= 3
+            && edge_normals(&self.points).all(|axis| {
+                let (min, max) = project(&self.points, axis);
+                let point = point.dot(axis);
+                point >= min && point <= max
+            })
+    }
+
+    pub fn intersects(&self, other: &Polygon) -> bool {
+        self.collide(other).is_some()
+    }
+
+    /// If the polygons overlap, the smallest translation that moves `self` out of `other`
+    pub fn collide(&self, other: &Polygon) -> Option<Vec2> {
+        let axes = edge_normals(&self.points).chain(edge_normals(&other.points));
+        separate(
+            axes,
+            |axis| project(&self.points, axis),
+            |axis| project(&other.points, axis),
+            self.centroid() - other.centroid(),
+        )
+    }
+
+    /// If the polygon overlaps the rectangle, the smallest translation that moves `self` out of it
+    pub fn collide_rect(&self, rect: &Rect) -> Option<Vec2> {
+        self.collide(&Polygon::from_rect(rect))
+    }
+
+    /// If the polygon overlaps the circle, the smallest translation that moves `self` out of it
+    pub fn collide_circle(&self, circle: &Circle) -> Option<Vec2> {
+        let closest = self.points.iter().min_by(|a, b| {
+            a.distance_squared(circle.center)
+                .total_cmp(&b.distance_squared(circle.center))
+        })?;
+        // Besides the edges, the only axis that can separate a circle is towards the closest corner
+        let corner_axis = (circle.center - *closest).normalize_or_zero();
+        let axes = edge_normals(&self.points).chain(std::iter::once(corner_axis));
+        separate(
+            axes,
+            |axis| project(&self.points, axis),
+            |axis| {
+                let center = circle.center.dot(axis);
+                (center - circle.radius, center + circle.radius)
+            },
+            self.centroid() - circle.center,
+        )
+    }
+}
+
+fn edge_normals(points: &[Vec2]) -> impl Iterator<Item = Vec2> + '_ {
+    (0..points.len()).map(|i| {
+        let edge = points[(i + 1) % points.len()] - points[i];
+        edge.perp().normalize_or_zero()
+    })
+}
+
+fn project(points: &[Vec2], axis: Vec2) -> (f32, f32) {
+    points
+        .iter()
+        .map(|point| point.dot(axis))
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        })
+}
+
+// The separating axis test: the shapes overlap only if their projections overlap on every axis,
+// and the axis with the least overlap gives the minimum translation vector
+fn separate(
+    axes: impl Iterator<Item = Vec2>,
+    project_a: impl Fn(Vec2) -> (f32, f32),
+    project_b: impl Fn(Vec2) -> (f32, f32),
+    a_from_b: Vec2,
+) -> Option<Vec2> {
+    let mut smallest: Option<(f32, Vec2)> = None;
+    for axis in axes {
+        if axis == Vec2::ZERO {
+            continue;
+        }
+        let (min_a, max_a) = project_a(axis);
+        let (min_b, max_b) = project_b(axis);
+        let overlap = (max_a - min_b).min(max_b - min_a);
+        if overlap <= 0.0 {
+            return None;
+        }
+        if smallest.is_none_or(|(smallest, _)| overlap < smallest) {
+            let axis = if a_from_b.dot(axis) < 0.0 {
+                -axis
+            } else {
+                axis
+            };
+            smallest = Some((overlap, axis));
+        }
+    }
+    smallest.map(|(overlap, axis)| axis * overlap)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IRect {
     pub x: i32,
     pub y: i32,
@@ -55,12 +312,21 @@ pub fn orthographic_projection(x: f32, y: f32, width: f32, height: f32) -> Mat3
         * Mat3::from_translation(Vec2::new(-x, -y))
 }
 
+pub fn screen_to_ndc(point: Vec2, screen_size: Vec2) -> Vec2 {
+    let normalized = point / screen_size;
+    Vec2::new(normalized.x * 2.0 - 1.0, 1.0 - normalized.y * 2.0)
+}
+
+pub fn ndc_to_screen(point: Vec2, screen_size: Vec2) -> Vec2 {
+    Vec2::new((point.x + 1.0) / 2.0, (1.0 - point.y) / 2.0) * screen_size
+}
+
 #[cfg(test)]
 mod test {
     use approx::assert_abs_diff_eq;
     use glam::Vec2;
 
-    use super::orthographic_projection;
+    use super::{Circle, Polygon, Rect, ndc_to_screen, orthographic_projection, screen_to_ndc};
 
     #[test]
     fn basic_orthographic() {
@@ -79,4 +345,89 @@ mod test {
             assert_abs_diff_eq!(from.y, to.y);
         }
     }
+
+    #[test]
+    fn screen_ndc_round_trip() {
+        let screen_size = Vec2::new(800.0, 600.0);
+        assert_eq!(screen_to_ndc(Vec2::ZERO, screen_size), Vec2::new(-1.0, 1.0));
+        assert_eq!(
+            screen_to_ndc(screen_size, screen_size),
+            Vec2::new(1.0, -1.0)
+        );
+        let point = Vec2::new(123.0, 456.0);
+        let round_trip = ndc_to_screen(screen_to_ndc(point, screen_size), screen_size);
+        assert_abs_diff_eq!(round_trip.x, point.x, epsilon = 1e-3);
+        assert_abs_diff_eq!(round_trip.y, point.y, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn rect_geometry() {
+        let a = Rect::new(0.0, 0.0, 10.0, 10.0);
+        let b = Rect::new(5.0, 5.0, 10.0, 10.0);
+        let c = Rect::new(20.0, 0.0, 5.0, 5.0);
+        assert!(a.contains(Vec2::new(0.0, 9.9)));
+        assert!(!a.contains(Vec2::new(10.0, 5.0)));
+        assert_eq!(a.intersection(&b), Some(Rect::new(5.0, 5.0, 5.0, 5.0)));
+        assert_eq!(a.intersection(&c), None);
+        assert_eq!(a.union(&c), Rect::new(0.0, 0.0, 25.0, 10.0));
+        assert_eq!(a.translate(Vec2::new(5.0, 5.0)), b);
+        assert_eq!(b.center(), Vec2::new(10.0, 10.0));
+        assert_eq!(Rect::from_center_size(b.center(), b.size()), b);
+    }
+
+    #[test]
+    fn circle_overlap() {
+        let a = Circle::new(Vec2::ZERO, 5.0);
+        let b = Circle::new(Vec2::new(8.0, 0.0), 4.0);
+        let c = Circle::new(Vec2::new(10.0, 0.0), 4.0);
+        assert!(a.intersects(&b));
+        assert!(!a.intersects(&c));
+        assert!(a.contains(Vec2::new(3.0, 4.0)));
+
+        let rect = Rect::new(4.0, 4.0, 10.0, 10.0);
+        // The corner of the rect is just outside the circle
+        assert!(!a.intersects_rect(&rect));
+        assert!(a.translate(Vec2::new(1.0, 1.0)).intersects_rect(&rect));
+        // A circle entirely inside a rect overlaps it
+        assert!(Circle::new(Vec2::new(9.0, 9.0), 1.0).intersects_rect(&rect));
+        assert!(rect.intersects_circle(&b.translate(Vec2::new(0.0, 1.0))));
+    }
+
+    #[test]
+    fn polygon_sat() {
+        let square = Polygon::from_rect(&Rect::new(0.0, 0.0, 10.0, 10.0));
+        let other = square.translate(Vec2::new(9.0, 2.0));
+        let mtv = square.collide(&other).unwrap();
+        assert_abs_diff_eq!(mtv.x, -1.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(mtv.y, 0.0, epsilon = 1e-5);
+        assert!(
+            square
+                .collide(&square.translate(Vec2::new(11.0, 0.0)))
+                .is_none()
+        );
+        assert!(square.contains(Vec2::new(5.0, 5.0)));
+        assert!(!square.contains(Vec2::new(-1.0, 5.0)));
+
+        let triangle = Polygon::new(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(0.0, 10.0),
+        ]);
+        // Near the hypotenuse, but not touching it
+        assert!(
+            triangle
+                .collide_circle(&Circle::new(Vec2::new(8.0, 8.0), 2.0))
+                .is_none()
+        );
+        let mtv = triangle
+            .collide_circle(&Circle::new(Vec2::new(-1.0, 5.0), 2.0))
+            .unwrap();
+        assert_abs_diff_eq!(mtv.x, 1.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(mtv.y, 0.0, epsilon = 1e-5);
+        assert!(
+            triangle
+                .collide_rect(&Rect::new(4.0, 4.0, 2.0, 2.0))
+                .is_some()
+        );
+    }
 }
@@ -0,0 +1,165 @@
+use glam::Vec2;
+#[cfg(feature = "images")]
+use image::RgbaImage;
+
+use crate::Color;
+
+/// A CPU stand-in for the window, which headless graphics draw into so frames can be inspected
+///
+/// It follows the same rules as the GPU: pixels are covered when their centers are inside a
+/// triangle, textures use nearest-neighbor sampling, and colors are alpha blended.
+pub(crate) struct SoftwareTarget {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+pub(crate) struct SoftwareVertex {
+    /// Position in pixels from the top left of the target
+    pub position: Vec2,
+    pub uv: Vec2,
+    pub color: Color,
+}
+
+impl SoftwareTarget {
+    pub fn new() -> SoftwareTarget {
+        SoftwareTarget {
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+        }
+    }
+
+    /// Make sure the target is at least the given size, keeping what's already drawn
+    pub fn grow(&mut self, width: u32, height: u32) {
+        let width = width.max(self.width);
+        let height = height.max(self.height);
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        let mut pixels = vec![Color::default(); (width * height) as usize];
+        for y in 0..self.height {
+            let old_row = &self.pixels[(y * self.width) as usize..][..self.width as usize];
+            pixels[(y * width) as usize..][..self.width as usize].copy_from_slice(old_row);
+        }
+        self.width = width;
+        self.height = height;
+        self.pixels = pixels;
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        self.pixels.fill(color);
+    }
+
+    /// Fill a triangle, multiplying each vertex color by the texture sampled at its coordinates
+    pub fn fill_triangle(
+        &mut self,
+        vertices: [&SoftwareVertex; 3],
+        sample: impl Fn(Vec2) -> Color,
+    ) {
+        let [a, b, c] = vertices.map(|vertex| vertex.position);
+        let area = edge(a, b, c);
+        if area == 0.0 {
+            return;
+        }
+        let min = a.min(b).min(c).max(Vec2::ZERO);
+        let max = a
+            .max(b)
+            .max(c)
+            .min(Vec2::new(self.width as f32, self.height as f32));
+        for y in (min.y.floor() as u32)..(max.y.ceil() as u32) {
+            for x in (min.x.floor() as u32)..(max.x.ceil() as u32) {
+                let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                // Weights of each vertex, which are all positive inside the triangle whichever
+                // way it winds
+                let weights = [edge(b, c, center), edge(c, a, center), edge(a, b, center)]
+                    .map(|weight| weight / area);
+                if weights.iter().any(|weight| *weight < 0.0) {
+                    continue;
+                }
+                let uv = vertices
+                    .iter()
+                    .zip(weights)
+                    .fold(Vec2::ZERO, |sum, (vertex, weight)| sum + vertex.uv * weight);
+                let color = vertices
+                    .iter()
+                    .zip(weights)
+                    .fold(Color::default(), |sum, (vertex, weight)| {
+                        sum + vertex.color * weight
+                    });
+                let color = color * sample(uv);
+                self.blend((x, y), color);
+            }
+        }
+    }
+
+    #[cfg(feature = "images")]
+    pub fn to_image(&self) -> RgbaImage {
+        let bytes = self
+            .pixels
+            .iter()
+            .flat_map(|color| color.to_array())
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+        RgbaImage::from_raw(self.width, self.height, bytes).expect("the buffer matches its size")
+    }
+
+    // Blend with the same function as the GPU, applied to every channel including alpha
+    fn blend(&mut self, (x, y): (u32, u32), source: Color) {
+        let pixel = &mut self.pixels[(y * self.width + x) as usize];
+        let alpha = source.a;
+        let mix = |source: f32, destination: f32| source * alpha + destination * (1.0 - alpha);
+        *pixel = Color {
+            r: mix(source.r, pixel.r),
+            g: mix(source.g, pixel.g),
+            b: mix(source.b, pixel.b),
+            a: mix(source.a, pixel.a),
+        };
+    }
+}
+
+// Twice the signed area of the triangle a, b, point
+fn edge(a: Vec2, b: Vec2, point: Vec2) -> f32 {
+    (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x)
+}
+
+#[cfg(all(test, feature = "images"))]
+mod test {
+    use glam::Vec2;
+
+    use super::{SoftwareTarget, SoftwareVertex};
+    use crate::Color;
+
+    fn vertex(x: f32, y: f32, color: Color) -> SoftwareVertex {
+        SoftwareVertex {
+            position: Vec2::new(x, y),
+            uv: Vec2::ZERO,
+            color,
+        }
+    }
+
+    #[test]
+    fn fills_and_blends() {
+        let mut target = SoftwareTarget::new();
+        target.grow(4, 4);
+        target.clear(Color::BLACK);
+        // A triangle along the left edge, wound counter-clockwise
+        let triangle = [
+            vertex(0.0, 0.0, Color::WHITE),
+            vertex(0.0, 4.0, Color::WHITE),
+            vertex(2.0, 4.0, Color::WHITE),
+        ];
+        target.fill_triangle([&triangle[0], &triangle[1], &triangle[2]], |_| {
+            Color::WHITE.with_alpha(0.5)
+        });
+        let image = target.to_image();
+        assert_eq!(image.get_pixel(0, 3).0, [128, 128, 128, 191]);
+        assert_eq!(image.get_pixel(3, 3).0, [0, 0, 0, 255]);
+        // The diagonal only covers pixels whose centers are inside
+        assert_eq!(image.get_pixel(1, 0).0, [0, 0, 0, 255]);
+
+        target.grow(6, 2);
+        assert_eq!(target.to_image().dimensions(), (6, 4));
+        assert_eq!(target.to_image().get_pixel(0, 3).0, [128, 128, 128, 191]);
+    }
+}
@@ -0,0 +1,699 @@
+use std::collections::BTreeMap;
+
+use rustc_hash::FxHashMap as HashMap;
+
+use glam::Vec2;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{Error, Texture, Transform, Venus, tilemap::resolve_path};
+
+/// A 2D skeleton and its animations, exported from Spine as JSON
+///
+/// Bones, slots, region attachments, and the rotate, translate, scale, and attachment timelines
+/// are supported. Meshes, constraints, and deform timelines are skipped, and bezier curves are
+/// played back as straight lines. Coordinates are converted from Spine's y-up space into
+/// Venus's y-down space when loading.
+pub struct Skeleton {
+    pub bones: Vec<Bone>,
+    pub slots: Vec<Slot>,
+    pub animations: Vec<SkeletonAnimation>,
+    // The default skin, by slot index and attachment name
+    attachments: HashMap<(usize, String), Attachment>,
+}
+
+/// A bone in its setup pose, relative to its parent
+#[derive(Clone, Debug)]
+pub struct Bone {
+    pub name: String,
+    /// Parents always come before their children
+    pub parent: Option<usize>,
+    pub position: Vec2,
+    /// Clockwise, in radians
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+/// A place an image can be attached to a bone, drawn in the order of the slots
+#[derive(Clone, Debug)]
+pub struct Slot {
+    pub name: String,
+    pub bone: usize,
+    /// The attachment shown in the setup pose
+    pub attachment: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct Attachment {
+    texture: Texture,
+    // From the top left of the texture to the bone's space
+    transform: Transform,
+}
+
+pub struct SkeletonAnimation {
+    pub name: String,
+    /// In seconds
+    pub duration: f32,
+    bones: Vec<BoneTimeline>,
+    slots: Vec<SlotTimeline>,
+}
+
+struct BoneTimeline {
+    bone: usize,
+    rotate: Vec<Key<f32>>,
+    translate: Vec<Key<Vec2>>,
+    scale: Vec<Key<Vec2>>,
+}
+
+struct SlotTimeline {
+    slot: usize,
+    attachments: Vec<(f32, Option<String>)>,
+}
+
+struct Key<T> {
+    time: f32,
+    value: T,
+    stepped: bool,
+}
+
+/// The local transform of every bone and the attachment of every slot at a moment in time
+#[derive(Clone, Debug)]
+pub struct SkeletonPose {
+    bones: Vec<BonePose>,
+    attachments: Vec<Option<String>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct BonePose {
+    position: Vec2,
+    rotation: f32,
+    scale: Vec2,
+}
+
+impl Skeleton {
+    /// Build a skeleton from the contents of a Spine JSON file, finding each attachment's texture
+    /// by its image name
+    ///
+    /// Use this with textures from a sprite sheet or atlas. Attachments without a texture are
+    /// an error.
+    pub fn from_spine_json(
+        bytes: &[u8],
+        mut textures: impl FnMut(&str) -> Option<Texture>,
+    ) -> Result<Skeleton, Error> {
+        let data = parse("skeleton", bytes)?;
+        build_skeleton(data, &mut textures).map_err(|error| spine_error("skeleton", error))
+    }
+
+    pub fn bone(&self, name: &str) -> Option<usize> {
+        self.bones.iter().position(|bone| bone.name == name)
+    }
+
+    pub fn animation(&self, name: &str) -> Option<&SkeletonAnimation> {
+        self.animations
+            .iter()
+            .find(|animation| animation.name == name)
+    }
+
+    pub fn setup_pose(&self) -> SkeletonPose {
+        SkeletonPose {
+            bones: self
+                .bones
+                .iter()
+                .map(|bone| BonePose {
+                    position: bone.position,
+                    rotation: bone.rotation,
+                    scale: bone.scale,
+                })
+                .collect(),
+            attachments: self
+                .slots
+                .iter()
+                .map(|slot| slot.attachment.clone())
+                .collect(),
+        }
+    }
+
+    /// The pose of a looping animation, a number of seconds after it started
+    pub fn pose(&self, animation: &str, time: f32) -> Option<SkeletonPose> {
+        let animation = self.animation(animation)?;
+        let time = if animation.duration > 0.0 {
+            time.rem_euclid(animation.duration)
+        } else {
+            0.0
+        };
+        let mut pose = self.setup_pose();
+        for timeline in &animation.bones {
+            let setup = &self.bones[timeline.bone];
+            let bone = &mut pose.bones[timeline.bone];
+            if let Some(rotation) = sample(&timeline.rotate, time, |a, b, t| a + (b - a) * t) {
+                bone.rotation = setup.rotation + rotation;
+            }
+            if let Some(offset) = sample(&timeline.translate, time, Vec2::lerp) {
+                bone.position = setup.position + offset;
+            }
+            if let Some(scale) = sample(&timeline.scale, time, Vec2::lerp) {
+                bone.scale = setup.scale * scale;
+            }
+        }
+        for timeline in &animation.slots {
+            if let Some((_, attachment)) = timeline
+                .attachments
+                .iter()
+                .rev()
+                .find(|(key_time, _)| *key_time <= time)
+            {
+                pose.attachments[timeline.slot] = attachment.clone();
+            }
+        }
+        Some(pose)
+    }
+
+    /// The transform of each bone from its own space to the skeleton's space
+    pub fn world_transforms(&self, pose: &SkeletonPose) -> Vec<Transform> {
+        let mut transforms: Vec<Transform> = Vec::with_capacity(self.bones.len());
+        for (bone, local) in self.bones.iter().zip(&pose.bones) {
+            let local = Transform::from_parts(local.position, local.rotation, local.scale);
+            let transform = match bone.parent {
+                Some(parent) => transforms[parent] * local,
+                None => local,
+            };
+            transforms.push(transform);
+        }
+        transforms
+    }
+}
+
+impl SkeletonPose {
+    /// Mix two poses, where an alpha of 0 is this pose and 1 is the other
+    ///
+    /// Use this to fade from one animation into another. Attachments switch halfway through.
+    pub fn blend(&self, other: &SkeletonPose, alpha: f32) -> SkeletonPose {
+        let bones = self
+            .bones
+            .iter()
+            .zip(&other.bones)
+            .map(|(from, to)| {
+                // Turn whichever way around is shorter
+                let turn = (to.rotation - from.rotation + std::f32::consts::PI)
+                    .rem_euclid(std::f32::consts::TAU)
+                    - std::f32::consts::PI;
+                BonePose {
+                    position: from.position.lerp(to.position, alpha),
+                    rotation: from.rotation + turn * alpha,
+                    scale: from.scale.lerp(to.scale, alpha),
+                }
+            })
+            .collect();
+        let attachments = if alpha < 0.5 { self } else { other };
+        SkeletonPose {
+            bones,
+            attachments: attachments.attachments.clone(),
+        }
+    }
+}
+
+impl Venus {
+    /// Load a skeleton from a Spine JSON file, along with the images of its attachments
+    ///
+    /// Images are loaded from the images folder set in Spine, relative to the JSON file. To use
+    /// a texture atlas instead, see `Skeleton::from_spine_json`.
+    pub async fn load_spine_skeleton(&mut self, path: &str) -> Result<Skeleton, Error> {
+        let bytes = self.load_file(path).await?;
+        let data = parse(path, &bytes)?;
+        let images = data.skeleton.images.as_deref().unwrap_or("");
+        let mut textures = HashMap::default();
+        for image in data.region_images() {
+            if !textures.contains_key(image) {
+                let file = resolve_path(path, &format!("{images}{image}.png"));
+                let texture = self.load_texture(&file).await?;
+                textures.insert(image.to_string(), texture);
+            }
+        }
+        build_skeleton(data, &mut |image: &str| textures.get(image).cloned())
+            .map_err(|error| spine_error(path, error))
+    }
+
+    /// Draw the attachments of a skeleton in a pose, with the skeleton's origin moved by the
+    /// transform
+    pub fn draw_skeleton(
+        &mut self,
+        skeleton: &Skeleton,
+        pose: &SkeletonPose,
+        transform: Transform,
+    ) {
+        let bones = skeleton.world_transforms(pose);
+        for (index, slot) in skeleton.slots.iter().enumerate() {
+            let Some(name) = &pose.attachments[index] else {
+                continue;
+            };
+            if let Some(attachment) = skeleton.attachments.get(&(index, name.clone())) {
+                self.draw_image_transformed(
+                    &attachment.texture,
+                    transform * bones[slot.bone] * attachment.transform,
+                );
+            }
+        }
+    }
+}
+
+fn spine_error(path: &str, error: impl ToString) -> Error {
+    Error::ParseError {
+        path: path.to_string(),
+        error: error.to_string(),
+    }
+}
+
+fn parse(path: &str, bytes: &[u8]) -> Result<SkeletonJson, Error> {
+    serde_json::from_slice(bytes).map_err(|error| spine_error(path, error))
+}
+
+// The value between the keys either side of a time, or None if there are no keys
+fn sample<T: Copy>(keys: &[Key<T>], time: f32, lerp: impl Fn(T, T, f32) -> T) -> Option<T> {
+    let next = keys.iter().position(|key| key.time > time);
+    match next {
+        None => keys.last().map(|key| key.value),
+        Some(0) => Some(keys[0].value),
+        Some(next) => {
+            let (previous, next) = (&keys[next - 1], &keys[next]);
+            let span = next.time - previous.time;
+            // Keys out of order or infinitely far apart leave nothing to interpolate between
+            if previous.stepped || !(span > 0.0 && span.is_finite()) {
+                return Some(previous.value);
+            }
+            let t = (time - previous.time) / span;
+            Some(lerp(previous.value, next.value, t))
+        }
+    }
+}
+
+// Spine is y-up with counter-clockwise rotations in degrees
+fn flip_position(x: f32, y: f32) -> Vec2 {
+    Vec2::new(x, -y)
+}
+
+fn flip_rotation(degrees: f32) -> f32 {
+    -degrees.to_radians()
+}
+
+fn build_skeleton(
+    data: SkeletonJson,
+    textures: &mut dyn FnMut(&str) -> Option<Texture>,
+) -> Result<Skeleton, String> {
+    let mut skeleton = Skeleton {
+        bones: Vec::new(),
+        slots: Vec::new(),
+        animations: Vec::new(),
+        attachments: HashMap::default(),
+    };
+    for bone in &data.bones {
+        let parent = match &bone.parent {
+            Some(parent) => Some(
+                skeleton
+                    .bone(parent)
+                    .ok_or_else(|| format!("bone {:?} comes before its parent", bone.name))?,
+            ),
+            None => None,
+        };
+        skeleton.bones.push(Bone {
+            name: bone.name.clone(),
+            parent,
+            position: flip_position(bone.x, bone.y),
+            rotation: flip_rotation(bone.rotation),
+            scale: Vec2::new(bone.scale_x, bone.scale_y),
+        });
+    }
+    for slot in &data.slots {
+        let bone = skeleton
+            .bone(&slot.bone)
+            .ok_or_else(|| format!("slot {:?} uses a missing bone", slot.name))?;
+        skeleton.slots.push(Slot {
+            name: slot.name.clone(),
+            bone,
+            attachment: slot.attachment.clone(),
+        });
+    }
+    if let Some(skin) = data.default_skin() {
+        for (slot, attachments) in skin {
+            let slot = slot_index(&skeleton.slots, slot)?;
+            for (name, attachment) in attachments {
+                if !attachment.is_region() {
+                    continue;
+                }
+                let image = attachment.image_or(name);
+                let texture = textures(image).ok_or_else(|| format!("missing image {image:?}"))?;
+                let texture_size = Vec2::new(texture.width as f32, texture.height as f32);
+                let size = Vec2::new(attachment.width, attachment.height);
+                let transform = Transform::from_parts(
+                    flip_position(attachment.x, attachment.y),
+                    flip_rotation(attachment.rotation),
+                    Vec2::new(attachment.scale_x, attachment.scale_y),
+                ) * Transform::scale(size / texture_size)
+                    * Transform::translate(-texture_size / 2.0);
+                skeleton
+                    .attachments
+                    .insert((slot, name.clone()), Attachment { texture, transform });
+            }
+        }
+    }
+    for (name, animation) in &data.animations {
+        let mut duration = 0.0f32;
+        let mut bones = Vec::new();
+        for (bone_name, timelines) in &animation.bones {
+            let bone = skeleton
+                .bone(bone_name)
+                .ok_or_else(|| format!("animation {name:?} uses missing bone {bone_name:?}"))?;
+            let rotate: Vec<_> = timelines
+                .rotate
+                .iter()
+                .map(|key| key.key(flip_rotation(key.value.or(key.angle).unwrap_or(0.0))))
+                .collect();
+            let translate: Vec<_> = timelines
+                .translate
+                .iter()
+                .map(|key| key.key(flip_position(key.x.unwrap_or(0.0), key.y.unwrap_or(0.0))))
+                .collect();
+            let scale: Vec<_> = timelines
+                .scale
+                .iter()
+                .map(|key| key.key(Vec2::new(key.x.unwrap_or(1.0), key.y.unwrap_or(1.0))))
+                .collect();
+            let times = rotate.iter().map(|key| key.time);
+            let times = times
+                .chain(translate.iter().map(|key| key.time))
+                .chain(scale.iter().map(|key| key.time));
+            duration = times.fold(duration, f32::max);
+            bones.push(BoneTimeline {
+                bone,
+                rotate,
+                translate,
+                scale,
+            });
+        }
+        let mut slots = Vec::new();
+        for (slot_name, timelines) in &animation.slots {
+            let attachments: Vec<_> = timelines
+                .attachment
+                .iter()
+                .map(|key| (key.time, key.name.clone()))
+                .collect();
+            duration = attachments
+                .iter()
+                .map(|(time, _)| *time)
+                .fold(duration, f32::max);
+            slots.push(SlotTimeline {
+                slot: slot_index(&skeleton.slots, slot_name)?,
+                attachments,
+            });
+        }
+        skeleton.animations.push(SkeletonAnimation {
+            name: name.clone(),
+            duration,
+            bones,
+            slots,
+        });
+    }
+    Ok(skeleton)
+}
+
+fn slot_index(slots: &[Slot], name: &str) -> Result<usize, String> {
+    slots
+        .iter()
+        .position(|slot| slot.name == name)
+        .ok_or_else(|| format!("missing slot {name:?}"))
+}
+
+#[derive(Deserialize)]
+struct SkeletonJson {
+    #[serde(default)]
+    skeleton: InfoJson,
+    #[serde(default)]
+    bones: Vec<BoneJson>,
+    #[serde(default)]
+    slots: Vec<SlotJson>,
+    skins: Option<SkinsJson>,
+    #[serde(default)]
+    animations: BTreeMap<String, AnimationJson>,
+}
+
+type SkinAttachments = HashMap<String, HashMap<String, AttachmentJson>>;
+
+impl SkeletonJson {
+    fn default_skin(&self) -> Option<&SkinAttachments> {
+        match self.skins.as_ref()? {
+            SkinsJson::List(skins) => skins
+                .iter()
+                .find(|skin| skin.name == "default")
+                .map(|skin| &skin.attachments),
+            SkinsJson::Map(skins) => skins.get("default"),
+        }
+    }
+
+    // The image name of every region attachment in the default skin
+    fn region_images(&self) -> impl Iterator<Item = &str> {
+        self.default_skin().into_iter().flat_map(|skin| {
+            skin.values().flat_map(|attachments| {
+                attachments
+                    .iter()
+                    .filter(|(_, attachment)| attachment.is_region())
+                    .map(|(name, attachment)| attachment.image_or(name))
+            })
+        })
+    }
+}
+
+#[derive(Default, Deserialize)]
+struct InfoJson {
+    images: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BoneJson {
+    name: String,
+    parent: Option<String>,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default = "one")]
+    scale_x: f32,
+    #[serde(default = "one")]
+    scale_y: f32,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+struct SlotJson {
+    name: String,
+    bone: String,
+    attachment: Option<String>,
+}
+
+// Spine 4 writes a list of skins, older versions write a map from skin name to attachments
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SkinsJson {
+    List(Vec<SkinJson>),
+    Map(HashMap<String, SkinAttachments>),
+}
+
+#[derive(Deserialize)]
+struct SkinJson {
+    name: String,
+    #[serde(default)]
+    attachments: SkinAttachments,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AttachmentJson {
+    // Left out for region attachments
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    path: Option<String>,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default = "one")]
+    scale_x: f32,
+    #[serde(default = "one")]
+    scale_y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+}
+
+impl AttachmentJson {
+    fn is_region(&self) -> bool {
+        matches!(self.kind.as_deref(), None | Some("region"))
+    }
+
+    fn image_or<'a>(&'a self, name: &'a str) -> &'a str {
+        self.path.as_deref().unwrap_or(name)
+    }
+}
+
+#[derive(Deserialize)]
+struct AnimationJson {
+    #[serde(default)]
+    bones: HashMap<String, BoneTimelinesJson>,
+    #[serde(default)]
+    slots: HashMap<String, SlotTimelinesJson>,
+}
+
+#[derive(Deserialize)]
+struct BoneTimelinesJson {
+    #[serde(default)]
+    rotate: Vec<KeyJson>,
+    #[serde(default)]
+    translate: Vec<KeyJson>,
+    #[serde(default)]
+    scale: Vec<KeyJson>,
+}
+
+#[derive(Deserialize)]
+struct SlotTimelinesJson {
+    #[serde(default)]
+    attachment: Vec<AttachmentKeyJson>,
+}
+
+// Rotations are written as `value` since Spine 4, and `angle` before it
+#[derive(Deserialize)]
+struct KeyJson {
+    #[serde(default)]
+    time: f32,
+    value: Option<f32>,
+    angle: Option<f32>,
+    x: Option<f32>,
+    y: Option<f32>,
+    curve: Option<Value>,
+}
+
+impl KeyJson {
+    fn key<T>(&self, value: T) -> Key<T> {
+        Key {
+            time: self.time,
+            value,
+            stepped: self.curve.as_ref().and_then(Value::as_str) == Some("stepped"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AttachmentKeyJson {
+    #[serde(default)]
+    time: f32,
+    name: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::FRAC_PI_2;
+
+    use approx::assert_abs_diff_eq;
+    use glam::Vec2;
+
+    use super::{Key, Skeleton, sample};
+    use crate::{Rect, Texture, texture_atlas::TextureHandle};
+
+    const SKELETON: &str = r#"{
+        "skeleton": { "spine": "4.1.24", "images": "./images/" },
+        "bones": [
+            { "name": "root" },
+            { "name": "arm", "parent": "root", "x": 10, "rotation": 90 }
+        ],
+        "slots": [{ "name": "arm", "bone": "arm", "attachment": "arm" }],
+        "skins": [{
+            "name": "default",
+            "attachments": {
+                "arm": {
+                    "arm": { "width": 4, "height": 2 },
+                    "arm-mesh": { "type": "mesh", "uvs": [], "vertices": [] }
+                }
+            }
+        }],
+        "animations": {
+            "wave": {
+                "bones": { "arm": { "rotate": [{ "value": 0 }, { "time": 1, "value": 90 }] } },
+                "slots": { "arm": { "attachment": [{ "time": 0.5, "name": null }] } }
+            }
+        }
+    }"#;
+
+    fn skeleton() -> Skeleton {
+        Skeleton::from_spine_json(SKELETON.as_bytes(), |image| {
+            (image == "arm").then(|| Texture {
+                handle: TextureHandle::mock(),
+                uv: Rect::new(0.0, 0.0, 1.0, 1.0),
+                width: 8,
+                height: 4,
+            })
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn setup_pose() {
+        let skeleton = skeleton();
+        let transforms = skeleton.world_transforms(&skeleton.setup_pose());
+        // Spine rotates counter-clockwise with y up, so the arm points up the screen
+        let hand = transforms[1].transform_point(Vec2::new(1.0, 0.0));
+        assert_abs_diff_eq!(hand.x, 10.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(hand.y, -1.0, epsilon = 1e-5);
+
+        // Attachments are centered on their bone and stretched to their size
+        let attachment = &skeleton.attachments[&(0, "arm".to_string())];
+        let corner = attachment.transform.transform_point(Vec2::new(8.0, 4.0));
+        assert_abs_diff_eq!(corner.x, 2.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(corner.y, 1.0, epsilon = 1e-5);
+        assert_eq!(skeleton.attachments.len(), 1);
+    }
+
+    #[test]
+    fn animation() {
+        let skeleton = skeleton();
+        assert_eq!(skeleton.animation("wave").unwrap().duration, 1.0);
+        let start = skeleton.pose("wave", 0.0).unwrap();
+        let middle = skeleton.pose("wave", 1.5).unwrap();
+        assert_abs_diff_eq!(middle.bones[1].rotation, -FRAC_PI_2 * 1.5, epsilon = 1e-5);
+        assert_eq!(start.attachments[0].as_deref(), Some("arm"));
+        assert_eq!(middle.attachments[0], None);
+
+        let blended = start.blend(&middle, 0.5);
+        assert_abs_diff_eq!(blended.bones[1].rotation, -FRAC_PI_2 * 1.25, epsilon = 1e-5);
+        assert!(skeleton.pose("run", 0.0).is_none());
+    }
+
+    #[test]
+    fn degenerate_keys() {
+        let key = |time, value| Key {
+            time,
+            value,
+            stepped: false,
+        };
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let keys = [
+            key(0.0, 1.0),
+            key(1.0, 2.0),
+            key(-1.0, 3.0),
+            key(f32::INFINITY, 4.0),
+        ];
+        assert_eq!(sample(&keys, 0.5, lerp), Some(1.5));
+        // The key before an infinitely distant one holds instead of becoming NaN
+        assert_eq!(sample(&keys, 1.5, lerp), Some(3.0));
+        assert_eq!(sample(&keys[..0], 0.5, lerp), None);
+        assert_eq!(
+            sample(&[key(0.0, 1.0), key(f32::NAN, 2.0)], 0.5, lerp),
+            Some(2.0)
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use rustc_hash::FxHashMap as HashMap;
+
+use glam::Vec2;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{Error, Texture, Venus, tilemap::resolve_path};
+
+/// Named images packed into a single texture, described by a JSON data file
+///
+/// This is the format written by TexturePacker ("JSON (Hash)" or "JSON (Array)"),
+/// free-tex-packer, and many other packing tools. Frames are looked up by the name the packer
+/// gave them, which usually includes the original file extension.
+pub struct SpriteSheet {
+    frames: Vec<SpriteFrame>,
+    names: HashMap<String, usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SpriteFrame {
+    pub name: String,
+    pub texture: Texture,
+    /// Where the texture sits within the original image, if the packer trimmed empty space
+    pub offset: Vec2,
+    /// The size of the original, untrimmed image
+    pub source_size: Vec2,
+}
+
+impl SpriteSheet {
+    pub fn frames(&self) -> &[SpriteFrame] {
+        &self.frames
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SpriteFrame> {
+        self.names.get(name).map(|index| &self.frames[*index])
+    }
+
+    pub fn texture(&self, name: &str) -> Option<&Texture> {
+        self.get(name).map(|frame| &frame.texture)
+    }
+}
+
+impl Venus {
+    /// Load a packed sprite sheet, from the path of its JSON data file
+    ///
+    /// The sheet image is loaded relative to the data file. Frames the packer rotated to save
+    /// space aren't supported, so turn rotation off when packing.
+    pub async fn load_sprite_sheet(&mut self, path: &str) -> Result<SpriteSheet, Error> {
+        let bytes = self.load_file(path).await?;
+        let data: SpriteSheetJson =
+            serde_json::from_slice(&bytes).map_err(|error| sheet_error(path, error))?;
+        let texture = self
+            .load_texture(&resolve_path(path, &data.meta.image))
+            .await?;
+        build_sheet(data.frames, &texture).map_err(|error| sheet_error(path, error))
+    }
+}
+
+pub(crate) fn sheet_error(path: &str, error: impl ToString) -> Error {
+    Error::ParseError {
+        path: path.to_string(),
+        error: error.to_string(),
+    }
+}
+
+fn build_sheet(frames: FramesJson, texture: &Texture) -> Result<SpriteSheet, String> {
+    let mut sheet = SpriteSheet {
+        frames: Vec::new(),
+        names: HashMap::default(),
+    };
+    for (name, frame) in frames.into_frames()? {
+        sheet.names.insert(name.clone(), sheet.frames.len());
+        sheet.frames.push(SpriteFrame {
+            name,
+            texture: frame.texture(texture)?,
+            offset: frame.offset(),
+            source_size: frame.source_size(),
+        });
+    }
+    Ok(sheet)
+}
+
+#[derive(Deserialize)]
+struct SpriteSheetJson {
+    frames: FramesJson,
+    meta: MetaJson,
+}
+
+#[derive(Deserialize)]
+struct MetaJson {
+    image: String,
+}
+
+// The hash layout keys frames by name, the array layout stores the name in each frame
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum FramesJson {
+    Array(Vec<FrameJson>),
+    Hash(Map<String, Value>),
+}
+
+impl FramesJson {
+    pub fn into_frames(self) -> Result<Vec<(String, FrameJson)>, String> {
+        match self {
+            FramesJson::Array(frames) => Ok(frames
+                .into_iter()
+                .map(|frame| (frame.filename.clone(), frame))
+                .collect()),
+            FramesJson::Hash(frames) => frames
+                .into_iter()
+                .map(|(name, frame)| {
+                    let frame = serde_json::from_value(frame).map_err(|error| error.to_string())?;
+                    Ok((name, frame))
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FrameJson {
+    #[serde(default)]
+    filename: String,
+    frame: RectJson,
+    #[serde(default)]
+    rotated: bool,
+    sprite_source_size: Option<RectJson>,
+    source_size: Option<SizeJson>,
+    // In milliseconds, only written by Aseprite
+    #[serde(default)]
+    pub duration: u64,
+}
+
+impl FrameJson {
+    pub fn texture(&self, sheet: &Texture) -> Result<Texture, String> {
+        let RectJson { x, y, w, h } = self.frame;
+        if self.rotated {
+            return Err(format!("frame {:?} is rotated", self.filename));
+        }
+        let inside = |start: u32, length: u32, total: u32| {
+            start.checked_add(length).is_some_and(|end| end <= total)
+        };
+        if !inside(x, w, sheet.width) || !inside(y, h, sheet.height) {
+            return Err(format!(
+                "frame {:?} is outside the sheet image",
+                self.filename
+            ));
+        }
+        Ok(sheet.sub_texture(x, y, w, h))
+    }
+
+    pub fn offset(&self) -> Vec2 {
+        self.sprite_source_size
+            .as_ref()
+            .map_or(Vec2::ZERO, |rect| Vec2::new(rect.x as f32, rect.y as f32))
+    }
+
+    pub fn source_size(&self) -> Vec2 {
+        match &self.source_size {
+            Some(size) => Vec2::new(size.w as f32, size.h as f32),
+            None => Vec2::new(self.frame.w as f32, self.frame.h as f32),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+struct RectJson {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct SizeJson {
+    w: u32,
+    h: u32,
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SpriteSheetJson, build_sheet};
+    use crate::{Rect, Texture, texture_atlas::TextureHandle};
+
+    fn sheet_texture() -> Texture {
+        Texture {
+            handle: TextureHandle::mock(),
+            uv: Rect::new(0.0, 0.0, 1.0, 1.0),
+            width: 64,
+            height: 32,
+        }
+    }
+
+    #[test]
+    fn array_layout() {
+        let data: SpriteSheetJson = serde_json::from_str(
+            r#"{
+                "frames": [
+                    {
+                        "filename": "coin.png", "frame": { "x": 32, "y": 0, "w": 16, "h": 16 },
+                        "rotated": false, "trimmed": true,
+                        "spriteSourceSize": { "x": 2, "y": 1, "w": 16, "h": 16 },
+                        "sourceSize": { "w": 20, "h": 18 }
+                    }
+                ],
+                "meta": { "image": "sheet.png", "size": { "w": 64, "h": 32 } }
+            }"#,
+        )
+        .unwrap();
+        let sheet = build_sheet(data.frames, &sheet_texture()).unwrap();
+        let coin = sheet.get("coin.png").unwrap();
+        assert_eq!(coin.texture.uv.x, 0.5);
+        assert_eq!(coin.offset, glam::Vec2::new(2.0, 1.0));
+        assert_eq!(coin.source_size, glam::Vec2::new(20.0, 18.0));
+        assert!(sheet.get("coin").is_none());
+    }
+
+    #[test]
+    fn rejects_bad_frames() {
+        let data: SpriteSheetJson = serde_json::from_str(
+            r#"{
+                "frames": {
+                    "big.png": { "frame": { "x": 32, "y": 0, "w": 64, "h": 16 } }
+                },
+                "meta": { "image": "sheet.png" }
+            }"#,
+        )
+        .unwrap();
+        assert!(build_sheet(data.frames, &sheet_texture()).is_err());
+
+        // Large enough to wrap around to inside the sheet
+        let data: SpriteSheetJson = serde_json::from_str(
+            r#"{
+                "frames": {
+                    "huge.png": { "frame": { "x": 8, "y": 0, "w": 4294967292, "h": 16 } }
+                },
+                "meta": { "image": "sheet.png" }
+            }"#,
+        )
+        .unwrap();
+        assert!(build_sheet(data.frames, &sheet_texture()).is_err());
+    }
+}
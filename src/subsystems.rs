@@ -0,0 +1,373 @@
+#[cfg(not(feature = "audio"))]
+use std::marker::PhantomData;
+
+use blinds::CachedEventStream;
+use glam::Vec2;
+use rustc_hash::FxHashSet as HashSet;
+#[cfg(feature = "text")]
+use slotmap::SlotMap;
+
+#[cfg(feature = "audio")]
+use crate::{Audio, AudioTap, PlayingAudio, audio::AudioPlayer};
+use crate::{Circle, Color, Key, Rect, Texture, Transform, Venus, graphics::Graphics};
+#[cfg(feature = "text")]
+use crate::{
+    FontHandle,
+    capture::CapturedCommand,
+    font::{Font, TextRenderer},
+};
+#[cfg(feature = "gamepad")]
+use crate::{GamepadAxis, GamepadButton, GamepadEvent, GamepadId, Stick, gamepad::Gamepads};
+
+/// Drawing and text, borrowed from `Venus` apart from input and audio
+///
+/// Draws go through the camera and transforms that are active on `Venus`.
+pub struct Canvas<'a> {
+    gfx: &'a mut Graphics,
+    #[cfg(feature = "text")]
+    fonts: &'a mut SlotMap<FontHandle, Font>,
+    #[cfg(feature = "text")]
+    text_renderer: &'a mut TextRenderer,
+}
+
+/// Keyboard, mouse, and gamepad state, borrowed from `Venus` apart from drawing and audio
+pub struct Input<'a> {
+    events: Option<&'a CachedEventStream>,
+    just_pressed: &'a HashSet<Key>,
+    #[cfg(feature = "gamepad")]
+    gamepads: &'a Gamepads,
+    mouse_position: Vec2,
+    mouse_delta: Vec2,
+    focused: bool,
+}
+
+/// Audio playback, borrowed from `Venus` apart from drawing and input
+///
+/// Without the `audio` feature, it can't play anything.
+pub struct Mixer<'a> {
+    #[cfg(feature = "audio")]
+    audio: &'a mut AudioPlayer,
+    #[cfg(not(feature = "audio"))]
+    audio: PhantomData<&'a mut Venus>,
+}
+
+impl Venus {
+    /// Borrow drawing, input, and audio separately, so they can be used side by side
+    ///
+    /// ```ignore
+    /// let (mut canvas, input, mut mixer) = venus.split();
+    /// if input.is_key_pressed(Key::Space) {
+    ///     mixer.play_audio(&jump);
+    /// }
+    /// canvas.draw_image(&player, position);
+    /// ```
+    pub fn split(&mut self) -> (Canvas<'_>, Input<'_>, Mixer<'_>) {
+        let canvas = Canvas {
+            gfx: &mut self.gfx,
+            #[cfg(feature = "text")]
+            fonts: &mut self.fonts,
+            #[cfg(feature = "text")]
+            text_renderer: &mut self.text_renderer,
+        };
+        let input = Input {
+            events: self.event_stream.as_ref(),
+            just_pressed: &self.just_pressed,
+            #[cfg(feature = "gamepad")]
+            gamepads: &self.gamepads,
+            mouse_position: self.mouse_position,
+            mouse_delta: self.mouse_delta.unwrap_or(Vec2::ZERO),
+            focused: self.focused,
+        };
+        let mixer = Mixer {
+            #[cfg(feature = "audio")]
+            audio: &mut self.audio,
+            #[cfg(not(feature = "audio"))]
+            audio: PhantomData,
+        };
+        (canvas, input, mixer)
+    }
+
+    pub fn canvas(&mut self) -> Canvas<'_> {
+        self.split().0
+    }
+
+    pub fn input(&mut self) -> Input<'_> {
+        self.split().1
+    }
+
+    pub fn mixer(&mut self) -> Mixer<'_> {
+        self.split().2
+    }
+}
+
+impl Canvas<'_> {
+    pub fn draw_rect(&mut self, position: impl Into<Vec2>, size: impl Into<Vec2>, color: Color) {
+        let position = position.into();
+        let size = size.into();
+        self.gfx.push_rect(
+            Rect::new(position.x, position.y, size.x, size.y),
+            color,
+            None,
+        );
+    }
+
+    pub fn draw_circle(&mut self, center: impl Into<Vec2>, radius: f32, color: Color) {
+        let points: Vec<Vec2> = Circle::new(center.into(), radius).outline().collect();
+        self.gfx.push_convex_polygon(&points, color);
+    }
+
+    pub fn draw_image(&mut self, texture: &Texture, position: impl Into<Vec2>) {
+        let size = Vec2::new(texture.width as f32, texture.height as f32);
+        self.draw_image_sized(texture, position, size);
+    }
+
+    pub fn draw_image_sized(
+        &mut self,
+        texture: &Texture,
+        position: impl Into<Vec2>,
+        size: impl Into<Vec2>,
+    ) {
+        let position = position.into();
+        let size = size.into();
+        self.gfx.push_rect(
+            Rect::new(position.x, position.y, size.x, size.y),
+            Color::WHITE,
+            Some((texture.handle, texture.uv)),
+        );
+    }
+
+    /// Draw a texture with its top left corner at the origin, then moved by the transform
+    pub fn draw_image_transformed(&mut self, texture: &Texture, transform: Transform) {
+        let size = Vec2::new(texture.width as f32, texture.height as f32);
+        let corners = [
+            Vec2::ZERO,
+            Vec2::new(size.x, 0.0),
+            size,
+            Vec2::new(0.0, size.y),
+        ]
+        .map(|corner| transform.transform_point(corner));
+        self.gfx
+            .push_quad(corners, Color::WHITE, Some((texture.handle, texture.uv)));
+    }
+
+    #[cfg(feature = "text")]
+    pub fn draw_text(
+        &mut self,
+        font: FontHandle,
+        position: impl Into<Vec2>,
+        text: &str,
+        size: u32,
+    ) {
+        self.draw_text_wrap(font, position, text, size, f32::MAX);
+    }
+
+    #[cfg(feature = "text")]
+    pub fn draw_text_wrap(
+        &mut self,
+        font: FontHandle,
+        position: impl Into<Vec2>,
+        text: &str,
+        size: u32,
+        max_line_length: f32,
+    ) {
+        self.draw_text_revealed(font, position, text, size, max_line_length, usize::MAX);
+    }
+
+    /// Draw only the first characters of some text, laid out as if all of it were there
+    ///
+    /// Words stay on the same line as more of them is revealed, see `Typewriter`.
+    #[cfg(feature = "text")]
+    pub fn draw_text_revealed(
+        &mut self,
+        font: FontHandle,
+        position: impl Into<Vec2>,
+        text: &str,
+        size: u32,
+        max_line_length: f32,
+        characters: usize,
+    ) {
+        let position = position.into();
+        self.gfx.capture(|_| CapturedCommand::Text {
+            text: text.to_string(),
+            position,
+            size,
+        });
+        self.lay_out(font, position, text, size, max_line_length);
+        // Line breaks take up a character of the text, but aren't laid out
+        let glyphs = text
+            .chars()
+            .take(characters)
+            .filter(|ch| *ch != '\n')
+            .count();
+        for (texture, _, x, y) in self.text_renderer.characters().take(glyphs) {
+            let size = Vec2::new(texture.width as f32, texture.height as f32);
+            self.gfx.push_rect(
+                Rect::new(x, y, size.x, size.y),
+                Color::WHITE,
+                Some((texture.handle, texture.uv)),
+            );
+        }
+    }
+
+    #[cfg(feature = "text")]
+    pub fn layout_text(
+        &mut self,
+        font: FontHandle,
+        position: impl Into<Vec2>,
+        text: &str,
+        size: u32,
+        max_line_length: f32,
+        character_buffer: &mut Vec<(Texture, char, f32, f32)>,
+    ) {
+        self.lay_out(font, position.into(), text, size, max_line_length);
+        character_buffer.extend(self.text_renderer.characters());
+    }
+
+    #[cfg(feature = "text")]
+    /// How wide some text would be, or 0 if the font has been unloaded
+    pub fn text_width(&self, font: FontHandle, text: &str, size: u32) -> f32 {
+        self.fonts
+            .get(font)
+            .map_or(0.0, |font| font.text_width(text, size))
+    }
+
+    #[cfg(feature = "text")]
+    pub fn line_height(&self, font: FontHandle, size: u32) -> f32 {
+        self.fonts
+            .get(font)
+            .map_or(0.0, |font| font.line_height(size))
+    }
+
+    #[cfg(feature = "text")]
+    fn lay_out(
+        &mut self,
+        font: FontHandle,
+        position: Vec2,
+        text: &str,
+        size: u32,
+        max_line_length: f32,
+    ) {
+        // Text in a font that's been unloaded isn't drawn
+        let Some(font) = self.fonts.get_mut(font) else {
+            return;
+        };
+        self.text_renderer.layout_text(
+            self.gfx,
+            font,
+            position.x,
+            position.y,
+            text,
+            size,
+            max_line_length,
+        );
+    }
+}
+
+impl Input<'_> {
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.events.is_some_and(|events| events.cache().key(key))
+    }
+
+    pub fn is_key_pressed(&self, key: Key) -> bool {
+        self.just_pressed.contains(&key)
+    }
+
+    /// The position of the mouse in window coordinates, see `Venus::screen_to_world`
+    pub fn mouse_position(&self) -> Vec2 {
+        self.mouse_position
+    }
+
+    /// See `Venus::mouse_delta`
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_delta
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl Input<'_> {
+    /// See `Venus::gamepads`
+    pub fn gamepads(&self) -> &[GamepadId] {
+        self.gamepads.connected()
+    }
+
+    /// See `Venus::gamepad_events`
+    pub fn gamepad_events(&self) -> &[GamepadEvent] {
+        self.gamepads.events()
+    }
+
+    pub fn is_gamepad_button_down(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads.is_button_down(id, button)
+    }
+
+    pub fn is_gamepad_button_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads.is_button_pressed(id, button)
+    }
+
+    pub fn gamepad_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.gamepads.axis(id, axis)
+    }
+
+    /// See `Venus::gamepad_stick`
+    pub fn gamepad_stick(&self, id: GamepadId, stick: Stick) -> Vec2 {
+        self.gamepads.stick(id, stick)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl Mixer<'_> {
+    pub fn play_audio(&mut self, audio: &Audio) -> PlayingAudio {
+        self.audio.start(audio.source().unwrap().pausable(false))
+    }
+
+    pub fn loop_audio(&mut self, audio: &Audio) -> PlayingAudio {
+        self.audio
+            .start(audio.source().unwrap().pausable(false).repeat_infinite())
+    }
+
+    /// Play audio from any rodio source, see `Venus::play_source`
+    pub fn play_source(&mut self, source: impl rodio::Source + Send + 'static) -> PlayingAudio {
+        self.audio.start(source.pausable(false))
+    }
+
+    /// Play audio while watching its waveform and spectrum, see `AudioTap`
+    pub fn play_audio_tapped(&mut self, audio: &Audio) -> (PlayingAudio, AudioTap) {
+        let (source, tap) = AudioTap::wrap(audio.source().unwrap());
+        (self.play_source(source), tap)
+    }
+
+    pub fn pause_audio(&self, handle: PlayingAudio) {
+        self.audio.pause(handle);
+    }
+
+    pub fn resume_audio(&self, handle: PlayingAudio) {
+        self.audio.play(handle);
+    }
+
+    pub fn stop_audio(&self, handle: PlayingAudio) {
+        self.audio.stop(handle);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Color, DrawCommand, Key, Settings, Venus};
+
+    #[test]
+    fn borrow_side_by_side() {
+        let mut venus = Venus::headless(Settings::default());
+        let (mut canvas, input, _mixer) = venus.split();
+        if !input.is_key_down(Key::Space) {
+            canvas.draw_rect((1.0, 2.0), (3.0, 4.0), Color::WHITE);
+        }
+        #[cfg(feature = "audio")]
+        _mixer.stop_audio(Default::default());
+        assert!(matches!(
+            venus.take_draw_commands().as_slice(),
+            [DrawCommand::Quad { .. }]
+        ));
+    }
+}
@@ -0,0 +1,87 @@
+use resvg::{tiny_skia, usvg};
+
+use crate::{Error, Texture, Venus};
+
+impl Venus {
+    /// Load an SVG file and rasterize it into a texture of the given size in pixels
+    ///
+    /// The image is stretched to fill the size, so keep its aspect ratio to avoid distortion. Load
+    /// the same file again at a new size to keep it crisp after the window is resized. Text in
+    /// the SVG is only drawn if it has been converted to paths.
+    pub async fn load_svg(
+        &mut self,
+        path: &str,
+        width: u32,
+        height: u32,
+    ) -> Result<Texture, Error> {
+        let bytes = self.load_file(path).await?;
+        self.decode_svg(Some(path), &bytes, width, height)
+    }
+
+    /// Rasterize the contents of an SVG file into a texture, see `load_svg`
+    pub fn new_texture_from_svg(
+        &mut self,
+        bytes: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<Texture, Error> {
+        self.decode_svg(None, bytes, width, height)
+    }
+
+    fn decode_svg(
+        &mut self,
+        path: Option<&str>,
+        bytes: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<Texture, Error> {
+        let tree = usvg::Tree::from_data(bytes, &usvg::Options::default()).map_err(|error| {
+            Error::ImageDecodeError {
+                path: path.unwrap_or("SVG data").to_string(),
+                error: Box::new(error),
+            }
+        })?;
+        let pixels = rasterize(&tree, width.max(1), height.max(1));
+        self.new_texture_from_bytes(&pixels, width.max(1), height.max(1))
+    }
+}
+
+fn rasterize(tree: &usvg::Tree, width: u32, height: u32) -> Vec<u8> {
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).expect("a non-empty pixmap");
+    let size = tree.size();
+    let transform = tiny_skia::Transform::from_scale(
+        width as f32 / size.width(),
+        height as f32 / size.height(),
+    );
+    resvg::render(tree, transform, &mut pixmap.as_mut());
+    // tiny-skia works in premultiplied alpha, but textures are uploaded unpremultiplied
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use resvg::usvg;
+
+    use super::rasterize;
+
+    #[test]
+    fn scales_to_size() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+            <rect x="5" y="0" width="5" height="10" fill="#ff0000" fill-opacity="0.5"/>
+        </svg>"##;
+        let tree = usvg::Tree::from_data(svg, &usvg::Options::default()).unwrap();
+        let pixels = rasterize(&tree, 4, 2);
+        assert_eq!(pixels.len(), 4 * 2 * 4);
+        // The left half is empty, and the right half is half-transparent red
+        assert_eq!(&pixels[0..4], &[0, 0, 0, 0]);
+        assert_eq!(pixels[12], 255);
+        assert!((127..=128).contains(&pixels[15]));
+    }
+}
@@ -2,10 +2,16 @@ use std::num::NonZeroU32;
 
 use glam::f32::Vec2;
 use golem::TextureFilter;
+use rustc_hash::FxHashMap as HashMap;
 
-use crate::shape::{IRect, Rect};
+use crate::{
+    Color, Error,
+    profiling::profile_scope,
+    shape::{IRect, Rect},
+};
 
-#[derive(Copy, Clone, Debug)]
+/// Identifies the image a texture was uploaded as
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct TextureHandle {
     atlas: u32,
     index: u32,
@@ -13,7 +19,12 @@ pub struct TextureHandle {
 
 impl TextureHandle {
     pub(crate) fn bind_point(&self) -> NonZeroU32 {
-        bind_point_for_atlas(self.atlas)
+        bind_point_for_page(self.atlas)
+    }
+
+    /// Which atlas page the texture is on
+    pub(crate) fn page(&self) -> u32 {
+        self.atlas
     }
 
     #[cfg(test)]
@@ -24,20 +35,45 @@ impl TextureHandle {
 
 pub struct TextureAtlas {
     pages: Vec<TexturePage>,
+    // However many fit on the texture units, see `max_pages`
+    max_pages: u32,
+    // A copy of every image in RGBA, kept when running headless so frames can be drawn on the CPU
+    copies: Option<HashMap<TextureHandle, ImageCopy>>,
 }
 
 impl TextureAtlas {
-    pub fn new() -> TextureAtlas {
-        TextureAtlas { pages: Vec::new() }
+    /// An atlas with its first page already allocated, so a failure to allocate textures shows
+    /// up during startup rather than when the first image is loaded
+    pub fn new(
+        ctx: &golem::Context,
+        texture_units: u32,
+    ) -> Result<TextureAtlas, golem::GolemError> {
+        let page = TexturePage::new(Some(ctx))?;
+        page.activate(0);
+        Ok(TextureAtlas {
+            pages: vec![page],
+            max_pages: max_pages(texture_units),
+            copies: None,
+        })
+    }
+
+    /// An atlas which only keeps track of where textures would go, for running headless
+    pub fn headless() -> TextureAtlas {
+        TextureAtlas {
+            pages: Vec::new(),
+            max_pages: max_pages(GUARANTEED_TEXTURE_UNITS),
+            copies: Some(HashMap::default()),
+        }
     }
 
     pub fn upload_image(
         &mut self,
-        ctx: &golem::Context,
+        ctx: Option<&golem::Context>,
         image_data: &[u8],
         width: u32,
         height: u32,
-    ) -> TextureHandle {
+    ) -> Result<TextureHandle, Error> {
+        profile_scope!("texture upload");
         let mut texture = None;
         for (page_index, page) in self.pages.iter_mut().enumerate() {
             let upload_result = page.upload_texture(image_data, width, height);
@@ -49,19 +85,182 @@ impl TextureAtlas {
                 break;
             }
         }
-        match texture {
+        let texture = match texture {
             Some(texture) => texture,
             None => {
                 let atlas = self.pages.len() as u32;
-                let mut page = TexturePage::new(ctx);
+                if atlas >= self.max_pages {
+                    return Err(Error::GraphicsError {
+                        action: "allocating a texture page",
+                        error: format!("the texture atlas is full, with {atlas} pages"),
+                    });
+                }
+                let mut page = TexturePage::new(ctx).map_err(|error| Error::GraphicsError {
+                    action: "allocating a texture page",
+                    error: format!("{error:?}"),
+                })?;
                 let index = page
                     .upload_texture(image_data, width, height)
-                    .expect("uploading texture");
-                page.backing_texture.set_active(bind_point_for_atlas(atlas));
+                    .map_err(|_| Error::GraphicsError {
+                        action: "uploading a texture",
+                        error: format!(
+                            "a {width}x{height} image doesn't fit on a {ATLAS_SIZE}x{ATLAS_SIZE} \
+                             texture page"
+                        ),
+                    })?;
+                page.activate(atlas);
+                log::debug!("Allocated texture atlas page {atlas}");
                 self.pages.push(page);
                 TextureHandle { atlas, index }
             }
+        };
+        if let Some(copies) = &mut self.copies {
+            let copy = ImageCopy {
+                width,
+                height,
+                pixels: image_data.to_vec(),
+            };
+            copies.insert(texture, copy);
+        }
+        Ok(texture)
+    }
+
+    /// Overwrite part of an uploaded image, which has to stay within its bounds
+    pub fn update_image(
+        &mut self,
+        texture: TextureHandle,
+        x: u32,
+        y: u32,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+    ) {
+        let page = &mut self.pages[texture.atlas as usize];
+        let region = page.texture_uvs[texture.index as usize];
+        assert!(
+            x + width <= region.width as u32 && y + height <= region.height as u32,
+            "updating outside of the texture"
+        );
+        if let Some(copy) = self
+            .copies
+            .as_mut()
+            .and_then(|copies| copies.get_mut(&texture))
+        {
+            let row_length = (width * 4) as usize;
+            for (row, source) in image_data.chunks_exact(row_length).enumerate() {
+                let start = (((y + row as u32) * copy.width + x) * 4) as usize;
+                copy.pixels[start..start + row_length].copy_from_slice(source);
+            }
+        }
+        if let Some(backing_texture) = &mut page.backing_texture {
+            backing_texture.set_subimage(
+                image_data,
+                region.x as u32 + x,
+                region.y as u32 + y,
+                width,
+                height,
+                golem::ColorFormat::RGBA,
+            );
+        }
+    }
+
+    /// Give an image's space back to its page, so later uploads can reuse it
+    ///
+    /// The handle mustn't be drawn with afterwards, since something else may be put in its place.
+    pub fn free_image(&mut self, texture: TextureHandle) {
+        if let Some(copies) = &mut self.copies {
+            copies.remove(&texture);
+        }
+        if let Some(page) = self.pages.get_mut(texture.atlas as usize) {
+            page.free(texture.index);
+        }
+    }
+
+    /// The color of an image at texture coordinates from 0 to 1, if running headless
+    pub fn sample(&self, texture: TextureHandle, uv: Vec2) -> Option<Color> {
+        let copy = self.copies.as_ref()?.get(&texture)?;
+        if copy.width == 0 || copy.height == 0 {
+            return None;
         }
+        let size = Vec2::new(copy.width as f32, copy.height as f32);
+        let pixel = (uv * size).floor().max(Vec2::ZERO).min(size - 1.0);
+        let start = ((pixel.y as u32 * copy.width + pixel.x as u32) * 4) as usize;
+        let rgba = &copy.pixels[start..start + 4];
+        Some(Color::from_rgba(
+            rgba[0],
+            rgba[1],
+            rgba[2],
+            rgba[3] as f32 / 255.0,
+        ))
+    }
+
+    /// The RGBA pixels of an uploaded image, with its width and height
+    ///
+    /// When running in a window, the page is attached to a framebuffer to read it back from the
+    /// GPU, which leaves no framebuffer bound. The framebuffer is made around a placeholder
+    /// texture first, so the page can't be lost if that fails.
+    pub fn read_image(
+        &mut self,
+        ctx: Option<&golem::Context>,
+        texture: TextureHandle,
+    ) -> Option<(Vec<u8>, u32, u32)> {
+        if let Some(copies) = &self.copies {
+            let copy = copies.get(&texture)?;
+            return Some((copy.pixels.clone(), copy.width, copy.height));
+        }
+        let page = self.pages.get_mut(texture.atlas as usize)?;
+        let region = *page.texture_uvs.get(texture.index as usize)?;
+        let (width, height) = (region.width as u32, region.height as u32);
+        let ctx = ctx?;
+        let surface =
+            golem::Texture::new(ctx).and_then(|placeholder| golem::Surface::new(ctx, placeholder));
+        let mut surface = match surface {
+            Ok(surface) => surface,
+            Err(error) => {
+                log::error!("Couldn't read back atlas page {}: {error:?}", texture.atlas);
+                return None;
+            }
+        };
+        let backing_texture = page.backing_texture.take()?;
+        // Attaching and detaching textures applies to whichever framebuffer is bound
+        surface.bind();
+        surface.take_texture();
+        surface.put_texture(backing_texture);
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        surface.get_pixel_data(
+            region.x as u32,
+            region.y as u32,
+            width,
+            height,
+            golem::ColorFormat::RGBA,
+            &mut pixels,
+        );
+        surface.bind();
+        page.backing_texture = surface.take_texture();
+        golem::Surface::unbind(ctx);
+        page.activate(texture.atlas);
+        Some((pixels, width, height))
+    }
+
+    pub fn max_pages(&self) -> u32 {
+        self.max_pages
+    }
+
+    /// Bind every page to its texture unit again, in case something else has used them
+    pub fn reactivate(&self) {
+        for (atlas, page) in self.pages.iter().enumerate() {
+            page.activate(atlas as u32);
+        }
+    }
+
+    /// How much of each page has been filled, from 0 to 1
+    ///
+    /// Textures are packed in rows, so this counts the whole height of the current row as used.
+    pub fn usage(&self) -> Vec<f32> {
+        self.pages
+            .iter()
+            .map(|page| (page.cursor_y + page.line_height) as f32 / ATLAS_SIZE as f32)
+            .collect()
     }
 
     pub fn uv(&self, texture: TextureHandle, uv: Rect) -> Rect {
@@ -79,20 +278,41 @@ impl TextureAtlas {
     }
 }
 
-fn bind_point_for_atlas(atlas: u32) -> NonZeroU32 {
+// RGBA pixels, row by row from the top left
+struct ImageCopy {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+/// How many pages can be bound at once, given how many texture units there are
+///
+/// Pages are bound from unit 1 up, leaving unit 0 free, and post processing uses the two units
+/// after the last page.
+pub(crate) fn max_pages(texture_units: u32) -> u32 {
+    texture_units.saturating_sub(3).max(1)
+}
+
+pub(crate) fn bind_point_for_page(atlas: u32) -> NonZeroU32 {
     // SAFETY: given an input of 0, 1 will be passed to the function.
     unsafe { NonZeroU32::new_unchecked(atlas + 1) }
 }
 
 struct TexturePage {
-    backing_texture: golem::Texture,
+    backing_texture: Option<golem::Texture>,
     cursor_x: u32,
     cursor_y: u32,
     line_height: u32,
     texture_uvs: Vec<IRect>,
+    // Whether each texture is still in use, by index
+    live: Vec<bool>,
+    // Space given back by freed textures, to be reused before the rows grow any further
+    free_regions: Vec<IRect>,
 }
 
 const ATLAS_SIZE: u32 = 2048;
+// Texture units that every GL 3.3 and WebGL 2 context has, assumed when running headless
+const GUARANTEED_TEXTURE_UNITS: u32 = 16;
 const ATLAS_SIZE_VEC2: Vec2 = Vec2::new(ATLAS_SIZE as f32, ATLAS_SIZE as f32);
 
 #[derive(Debug)]
@@ -101,22 +321,32 @@ enum TextureAllocationError {
 }
 
 impl TexturePage {
-    fn new(ctx: &golem::Context) -> TexturePage {
-        // TODO-someday: make linear vs nearest configurable
-        let mut backing_texture = golem::Texture::new(ctx).expect("failed to allocate a texture");
-        backing_texture.set_image(None, ATLAS_SIZE, ATLAS_SIZE, golem::ColorFormat::RGBA);
-        backing_texture
-            .set_minification(TextureFilter::Nearest)
-            .expect("failed to set minification");
-        backing_texture
-            .set_magnification(TextureFilter::Nearest)
-            .expect("failed to set magnification");
-        TexturePage {
+    fn new(ctx: Option<&golem::Context>) -> Result<TexturePage, golem::GolemError> {
+        let backing_texture = match ctx {
+            Some(ctx) => {
+                // TODO-someday: make linear vs nearest configurable
+                let mut texture = golem::Texture::new(ctx)?;
+                texture.set_image(None, ATLAS_SIZE, ATLAS_SIZE, golem::ColorFormat::RGBA);
+                texture.set_minification(TextureFilter::Nearest)?;
+                texture.set_magnification(TextureFilter::Nearest)?;
+                Some(texture)
+            }
+            None => None,
+        };
+        Ok(TexturePage {
             backing_texture,
             cursor_x: 0,
             cursor_y: 0,
             line_height: 0,
             texture_uvs: Vec::new(),
+            live: Vec::new(),
+            free_regions: Vec::new(),
+        })
+    }
+
+    fn activate(&self, atlas: u32) {
+        if let Some(backing_texture) = &self.backing_texture {
+            backing_texture.set_active(bind_point_for_page(atlas));
         }
     }
 
@@ -126,6 +356,31 @@ impl TexturePage {
         width: u32,
         height: u32,
     ) -> Result<u32, TextureAllocationError> {
+        if width >= ATLAS_SIZE {
+            return Err(TextureAllocationError::CantFit);
+        }
+        let (x, y) = match self.reuse_region(width, height) {
+            Some(position) => position,
+            None => self.allocate(width, height)?,
+        };
+
+        if let Some(backing_texture) = &mut self.backing_texture {
+            backing_texture.set_subimage(image_data, x, y, width, height, golem::ColorFormat::RGBA);
+        }
+        let index = self.texture_uvs.len() as u32;
+        self.texture_uvs.push(IRect {
+            x: x as i32,
+            y: y as i32,
+            width: width as i32,
+            height: height as i32,
+        });
+        self.live.push(true);
+
+        Ok(index)
+    }
+
+    // The next spot along the current row, starting a new row if the image doesn't fit
+    fn allocate(&mut self, width: u32, height: u32) -> Result<(u32, u32), TextureAllocationError> {
         if self.cursor_y + self.line_height + height >= ATLAS_SIZE {
             return Err(TextureAllocationError::CantFit);
         }
@@ -134,25 +389,126 @@ impl TexturePage {
             self.cursor_x = 0;
             self.line_height = 0;
         }
+        let position = (self.cursor_x, self.cursor_y);
+        self.cursor_x += width;
+        self.line_height = self.line_height.max(height);
+        Ok(position)
+    }
 
-        self.backing_texture.set_subimage(
-            image_data,
-            self.cursor_x,
-            self.cursor_y,
-            width,
+    // The top left of the smallest freed region the image fits in, keeping the rest of the
+    // region free
+    fn reuse_region(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (index, region) = self
+            .free_regions
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, region)| region.width as u32 >= width && region.height as u32 >= height)
+            .min_by_key(|(_, region)| region.width * region.height)?;
+        self.free_regions.swap_remove(index);
+        let (width, height) = (width as i32, height as i32);
+        let right = IRect {
+            x: region.x + width,
+            y: region.y,
+            width: region.width - width,
             height,
-            golem::ColorFormat::RGBA,
+        };
+        let below = IRect {
+            x: region.x,
+            y: region.y + height,
+            width: region.width,
+            height: region.height - height,
+        };
+        self.free_regions.extend(
+            [right, below]
+                .into_iter()
+                .filter(|rest| rest.width > 0 && rest.height > 0),
         );
-        let index = self.texture_uvs.len() as u32;
-        self.texture_uvs.push(IRect {
-            x: self.cursor_x as i32,
-            y: self.cursor_y as i32,
-            width: width as i32,
-            height: height as i32,
-        });
-        self.cursor_x += width;
-        self.line_height = self.line_height.max(height);
+        Some((region.x as u32, region.y as u32))
+    }
 
-        Ok(index)
+    fn free(&mut self, index: u32) {
+        let Some(live) = self.live.get_mut(index as usize).filter(|live| **live) else {
+            return;
+        };
+        *live = false;
+        if self.live.contains(&true) {
+            self.free_regions.push(self.texture_uvs[index as usize]);
+        } else {
+            // Once nothing is left on the page, fill it from the top again
+            self.cursor_x = 0;
+            self.cursor_y = 0;
+            self.line_height = 0;
+            self.free_regions.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use glam::Vec2;
+
+    use super::{TextureAtlas, TextureHandle, max_pages};
+    use crate::shape::IRect;
+
+    #[test]
+    fn pages_fit_texture_units() {
+        // WebGL 1 and GLES 2 only promise 8 units, and unit 0 and the last two are kept free
+        assert_eq!(max_pages(8), 5);
+        assert_eq!(max_pages(16), 13);
+        assert_eq!(max_pages(0), 1);
+    }
+
+    #[test]
+    fn reuses_freed_space() {
+        let mut atlas = TextureAtlas::headless();
+        let upload = |atlas: &mut TextureAtlas, size: u32| {
+            let pixels = vec![255; (size * size * 4) as usize];
+            atlas.upload_image(None, &pixels, size, size).unwrap()
+        };
+        let region = |atlas: &TextureAtlas, texture: TextureHandle| {
+            atlas.pages[texture.atlas as usize].texture_uvs[texture.index as usize]
+        };
+        let first = upload(&mut atlas, 4);
+        let second = upload(&mut atlas, 4);
+        atlas.free_image(first);
+        assert!(atlas.sample(first, Vec2::ZERO).is_none());
+
+        let third = upload(&mut atlas, 2);
+        assert_eq!(
+            region(&atlas, third),
+            IRect {
+                x: 0,
+                y: 0,
+                width: 2,
+                height: 2,
+            }
+        );
+        // The rest of the freed space is still available
+        let fourth = upload(&mut atlas, 2);
+        assert_eq!(
+            region(&atlas, fourth),
+            IRect {
+                x: 2,
+                y: 0,
+                width: 2,
+                height: 2,
+            }
+        );
+
+        // Freeing everything starts the page again from the top
+        for texture in [second, third, fourth] {
+            atlas.free_image(texture);
+        }
+        let fifth = upload(&mut atlas, 8);
+        assert_eq!(
+            region(&atlas, fifth),
+            IRect {
+                x: 0,
+                y: 0,
+                width: 8,
+                height: 8,
+            }
+        );
     }
 }
@@ -0,0 +1,472 @@
+use glam::Vec2;
+use roxmltree::{Document, Node};
+use rustc_hash::FxHashMap as HashMap;
+
+use crate::{
+    Color, Error, Venus,
+    shape::Rect,
+    tilemap::{
+        Layer, MapObject, ObjectLayer, Properties, Property, TileLayer, Tilemap, Tileset,
+        resolve_path,
+    },
+};
+
+// Tiled stores flipped and rotated tiles by setting the top bits of the global id
+const FLIP_FLAGS: u32 = 0xF000_0000;
+
+impl Venus {
+    /// Load a map saved by the Tiled editor (a `.tmx` file)
+    ///
+    /// External tilesets and tileset images are loaded relative to the file that refers to them,
+    /// through `load_file` and `load_texture`, so they can come from a mounted `AssetPack`.
+    /// Orthogonal, finite maps are supported, with tile data stored as CSV, XML, or uncompressed
+    /// base64. Flipped tiles are drawn unflipped.
+    pub async fn load_tiled_map(&mut self, path: &str) -> Result<Tilemap, Error> {
+        let bytes = self.load_file(path).await?;
+        let map = parse_map(&bytes).map_err(|error| map_error(path, error))?;
+
+        let mut tilesets = Vec::new();
+        for (first_gid, source) in map.tilesets {
+            let (data, tileset_path) = match source {
+                TilesetSource::Inline(data) => (data, path.to_string()),
+                TilesetSource::External(source) => {
+                    let tileset_path = resolve_path(path, &source);
+                    let bytes = self.load_file(&tileset_path).await?;
+                    let data =
+                        parse_tileset(&bytes).map_err(|error| map_error(&tileset_path, error))?;
+                    (data, tileset_path)
+                }
+            };
+            let texture = self
+                .load_texture(&resolve_path(&tileset_path, &data.image))
+                .await?;
+            tilesets.push(Tileset {
+                name: data.name,
+                first_gid,
+                texture,
+                tile_width: data.tile_width,
+                tile_height: data.tile_height,
+                columns: data.columns,
+                tile_count: data.tile_count,
+                margin: data.margin,
+                spacing: data.spacing,
+                tile_properties: data.tile_properties,
+            });
+        }
+
+        Ok(Tilemap {
+            width: map.width,
+            height: map.height,
+            tile_size: map.tile_size,
+            tilesets,
+            layers: map.layers,
+            properties: map.properties,
+        })
+    }
+}
+
+fn map_error(path: &str, error: String) -> Error {
+    Error::ParseError {
+        path: path.to_string(),
+        error,
+    }
+}
+
+struct MapData {
+    width: u32,
+    height: u32,
+    tile_size: Vec2,
+    tilesets: Vec<(u32, TilesetSource)>,
+    layers: Vec<Layer>,
+    properties: Properties,
+}
+
+enum TilesetSource {
+    Inline(TilesetData),
+    External(String),
+}
+
+struct TilesetData {
+    name: String,
+    tile_width: u32,
+    tile_height: u32,
+    columns: u32,
+    tile_count: u32,
+    margin: u32,
+    spacing: u32,
+    image: String,
+    tile_properties: HashMap<u32, Properties>,
+}
+
+fn parse_document(bytes: &[u8]) -> Result<Document<'_>, String> {
+    let text = std::str::from_utf8(bytes).map_err(|_| "file is not UTF-8".to_string())?;
+    Document::parse(text).map_err(|error| error.to_string())
+}
+
+fn parse_map(bytes: &[u8]) -> Result<MapData, String> {
+    let document = parse_document(bytes)?;
+    let root = document.root_element();
+    if !root.has_tag_name("map") {
+        return Err("not a Tiled map".to_string());
+    }
+    if root.attribute("orientation").unwrap_or("orthogonal") != "orthogonal" {
+        return Err("only orthogonal maps are supported".to_string());
+    }
+    if root.attribute("infinite") == Some("1") {
+        return Err("infinite maps are not supported".to_string());
+    }
+
+    let mut tilesets = Vec::new();
+    for node in root.children().filter(|node| node.has_tag_name("tileset")) {
+        let first_gid = attribute(node, "firstgid")?;
+        let source = match node.attribute("source") {
+            Some(source) => TilesetSource::External(source.to_string()),
+            None => TilesetSource::Inline(tileset_data(node)?),
+        };
+        tilesets.push((first_gid, source));
+    }
+
+    let tile_size = Vec2::new(
+        attribute(root, "tilewidth")?,
+        attribute(root, "tileheight")?,
+    );
+    let mut layers = Vec::new();
+    parse_layers(root, tile_size, Vec2::ZERO, true, &mut layers)?;
+
+    Ok(MapData {
+        width: attribute(root, "width")?,
+        height: attribute(root, "height")?,
+        tile_size,
+        tilesets,
+        layers,
+        properties: properties(root)?,
+    })
+}
+
+fn parse_tileset(bytes: &[u8]) -> Result<TilesetData, String> {
+    let document = parse_document(bytes)?;
+    let root = document.root_element();
+    if !root.has_tag_name("tileset") {
+        return Err("not a Tiled tileset".to_string());
+    }
+    tileset_data(root)
+}
+
+fn tileset_data(node: Node) -> Result<TilesetData, String> {
+    let image = node
+        .children()
+        .find(|child| child.has_tag_name("image"))
+        .ok_or("tilesets made of separate images are not supported")?;
+    let mut tile_properties = HashMap::default();
+    for tile in node.children().filter(|child| child.has_tag_name("tile")) {
+        let properties = properties(tile)?;
+        if !properties.is_empty() {
+            tile_properties.insert(attribute(tile, "id")?, properties);
+        }
+    }
+
+    Ok(TilesetData {
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        tile_width: attribute(node, "tilewidth")?,
+        tile_height: attribute(node, "tileheight")?,
+        columns: attribute(node, "columns")?,
+        tile_count: attribute(node, "tilecount")?,
+        margin: optional_attribute(node, "margin")?.unwrap_or(0),
+        spacing: optional_attribute(node, "spacing")?.unwrap_or(0),
+        image: image
+            .attribute("source")
+            .ok_or("tileset image has no source")?
+            .to_string(),
+        tile_properties,
+    })
+}
+
+// Layer groups are flattened, with their offsets and visibility applied to their children
+fn parse_layers(
+    parent: Node,
+    tile_size: Vec2,
+    offset: Vec2,
+    visible: bool,
+    layers: &mut Vec<Layer>,
+) -> Result<(), String> {
+    for node in parent.children().filter(Node::is_element) {
+        let offset = offset
+            + Vec2::new(
+                optional_attribute(node, "offsetx")?.unwrap_or(0.0),
+                optional_attribute(node, "offsety")?.unwrap_or(0.0),
+            );
+        let visible = visible && node.attribute("visible") != Some("0");
+        let name = node.attribute("name").unwrap_or_default().to_string();
+        match node.tag_name().name() {
+            "layer" => {
+                let width = attribute(node, "width")?;
+                let height = attribute(node, "height")?;
+                layers.push(Layer::Tiles(TileLayer {
+                    name,
+                    width,
+                    height,
+                    tile_size,
+                    offset,
+                    opacity: optional_attribute(node, "opacity")?.unwrap_or(1.0),
+                    visible,
+                    tiles: tile_data(node, width, height)?,
+                    properties: properties(node)?,
+                }));
+            }
+            "objectgroup" => {
+                let objects = node
+                    .children()
+                    .filter(|child| child.has_tag_name("object"))
+                    .map(map_object)
+                    .collect::<Result<_, _>>()?;
+                layers.push(Layer::Objects(ObjectLayer {
+                    name,
+                    offset,
+                    visible,
+                    objects,
+                    properties: properties(node)?,
+                }))
+            }
+            "group" => parse_layers(node, tile_size, offset, visible, layers)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn tile_data(layer: Node, width: u32, height: u32) -> Result<Vec<u32>, String> {
+    let data = layer
+        .children()
+        .find(|child| child.has_tag_name("data"))
+        .ok_or("tile layer has no data")?;
+    if data.attribute("compression").is_some() {
+        return Err("compressed tile data is not supported".to_string());
+    }
+    let text = data.text().unwrap_or_default();
+    let tiles = match data.attribute("encoding") {
+        Some("csv") => text
+            .split(',')
+            .map(|gid| {
+                gid.trim()
+                    .parse::<u32>()
+                    .map_err(|_| format!("invalid tile id {gid:?}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some("base64") => decode_base64(text.trim())?
+            .chunks_exact(4)
+            .map(|gid| u32::from_le_bytes(gid.try_into().unwrap()))
+            .collect(),
+        Some(encoding) => return Err(format!("unknown tile encoding {encoding:?}")),
+        None => data
+            .children()
+            .filter(|child| child.has_tag_name("tile"))
+            .map(|tile| optional_attribute(tile, "gid").map(Option::unwrap_or_default))
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    if tiles.len() != width as usize * height as usize {
+        return Err(format!(
+            "expected {} tiles for a {width}x{height} layer, found {}",
+            width as usize * height as usize,
+            tiles.len()
+        ));
+    }
+    Ok(tiles.into_iter().map(|gid| gid & !FLIP_FLAGS).collect())
+}
+
+fn map_object(node: Node) -> Result<MapObject, String> {
+    let gid = optional_attribute::<u32>(node, "gid")?.map(|gid| gid & !FLIP_FLAGS);
+    let width = optional_attribute(node, "width")?.unwrap_or(0.0);
+    let height = optional_attribute(node, "height")?.unwrap_or(0.0);
+    let x = attribute(node, "x")?;
+    let mut y = attribute(node, "y")?;
+    // Tile objects are positioned by their bottom left corner
+    if gid.is_some() {
+        y -= height;
+    }
+    let rotation: f32 = optional_attribute(node, "rotation")?.unwrap_or(0.0);
+
+    Ok(MapObject {
+        id: optional_attribute(node, "id")?.unwrap_or(0),
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        // Tiled 1.9 saved this as "class", earlier and later versions use "type"
+        class: node
+            .attribute("type")
+            .or(node.attribute("class"))
+            .unwrap_or_default()
+            .to_string(),
+        rect: Rect::new(x, y, width, height),
+        rotation: rotation.to_radians(),
+        gid,
+        properties: properties(node)?,
+    })
+}
+
+fn properties(node: Node) -> Result<Properties, String> {
+    let mut properties = Properties::default();
+    let Some(list) = node
+        .children()
+        .find(|child| child.has_tag_name("properties"))
+    else {
+        return Ok(properties);
+    };
+    for property in list
+        .children()
+        .filter(|child| child.has_tag_name("property"))
+    {
+        let name = property.attribute("name").ok_or("property has no name")?;
+        // Multi-line strings are stored as text instead of in the value attribute
+        let value = property
+            .attribute("value")
+            .or(property.text())
+            .unwrap_or_default();
+        let invalid = || format!("invalid value for property {name:?}");
+        let value = match property.attribute("type").unwrap_or("string") {
+            "bool" => Property::Bool(value == "true"),
+            "int" => Property::Int(value.parse().map_err(|_| invalid())?),
+            "float" => Property::Float(value.parse().map_err(|_| invalid())?),
+            "color" => Property::Color(parse_color(value).ok_or_else(invalid)?),
+            "file" => Property::File(value.to_string()),
+            "object" => Property::Object(value.parse().map_err(|_| invalid())?),
+            _ => Property::String(value.to_string()),
+        };
+        properties.insert(name.to_string(), value);
+    }
+    Ok(properties)
+}
+
+// Colors are written as #AARRGGBB, or #RRGGBB when fully opaque
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let [alpha, red, green, blue] = match hex.len() {
+        6 => (value | 0xFF00_0000).to_be_bytes(),
+        8 => value.to_be_bytes(),
+        _ => return None,
+    };
+    Some(Color::from_rgba(red, green, blue, alpha as f32 / 255.0))
+}
+
+fn attribute<T: std::str::FromStr>(node: Node, name: &str) -> Result<T, String> {
+    optional_attribute(node, name)?.ok_or_else(|| {
+        format!(
+            "<{}> is missing the {name:?} attribute",
+            node.tag_name().name()
+        )
+    })
+}
+
+fn optional_attribute<T: std::str::FromStr>(node: Node, name: &str) -> Result<Option<T>, String> {
+    node.attribute(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("invalid {name:?} attribute on <{}>", node.tag_name().name()))
+        })
+        .transpose()
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for ch in text
+        .bytes()
+        .filter(|ch| !ch.is_ascii_whitespace() && *ch != b'=')
+    {
+        let value = match ch {
+            b'A'..=b'Z' => ch - b'A',
+            b'a'..=b'z' => ch - b'a' + 26,
+            b'0'..=b'9' => ch - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err("invalid base64 tile data".to_string()),
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TilesetSource, decode_base64, parse_map};
+    use crate::tilemap::{Layer, Property};
+
+    const MAP: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="3" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <properties>
+  <property name="music" type="file" value="../audio/level1.ogg"/>
+ </properties>
+ <tileset firstgid="1" source="tiles.tsx"/>
+ <layer id="1" name="ground" width="3" height="2">
+  <data encoding="csv">
+1,2,0,
+2147483651,0,4
+</data>
+ </layer>
+ <group name="decoration" offsetx="4" visible="0">
+  <layer id="2" name="grass" width="3" height="2" opacity="0.5">
+   <data encoding="base64">AQAAAAAAAAAAAAAAAAAAAAAAAAACAAAA</data>
+  </layer>
+ </group>
+ <objectgroup id="3" name="spawns">
+  <object id="7" name="player" type="spawn" x="8" y="24" width="16" height="16" gid="5">
+   <properties>
+    <property name="health" type="int" value="3"/>
+    <property name="tint" type="color" value="#80ff0000"/>
+   </properties>
+  </object>
+ </objectgroup>
+</map>"##;
+
+    #[test]
+    fn parse() {
+        let map = parse_map(MAP.as_bytes()).unwrap();
+        assert_eq!((map.width, map.height), (3, 2));
+        assert!(
+            matches!(&map.tilesets[..], [(1, TilesetSource::External(path))] if path == "tiles.tsx")
+        );
+        assert_eq!(
+            map.properties["music"],
+            Property::File("../audio/level1.ogg".to_string())
+        );
+
+        let [
+            Layer::Tiles(ground),
+            Layer::Tiles(grass),
+            Layer::Objects(spawns),
+        ] = &map.layers[..]
+        else {
+            panic!("wrong layers");
+        };
+        // The flip flag on the fourth tile is removed
+        assert_eq!(ground.tiles, [1, 2, 0, 3, 0, 4]);
+        assert_eq!(grass.tiles, [1, 0, 0, 0, 0, 2]);
+        assert_eq!(grass.offset.x, 4.0);
+        assert!(!grass.visible);
+
+        let player = spawns.object("player").unwrap();
+        assert_eq!(player.class, "spawn");
+        assert_eq!(player.rect.y, 8.0);
+        assert_eq!(player.properties["health"], Property::Int(3));
+        let Property::Color(tint) = player.properties["tint"] else {
+            panic!("wrong property type");
+        };
+        assert_eq!((tint.r, tint.a), (1.0, 128.0 / 255.0));
+    }
+
+    #[test]
+    fn truncated() {
+        let map = MAP.replace("2147483651,0,4", "2147483651,0");
+        assert!(parse_map(map.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(decode_base64("aGVsbG8=").unwrap(), b"hello");
+    }
+}
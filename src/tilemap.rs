@@ -0,0 +1,352 @@
+use glam::{IVec2, Vec2};
+use rustc_hash::FxHashMap as HashMap;
+
+use crate::{Color, Texture, grid::Grid, shape::Rect};
+
+/// Custom properties attached to maps, layers, objects, and tiles by a level editor
+pub type Properties = HashMap<String, Property>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Property {
+    Bool(bool),
+    Int(i64),
+    Float(f32),
+    String(String),
+    Color(Color),
+    /// A path to a file, relative to the map
+    File(String),
+    /// The id of another object in the same map
+    Object(u32),
+}
+
+/// A level made of layers of tiles and objects
+///
+/// Tiles are stored as global ids: 0 is an empty cell, and every tileset covers the range of ids
+/// starting at its `first_gid`.
+pub struct Tilemap {
+    /// The size of the map, in tiles
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: Vec2,
+    pub tilesets: Vec<Tileset>,
+    /// From bottom to top, in drawing order
+    pub layers: Vec<Layer>,
+    pub properties: Properties,
+}
+
+impl Tilemap {
+    /// A grid for converting between world positions and tile coordinates
+    pub fn grid(&self) -> Grid {
+        Grid::new(self.tile_size)
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.name() == name)
+    }
+
+    pub fn tile_layers(&self) -> impl Iterator<Item = &TileLayer> {
+        self.layers.iter().filter_map(|layer| match layer {
+            Layer::Tiles(layer) => Some(layer),
+            _ => None,
+        })
+    }
+
+    pub fn object_layers(&self) -> impl Iterator<Item = &ObjectLayer> {
+        self.layers.iter().filter_map(|layer| match layer {
+            Layer::Objects(layer) => Some(layer),
+            _ => None,
+        })
+    }
+
+    pub fn int_grid_layers(&self) -> impl Iterator<Item = &IntGridLayer> {
+        self.layers.iter().filter_map(|layer| match layer {
+            Layer::IntGrid(layer) => Some(layer),
+            _ => None,
+        })
+    }
+
+    /// The tileset a global tile id belongs to, and the id of the tile within it
+    pub fn tileset_for(&self, gid: u32) -> Option<(&Tileset, u32)> {
+        if gid == 0 {
+            return None;
+        }
+        let tileset = self
+            .tilesets
+            .iter()
+            .filter(|tileset| tileset.first_gid <= gid)
+            .max_by_key(|tileset| tileset.first_gid)?;
+        let id = gid - tileset.first_gid;
+        (id < tileset.tile_count).then_some((tileset, id))
+    }
+
+    pub fn tile_texture(&self, gid: u32) -> Option<Texture> {
+        let (tileset, id) = self.tileset_for(gid)?;
+        tileset.tile(id)
+    }
+
+    pub fn tile_properties(&self, gid: u32) -> Option<&Properties> {
+        let (tileset, id) = self.tileset_for(gid)?;
+        tileset.tile_properties.get(&id)
+    }
+}
+
+/// A single image cut into a grid of equally-sized tiles
+#[derive(Clone)]
+pub struct Tileset {
+    pub name: String,
+    /// The global id of the first tile in this set
+    pub first_gid: u32,
+    pub texture: Texture,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub columns: u32,
+    pub tile_count: u32,
+    /// Pixels around the edge of the image before the first tile
+    pub margin: u32,
+    /// Pixels between neighbouring tiles
+    pub spacing: u32,
+    /// Properties for individual tiles, by their id within the set
+    pub tile_properties: HashMap<u32, Properties>,
+}
+
+impl Tileset {
+    /// The part of the image for a tile, by its id within the set
+    pub fn tile(&self, id: u32) -> Option<Texture> {
+        if id >= self.tile_count || self.columns == 0 {
+            return None;
+        }
+        let x = self.margin + (id % self.columns) * (self.tile_width + self.spacing);
+        let y = self.margin + (id / self.columns) * (self.tile_height + self.spacing);
+        if x + self.tile_width > self.texture.width || y + self.tile_height > self.texture.height {
+            return None;
+        }
+        Some(
+            self.texture
+                .sub_texture(x, y, self.tile_width, self.tile_height),
+        )
+    }
+}
+
+pub enum Layer {
+    Tiles(TileLayer),
+    Objects(ObjectLayer),
+    IntGrid(IntGridLayer),
+}
+
+impl Layer {
+    pub fn name(&self) -> &str {
+        match self {
+            Layer::Tiles(layer) => &layer.name,
+            Layer::Objects(layer) => &layer.name,
+            Layer::IntGrid(layer) => &layer.name,
+        }
+    }
+
+    pub fn properties(&self) -> &Properties {
+        match self {
+            Layer::Tiles(layer) => &layer.properties,
+            Layer::Objects(layer) => &layer.properties,
+            Layer::IntGrid(layer) => &layer.properties,
+        }
+    }
+}
+
+pub struct TileLayer {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// The size of a cell in this layer, which is usually the map's `tile_size`
+    pub tile_size: Vec2,
+    /// Where the layer is drawn, relative to the map
+    pub offset: Vec2,
+    pub opacity: f32,
+    pub visible: bool,
+    /// Global tile ids, row by row
+    pub tiles: Vec<u32>,
+    pub properties: Properties,
+}
+
+impl TileLayer {
+    /// A grid for converting between world positions and cells of this layer
+    pub fn grid(&self) -> Grid {
+        Grid::new(self.tile_size).with_origin(self.offset)
+    }
+
+    /// The global id of the tile at the given coordinates, or None if the cell is empty
+    pub fn tile(&self, tile: IVec2) -> Option<u32> {
+        if tile.x < 0 || tile.y < 0 || tile.x as u32 >= self.width || tile.y as u32 >= self.height {
+            return None;
+        }
+        let gid = *self
+            .tiles
+            .get((tile.y as u32 * self.width + tile.x as u32) as usize)?;
+        (gid != 0).then_some(gid)
+    }
+}
+
+/// A grid of numbers, such as the collision or terrain types painted in LDtk
+pub struct IntGridLayer {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: Vec2,
+    pub offset: Vec2,
+    pub visible: bool,
+    /// Values row by row, where 0 is an empty cell
+    pub values: Vec<i32>,
+    pub properties: Properties,
+}
+
+impl IntGridLayer {
+    pub fn grid(&self) -> Grid {
+        Grid::new(self.tile_size).with_origin(self.offset)
+    }
+
+    /// The value at the given coordinates, or None if the cell is empty
+    pub fn value(&self, tile: IVec2) -> Option<i32> {
+        if tile.x < 0 || tile.y < 0 || tile.x as u32 >= self.width || tile.y as u32 >= self.height {
+            return None;
+        }
+        let value = *self
+            .values
+            .get((tile.y as u32 * self.width + tile.x as u32) as usize)?;
+        (value != 0).then_some(value)
+    }
+}
+
+pub struct ObjectLayer {
+    pub name: String,
+    pub offset: Vec2,
+    pub visible: bool,
+    pub objects: Vec<MapObject>,
+    pub properties: Properties,
+}
+
+impl ObjectLayer {
+    pub fn object(&self, name: &str) -> Option<&MapObject> {
+        self.objects.iter().find(|object| object.name == name)
+    }
+}
+
+/// A spawn point, trigger area, or other marker placed in a level
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapObject {
+    pub id: u32,
+    pub name: String,
+    /// The type of the object, as set in the editor
+    pub class: String,
+    /// The region the object covers, which is empty for points
+    pub rect: Rect,
+    /// Clockwise rotation around the top left corner, in radians
+    pub rotation: f32,
+    /// The tile drawn for the object, if it's a tile object
+    pub gid: Option<u32>,
+    pub properties: Properties,
+}
+
+// Paths inside a map are relative to the directory of the file they appear in
+#[cfg(any(
+    feature = "ldtk",
+    feature = "spine",
+    feature = "sprite-sheets",
+    feature = "tiled"
+))]
+pub(crate) fn resolve_path(base: &str, relative: &str) -> String {
+    let mut parts: Vec<&str> = base.split('/').collect();
+    parts.pop();
+    for part in relative.split('/') {
+        match part {
+            "." | "" => {}
+            ".." if parts.last().is_some_and(|last| *last != "..") => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod test {
+    use glam::IVec2;
+
+    use super::{TileLayer, Tilemap, Tileset};
+    use crate::{Rect, Texture, texture_atlas::TextureHandle};
+
+    fn tileset(first_gid: u32) -> Tileset {
+        Tileset {
+            name: String::new(),
+            first_gid,
+            texture: Texture {
+                handle: TextureHandle::mock(),
+                uv: Rect::new(0.0, 0.0, 1.0, 1.0),
+                width: 34,
+                height: 34,
+            },
+            tile_width: 16,
+            tile_height: 16,
+            columns: 2,
+            tile_count: 4,
+            margin: 0,
+            spacing: 2,
+            tile_properties: Default::default(),
+        }
+    }
+
+    #[test]
+    fn tile_lookup() {
+        let map = Tilemap {
+            width: 2,
+            height: 1,
+            tile_size: glam::Vec2::splat(16.0),
+            tilesets: vec![tileset(1), tileset(5)],
+            layers: Vec::new(),
+            properties: Default::default(),
+        };
+        assert!(map.tileset_for(0).is_none());
+        assert_eq!(map.tileset_for(4).unwrap().1, 3);
+        assert_eq!(map.tileset_for(6).unwrap().0.first_gid, 5);
+        assert!(map.tileset_for(9).is_none());
+
+        let corner = map.tile_texture(4).unwrap();
+        assert_eq!(corner.uv.x, 18.0 / 34.0);
+        assert_eq!(corner.uv.y, 18.0 / 34.0);
+
+        let layer = TileLayer {
+            name: String::new(),
+            width: 2,
+            height: 1,
+            tile_size: glam::Vec2::splat(16.0),
+            offset: glam::Vec2::ZERO,
+            opacity: 1.0,
+            visible: true,
+            tiles: vec![0, 3],
+            properties: Default::default(),
+        };
+        assert_eq!(layer.tile(IVec2::new(0, 0)), None);
+        assert_eq!(layer.tile(IVec2::new(1, 0)), Some(3));
+        assert_eq!(layer.tile(IVec2::new(2, 0)), None);
+    }
+
+    #[test]
+    #[cfg(any(
+        feature = "ldtk",
+        feature = "spine",
+        feature = "sprite-sheets",
+        feature = "tiled"
+    ))]
+    fn paths() {
+        assert_eq!(
+            super::resolve_path("maps/level1.tmx", "../tiles/ground.tsx"),
+            "tiles/ground.tsx"
+        );
+        assert_eq!(
+            super::resolve_path("level1.tmx", "./tiles.png"),
+            "tiles.png"
+        );
+        assert_eq!(
+            super::resolve_path("level1.tmx", "../tiles.png"),
+            "../tiles.png"
+        );
+    }
+}
@@ -0,0 +1,376 @@
+use glam::Vec2;
+use rustc_hash::FxHashMap as HashMap;
+
+#[cfg(feature = "audio")]
+use crate::Audio;
+use crate::{Camera, Color, Transform, Venus};
+
+/// How a value moves between two keyframes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts slow and speeds up
+    EaseIn,
+    /// Starts fast and slows down
+    EaseOut,
+    /// Slow at both ends
+    EaseInOut,
+}
+
+impl Easing {
+    /// Map progress from 0 to 1 onto the eased progress, also from 0 to 1
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Values which can be keyframed, by blending between two of them
+pub trait Lerp: Copy {
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, other: Vec2, t: f32) -> Vec2 {
+        Vec2::lerp(self, other, t)
+    }
+}
+
+impl Lerp for Transform {
+    fn lerp(self, other: Transform, t: f32) -> Transform {
+        Transform::lerp(&self, &other, t)
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(self, other: Color, t: f32) -> Color {
+        Color::lerp(self, other, t)
+    }
+}
+
+/// A value that changes over time, set at keyframes and blended in between
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track<T> {
+    // Sorted by time
+    keys: Vec<(f32, T, Easing)>,
+}
+
+impl<T: Lerp> Track<T> {
+    pub fn new() -> Track<T> {
+        Track { keys: Vec::new() }
+    }
+
+    /// Reach a value at a time, moving from the previous keyframe with an easing
+    pub fn key(&mut self, time: f32, value: T, easing: Easing) {
+        let index = self.keys.partition_point(|(other, _, _)| *other <= time);
+        self.keys.insert(index, (time, value, easing));
+    }
+
+    /// The value at a time, holding the first and last keyframes before and after them, or
+    /// None if there aren't any
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let next = self.keys.partition_point(|(other, _, _)| *other <= time);
+        if next == 0 {
+            return self.keys.first().map(|(_, value, _)| *value);
+        }
+        let (start, from, _) = self.keys[next - 1];
+        let Some((end, to, easing)) = self.keys.get(next) else {
+            return Some(from);
+        };
+        let t = (time - start) / (end - start);
+        Some(from.lerp(*to, easing.apply(t)))
+    }
+
+    /// The time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.keys.last().map_or(0.0, |(time, _, _)| *time)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl<T: Lerp> Default for Track<T> {
+    fn default() -> Track<T> {
+        Track::new()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct TextSpan {
+    start: f32,
+    end: f32,
+    text: String,
+}
+
+/// A scripted sequence, like a cutscene, with everything that happens laid out by time
+///
+/// Keyframe the camera and any named positions, e.g. for actors to walk along, show text for a
+/// while, and place cues and sounds at points in time. Each frame, update it with
+/// `Venus::update_timeline`, then read the positions, text, and cues that fired to draw the
+/// scene and run game logic.
+#[derive(Clone)]
+pub struct Timeline {
+    time: f32,
+    playing: bool,
+    camera_center: Track<Vec2>,
+    camera_zoom: Track<f32>,
+    positions: HashMap<String, Track<Vec2>>,
+    texts: Vec<TextSpan>,
+    cues: Vec<(f32, String)>,
+    #[cfg(feature = "audio")]
+    sounds: Vec<(f32, Audio)>,
+    // The times passed during the last update, and whether that includes the time it started at
+    passed: (f32, f32, bool),
+    // Whether the next update includes the time it starts at, at the beginning and after seeking
+    include_start: bool,
+}
+
+impl Timeline {
+    /// An empty timeline, which starts playing from the beginning
+    pub fn new() -> Timeline {
+        Timeline {
+            time: 0.0,
+            playing: true,
+            camera_center: Track::new(),
+            camera_zoom: Track::new(),
+            positions: HashMap::default(),
+            texts: Vec::new(),
+            cues: Vec::new(),
+            #[cfg(feature = "audio")]
+            sounds: Vec::new(),
+            passed: (0.0, 0.0, false),
+            include_start: true,
+        }
+    }
+
+    /// Move the camera so its center reaches a point at a time
+    pub fn camera_center(&mut self, time: f32, center: impl Into<Vec2>, easing: Easing) {
+        self.camera_center.key(time, center.into(), easing);
+    }
+
+    pub fn camera_zoom(&mut self, time: f32, zoom: f32, easing: Easing) {
+        self.camera_zoom.key(time, zoom, easing);
+    }
+
+    /// Move something named, e.g. an actor or sprite, so it reaches a point at a time
+    pub fn position(&mut self, name: &str, time: f32, position: impl Into<Vec2>, easing: Easing) {
+        self.positions
+            .entry(name.to_string())
+            .or_default()
+            .key(time, position.into(), easing);
+    }
+
+    /// Show some text, like a line of dialogue, for a number of seconds
+    pub fn text(&mut self, start: f32, duration: f32, text: impl Into<String>) {
+        self.texts.push(TextSpan {
+            start,
+            end: start + duration,
+            text: text.into(),
+        });
+    }
+
+    /// Mark a point in time, which shows up in `fired_cues` when it's passed
+    pub fn cue(&mut self, time: f32, name: impl Into<String>) {
+        self.cues.push((time, name.into()));
+    }
+
+    #[cfg(feature = "audio")]
+    /// Play a sound when a point in time is passed
+    pub fn sound(&mut self, time: f32, audio: &Audio) {
+        self.sounds.push((time, audio.clone()));
+    }
+
+    /// Move the timeline forward by a number of seconds, unless it's paused
+    ///
+    /// Prefer `Venus::update_timeline`, which also plays sounds and moves the camera.
+    pub fn update(&mut self, delta: f32) {
+        let start = self.time;
+        if self.playing {
+            self.time = (self.time + delta).min(self.duration());
+            self.passed = (start, self.time, self.include_start);
+            self.include_start = false;
+        } else {
+            self.passed = (start, start, false);
+        }
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Jump to a time, without firing any cues or sounds in between
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.clamp(0.0, self.duration());
+        self.passed = (self.time, self.time, false);
+        self.include_start = true;
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// When the last keyframe, text, cue, or sound ends
+    pub fn duration(&self) -> f32 {
+        let mut duration = self
+            .camera_center
+            .duration()
+            .max(self.camera_zoom.duration());
+        for track in self.positions.values() {
+            duration = duration.max(track.duration());
+        }
+        for span in &self.texts {
+            duration = duration.max(span.end);
+        }
+        for (time, _) in &self.cues {
+            duration = duration.max(*time);
+        }
+        #[cfg(feature = "audio")]
+        for (time, _) in &self.sounds {
+            duration = duration.max(*time);
+        }
+        duration
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.time >= self.duration()
+    }
+
+    /// Where something named is at the current time, or None if it has no keyframes
+    pub fn position_of(&self, name: &str) -> Option<Vec2> {
+        self.positions.get(name)?.sample(self.time)
+    }
+
+    /// Move and zoom a camera to where the timeline has it, if it has camera keyframes
+    pub fn apply_camera(&self, camera: &mut Camera) {
+        if let Some(center) = self.camera_center.sample(self.time) {
+            camera.center = center;
+        }
+        if let Some(zoom) = self.camera_zoom.sample(self.time) {
+            camera.zoom = zoom;
+        }
+    }
+
+    /// The text showing at the current time, in the order it was added
+    pub fn visible_text(&self) -> impl Iterator<Item = &str> {
+        self.texts
+            .iter()
+            .filter(|span| span.start <= self.time && self.time < span.end)
+            .map(|span| span.text.as_str())
+    }
+
+    /// The cues passed during the last update
+    pub fn fired_cues(&self) -> impl Iterator<Item = &str> {
+        self.passed(&self.cues).map(String::as_str)
+    }
+
+    // Things at times passed during the last update
+    fn passed<'a, T>(&self, items: &'a [(f32, T)]) -> impl Iterator<Item = &'a T> {
+        let (start, end, include_start) = self.passed;
+        items
+            .iter()
+            .filter(move |(at, _)| (include_start && *at == start) || (start < *at && *at <= end))
+            .map(|(_, item)| item)
+    }
+}
+
+impl Default for Timeline {
+    fn default() -> Timeline {
+        Timeline::new()
+    }
+}
+
+impl Venus {
+    /// Move a timeline forward, playing the sounds it passes and moving the camera along it
+    pub fn update_timeline(&mut self, timeline: &mut Timeline, delta: f32) {
+        timeline.update(delta);
+        #[cfg(feature = "audio")]
+        for audio in timeline.passed(&timeline.sounds) {
+            self.play_audio(audio);
+        }
+        if !timeline.camera_center.is_empty() || !timeline.camera_zoom.is_empty() {
+            let mut camera = self.camera().clone();
+            timeline.apply_camera(&mut camera);
+            self.use_camera(&camera);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use glam::Vec2;
+
+    use super::{Easing, Timeline, Track};
+
+    #[test]
+    fn tracks() {
+        let mut track = Track::new();
+        assert_eq!(track.sample(1.0), None);
+        track.key(2.0, 10.0, Easing::Linear);
+        track.key(4.0, 0.0, Easing::EaseIn);
+        track.key(0.0, 0.0, Easing::Linear);
+        assert_eq!(track.sample(-1.0), Some(0.0));
+        assert_eq!(track.sample(1.0), Some(5.0));
+        assert_eq!(track.sample(3.0), Some(7.5));
+        assert_eq!(track.sample(10.0), Some(0.0));
+        assert_eq!(track.duration(), 4.0);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert_eq!(Easing::EaseOut.apply(2.0), 1.0);
+    }
+
+    #[test]
+    fn cutscene() {
+        let mut timeline = Timeline::new();
+        timeline.position("hero", 0.0, (0.0, 0.0), Easing::Linear);
+        timeline.position("hero", 2.0, (100.0, 0.0), Easing::Linear);
+        timeline.text(1.0, 2.0, "Where am I?");
+        timeline.cue(0.0, "start");
+        timeline.cue(2.5, "explosion");
+        assert_eq!(timeline.duration(), 3.0);
+
+        timeline.update(0.5);
+        assert_eq!(timeline.fired_cues().collect::<Vec<_>>(), ["start"]);
+        assert_eq!(timeline.position_of("hero"), Some(Vec2::new(25.0, 0.0)));
+        assert_eq!(timeline.visible_text().count(), 0);
+        timeline.update(1.0);
+        assert_eq!(timeline.fired_cues().count(), 0);
+        assert_eq!(timeline.visible_text().collect::<Vec<_>>(), ["Where am I?"]);
+
+        timeline.pause();
+        timeline.update(5.0);
+        assert_eq!(timeline.time(), 1.5);
+        timeline.play();
+        timeline.update(5.0);
+        assert_eq!(timeline.fired_cues().collect::<Vec<_>>(), ["explosion"]);
+        assert!(timeline.is_finished());
+        assert_eq!(timeline.position_of("hero"), Some(Vec2::new(100.0, 0.0)));
+        assert_eq!(timeline.position_of("villain"), None);
+
+        // Seeking back doesn't fire anything, but playing on from there does
+        timeline.seek(2.0);
+        assert_eq!(timeline.fired_cues().count(), 0);
+        timeline.update(1.0);
+        assert_eq!(timeline.fired_cues().collect::<Vec<_>>(), ["explosion"]);
+    }
+}
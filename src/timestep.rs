@@ -0,0 +1,123 @@
+use crate::Lerp;
+
+// After a long stall, catching up any further would make the next frame take even longer
+const MAX_STEPS: u32 = 8;
+
+/// Turns frames of any length into a whole number of fixed-length simulation steps
+///
+/// Time that doesn't add up to a whole step is carried over to the next frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixedTimestep {
+    step: f32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(steps_per_second: f32) -> FixedTimestep {
+        FixedTimestep {
+            step: 1.0 / steps_per_second,
+            accumulator: 0.0,
+        }
+    }
+
+    /// The length of each step, in seconds
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Add the seconds that have passed, returning how many steps to run
+    ///
+    /// At most 8 steps are run at once, and any time past that is dropped, so the simulation
+    /// slows down rather than falling further and further behind.
+    pub fn advance(&mut self, delta: f32) -> u32 {
+        self.accumulator += delta;
+        let steps = (self.accumulator / self.step).floor() as u32;
+        if steps > MAX_STEPS {
+            self.accumulator = 0.0;
+            return MAX_STEPS;
+        }
+        self.accumulator -= steps as f32 * self.step;
+        steps
+    }
+
+    /// How far between the last step and the next one the current time is, from 0 to 1
+    ///
+    /// Rendering things part way between their last two states by this much keeps motion
+    /// smooth when the frame rate is higher than the step rate, see `Interpolated`.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).clamp(0.0, 1.0)
+    }
+}
+
+/// A value's state at the last two fixed timesteps, for drawing it smoothly in between
+///
+/// Call `set` once per step, and draw `get(timestep.alpha())`. This draws a step behind the
+/// simulation, which is what keeps it from jumping ahead.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interpolated<T> {
+    previous: T,
+    current: T,
+}
+
+impl<T: Lerp> Interpolated<T> {
+    pub fn new(value: T) -> Interpolated<T> {
+        Interpolated {
+            previous: value,
+            current: value,
+        }
+    }
+
+    /// Record the value after a step
+    pub fn set(&mut self, value: T) {
+        self.previous = self.current;
+        self.current = value;
+    }
+
+    /// Jump straight to a value without blending from the last one, e.g. after a teleport
+    pub fn reset(&mut self, value: T) {
+        self.previous = value;
+        self.current = value;
+    }
+
+    pub fn current(&self) -> T {
+        self.current
+    }
+
+    /// The value part way from the previous step to the current one
+    pub fn get(&self, alpha: f32) -> T {
+        self.previous.lerp(self.current, alpha)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+    use glam::Vec2;
+
+    use super::{FixedTimestep, Interpolated};
+
+    #[test]
+    fn steps() {
+        let mut timestep = FixedTimestep::new(60.0);
+        assert_eq!(timestep.advance(1.0 / 144.0), 0);
+        assert_eq!(timestep.advance(1.0 / 144.0), 0);
+        assert_eq!(timestep.advance(1.0 / 144.0), 1);
+        assert_eq!(timestep.advance(0.05), 3);
+        // A stall doesn't have to be caught up on
+        assert_eq!(timestep.advance(5.0), 8);
+        assert_eq!(timestep.advance(0.0), 0);
+    }
+
+    #[test]
+    fn interpolation() {
+        let mut timestep = FixedTimestep::new(10.0);
+        let mut position = Interpolated::new(Vec2::ZERO);
+        for _ in 0..timestep.advance(0.125) {
+            position.set(position.current() + Vec2::new(10.0, 0.0));
+        }
+        assert_abs_diff_eq!(timestep.alpha(), 0.25, epsilon = 1e-5);
+        assert_abs_diff_eq!(position.get(timestep.alpha()).x, 2.5, epsilon = 1e-4);
+        position.reset(Vec2::new(100.0, 0.0));
+        assert_eq!(position.get(0.5), Vec2::new(100.0, 0.0));
+    }
+}
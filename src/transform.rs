@@ -0,0 +1,143 @@
+use std::{
+    f32::consts::{PI, TAU},
+    ops::Mul,
+};
+
+use glam::{Affine2, Mat3, Vec2};
+
+/// A 2D affine transformation: any combination of translation, rotation, and scale
+///
+/// Transforms compose with `*`, where `a * b` applies `b` first and then `a`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform(Affine2);
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform(Affine2::IDENTITY);
+
+    pub fn translate(offset: Vec2) -> Transform {
+        Transform(Affine2::from_translation(offset))
+    }
+
+    /// A clockwise rotation around the origin, in radians
+    pub fn rotate(angle: f32) -> Transform {
+        Transform(Affine2::from_angle(angle))
+    }
+
+    pub fn rotate_around(center: Vec2, angle: f32) -> Transform {
+        Transform::translate(center) * Transform::rotate(angle) * Transform::translate(-center)
+    }
+
+    pub fn scale(scale: Vec2) -> Transform {
+        Transform(Affine2::from_scale(scale))
+    }
+
+    /// Scale, then rotate, then translate
+    pub fn from_parts(translation: Vec2, rotation: f32, scale: Vec2) -> Transform {
+        Transform(Affine2::from_scale_angle_translation(
+            scale,
+            rotation,
+            translation,
+        ))
+    }
+
+    /// Split the transform back into translation, rotation, and scale
+    ///
+    /// Transforms which skew (e.g. a non-uniform scale after a rotation) can't be represented
+    /// exactly this way
+    pub fn to_parts(&self) -> (Vec2, f32, Vec2) {
+        let (scale, rotation, translation) = self.0.to_scale_angle_translation();
+        (translation, rotation, scale)
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform(self.0.inverse())
+    }
+
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        self.0.transform_point2(point)
+    }
+
+    pub fn to_matrix(&self) -> Mat3 {
+        Mat3::from(self.0)
+    }
+
+    /// Blend between two transforms by their parts, rotating the shorter way around
+    ///
+    /// This is for smoothing motion, like rendering between two fixed timesteps.
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        let (from_translation, from_rotation, from_scale) = self.to_parts();
+        let (to_translation, to_rotation, to_scale) = other.to_parts();
+        let turn = (to_rotation - from_rotation + PI).rem_euclid(TAU) - PI;
+        Transform::from_parts(
+            from_translation.lerp(to_translation, t),
+            from_rotation + turn * t,
+            from_scale.lerp(to_scale, t),
+        )
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::IDENTITY
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, rhs: Transform) -> Transform {
+        Transform(self.0 * rhs.0)
+    }
+}
+
+impl From<Transform> for Mat3 {
+    fn from(transform: Transform) -> Mat3 {
+        transform.to_matrix()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+    use glam::Vec2;
+
+    use super::Transform;
+
+    #[test]
+    fn compose() {
+        let transform = Transform::translate(Vec2::new(10.0, 0.0))
+            * Transform::rotate(std::f32::consts::FRAC_PI_2)
+            * Transform::scale(Vec2::splat(2.0));
+        let point = transform.transform_point(Vec2::new(1.0, 0.0));
+        assert_abs_diff_eq!(point.x, 10.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(point.y, 2.0, epsilon = 1e-5);
+        let back = transform.inverse().transform_point(point);
+        assert_abs_diff_eq!(back.x, 1.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(back.y, 0.0, epsilon = 1e-5);
+        let matrix_point = transform.to_matrix().transform_point2(Vec2::new(1.0, 0.0));
+        assert_abs_diff_eq!(matrix_point.x, point.x, epsilon = 1e-5);
+        assert_abs_diff_eq!(matrix_point.y, point.y, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn parts_round_trip() {
+        let transform = Transform::from_parts(Vec2::new(3.0, -4.0), 0.5, Vec2::new(2.0, 2.0));
+        let (translation, rotation, scale) = transform.to_parts();
+        assert_abs_diff_eq!(translation.x, 3.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(translation.y, -4.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(rotation, 0.5, epsilon = 1e-5);
+        assert_abs_diff_eq!(scale.x, 2.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn lerp() {
+        let from = Transform::from_parts(Vec2::ZERO, 3.0, Vec2::ONE);
+        let to = Transform::from_parts(Vec2::new(10.0, 0.0), -3.0, Vec2::splat(3.0));
+        let (translation, rotation, scale) = from.lerp(&to, 0.5).to_parts();
+        assert_abs_diff_eq!(translation.x, 5.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(scale.x, 2.0, epsilon = 1e-5);
+        // Through pi rather than back through zero
+        assert_abs_diff_eq!(rotation.abs(), std::f32::consts::PI, epsilon = 1e-4);
+    }
+}
@@ -0,0 +1,104 @@
+/// Reveals text a character at a time, like dialogue in an RPG
+///
+/// Draw it with `Venus::draw_text_revealed`, passing `visible_characters`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Typewriter {
+    text: String,
+    characters_per_second: f32,
+    // Fractional, so slow rates still make progress between frames
+    revealed: f32,
+    length: usize,
+}
+
+impl Typewriter {
+    pub fn new(text: impl Into<String>, characters_per_second: f32) -> Typewriter {
+        let text = text.into();
+        let length = text.chars().count();
+        Typewriter {
+            text,
+            characters_per_second,
+            revealed: 0.0,
+            length,
+        }
+    }
+
+    /// Reveal more of the text, calling `on_character` with each newly revealed character
+    ///
+    /// This is the place to play a blip for each letter, skipping whitespace if it should be
+    /// silent.
+    pub fn update(&mut self, delta: f32, mut on_character: impl FnMut(char)) {
+        let before = self.visible_characters();
+        self.revealed =
+            (self.revealed + delta * self.characters_per_second).clamp(0.0, self.length as f32);
+        // A negative delta or rate hides text again, which reveals nothing new
+        let after = self.visible_characters();
+        for ch in self
+            .text
+            .chars()
+            .skip(before)
+            .take(after.saturating_sub(before))
+        {
+            on_character(ch);
+        }
+    }
+
+    /// Show the rest of the text at once, e.g. when the player presses a button
+    pub fn skip(&mut self) {
+        self.revealed = self.length as f32;
+    }
+
+    pub fn set_characters_per_second(&mut self, characters_per_second: f32) {
+        self.characters_per_second = characters_per_second;
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn visible_characters(&self) -> usize {
+        self.revealed as usize
+    }
+
+    /// The part of the text revealed so far
+    pub fn visible_text(&self) -> &str {
+        let end = self
+            .text
+            .char_indices()
+            .nth(self.visible_characters())
+            .map_or(self.text.len(), |(index, _)| index);
+        &self.text[..end]
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.visible_characters() == self.length
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Typewriter;
+
+    #[test]
+    fn reveal() {
+        let mut typewriter = Typewriter::new("Hi, é!", 10.0);
+        let mut blips = String::new();
+        typewriter.update(0.15, |ch| blips.push(ch));
+        assert_eq!(blips, "H");
+        typewriter.update(0.3, |ch| blips.push(ch));
+        assert_eq!(blips, "Hi, ");
+        assert_eq!(typewriter.visible_text(), "Hi, ");
+        typewriter.update(0.1, |ch| blips.push(ch));
+        assert_eq!(typewriter.visible_text(), "Hi, é");
+        assert!(!typewriter.is_finished());
+
+        typewriter.skip();
+        assert!(typewriter.is_finished());
+        assert_eq!(typewriter.visible_text(), "Hi, é!");
+        typewriter.update(1.0, |_| panic!("everything was already revealed"));
+
+        typewriter.update(-0.25, |_| panic!("nothing new was revealed"));
+        assert_eq!(typewriter.visible_text(), "Hi,");
+        typewriter.update(-10.0, |_| panic!("nothing new was revealed"));
+        assert_eq!(typewriter.visible_characters(), 0);
+    }
+}
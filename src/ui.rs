@@ -0,0 +1,550 @@
+use std::ops::RangeInclusive;
+
+use glam::Vec2;
+use slotmap::SlotMap;
+
+#[cfg(feature = "gamepad")]
+use crate::GamepadButton;
+use crate::{Color, FontHandle, Key, Texture, Venus, shape::Rect};
+
+slotmap::new_key_type! {
+    pub struct WidgetId;
+}
+
+/// A texture cut into a 3x3 grid, so it can cover any size of rectangle without stretching its
+/// corners, e.g. for panel and button borders
+#[derive(Clone, Debug, PartialEq)]
+pub struct NineSlice {
+    texture: Texture,
+    left: u32,
+    top: u32,
+    right: u32,
+    bottom: u32,
+}
+
+impl NineSlice {
+    /// The borders are how many pixels in from each edge of the texture the corners end
+    pub fn new(texture: Texture, left: u32, top: u32, right: u32, bottom: u32) -> NineSlice {
+        assert!(
+            left + right <= texture.width && top + bottom <= texture.height,
+            "nine-slice borders must fit within the texture"
+        );
+        NineSlice {
+            texture,
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// Each piece of the texture and the part of the rectangle it covers, in reading order
+    ///
+    /// The corners keep their size, the edges stretch along their length, and the middle
+    /// stretches both ways. Pieces with nothing to cover are left out.
+    pub fn pieces(&self, rect: Rect) -> Vec<(Texture, Rect)> {
+        let (width, height) = (self.texture.width, self.texture.height);
+        let columns = [
+            (0, self.left, self.left as f32),
+            (
+                self.left,
+                width - self.left - self.right,
+                (rect.width - (self.left + self.right) as f32).max(0.0),
+            ),
+            (width - self.right, self.right, self.right as f32),
+        ];
+        let rows = [
+            (0, self.top, self.top as f32),
+            (
+                self.top,
+                height - self.top - self.bottom,
+                (rect.height - (self.top + self.bottom) as f32).max(0.0),
+            ),
+            (height - self.bottom, self.bottom, self.bottom as f32),
+        ];
+        let mut pieces = Vec::new();
+        let mut y = rect.y;
+        for (source_y, source_height, dest_height) in rows {
+            let mut x = rect.x;
+            for (source_x, source_width, dest_width) in columns {
+                if source_width > 0 && source_height > 0 && dest_width > 0.0 && dest_height > 0.0 {
+                    pieces.push((
+                        self.texture
+                            .sub_texture(source_x, source_y, source_width, source_height),
+                        Rect::new(x, y, dest_width, dest_height),
+                    ));
+                }
+                x += dest_width;
+            }
+            y += dest_height;
+        }
+        pieces
+    }
+}
+
+impl Venus {
+    pub fn draw_nine_slice(&mut self, nine_slice: &NineSlice, rect: Rect) {
+        for (texture, piece) in nine_slice.pieces(rect) {
+            self.draw_image_sized(&texture, piece.position(), piece.size());
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Widget {
+    /// Text, left-aligned and centered vertically
+    Label(String),
+    /// Text on a background, which can be focused and clicked
+    Button(String),
+    /// A value picked by dragging or with left and right, snapped to multiples of the step
+    /// above the start of the range if the step isn't zero
+    Slider {
+        value: f32,
+        range: RangeInclusive<f32>,
+        step: f32,
+    },
+    /// A background for other widgets, drawn under anything added after it
+    Panel,
+}
+
+impl Widget {
+    fn is_focusable(&self) -> bool {
+        matches!(self, Widget::Button(_) | Widget::Slider { .. })
+    }
+}
+
+/// How a `Ui` draws its widgets
+///
+/// Panels and buttons use their nine-slices if they have them, and plain colors otherwise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UiStyle {
+    pub font: FontHandle,
+    pub text_size: u32,
+    pub panel: Option<NineSlice>,
+    pub panel_color: Color,
+    pub button: Option<NineSlice>,
+    pub button_focused: Option<NineSlice>,
+    pub button_color: Color,
+    pub focused_color: Color,
+    pub pressed_color: Color,
+    pub track_color: Color,
+    pub handle_color: Color,
+}
+
+impl UiStyle {
+    pub fn new(font: FontHandle) -> UiStyle {
+        UiStyle {
+            font,
+            text_size: 16,
+            panel: None,
+            panel_color: Color::from_rgba(20, 20, 30, 0.9),
+            button: None,
+            button_focused: None,
+            button_color: Color::from_rgba(60, 60, 80, 1.0),
+            focused_color: Color::from_rgba(90, 90, 130, 1.0),
+            pressed_color: Color::from_rgba(40, 40, 60, 1.0),
+            track_color: Color::from_rgba(40, 40, 50, 1.0),
+            handle_color: Color::WHITE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UiEvent {
+    Clicked(WidgetId),
+    /// A slider moved to a new value
+    Changed(WidgetId, f32),
+}
+
+/// A step through a menu without the mouse, see `Ui::navigate`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Navigation {
+    Previous,
+    Next,
+    /// Move the focused slider down by a step
+    Decrease,
+    /// Move the focused slider up by a step
+    Increase,
+    /// Click the focused button
+    Activate,
+}
+
+struct Node {
+    rect: Rect,
+    widget: Widget,
+}
+
+/// Retained widgets for game menus, drawn over everything in screen space
+///
+/// Widgets are added once and kept between frames. Each frame, call `update` to handle input,
+/// check `events`, and then `draw`. Rectangles are in the same units as the settings' width and
+/// height, e.g. from `Venus::screen_rect` and the helpers in `Anchor` and `Stack`.
+///
+/// Buttons and sliders can be focused, in the order they were added. The mouse focuses what
+/// it moves over, the arrow keys and Tab move between widgets, and Enter or Space clicks. With
+/// the `gamepad` feature, the d-pad and south face button work on every connected pad too.
+pub struct Ui {
+    pub style: UiStyle,
+    widgets: SlotMap<WidgetId, Node>,
+    // Drawing and focus order
+    order: Vec<WidgetId>,
+    focused: Option<WidgetId>,
+    // The widget the mouse was pressed on, until it's released
+    pressed: Option<WidgetId>,
+    mouse_was_down: bool,
+    events: Vec<UiEvent>,
+}
+
+impl Ui {
+    pub fn new(style: UiStyle) -> Ui {
+        Ui {
+            style,
+            widgets: SlotMap::with_key(),
+            order: Vec::new(),
+            focused: None,
+            pressed: None,
+            mouse_was_down: false,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, rect: Rect, widget: Widget) -> WidgetId {
+        let id = self.widgets.insert(Node { rect, widget });
+        self.order.push(id);
+        id
+    }
+
+    pub fn remove(&mut self, id: WidgetId) -> Option<Widget> {
+        self.order.retain(|other| *other != id);
+        if self.focused == Some(id) {
+            self.focused = None;
+        }
+        if self.pressed == Some(id) {
+            self.pressed = None;
+        }
+        self.widgets.remove(id).map(|node| node.widget)
+    }
+
+    pub fn widget(&self, id: WidgetId) -> Option<&Widget> {
+        self.widgets.get(id).map(|node| &node.widget)
+    }
+
+    /// Change a widget, e.g. to relabel a button or set a slider from a saved setting
+    pub fn widget_mut(&mut self, id: WidgetId) -> Option<&mut Widget> {
+        self.widgets.get_mut(id).map(|node| &mut node.widget)
+    }
+
+    pub fn rect(&self, id: WidgetId) -> Option<Rect> {
+        self.widgets.get(id).map(|node| node.rect)
+    }
+
+    pub fn set_rect(&mut self, id: WidgetId, rect: Rect) {
+        if let Some(node) = self.widgets.get_mut(id) {
+            node.rect = rect;
+        }
+    }
+
+    /// A slider's value, or None for other widgets
+    pub fn value(&self, id: WidgetId) -> Option<f32> {
+        match self.widget(id)? {
+            Widget::Slider { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn focused(&self) -> Option<WidgetId> {
+        self.focused
+    }
+
+    /// Focus a button or slider, e.g. the first item when a menu opens
+    pub fn focus(&mut self, id: WidgetId) {
+        if self.widget(id).is_some_and(Widget::is_focusable) {
+            self.focused = Some(id);
+        }
+    }
+
+    /// What happened during the last update
+    pub fn events(&self) -> &[UiEvent] {
+        &self.events
+    }
+
+    /// Whether a button was clicked during the last update
+    pub fn clicked(&self, id: WidgetId) -> bool {
+        self.events.contains(&UiEvent::Clicked(id))
+    }
+
+    /// Handle this frame's mouse, keyboard, and gamepad input
+    pub fn update(&mut self, venus: &Venus) {
+        self.events.clear();
+        for (key, navigation) in KEYS {
+            if venus.is_key_pressed(key) {
+                self.navigate(navigation);
+            }
+        }
+        #[cfg(feature = "gamepad")]
+        for id in venus.gamepads() {
+            for (button, navigation) in BUTTONS {
+                if venus.is_gamepad_button_pressed(*id, button) {
+                    self.navigate(navigation);
+                }
+            }
+        }
+
+        let mouse = venus.logical_mouse_position();
+        let mouse_down = venus.is_left_mouse_down();
+        let hovered = self.order.iter().rev().copied().find(|id| {
+            let node = &self.widgets[*id];
+            node.widget.is_focusable() && node.rect.contains(mouse)
+        });
+        // Only when the mouse moves, so it doesn't fight the keyboard for focus
+        if venus.mouse_delta() != Vec2::ZERO && hovered.is_some() {
+            self.focused = hovered;
+        }
+        if mouse_down && !self.mouse_was_down {
+            self.pressed = hovered;
+            self.focused = hovered.or(self.focused);
+        }
+        if let Some(id) = self.pressed {
+            let node = &self.widgets[id];
+            if let Widget::Slider { range, .. } = &node.widget
+                && mouse_down
+            {
+                let fraction = ((mouse.x - node.rect.x) / node.rect.width).clamp(0.0, 1.0);
+                let value = range.start() + (range.end() - range.start()) * fraction;
+                self.set_value(id, value);
+            }
+            if !mouse_down {
+                if matches!(node.widget, Widget::Button(_)) && hovered == Some(id) {
+                    self.events.push(UiEvent::Clicked(id));
+                }
+                self.pressed = None;
+            }
+        }
+        self.mouse_was_down = mouse_down;
+    }
+
+    /// Move the focus or use the focused widget, for input `update` doesn't handle itself
+    pub fn navigate(&mut self, navigation: Navigation) {
+        match navigation {
+            Navigation::Previous => self.move_focus(false),
+            Navigation::Next => self.move_focus(true),
+            Navigation::Decrease | Navigation::Increase => {
+                let Some(id) = self.focused else {
+                    return;
+                };
+                if let Widget::Slider { value, step, .. } = self.widgets[id].widget {
+                    let step = if navigation == Navigation::Increase {
+                        step
+                    } else {
+                        -step
+                    };
+                    self.set_value(id, value + step);
+                }
+            }
+            Navigation::Activate => {
+                if let Some(id) = self.focused
+                    && matches!(self.widgets[id].widget, Widget::Button(_))
+                {
+                    self.events.push(UiEvent::Clicked(id));
+                }
+            }
+        }
+    }
+
+    pub fn draw(&self, venus: &mut Venus) {
+        let previous_camera = venus.begin_screen_space();
+        let style = &self.style;
+        for id in &self.order {
+            let Node { rect, widget } = &self.widgets[*id];
+            let focused = self.focused == Some(*id);
+            match widget {
+                Widget::Panel => match &style.panel {
+                    Some(panel) => venus.draw_nine_slice(panel, *rect),
+                    None => venus.draw_rect(rect.position(), rect.size(), style.panel_color),
+                },
+                Widget::Label(text) => {
+                    let height = venus.line_height(style.font, style.text_size);
+                    let position = Vec2::new(rect.x, rect.y + (rect.height - height) / 2.0);
+                    venus.draw_text(style.font, position, text, style.text_size);
+                }
+                Widget::Button(text) => {
+                    let pressed = self.pressed == Some(*id);
+                    let background = match (&style.button, &style.button_focused) {
+                        (_, Some(nine_slice)) if focused => Some(nine_slice),
+                        (nine_slice, _) => nine_slice.as_ref(),
+                    };
+                    match background {
+                        Some(nine_slice) if !pressed => venus.draw_nine_slice(nine_slice, *rect),
+                        _ => {
+                            let color = if pressed {
+                                style.pressed_color
+                            } else if focused {
+                                style.focused_color
+                            } else {
+                                style.button_color
+                            };
+                            venus.draw_rect(rect.position(), rect.size(), color);
+                        }
+                    }
+                    let size = Vec2::new(
+                        venus.text_width(style.font, text, style.text_size),
+                        venus.line_height(style.font, style.text_size),
+                    );
+                    let position = rect.center() - size / 2.0;
+                    venus.draw_text(style.font, position, text, style.text_size);
+                }
+                Widget::Slider { value, range, .. } => {
+                    let track = Rect::new(
+                        rect.x,
+                        rect.y + rect.height / 3.0,
+                        rect.width,
+                        rect.height / 3.0,
+                    );
+                    venus.draw_rect(track.position(), track.size(), style.track_color);
+                    let handle_width = rect.height / 2.0;
+                    let x = rect.x + (rect.width - handle_width) * fraction(*value, range);
+                    let color = if focused {
+                        style.focused_color
+                    } else {
+                        style.handle_color
+                    };
+                    venus.draw_rect((x, rect.y), (handle_width, rect.height), color);
+                }
+            }
+        }
+        venus.end_screen_space(&previous_camera);
+    }
+
+    fn move_focus(&mut self, forward: bool) {
+        let focusable: Vec<WidgetId> = self
+            .order
+            .iter()
+            .copied()
+            .filter(|id| self.widgets[*id].widget.is_focusable())
+            .collect();
+        if focusable.is_empty() {
+            return;
+        }
+        let current = self
+            .focused
+            .and_then(|id| focusable.iter().position(|other| *other == id));
+        let next = match (current, forward) {
+            (Some(index), true) => (index + 1) % focusable.len(),
+            (Some(index), false) => (index + focusable.len() - 1) % focusable.len(),
+            (None, true) => 0,
+            (None, false) => focusable.len() - 1,
+        };
+        self.focused = Some(focusable[next]);
+    }
+
+    fn set_value(&mut self, id: WidgetId, new_value: f32) {
+        let Widget::Slider { value, range, step } = &mut self.widgets[id].widget else {
+            return;
+        };
+        let mut snapped = new_value;
+        if *step > 0.0 {
+            snapped = range.start() + ((snapped - range.start()) / *step).round() * *step;
+        }
+        let snapped = snapped.clamp(*range.start(), *range.end());
+        if snapped != *value {
+            *value = snapped;
+            self.events.push(UiEvent::Changed(id, snapped));
+        }
+    }
+}
+
+const KEYS: [(Key, Navigation); 7] = [
+    (Key::Up, Navigation::Previous),
+    (Key::Down, Navigation::Next),
+    (Key::Tab, Navigation::Next),
+    (Key::Left, Navigation::Decrease),
+    (Key::Right, Navigation::Increase),
+    (Key::Return, Navigation::Activate),
+    (Key::Space, Navigation::Activate),
+];
+
+#[cfg(feature = "gamepad")]
+const BUTTONS: [(GamepadButton, Navigation); 5] = [
+    (GamepadButton::DPadUp, Navigation::Previous),
+    (GamepadButton::DPadDown, Navigation::Next),
+    (GamepadButton::DPadLeft, Navigation::Decrease),
+    (GamepadButton::DPadRight, Navigation::Increase),
+    (GamepadButton::South, Navigation::Activate),
+];
+
+// Where a value sits within a range, from 0 to 1
+fn fraction(value: f32, range: &RangeInclusive<f32>) -> f32 {
+    let width = range.end() - range.start();
+    if width <= 0.0 {
+        return 0.0;
+    }
+    ((value - range.start()) / width).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Navigation, NineSlice, Ui, UiEvent, UiStyle, Widget};
+    use crate::{FontHandle, Rect, Texture, TextureHandle};
+
+    #[test]
+    fn nine_slice_pieces() {
+        let texture = Texture {
+            handle: TextureHandle::mock(),
+            uv: Rect::new(0.0, 0.0, 1.0, 1.0),
+            width: 12,
+            height: 12,
+        };
+        let nine_slice = NineSlice::new(texture, 4, 4, 4, 4);
+        let pieces = nine_slice.pieces(Rect::new(10.0, 10.0, 100.0, 40.0));
+        assert_eq!(pieces.len(), 9);
+        assert_eq!(pieces[0].1, Rect::new(10.0, 10.0, 4.0, 4.0));
+        assert_eq!(pieces[4].1, Rect::new(14.0, 14.0, 92.0, 32.0));
+        assert_eq!(pieces[8].1, Rect::new(106.0, 46.0, 4.0, 4.0));
+        // Too small for a middle, so only the corners are left
+        assert_eq!(nine_slice.pieces(Rect::new(0.0, 0.0, 8.0, 8.0)).len(), 4);
+    }
+
+    #[test]
+    fn navigation() {
+        let mut ui = Ui::new(UiStyle::new(FontHandle::default()));
+        ui.add(Rect::new(0.0, 0.0, 200.0, 200.0), Widget::Panel);
+        ui.add(
+            Rect::new(10.0, 10.0, 100.0, 20.0),
+            Widget::Label("Menu".into()),
+        );
+        let play = ui.add(
+            Rect::new(10.0, 40.0, 100.0, 20.0),
+            Widget::Button("Play".into()),
+        );
+        let volume = ui.add(
+            Rect::new(10.0, 70.0, 100.0, 20.0),
+            Widget::Slider {
+                value: 0.5,
+                range: 0.0..=1.0,
+                step: 0.1,
+            },
+        );
+
+        // Labels and panels are skipped, and focus wraps around
+        ui.navigate(Navigation::Next);
+        assert_eq!(ui.focused(), Some(play));
+        ui.navigate(Navigation::Activate);
+        assert!(ui.clicked(play));
+        ui.navigate(Navigation::Previous);
+        assert_eq!(ui.focused(), Some(volume));
+
+        ui.navigate(Navigation::Increase);
+        ui.navigate(Navigation::Increase);
+        assert_eq!(ui.value(volume), Some(0.7));
+        for _ in 0..10 {
+            ui.navigate(Navigation::Increase);
+        }
+        assert_eq!(ui.value(volume), Some(1.0));
+        assert_eq!(ui.events().last(), Some(&UiEvent::Changed(volume, 1.0)));
+
+        ui.remove(volume);
+        assert_eq!(ui.focused(), None);
+        ui.navigate(Navigation::Previous);
+        assert_eq!(ui.focused(), Some(play));
+    }
+}
@@ -0,0 +1,197 @@
+use std::borrow::Cow;
+
+use crate::{AssetPack, Error, load_file};
+
+/// Where asset paths are looked up, in order of priority
+///
+/// Each mount covers the paths under a prefix. Later mounts take priority over earlier ones, and
+/// anything not found in a mount comes from the asset root.
+pub(crate) struct Mounts {
+    mounts: Vec<(String, Mount)>,
+    root: Option<String>,
+}
+
+enum Mount {
+    Pack(AssetPack),
+    Directory(String),
+}
+
+/// A place the contents of a file might come from
+pub(crate) enum Source<'a> {
+    Contents(Cow<'a, [u8]>),
+    File(String),
+}
+
+impl Source<'_> {
+    pub fn into_owned(self) -> Source<'static> {
+        match self {
+            Source::Contents(contents) => Source::Contents(Cow::Owned(contents.into_owned())),
+            Source::File(file) => Source::File(file),
+        }
+    }
+}
+
+impl Mounts {
+    pub fn new(root: Option<String>) -> Mounts {
+        Mounts {
+            mounts: Vec::new(),
+            root,
+        }
+    }
+
+    pub fn mount_pack(&mut self, prefix: &str, pack: AssetPack) {
+        self.mounts.push((normalize(prefix), Mount::Pack(pack)));
+    }
+
+    pub fn mount_directory(&mut self, prefix: &str, directory: &str) {
+        self.mounts.push((
+            normalize(prefix),
+            Mount::Directory(directory.trim_end_matches('/').to_string()),
+        ));
+    }
+
+    /// Remove every pack and directory mounted at a prefix, ignoring an empty prefix
+    pub fn unmount(&mut self, prefix: &str) {
+        let prefix = normalize(prefix);
+        if prefix.is_empty() {
+            return;
+        }
+        self.mounts.retain(|(mounted, _)| *mounted != prefix);
+    }
+
+    /// Every place a path could be loaded from, best first
+    ///
+    /// Packs are checked immediately, so a pack containing the file ends the list. Directories
+    /// can only be checked by trying to load from them.
+    pub fn sources(&self, path: &str) -> Vec<Source<'_>> {
+        let mut sources = Vec::new();
+        for (prefix, mount) in self.mounts.iter().rev() {
+            let Some(relative) = strip_prefix(path, prefix) else {
+                continue;
+            };
+            match mount {
+                Mount::Pack(pack) => {
+                    if let Some(contents) = pack.get(relative) {
+                        sources.push(Source::Contents(Cow::Borrowed(contents)));
+                        return sources;
+                    }
+                }
+                Mount::Directory(directory) => {
+                    sources.push(Source::File(join(Some(directory), relative)));
+                }
+            }
+        }
+        sources.push(Source::File(join(self.root.as_deref(), path)));
+        sources
+    }
+
+    /// The file on disk a path currently loads from, or None if it comes from a pack
+    pub fn file_path(&self, path: &str) -> Option<String> {
+        for source in self.sources(path) {
+            match source {
+                Source::File(file) if file_exists(&file) => return Some(file),
+                Source::File(_) => {}
+                Source::Contents(_) => return None,
+            }
+        }
+        // Nothing exists yet, so expect the file to appear under the asset root
+        Some(join(self.root.as_deref(), path))
+    }
+}
+
+/// Load from the first source that has the file
+///
+/// If none of them do, the error is the one from the last source, which is usually the asset root
+pub(crate) async fn load_sources(sources: Vec<Source<'_>>) -> Result<Vec<u8>, Error> {
+    let mut result = Err(Error::PackError("no sources to load from"));
+    for source in sources {
+        result = match source {
+            Source::Contents(contents) => return Ok(contents.into_owned()),
+            Source::File(file) => load_file(&file).await,
+        };
+        if result.is_ok() {
+            break;
+        }
+    }
+    result
+}
+
+fn normalize(prefix: &str) -> String {
+    prefix.trim_matches('/').to_string()
+}
+
+fn strip_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    if prefix.is_empty() {
+        return Some(path);
+    }
+    path.strip_prefix(prefix)?.strip_prefix('/')
+}
+
+fn join(directory: Option<&str>, path: &str) -> String {
+    match directory {
+        Some(directory) if !path.starts_with('/') => {
+            format!("{}/{path}", directory.trim_end_matches('/'))
+        }
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn file_exists(path: &str) -> bool {
+    std::path::Path::new(path).is_file()
+}
+
+// Hot reloading doesn't work on the web, so which file would be used doesn't matter there
+#[cfg(target_arch = "wasm32")]
+fn file_exists(_path: &str) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Mounts, Source};
+    use crate::AssetPack;
+
+    fn files(sources: Vec<Source>) -> Vec<String> {
+        sources
+            .into_iter()
+            .map(|source| match source {
+                Source::File(file) => file,
+                Source::Contents(contents) => String::from_utf8(contents.into_owned()).unwrap(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn priority() {
+        let mut mounts = Mounts::new(Some("assets".to_string()));
+        mounts.mount_pack("", AssetPack::embedded(&[("music/theme.ogg", b"packed")]));
+        mounts.mount_directory("sprites/", "mods/hd");
+        assert_eq!(
+            files(mounts.sources("sprites/hero.png")),
+            ["mods/hd/hero.png", "assets/sprites/hero.png"]
+        );
+        assert_eq!(files(mounts.sources("music/theme.ogg")), ["packed"]);
+        assert_eq!(
+            files(mounts.sources("spritesheet.png")),
+            ["assets/spritesheet.png"]
+        );
+
+        mounts.mount_directory("", "mods/remix");
+        assert_eq!(
+            files(mounts.sources("music/theme.ogg")),
+            ["mods/remix/music/theme.ogg", "packed"]
+        );
+        // Unmounting the root would also drop packs mounted without a prefix
+        mounts.unmount("/");
+        assert_eq!(
+            files(mounts.sources("music/theme.ogg")),
+            ["mods/remix/music/theme.ogg", "packed"]
+        );
+        mounts.unmount("/sprites");
+        assert_eq!(
+            files(mounts.sources("sprites/hero.png")),
+            ["mods/remix/sprites/hero.png", "assets/sprites/hero.png"]
+        );
+    }
+}
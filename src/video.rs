@@ -0,0 +1,193 @@
+#[cfg(feature = "audio")]
+use crate::PlayingAudio;
+use crate::{Error, Texture, Venus};
+
+/// A decoded frame of video
+pub struct VideoFrame {
+    /// RGBA pixels, row by row from the top left
+    pub pixels: Vec<u8>,
+    /// When the frame should appear, in seconds from the start of the video
+    pub time: f32,
+}
+
+/// Decodes a video for `Venus::play_video`, usually by wrapping a codec crate
+///
+/// venus doesn't include any video codecs, so this is how MPEG-1, Theora, or anything else
+/// gets decoded.
+pub trait VideoDecoder {
+    /// The width and height of every frame
+    fn size(&self) -> (u32, u32);
+
+    /// The next frame, or None once the video is over
+    fn next_frame(&mut self) -> Option<VideoFrame>;
+
+    /// The soundtrack, which is taken once when the video starts playing
+    #[cfg(feature = "audio")]
+    fn take_audio(&mut self) -> Option<Box<dyn rodio::Source + Send>> {
+        None
+    }
+}
+
+/// A video being played into a texture, see `Venus::play_video`
+pub struct Video {
+    decoder: Box<dyn VideoDecoder>,
+    texture: Texture,
+    time: f32,
+    // Decoded ahead of time, waiting until it's due
+    next_frame: Option<VideoFrame>,
+    #[cfg(feature = "audio")]
+    audio: Option<PlayingAudio>,
+}
+
+impl Video {
+    /// The texture showing the current frame, which can be drawn like any other
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// How far into the video playback is, in seconds
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_frame.is_none()
+    }
+}
+
+impl Venus {
+    /// Start playing a video into a texture, along with its soundtrack
+    ///
+    /// Call `update_video` every frame to keep the texture up to date.
+    pub fn play_video(&mut self, decoder: impl VideoDecoder + 'static) -> Result<Video, Error> {
+        let mut decoder = Box::new(decoder);
+        let (width, height) = decoder.size();
+        let texture = self.new_texture_from_bytes(
+            &vec![0; width as usize * height as usize * 4],
+            width,
+            height,
+        )?;
+        #[cfg(feature = "audio")]
+        let audio = decoder.take_audio().map(|audio| self.play_source(audio));
+        let next_frame = decoder.next_frame();
+        let mut video = Video {
+            decoder,
+            texture,
+            time: 0.0,
+            next_frame,
+            #[cfg(feature = "audio")]
+            audio,
+        };
+        self.update_video(&mut video, 0.0);
+        Ok(video)
+    }
+
+    /// Advance a video by some seconds, showing the latest frame that's due
+    ///
+    /// Frames are timed by the seconds passed in rather than by the soundtrack, so pass the real
+    /// time between frames to keep them in sync.
+    pub fn update_video(&mut self, video: &mut Video, delta: f32) {
+        video.time += delta;
+        let mut latest = None;
+        while let Some(frame) = video.next_frame.take_if(|frame| frame.time <= video.time) {
+            latest = Some(frame);
+            video.next_frame = video.decoder.next_frame();
+        }
+        if let Some(frame) = latest {
+            let Texture {
+                handle,
+                width,
+                height,
+                ..
+            } = video.texture;
+            let expected = width as usize * height as usize * 4;
+            if frame.pixels.len() == expected {
+                self.gfx
+                    .update_texture(handle, 0, 0, &frame.pixels, width, height);
+            } else {
+                log::error!(
+                    "Skipped a video frame with {} bytes of pixels instead of {expected}",
+                    frame.pixels.len()
+                );
+            }
+        }
+    }
+
+    /// Stop a video's soundtrack, leaving its texture showing the last frame
+    #[cfg(feature = "audio")]
+    pub fn stop_video(&mut self, video: &Video) {
+        if let Some(audio) = video.audio {
+            self.stop_audio(audio);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{VideoDecoder, VideoFrame};
+    use crate::{Settings, Venus};
+
+    // Three frames, half a second apart
+    struct Countdown(u8);
+
+    impl VideoDecoder for Countdown {
+        fn size(&self) -> (u32, u32) {
+            (1, 1)
+        }
+
+        fn next_frame(&mut self) -> Option<VideoFrame> {
+            self.0 = self.0.checked_sub(1)?;
+            Some(VideoFrame {
+                pixels: vec![self.0 * 10, 0, 0, 255],
+                time: (2 - self.0) as f32 * 0.5,
+            })
+        }
+    }
+
+    // A first frame of the right size, then one with a pixel too many
+    struct Truncated(u8);
+
+    impl VideoDecoder for Truncated {
+        fn size(&self) -> (u32, u32) {
+            (2, 1)
+        }
+
+        fn next_frame(&mut self) -> Option<VideoFrame> {
+            self.0 += 1;
+            let pixels = match self.0 {
+                1 => vec![255; 8],
+                2 => vec![0; 12],
+                _ => return None,
+            };
+            Some(VideoFrame {
+                pixels,
+                time: (self.0 - 1) as f32,
+            })
+        }
+    }
+
+    #[test]
+    fn playback() {
+        let mut venus = Venus::headless(Settings::default());
+        let mut video = venus.play_video(Countdown(3)).unwrap();
+        let next_time = |video: &super::Video| video.next_frame.as_ref().map(|frame| frame.time);
+        // The first frame shows up straight away
+        assert_eq!(next_time(&video), Some(0.5));
+        venus.update_video(&mut video, 0.4);
+        assert_eq!(next_time(&video), Some(0.5));
+        // Skips straight to the last frame that's due
+        venus.update_video(&mut video, 0.7);
+        assert!(video.is_finished());
+        assert_eq!((video.texture().width, video.texture().height), (1, 1));
+    }
+
+    #[test]
+    fn wrong_frame_size() {
+        let mut venus = Venus::headless(Settings::default());
+        let mut video = venus.play_video(Truncated(0)).unwrap();
+        venus.update_video(&mut video, 1.0);
+        assert!(video.is_finished());
+        let pixels = video.texture().read_pixels(&mut venus);
+        assert_eq!(pixels, Some(vec![255; 8]));
+    }
+}
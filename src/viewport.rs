@@ -0,0 +1,56 @@
+use glam::Vec2;
+
+use crate::shape::Rect;
+
+/// How the game's logical resolution is fit into a window of a different size
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ScaleMode {
+    /// Fill the whole window, distorting the image if the aspect ratios don't match
+    #[default]
+    Stretch,
+    /// Scale as large as possible while keeping the aspect ratio, leaving bars around the edges
+    Letterbox,
+    /// Like `Letterbox`, but only scale by whole numbers so pixel art stays crisp
+    Integer,
+}
+
+/// The region of the window the game should be drawn into
+///
+/// Both sizes should be in the same units, usually physical pixels
+pub fn fit_viewport(logical_size: Vec2, window_size: Vec2, mode: ScaleMode) -> Rect {
+    let scale = window_size / logical_size;
+    let size = match mode {
+        ScaleMode::Stretch => return Rect::new(0.0, 0.0, window_size.x, window_size.y),
+        ScaleMode::Letterbox => logical_size * scale.min_element(),
+        ScaleMode::Integer => logical_size * scale.min_element().floor().max(1.0),
+    };
+    let position = ((window_size - size) / 2.0).floor();
+    Rect::new(position.x, position.y, size.x, size.y)
+}
+
+#[cfg(test)]
+mod test {
+    use glam::Vec2;
+
+    use super::{ScaleMode, fit_viewport};
+
+    #[test]
+    fn fit() {
+        let logical = Vec2::new(320.0, 180.0);
+        let window = Vec2::new(1000.0, 1000.0);
+        let stretch = fit_viewport(logical, window, ScaleMode::Stretch);
+        assert_eq!(stretch.position(), Vec2::ZERO);
+        assert_eq!(stretch.size(), window);
+        let letterbox = fit_viewport(logical, window, ScaleMode::Letterbox);
+        assert_eq!(letterbox.width, 1000.0);
+        assert_eq!(letterbox.height, 562.5);
+        assert_eq!(letterbox.x, 0.0);
+        assert_eq!(letterbox.y, 218.0);
+        let integer = fit_viewport(logical, window, ScaleMode::Integer);
+        assert_eq!(integer.position(), Vec2::new(20.0, 230.0));
+        assert_eq!(integer.size(), Vec2::new(960.0, 540.0));
+        // Windows smaller than the logical resolution still get something on screen
+        let tiny = fit_viewport(logical, Vec2::new(100.0, 100.0), ScaleMode::Integer);
+        assert_eq!(tiny.size(), logical);
+    }
+}